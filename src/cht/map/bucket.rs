@@ -173,6 +173,72 @@ impl<'g, K: 'g + Eq, V: 'g> BucketArray<K, V> {
         }
     }
 
+    pub(crate) fn modify<F>(
+        &self,
+        guard: &'g Guard,
+        hash: u64,
+        mut eq: impl FnMut(&K) -> bool,
+        mut modifier: F,
+    ) -> Result<Shared<'g, Bucket<K, V>>, F>
+    where
+        K: Clone,
+        F: FnMut(&K, &V) -> Option<V>,
+    {
+        let loop_result = self.probe_loop(guard, hash, |_, this_bucket, this_bucket_ptr| {
+            let this_bucket_ref = if let Some(r) = unsafe { this_bucket_ptr.as_ref() } {
+                r
+            } else {
+                // Nothing to modify.
+                return ProbeLoopAction::Return(Shared::null());
+            };
+
+            let this_key = &this_bucket_ref.key;
+
+            if !eq(this_key) {
+                // Different key. Try next bucket.
+                return ProbeLoopAction::Continue;
+            }
+
+            if is_tombstone(this_bucket_ptr) {
+                // Already removed.
+                return ProbeLoopAction::Return(Shared::null());
+            }
+
+            let this_value = unsafe { &*this_bucket_ref.maybe_value.as_ptr() };
+
+            let new_value = if let Some(v) = modifier(this_key, this_value) {
+                v
+            } else {
+                // Found but the modifier returned `None`. Do not modify.
+                return ProbeLoopAction::Return(Shared::null());
+            };
+
+            let new_bucket = Owned::new(Bucket::new(this_key.clone(), new_value));
+
+            match this_bucket.compare_exchange_weak(
+                this_bucket_ptr,
+                new_bucket,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+                guard,
+            ) {
+                // Succeeded. Return the previous value.
+                Ok(_) => ProbeLoopAction::Return(this_bucket_ptr),
+                // Failed. Drop the new value and reload to retry.
+                Err(CompareExchangeError { mut new, .. }) => {
+                    unsafe { ptr::drop_in_place(new.maybe_value.as_mut_ptr()) };
+                    ProbeLoopAction::Reload
+                }
+            }
+        });
+
+        match loop_result {
+            ProbeLoopResult::Returned(t) => Ok(t),
+            ProbeLoopResult::LoopEnded => Ok(Shared::null()),
+            ProbeLoopResult::FoundSentinelTag => Err(modifier),
+        }
+    }
+
     pub(crate) fn insert_if_not_present<F>(
         &self,
         guard: &'g Guard,
@@ -446,6 +446,30 @@ impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
         result
     }
 
+    /// If a value corresponds to the key, invoke a function to modify the
+    /// existing value and return the result of invoking a function with a
+    /// reference to the key-value pair previously corresponding to the key.
+    /// Unlike `insert_with_or_modify`, this never inserts a new key-value pair.
+    ///
+    /// If `on_modify` returns [`None`], the existing value is not modified and
+    /// [`None`] is returned. `on_modify` may be invoked one or more times.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    #[inline]
+    pub(crate) fn modify_entry_and<T>(
+        &self,
+        hash: u64,
+        eq: impl FnMut(&K) -> bool,
+        on_modify: impl FnMut(&K, &V) -> Option<V>,
+        with_old_entry: impl FnOnce(&K, &V) -> T,
+    ) -> Option<T>
+    where
+        K: Clone,
+    {
+        self.bucket_array_ref(hash)
+            .modify_entry_and(hash, eq, on_modify, with_old_entry)
+    }
+
     #[inline]
     pub(crate) fn insert_if_not_present(&self, key: K, hash: u64, value: V) -> Option<V>
     where
@@ -1084,6 +1108,81 @@ mod tests {
         run_deferred();
     }
 
+    #[test]
+    fn modify_entry_and() {
+        let map = HashMap::with_capacity(0);
+
+        let key = "key1";
+        let hash = map.hash(&key);
+
+        // Does not insert a missing key.
+        assert_eq!(
+            map.modify_entry_and(hash, |&k| k == key, |_, x| Some(x + 1), |_, x| *x),
+            None
+        );
+        assert_eq!(map.get(hash, |&k| k == key), None);
+
+        assert_eq!(map.insert_entry_and(key, hash, 1, |_, v| *v), None);
+        assert_eq!(
+            map.modify_entry_and(hash, |&k| k == key, |_, x| Some(x + 1), |_, x| *x),
+            Some(1)
+        );
+        assert_eq!(map.get(hash, |&k| k == key), Some(2));
+
+        // Does not modify if `on_modify` returns `None`.
+        assert_eq!(
+            map.modify_entry_and(hash, |&k| k == key, |_, _| None, |_, x| *x),
+            None
+        );
+        assert_eq!(map.get(hash, |&k| k == key), Some(2));
+
+        run_deferred();
+    }
+
+    #[cfg_attr(mips, ignore)]
+    #[test]
+    fn concurrent_modify_entry_and() {
+        const NUM_THREADS: usize = 64;
+        const MAX_VALUE: i32 = 512;
+
+        let map = Arc::new(HashMap::with_capacity(0));
+        let barrier = Arc::new(Barrier::new(NUM_THREADS));
+
+        for i in 0..MAX_VALUE {
+            assert_eq!(map.insert_entry_and(i, map.hash(&i), 0, |_, v| *v), None);
+        }
+
+        #[allow(clippy::needless_collect)]
+        let threads: Vec<_> = (0..NUM_THREADS)
+            .map(|_| {
+                let map = Arc::clone(&map);
+                let barrier = Arc::clone(&barrier);
+
+                spawn(move || {
+                    barrier.wait();
+
+                    for j in 0..(MAX_VALUE * 2) {
+                        let hash = map.hash(&j);
+                        map.modify_entry_and(hash, |&k| k == j, |_, x| Some(x + 1), |_, _| ());
+                    }
+                })
+            })
+            .collect();
+
+        for result in threads.into_iter().map(JoinHandle::join) {
+            assert!(result.is_ok());
+        }
+
+        // The missing keys were not inserted.
+        assert_eq!(map.len(), MAX_VALUE as usize);
+
+        for i in 0..MAX_VALUE {
+            assert_eq!(map.get(map.hash(&i), |&k| k == i), Some(NUM_THREADS as i32));
+        }
+
+        run_deferred();
+    }
+
     #[cfg_attr(mips, ignore)]
     #[test]
     fn concurrent_insert_with_or_modify() {
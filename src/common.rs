@@ -12,6 +12,7 @@ pub(crate) mod entry;
 pub(crate) mod builder_utils;
 pub(crate) mod deque;
 pub(crate) mod frequency_sketch;
+pub(crate) mod heap_size;
 pub(crate) mod time;
 
 #[cfg(all(test, any(feature = "sync", feature = "future")))]
@@ -52,6 +53,29 @@ impl PartialEq<usize> for CacheRegion {
     }
 }
 
+// The percentage of the max capacity that will be assigned to the admission window,
+// and the percentage of the main space that will be assigned to the protected
+// segment. These are the same defaults as Caffeine.
+const PERCENT_WINDOW: f64 = 0.01;
+const PERCENT_MAIN_PROTECTED: f64 = 0.80;
+
+// Returns the max weighted sizes of the admission window and the protected segment
+// for the given max capacity of a cache.
+//
+// A cache whose max capacity is too small to hold a window of at least one entry
+// (less than 100) gets a zero-sized window. Such a cache does not use the window
+// and the protected segment, and admits new entries directly to the probation
+// segment of the main space.
+pub(crate) fn window_and_protected_capacities(max_capacity: u64) -> (u64, u64) {
+    let window = (max_capacity as f64 * PERCENT_WINDOW) as u64;
+    if window == 0 {
+        (0, 0)
+    } else {
+        let protected = ((max_capacity - window) as f64 * PERCENT_MAIN_PROTECTED) as u64;
+        (window, protected)
+    }
+}
+
 // Ensures the value fits in a range of `128u32..=u32::MAX`.
 pub(crate) fn sketch_capacity(max_capacity: u64) -> u32 {
     max_capacity.try_into().unwrap_or(u32::MAX).max(128)
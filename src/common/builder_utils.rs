@@ -1,7 +1,9 @@
 use std::time::Duration;
 
 #[cfg(any(feature = "sync", feature = "future"))]
-use super::concurrent::housekeeper;
+use super::concurrent::{housekeeper, Weigher};
+#[cfg(any(feature = "sync", feature = "future"))]
+use crate::{executor::ExecutorArc, sync_base::refresher::Refresher};
 
 const YEAR_SECONDS: u64 = 365 * 24 * 3600;
 
@@ -18,11 +20,104 @@ pub(crate) fn ensure_expirations_or_panic(
     }
 }
 
+/// Creates a `Refresher` if `refresh_after_write` is set. The reloads will run on
+/// the `executor`, or on the global thread pool if it is `None`.
+///
+/// # Panics
+///
+/// Panics if `refresh_after_write` is set but `reloader` is not.
+#[cfg(feature = "sync")]
+pub(crate) fn refresher<R>(
+    refresh_after_write: Option<Duration>,
+    reloader: Option<R>,
+    executor: Option<ExecutorArc>,
+) -> Option<std::sync::Arc<Refresher<R>>> {
+    new_refresher(refresh_after_write, reloader, |duration, reloader| {
+        Refresher::new(duration, reloader, executor)
+    })
+}
+
+/// Creates a `Refresher` without an executor if `refresh_after_write` is set. The
+/// reloads will be driven by the cache.
+///
+/// # Panics
+///
+/// Panics if `refresh_after_write` is set but `reloader` is not.
+#[cfg(feature = "future")]
+pub(crate) fn refresher_without_executor<R>(
+    refresh_after_write: Option<Duration>,
+    reloader: Option<R>,
+) -> Option<std::sync::Arc<Refresher<R>>> {
+    new_refresher(refresh_after_write, reloader, Refresher::without_executor)
+}
+
+#[cfg(any(feature = "sync", feature = "future"))]
+fn new_refresher<R>(
+    refresh_after_write: Option<Duration>,
+    reloader: Option<R>,
+    new: impl FnOnce(Duration, R) -> Refresher<R>,
+) -> Option<std::sync::Arc<Refresher<R>>> {
+    match (refresh_after_write, reloader) {
+        (Some(duration), Some(reloader)) => Some(std::sync::Arc::new(new(duration, reloader))),
+        (Some(_), None) => panic!("refresh_after_write is set but reloader is not set"),
+        (None, _) => None,
+    }
+}
+
+/// Returns the weigher of a cache. If `entry_overhead` is given, it will be added
+/// to the weight of every entry. If no weigher is set in that case, the sizes of
+/// the key and value themselves (without their heap data) will be used as the
+/// weight.
+#[cfg(any(feature = "sync", feature = "future"))]
+pub(crate) fn weigher<K, V>(
+    weigher: Option<Weigher<K, V>>,
+    entry_overhead: Option<u64>,
+) -> Option<Weigher<K, V>>
+where
+    K: 'static,
+    V: 'static,
+{
+    match (weigher, entry_overhead) {
+        (weigher, None) => weigher,
+        (Some(weigher), Some(overhead)) => Some(std::sync::Arc::new(move |k: &K, v: &V| {
+            weigher(k, v).saturating_add(overhead)
+        })),
+        (None, Some(overhead)) => {
+            let weight = (std::mem::size_of::<K>() + std::mem::size_of::<V>()) as u64 + overhead;
+            Some(std::sync::Arc::new(move |_k: &K, _v: &V| weight))
+        }
+    }
+}
+
+/// Creates the housekeeper configuration of a cache. In the deterministic mode, the
+/// cache will have no background threads, and all background tasks will be run by
+/// the client threads.
 #[cfg(any(feature = "sync", feature = "future"))]
-pub(crate) fn housekeeper_conf(thread_pool_enabled: bool) -> housekeeper::Configuration {
-    if thread_pool_enabled {
-        housekeeper::Configuration::new_thread_pool(true)
+pub(crate) fn housekeeper_conf(
+    thread_pool_enabled: bool,
+    executor: Option<ExecutorArc>,
+    deterministic_mode: bool,
+) -> housekeeper::Configuration {
+    if deterministic_mode {
+        housekeeper::Configuration::new_blocking_with_task_queue()
+    } else if thread_pool_enabled {
+        housekeeper::Configuration::new_thread_pool(true, executor)
     } else {
-        housekeeper::Configuration::new_blocking()
+        housekeeper::Configuration::new_blocking(executor)
+    }
+}
+
+/// Creates the housekeeper configuration of a `future::Cache`. If the thread pool
+/// is disabled and no executor is given, the cache will have no background
+/// threads; all background tasks will be run by the client tasks.
+#[cfg(feature = "future")]
+pub(crate) fn async_housekeeper_conf(
+    thread_pool_enabled: bool,
+    executor: Option<ExecutorArc>,
+    deterministic_mode: bool,
+) -> housekeeper::Configuration {
+    match (thread_pool_enabled, executor) {
+        (false, None) => housekeeper::Configuration::new_blocking_with_task_queue(),
+        (enabled, executor) => housekeeper_conf(enabled, executor, deterministic_mode),
     }
 }
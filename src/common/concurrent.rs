@@ -1,4 +1,7 @@
-use crate::common::{deque::DeqNode, time::Instant};
+use crate::{
+    common::{deque::DeqNode, time::Instant, CacheRegion},
+    Expiry,
+};
 
 use parking_lot::Mutex;
 use std::{ptr::NonNull, sync::Arc};
@@ -9,7 +12,9 @@ pub(crate) mod constants;
 pub(crate) mod deques;
 pub(crate) mod entry_info;
 pub(crate) mod housekeeper;
+pub(crate) mod task_queue;
 pub(crate) mod thread_pool;
+pub(crate) mod timer_wheel;
 pub(crate) mod unsafe_weak_pointer;
 
 // target_has_atomic is more convenient but yet unstable (Rust 1.55)
@@ -29,15 +34,23 @@ pub(crate) mod atomic_time;
 #[cfg(feature = "unstable-debug-counters")]
 pub(crate) mod debug_counters;
 
-use self::entry_info::EntryInfo;
+use self::{entry_info::EntryInfo, timer_wheel::TimerDeqNode};
 
-pub(crate) type Weigher<K, V> = Arc<dyn Fn(&K, &V) -> u32 + Send + Sync + 'static>;
+pub(crate) type Weigher<K, V> = Arc<dyn Fn(&K, &V) -> u64 + Send + Sync + 'static>;
+
+pub(crate) type ExpiryArc<K, V> = Arc<dyn Expiry<K, V> + Send + Sync + 'static>;
 
 pub(crate) trait AccessTime {
     fn last_accessed(&self) -> Option<Instant>;
     fn set_last_accessed(&self, timestamp: Instant);
     fn last_modified(&self) -> Option<Instant>;
     fn set_last_modified(&self, timestamp: Instant);
+
+    /// Returns `true` if the `time_to_live` of the cache should not be applied
+    /// to the entry, as it has its own expiration time.
+    fn is_ttl_overridden(&self) -> bool {
+        false
+    }
 }
 
 pub(crate) struct KeyHash<K> {
@@ -147,6 +160,11 @@ impl<K> AccessTime for DeqNode<KeyDate<K>> {
     fn set_last_modified(&self, timestamp: Instant) {
         self.element.entry_info.set_last_modified(timestamp);
     }
+
+    #[inline]
+    fn is_ttl_overridden(&self) -> bool {
+        self.element.entry_info.is_ttl_overridden()
+    }
 }
 
 impl<K> AccessTime for DeqNode<KeyHashDate<K>> {
@@ -180,6 +198,7 @@ type KeyDeqNodeWo<K> = NonNull<DeqNode<KeyDate<K>>>;
 pub(crate) struct DeqNodes<K> {
     access_order_q_node: Option<KeyDeqNodeAo<K>>,
     write_order_q_node: Option<KeyDeqNodeWo<K>>,
+    timer_node: Option<TimerDeqNode<K>>,
 }
 
 // We need this `unsafe impl` as DeqNodes have NonNull pointers.
@@ -188,7 +207,10 @@ unsafe impl<K> Send for DeqNodes<K> {}
 pub(crate) struct ValueEntry<K, V> {
     pub(crate) value: V,
     info: TrioArc<EntryInfo>,
-    nodes: Mutex<DeqNodes<K>>,
+    // The deque nodes are shared with the `ValueEntry`s created by `new_from`, so
+    // that moving a node to another deque (e.g. from the window to the main space)
+    // is visible to all of them.
+    nodes: TrioArc<Mutex<DeqNodes<K>>>,
 }
 
 impl<K, V> ValueEntry<K, V> {
@@ -199,10 +221,11 @@ impl<K, V> ValueEntry<K, V> {
         Self {
             value,
             info: entry_info,
-            nodes: Mutex::new(DeqNodes {
+            nodes: TrioArc::new(Mutex::new(DeqNodes {
                 access_order_q_node: None,
                 write_order_q_node: None,
-            }),
+                timer_node: None,
+            })),
         }
     }
 
@@ -210,17 +233,10 @@ impl<K, V> ValueEntry<K, V> {
         #[cfg(feature = "unstable-debug-counters")]
         self::debug_counters::InternalGlobalDebugCounters::value_entry_created();
 
-        let nodes = {
-            let other_nodes = other.nodes.lock();
-            DeqNodes {
-                access_order_q_node: other_nodes.access_order_q_node,
-                write_order_q_node: other_nodes.write_order_q_node,
-            }
-        };
         Self {
             value,
             info: entry_info,
-            nodes: Mutex::new(nodes),
+            nodes: TrioArc::clone(&other.nodes),
         }
     }
 
@@ -245,7 +261,7 @@ impl<K, V> ValueEntry<K, V> {
     }
 
     #[inline]
-    pub(crate) fn policy_weight(&self) -> u32 {
+    pub(crate) fn policy_weight(&self) -> u64 {
         self.info.policy_weight()
     }
 
@@ -253,6 +269,13 @@ impl<K, V> ValueEntry<K, V> {
         self.nodes.lock().access_order_q_node
     }
 
+    /// Returns the cache region (access-order deque) where this entry currently
+    /// lives, or `None` if the entry has no access-order node.
+    pub(crate) fn access_order_q_region(&self) -> Option<CacheRegion> {
+        self.access_order_q_node()
+            .map(|node| CacheRegion::from(node.decompose_tag()))
+    }
+
     pub(crate) fn set_access_order_q_node(&self, node: Option<KeyDeqNodeAo<K>>) {
         self.nodes.lock().access_order_q_node = node;
     }
@@ -273,6 +296,14 @@ impl<K, V> ValueEntry<K, V> {
         self.nodes.lock().write_order_q_node.take()
     }
 
+    pub(crate) fn timer_node(&self) -> Option<TimerDeqNode<K>> {
+        self.nodes.lock().timer_node
+    }
+
+    pub(crate) fn take_timer_node(&self) -> Option<TimerDeqNode<K>> {
+        self.nodes.lock().timer_node.take()
+    }
+
     pub(crate) fn unset_q_nodes(&self) {
         let mut nodes = self.nodes.lock();
         nodes.access_order_q_node = None;
@@ -307,6 +338,45 @@ impl<K, V> AccessTime for TrioArc<ValueEntry<K, V>> {
     fn set_last_modified(&self, timestamp: Instant) {
         self.info.set_last_modified(timestamp);
     }
+
+    #[inline]
+    fn is_ttl_overridden(&self) -> bool {
+        self.info.is_ttl_overridden()
+    }
+}
+
+/// Returns the estimated number of bytes the cache allocates to store an entry, not
+/// including the key and the value themselves.
+///
+/// - `write_order_queue_enabled`: `true` if the entries are also linked to the
+///   write order queue (`time_to_live` or the invalidator is enabled).
+/// - `timer_enabled`: `true` if the entries have timer nodes (`expire_after` is
+///   set). The timer nodes for `insert_with_ttl` are not counted.
+pub(crate) fn entry_overhead<K, V>(write_order_queue_enabled: bool, timer_enabled: bool) -> u64 {
+    use std::mem::size_of;
+
+    // The reference counts of `Arc` (strong and weak) and `TrioArc` (strong only).
+    const ARC_COUNTS: usize = 2 * size_of::<usize>();
+    const TRIO_ARC_COUNT: usize = size_of::<usize>();
+
+    // The bucket of the concurrent hash table and two slots of its bucket array, as
+    // the array is kept at most half full.
+    let cht = size_of::<crate::cht::map::bucket::Bucket<Arc<K>, TrioArc<ValueEntry<K, V>>>>()
+        + 2 * size_of::<usize>();
+    // The value itself is counted by the weigher.
+    let value_entry = TRIO_ARC_COUNT + size_of::<ValueEntry<K, V>>() - size_of::<V>();
+    let entry_info = TRIO_ARC_COUNT + size_of::<EntryInfo>();
+    let deq_nodes = TRIO_ARC_COUNT + size_of::<Mutex<DeqNodes<K>>>();
+    let access_order_node = size_of::<DeqNode<KeyHashDate<K>>>();
+
+    let mut overhead = ARC_COUNTS + cht + value_entry + entry_info + deq_nodes + access_order_node;
+    if write_order_queue_enabled {
+        overhead += size_of::<DeqNode<KeyDate<K>>>();
+    }
+    if timer_enabled {
+        overhead += size_of::<DeqNode<timer_wheel::TimerNode<K>>>();
+    }
+    overhead as u64
 }
 
 pub(crate) enum ReadOp<K, V> {
@@ -319,8 +389,8 @@ pub(crate) enum WriteOp<K, V> {
     Upsert {
         key_hash: KeyHash<K>,
         value_entry: TrioArc<ValueEntry<K, V>>,
-        old_weight: u32,
-        new_weight: u32,
+        old_weight: u64,
+        new_weight: u64,
     },
     Remove(KvEntry<K, V>),
 }
@@ -52,4 +52,8 @@ impl AtomicInstant {
         let ts = unsafe { std::mem::transmute(instant.inner_clock()) };
         self.instant.store(ts, Ordering::Release);
     }
+
+    pub(crate) fn clear(&self) {
+        self.instant.store(u64::MAX, Ordering::Release);
+    }
 }
@@ -32,4 +32,8 @@ impl AtomicInstant {
     pub(crate) fn set_instant(&self, instant: Instant) {
         *self.instant.write() = Some(instant);
     }
+
+    pub(crate) fn clear(&self) {
+        *self.instant.write() = None;
+    }
 }
@@ -2,6 +2,7 @@ pub(crate) const MAX_SYNC_REPEATS: usize = 4;
 pub(crate) const PERIODICAL_SYNC_INITIAL_DELAY_MILLIS: u64 = 500;
 pub(crate) const PERIODICAL_SYNC_NORMAL_PACE_MILLIS: u64 = 300;
 pub(crate) const PERIODICAL_SYNC_FAST_PACE_NANOS: u64 = 500;
+pub(crate) const EXPIRATION_SYNC_MIN_DELAY_MILLIS: u64 = 10;
 
 pub(crate) const READ_LOG_FLUSH_POINT: usize = 512;
 pub(crate) const READ_LOG_SIZE: usize = READ_LOG_FLUSH_POINT * (MAX_SYNC_REPEATS + 2);
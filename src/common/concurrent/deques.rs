@@ -1,4 +1,4 @@
-use super::{KeyDate, KeyHashDate, ValueEntry};
+use super::{timer_wheel::TimerWheel, KeyDate, KeyHashDate, ValueEntry};
 use crate::common::{
     deque::{DeqNode, Deque},
     CacheRegion,
@@ -8,10 +8,13 @@ use std::ptr::NonNull;
 use tagptr::TagNonNull;
 use triomphe::Arc as TrioArc;
 pub(crate) struct Deques<K> {
-    pub(crate) window: Deque<KeyHashDate<K>>, //    Not used yet.
+    pub(crate) window: Deque<KeyHashDate<K>>,
     pub(crate) probation: Deque<KeyHashDate<K>>,
-    pub(crate) protected: Deque<KeyHashDate<K>>, // Not used yet.
+    pub(crate) protected: Deque<KeyHashDate<K>>,
     pub(crate) write_order: Deque<KeyDate<K>>,
+    /// The timers for the per-entry expiration. Enabled only when the cache has an
+    /// `Expiry`.
+    pub(crate) timer_wheel: TimerWheel<K>,
 }
 
 #[cfg(feature = "future")]
@@ -29,6 +32,7 @@ impl<K> Default for Deques<K> {
             probation: Deque::new(CacheRegion::MainProbation),
             protected: Deque::new(CacheRegion::MainProtected),
             write_order: Deque::new(CacheRegion::Other),
+            timer_wheel: Default::default(),
         }
     }
 }
@@ -76,6 +80,44 @@ impl<K> Deques<K> {
         }
     }
 
+    /// Moves the access-order node of the entry from its current deque to the back
+    /// of the deque for the given `region`. Returns `false` if the entry does not
+    /// have a node in any of the access-order deques.
+    pub(crate) fn move_to_region_ao<V>(
+        &mut self,
+        region: CacheRegion,
+        entry: &TrioArc<ValueEntry<K, V>>,
+    ) -> bool {
+        if let Some(tagged_node) = entry.access_order_q_node() {
+            let (node, tag) = tagged_node.decompose();
+            let from = self.ao_deque_mut(tag.into());
+            if !from.contains(unsafe { node.as_ref() }) {
+                return false;
+            }
+            // Unlink the node without dropping it, and then push it to the back of
+            // the other deque.
+            let node = unsafe {
+                from.unlink(node);
+                Box::from_raw(node.as_ptr())
+            };
+            let node = self.ao_deque_mut(region).push_back(node);
+            let tagged_node = TagNonNull::compose(node, region as usize);
+            entry.set_access_order_q_node(Some(tagged_node));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn ao_deque_mut(&mut self, region: CacheRegion) -> &mut Deque<KeyHashDate<K>> {
+        match region {
+            CacheRegion::Window => &mut self.window,
+            CacheRegion::MainProbation => &mut self.probation,
+            CacheRegion::MainProtected => &mut self.protected,
+            CacheRegion::Other => unreachable!(),
+        }
+    }
+
     pub(crate) fn move_to_back_ao_in_deque<V>(
         deq_name: &str,
         deq: &mut Deque<KeyHashDate<K>>,
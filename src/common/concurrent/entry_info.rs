@@ -1,4 +1,5 @@
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use crossbeam_utils::atomic::AtomicCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use super::AccessTime;
 use crate::common::{concurrent::atomic_time::AtomicInstant, time::Instant};
@@ -14,12 +15,19 @@ pub(crate) struct EntryInfo {
     is_dirty: AtomicBool,
     last_accessed: AtomicInstant,
     last_modified: AtomicInstant,
-    policy_weight: AtomicU32,
+    /// `is_ttl_overridden` indicates that the expiration time was given by the
+    /// `insert_with_ttl` family methods, so the `time_to_live` of the cache does
+    /// not apply to the entry.
+    is_ttl_overridden: AtomicBool,
+    /// The deadline calculated by the `Expiry` of the cache, or given by the
+    /// `insert_with_ttl` family methods, if any.
+    expiration_time: AtomicInstant,
+    policy_weight: AtomicCell<u64>,
 }
 
 impl EntryInfo {
     #[inline]
-    pub(crate) fn new(timestamp: Instant, policy_weight: u32) -> Self {
+    pub(crate) fn new(timestamp: Instant, policy_weight: u64) -> Self {
         #[cfg(feature = "unstable-debug-counters")]
         super::debug_counters::InternalGlobalDebugCounters::entry_info_created();
 
@@ -28,7 +36,9 @@ impl EntryInfo {
             is_dirty: AtomicBool::new(true),
             last_accessed: AtomicInstant::new(timestamp),
             last_modified: AtomicInstant::new(timestamp),
-            policy_weight: AtomicU32::new(policy_weight),
+            is_ttl_overridden: Default::default(),
+            expiration_time: Default::default(),
+            policy_weight: AtomicCell::new(policy_weight),
         }
     }
 
@@ -53,12 +63,35 @@ impl EntryInfo {
     }
 
     #[inline]
-    pub(crate) fn policy_weight(&self) -> u32 {
-        self.policy_weight.load(Ordering::Acquire)
+    pub(crate) fn policy_weight(&self) -> u64 {
+        self.policy_weight.load()
     }
 
-    pub(crate) fn set_policy_weight(&self, size: u32) {
-        self.policy_weight.store(size, Ordering::Release);
+    pub(crate) fn set_policy_weight(&self, size: u64) {
+        self.policy_weight.store(size);
+    }
+
+    #[inline]
+    pub(crate) fn is_ttl_overridden(&self) -> bool {
+        self.is_ttl_overridden.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_ttl_overridden(&self, value: bool) {
+        self.is_ttl_overridden.store(value, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn expiration_time(&self) -> Option<Instant> {
+        self.expiration_time.instant()
+    }
+
+    pub(crate) fn set_expiration_time(&self, time: Option<Instant>) {
+        if let Some(time) = time {
+            self.expiration_time.set_instant(time);
+        } else {
+            self.expiration_time.clear();
+        }
     }
 }
 
@@ -89,6 +122,11 @@ impl AccessTime for EntryInfo {
     fn set_last_modified(&self, timestamp: Instant) {
         self.last_modified.set_instant(timestamp);
     }
+
+    #[inline]
+    fn is_ttl_overridden(&self) -> bool {
+        self.is_ttl_overridden()
+    }
 }
 
 #[cfg(test)]
@@ -135,12 +173,12 @@ mod test {
         };
 
         let expected_sizes = match (arch, is_quanta_enabled) {
-            (Linux64, true) => vec![("1.51", 24)],
-            (Linux32, true) => vec![("1.51", 24)],
-            (MacOS64, true) => vec![("1.62", 24)],
-            (Linux64, false) => vec![("1.66", 56), ("1.51", 72)],
-            (Linux32, false) => vec![("1.66", 56), ("1.62", 72), ("1.51", 40)],
-            (MacOS64, false) => vec![("1.62", 56)],
+            (Linux64, true) => vec![("1.51", 32)],
+            (Linux32, true) => vec![("1.51", 32)],
+            (MacOS64, true) => vec![("1.62", 32)],
+            (Linux64, false) => vec![("1.66", 80), ("1.51", 104)],
+            (Linux32, false) => vec![("1.66", 80), ("1.62", 104), ("1.51", 56)],
+            (MacOS64, false) => vec![("1.62", 80)],
         };
 
         let mut expected = None;
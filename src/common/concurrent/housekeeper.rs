@@ -1,11 +1,13 @@
 use super::constants::{
-    MAX_SYNC_REPEATS, PERIODICAL_SYNC_FAST_PACE_NANOS, PERIODICAL_SYNC_INITIAL_DELAY_MILLIS,
-    PERIODICAL_SYNC_NORMAL_PACE_MILLIS,
+    EXPIRATION_SYNC_MIN_DELAY_MILLIS, MAX_SYNC_REPEATS, PERIODICAL_SYNC_FAST_PACE_NANOS,
+    PERIODICAL_SYNC_INITIAL_DELAY_MILLIS, PERIODICAL_SYNC_NORMAL_PACE_MILLIS,
 };
 use super::{
-    thread_pool::{ThreadPool, ThreadPoolRegistry},
+    task_queue::TaskQueue,
+    thread_pool::{PoolExecutor, PoolName},
     unsafe_weak_pointer::UnsafeWeakPointer,
 };
+use crate::executor::{Executor, ExecutorArc};
 
 #[cfg(any(feature = "sync", feature = "future"))]
 use super::atomic_time::AtomicInstant;
@@ -17,7 +19,6 @@ use crate::common::time::{CheckedTimeOps, Instant};
 use super::constants::{READ_LOG_FLUSH_POINT, WRITE_LOG_FLUSH_POINT};
 
 use parking_lot::Mutex;
-use scheduled_thread_pool::JobHandle;
 use std::{
     marker::PhantomData,
     sync::{
@@ -32,29 +33,81 @@ pub(crate) trait InnerSync {
 
     #[cfg(any(feature = "sync", feature = "future"))]
     fn now(&self) -> Instant;
+
+    /// Returns the earliest time when the housekeeping will have an entry to
+    /// expire, or `None` if no entry will expire.
+    #[cfg(any(feature = "sync", feature = "future"))]
+    fn next_expiration(&self) -> Option<Instant>;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub(crate) struct Configuration {
     is_blocking: bool,
     periodical_sync_enabled: bool,
+    /// The executor given via the cache builder. If `None`, the thread pools
+    /// in the `ThreadPoolRegistry` are used.
+    executor: Option<ExecutorArc>,
+    /// The queue of the background tasks to be run by the client threads, when
+    /// the cache has no background threads.
+    task_queue: Option<Arc<TaskQueue>>,
+    /// If true, a sync is scheduled at the next expiration time of the cache.
+    /// (Only for the thread pool housekeeper)
+    expiration_scheduling_enabled: bool,
 }
 
 impl Configuration {
     #[cfg(any(feature = "sync", feature = "future"))]
-    pub(crate) fn new_blocking() -> Self {
+    pub(crate) fn new_blocking(executor: Option<ExecutorArc>) -> Self {
         Self {
             is_blocking: true,
             periodical_sync_enabled: false,
+            executor,
+            task_queue: None,
+            expiration_scheduling_enabled: false,
         }
     }
 
-    pub(crate) fn new_thread_pool(periodical_sync_enabled: bool) -> Self {
+    /// Creates a configuration for a cache without any background threads. The
+    /// housekeeping is done by the client threads, and the other background tasks
+    /// are queued in a `TaskQueue` to be run by them too.
+    pub(crate) fn new_blocking_with_task_queue() -> Self {
+        let task_queue = Arc::new(TaskQueue::default());
+        Self {
+            is_blocking: true,
+            periodical_sync_enabled: false,
+            executor: Some(Arc::clone(&task_queue) as ExecutorArc),
+            task_queue: Some(task_queue),
+            expiration_scheduling_enabled: false,
+        }
+    }
+
+    pub(crate) fn new_thread_pool(
+        periodical_sync_enabled: bool,
+        executor: Option<ExecutorArc>,
+    ) -> Self {
         Self {
             is_blocking: false,
             periodical_sync_enabled,
+            executor,
+            task_queue: None,
+            expiration_scheduling_enabled: false,
         }
     }
+
+    pub(crate) fn with_expiration_scheduling(mut self, enabled: bool) -> Self {
+        self.expiration_scheduling_enabled = enabled;
+        self
+    }
+
+    pub(crate) fn executor(&self) -> Option<&ExecutorArc> {
+        self.executor.as_ref()
+    }
+
+    /// Returns `true` if the executor is a `TaskQueue`, which runs the tasks only
+    /// when the client threads run the pending tasks.
+    pub(crate) fn has_task_queue(&self) -> bool {
+        self.task_queue.is_some()
+    }
 }
 
 pub(crate) enum Housekeeper<T> {
@@ -68,11 +121,13 @@ where
 {
     pub(crate) fn new(inner: Weak<T>, config: Configuration) -> Self {
         if config.is_blocking {
-            Housekeeper::Blocking(BlockingHousekeeper::default())
+            Housekeeper::Blocking(BlockingHousekeeper::new(config.task_queue))
         } else {
             Housekeeper::ThreadPool(ThreadPoolHousekeeper::new(
                 inner,
                 config.periodical_sync_enabled,
+                config.executor,
+                config.expiration_scheduling_enabled,
             ))
         }
     }
@@ -93,6 +148,15 @@ where
         }
     }
 
+    /// Returns the queue of the background tasks if the cache has no background
+    /// threads.
+    pub(crate) fn task_queue(&self) -> Option<&Arc<TaskQueue>> {
+        match self {
+            Housekeeper::Blocking(h) => h.task_queue.as_ref(),
+            Housekeeper::ThreadPool(_) => None,
+        }
+    }
+
     pub(crate) fn try_sync(&self, cache: &impl InnerSync) -> bool {
         match self {
             Housekeeper::Blocking(h) => h.try_sync(cache),
@@ -113,19 +177,19 @@ pub(crate) struct BlockingHousekeeper {
     is_sync_running: AtomicBool,
     #[cfg(any(feature = "sync", feature = "future"))]
     sync_after: AtomicInstant,
+    task_queue: Option<Arc<TaskQueue>>,
 }
 
-impl Default for BlockingHousekeeper {
-    fn default() -> Self {
+impl BlockingHousekeeper {
+    fn new(task_queue: Option<Arc<TaskQueue>>) -> Self {
         Self {
             is_sync_running: Default::default(),
             #[cfg(any(feature = "sync", feature = "future"))]
             sync_after: AtomicInstant::new(Self::sync_after(Instant::now())),
+            task_queue,
         }
     }
-}
 
-impl BlockingHousekeeper {
     #[cfg(any(feature = "sync", feature = "future"))]
     fn should_apply_reads(&self, ch_len: usize, now: Instant) -> bool {
         self.should_apply(ch_len, READ_LOG_FLUSH_POINT / 8, now)
@@ -160,6 +224,12 @@ impl BlockingHousekeeper {
                 cache.sync(MAX_SYNC_REPEATS);
 
                 self.is_sync_running.store(false, Ordering::Release);
+
+                // Run the background tasks submitted by the sync, such as the
+                // invalidation and the notification tasks.
+                if let Some(queue) = &self.task_queue {
+                    queue.run_pending_tasks();
+                }
                 true
             }
             Err(_) => false,
@@ -194,11 +264,13 @@ impl SyncPace {
 
 pub(crate) struct ThreadPoolHousekeeper<T> {
     inner: Arc<Mutex<UnsafeWeakPointer<T>>>,
-    thread_pool: Arc<ThreadPool>,
+    executor: ExecutorArc,
     is_shutting_down: Arc<AtomicBool>,
-    periodical_sync_job: Mutex<Option<JobHandle>>,
+    #[cfg(test)]
+    periodical_sync_stopped: Arc<AtomicBool>,
     periodical_sync_running: Arc<Mutex<()>>,
     on_demand_sync_scheduled: Arc<AtomicBool>,
+    expiration_sync: Option<Arc<ExpirationSyncJob<T>>>,
     _marker: PhantomData<T>,
 }
 
@@ -207,12 +279,6 @@ impl<T> Drop for ThreadPoolHousekeeper<T> {
         // Disallow to create and/or run sync jobs by now.
         self.is_shutting_down.store(true, Ordering::Release);
 
-        // Cancel the periodical sync job. (This will not abort the job if it is
-        // already running)
-        if let Some(j) = self.periodical_sync_job.lock().take() {
-            j.cancel()
-        }
-
         // Wait for the periodical sync job to finish.
         //
         // NOTE: As suggested by Clippy 1.59, drop the lock explicitly rather
@@ -220,13 +286,13 @@ impl<T> Drop for ThreadPoolHousekeeper<T> {
         // https://rust-lang.github.io/rust-clippy/master/index.html#let_underscore_lock
         std::mem::drop(self.periodical_sync_running.lock());
 
-        // Wait for the on-demand sync job to finish. (busy loop)
-        while self.on_demand_sync_scheduled.load(Ordering::Acquire) {
-            std::thread::sleep(Duration::from_millis(1));
-        }
-
-        // All sync jobs should have been finished by now. Clean other stuff up.
-        ThreadPoolRegistry::release_pool(&self.thread_pool);
+        // Wait for the running on-demand sync job (if any) to finish by acquiring
+        // the lock of the pointer. We do not wait for the scheduled jobs, as the
+        // executor may run them late or drop them without running them. They
+        // will see the shutdown flag under the lock and return without touching
+        // the pointer.
+        //
+        // (The executor will be dropped after this method returns)
         std::mem::drop(unsafe { self.inner.lock().as_weak_arc() });
     }
 }
@@ -236,67 +302,56 @@ impl<T> ThreadPoolHousekeeper<T>
 where
     T: InnerSync + 'static,
 {
-    fn new(inner: Weak<T>, periodical_sync_enable: bool) -> Self {
-        use super::thread_pool::PoolName;
-
-        let thread_pool = ThreadPoolRegistry::acquire_pool(PoolName::Housekeeper);
+    fn new(
+        inner: Weak<T>,
+        periodical_sync_enable: bool,
+        executor: Option<ExecutorArc>,
+        expiration_scheduling_enabled: bool,
+    ) -> Self {
+        let executor = PoolExecutor::or_acquire(executor, PoolName::Housekeeper);
         let inner_ptr = Arc::new(Mutex::new(UnsafeWeakPointer::from_weak_arc(inner)));
         let is_shutting_down = Arc::new(AtomicBool::new(false));
+        let periodical_sync_stopped = Arc::new(AtomicBool::new(false));
         let periodical_sync_running = Arc::new(Mutex::new(()));
 
-        let maybe_sync_job = if periodical_sync_enable {
-            Some(Self::start_periodical_sync_job(
-                &thread_pool,
-                Arc::clone(&inner_ptr),
-                Arc::clone(&is_shutting_down),
-                Arc::clone(&periodical_sync_running),
-            ))
+        let expiration_sync = if expiration_scheduling_enabled {
+            Some(Arc::new(ExpirationSyncJob {
+                executor: Arc::downgrade(&executor),
+                unsafe_weak_ptr: Arc::clone(&inner_ptr),
+                is_shutting_down: Arc::clone(&is_shutting_down),
+                is_running: Arc::clone(&periodical_sync_running),
+                scheduled_at: Mutex::new(None),
+            }))
         } else {
             None
         };
 
+        if periodical_sync_enable {
+            let job = PeriodicalSyncJob {
+                executor: Arc::downgrade(&executor),
+                unsafe_weak_ptr: Arc::clone(&inner_ptr),
+                is_shutting_down: Arc::clone(&is_shutting_down),
+                is_stopped: Arc::clone(&periodical_sync_stopped),
+                is_running: Arc::clone(&periodical_sync_running),
+                expiration_sync: expiration_sync.clone(),
+            };
+            let initial_delay = Duration::from_millis(PERIODICAL_SYNC_INITIAL_DELAY_MILLIS);
+            job.schedule(&*executor, initial_delay);
+        }
+
         Self {
             inner: inner_ptr,
-            thread_pool,
+            executor,
             is_shutting_down,
-            periodical_sync_job: Mutex::new(maybe_sync_job),
+            #[cfg(test)]
+            periodical_sync_stopped,
             periodical_sync_running,
             on_demand_sync_scheduled: Arc::new(AtomicBool::new(false)),
+            expiration_sync,
             _marker: PhantomData::default(),
         }
     }
 
-    fn start_periodical_sync_job(
-        thread_pool: &Arc<ThreadPool>,
-        unsafe_weak_ptr: Arc<Mutex<UnsafeWeakPointer<T>>>,
-        is_shutting_down: Arc<AtomicBool>,
-        periodical_sync_running: Arc<Mutex<()>>,
-    ) -> JobHandle {
-        let mut sync_pace = SyncPace::Normal;
-
-        let housekeeper_closure = {
-            move || {
-                if !is_shutting_down.load(Ordering::Acquire) {
-                    let _lock = periodical_sync_running.lock();
-                    if let Some(new_pace) = Self::call_sync(&unsafe_weak_ptr) {
-                        if sync_pace != new_pace {
-                            sync_pace = new_pace
-                        }
-                    }
-                }
-
-                Some(sync_pace.make_duration())
-            }
-        };
-
-        let initial_delay = Duration::from_millis(PERIODICAL_SYNC_INITIAL_DELAY_MILLIS);
-
-        // Execute a task in a worker thread.
-        thread_pool
-            .pool
-            .execute_with_dynamic_delay(initial_delay, housekeeper_closure)
-    }
-
     #[cfg(any(feature = "sync", feature = "future"))]
     fn should_apply_reads(&self, ch_len: usize, _now: Instant) -> bool {
         ch_len >= READ_LOG_FLUSH_POINT
@@ -322,12 +377,18 @@ where
         ) {
             Ok(_) => {
                 let unsafe_weak_ptr = Arc::clone(&self.inner);
+                let is_shutting_down = Arc::clone(&self.is_shutting_down);
                 let sync_scheduled = Arc::clone(&self.on_demand_sync_scheduled);
+                let expiration_sync = self.expiration_sync.clone();
                 // Execute a task in a worker thread.
-                self.thread_pool.pool.execute(move || {
-                    Self::call_sync(&unsafe_weak_ptr);
+                self.executor.execute(Box::new(move || {
+                    Self::call_sync(
+                        &unsafe_weak_ptr,
+                        &is_shutting_down,
+                        expiration_sync.as_ref(),
+                    );
                     sync_scheduled.store(false, Ordering::Release);
-                });
+                }));
                 true
             }
             Err(_) => false,
@@ -336,20 +397,38 @@ where
 
     #[cfg(test)]
     pub(crate) fn stop_periodical_sync_job(&self) {
-        if let Some(j) = self.periodical_sync_job.lock().take() {
-            j.cancel();
-        }
+        self.periodical_sync_stopped.store(true, Ordering::Release);
+        // Wait for the periodical sync job to finish if it is running.
+        std::mem::drop(self.periodical_sync_running.lock());
     }
 }
 
-impl<T: InnerSync> ThreadPoolHousekeeper<T> {
-    fn call_sync(unsafe_weak_ptr: &Arc<Mutex<UnsafeWeakPointer<T>>>) -> Option<SyncPace> {
+impl<T> ThreadPoolHousekeeper<T>
+where
+    T: InnerSync + 'static,
+{
+    /// Calls `sync` on the `Inner`. If `expiration_sync` is given, also schedules
+    /// a sync at the next expiration time.
+    ///
+    /// Does nothing if the housekeeper is shutting down, as the pointer may have
+    /// been released by `Drop`.
+    fn call_sync(
+        unsafe_weak_ptr: &Arc<Mutex<UnsafeWeakPointer<T>>>,
+        is_shutting_down: &AtomicBool,
+        expiration_sync: Option<&Arc<ExpirationSyncJob<T>>>,
+    ) -> Option<SyncPace> {
         let lock = unsafe_weak_ptr.lock();
+        if is_shutting_down.load(Ordering::Acquire) {
+            return None;
+        }
         // Restore the Weak pointer to Inner<K, V, S>.
         let weak = unsafe { lock.as_weak_arc() };
         if let Some(inner) = weak.upgrade() {
             // TODO: Protect this call with catch_unwind().
             let sync_pace = inner.sync(MAX_SYNC_REPEATS);
+            if let Some(job) = expiration_sync {
+                job.schedule(&*inner);
+            }
             // Avoid to drop the Arc<Inner<K, V, S>>.
             UnsafeWeakPointer::forget_arc(inner);
             sync_pace
@@ -360,3 +439,119 @@ impl<T: InnerSync> ThreadPoolHousekeeper<T> {
         }
     }
 }
+
+/// The periodical sync job. Each run schedules the next run on the executor with
+/// the delay for the current `SyncPace`, until the housekeeper is dropped or the
+/// job is stopped.
+///
+/// The job holds only a `Weak` reference to the executor, so that a scheduled
+/// run does not keep the executor (and its thread pool) alive after the
+/// housekeeper is dropped.
+struct PeriodicalSyncJob<T> {
+    executor: Weak<dyn Executor>,
+    unsafe_weak_ptr: Arc<Mutex<UnsafeWeakPointer<T>>>,
+    is_shutting_down: Arc<AtomicBool>,
+    is_stopped: Arc<AtomicBool>,
+    is_running: Arc<Mutex<()>>,
+    expiration_sync: Option<Arc<ExpirationSyncJob<T>>>,
+}
+
+impl<T> PeriodicalSyncJob<T>
+where
+    T: InnerSync + 'static,
+{
+    fn schedule(self, executor: &dyn Executor, delay: Duration) {
+        executor.execute_after(delay, Box::new(move || self.run()));
+    }
+
+    fn run(self) {
+        let sync_pace = {
+            let _lock = self.is_running.lock();
+            if self.is_shutting_down.load(Ordering::Acquire)
+                || self.is_stopped.load(Ordering::Acquire)
+            {
+                return;
+            }
+            ThreadPoolHousekeeper::call_sync(
+                &self.unsafe_weak_ptr,
+                &self.is_shutting_down,
+                self.expiration_sync.as_ref(),
+            )
+            .unwrap_or(SyncPace::Normal)
+        };
+
+        if let Some(executor) = self.executor.upgrade() {
+            self.schedule(&*executor, sync_pace.make_duration());
+        }
+    }
+}
+
+/// The sync job scheduled at the next expiration time of the cache, so that the
+/// expired entries are removed (and the eviction listener is notified) close to
+/// their expiration time even when the cache is idle.
+///
+/// After every sync, the job is scheduled again if the next expiration time is
+/// earlier than the time of the already scheduled run.
+struct ExpirationSyncJob<T> {
+    executor: Weak<dyn Executor>,
+    unsafe_weak_ptr: Arc<Mutex<UnsafeWeakPointer<T>>>,
+    is_shutting_down: Arc<AtomicBool>,
+    /// Shared with the periodical sync job.
+    is_running: Arc<Mutex<()>>,
+    /// The time of the earliest scheduled run.
+    scheduled_at: Mutex<Option<Instant>>,
+}
+
+impl<T> ExpirationSyncJob<T>
+where
+    T: InnerSync + 'static,
+{
+    fn schedule(self: &Arc<Self>, inner: &T) {
+        let next = match inner.next_expiration() {
+            Some(next) => next,
+            None => return,
+        };
+
+        // Do not run the job too often when some entries have already expired
+        // but could not be removed by the last sync.
+        let now = inner.now();
+        let min_delay = Duration::from_millis(EXPIRATION_SYNC_MIN_DELAY_MILLIS);
+        let run_at = match now.checked_add(min_delay) {
+            Some(earliest) if earliest > next => earliest,
+            _ => next,
+        };
+
+        let mut scheduled_at = self.scheduled_at.lock();
+        // A run is already scheduled before `run_at`. (If the scheduled time has
+        // passed, the run may have been lost, so schedule a new one)
+        if matches!(*scheduled_at, Some(at) if at <= run_at && at > now) {
+            return;
+        }
+
+        if let Some(executor) = self.executor.upgrade() {
+            *scheduled_at = Some(run_at);
+            let delay = run_at.saturating_duration_since(now);
+            let job = Arc::clone(self);
+            executor.execute_after(delay, Box::new(move || job.run(run_at)));
+        }
+    }
+
+    fn run(self: Arc<Self>, run_at: Instant) {
+        {
+            let mut scheduled_at = self.scheduled_at.lock();
+            if matches!(*scheduled_at, Some(at) if at == run_at) {
+                *scheduled_at = None;
+            }
+        }
+
+        let _lock = self.is_running.lock();
+        if self.is_shutting_down.load(Ordering::Acquire) {
+            return;
+        }
+        ThreadPoolHousekeeper::call_sync(
+            &self.unsafe_weak_ptr,
+            &self.is_shutting_down,
+            Some(&self),
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::executor::{Executor, Task};
+
+use parking_lot::Mutex;
+use std::{collections::VecDeque, time::Duration};
+
+/// An `Executor` that does not run the tasks by itself but queues them, so that
+/// the client threads (or async tasks) of a cache can run them during the
+/// housekeeping. Used when a cache is configured not to have any background
+/// threads.
+#[derive(Default)]
+pub(crate) struct TaskQueue {
+    tasks: Mutex<VecDeque<Task>>,
+}
+
+impl TaskQueue {
+    /// Runs the queued tasks, including the ones queued while running them, on
+    /// the current thread. Returns `true` if any task has been run.
+    pub(crate) fn run_pending_tasks(&self) -> bool {
+        let mut has_run = false;
+        // Do not hold the lock while running a task, as the task may queue
+        // another task.
+        while let Some(task) = self.pop() {
+            task();
+            has_run = true;
+        }
+        has_run
+    }
+
+    fn pop(&self) -> Option<Task> {
+        self.tasks.lock().pop_front()
+    }
+}
+
+impl Executor for TaskQueue {
+    fn execute(&self, task: Task) {
+        self.tasks.lock().push_back(task);
+    }
+
+    /// Queues the task without the delay. The task will run when the queued tasks
+    /// are run next time.
+    fn execute_after(&self, _delay: Duration, task: Task) {
+        self.execute(task);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskQueue;
+    use crate::executor::Executor;
+
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn run_pending_tasks() {
+        let queue = Arc::new(TaskQueue::default());
+        let count = Arc::new(AtomicUsize::new(0));
+
+        assert!(!queue.run_pending_tasks());
+
+        let (q, c) = (Arc::clone(&queue), Arc::clone(&count));
+        queue.execute(Box::new(move || {
+            c.fetch_add(1, Ordering::AcqRel);
+            // A task queued by a running task should run in the same call.
+            let c1 = Arc::clone(&c);
+            q.execute(Box::new(move || {
+                c1.fetch_add(10, Ordering::AcqRel);
+            }));
+        }));
+        // Nothing runs until the tasks are run by the client.
+        assert_eq!(count.load(Ordering::Acquire), 0);
+
+        assert!(queue.run_pending_tasks());
+        assert_eq!(count.load(Ordering::Acquire), 11);
+        assert!(!queue.run_pending_tasks());
+    }
+}
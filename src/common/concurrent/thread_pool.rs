@@ -1,7 +1,9 @@
+use crate::executor::{Executor, ExecutorArc, Task};
+
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use scheduled_thread_pool::ScheduledThreadPool;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 static REGISTRY: Lazy<ThreadPoolRegistry> = Lazy::new(ThreadPoolRegistry::default);
 
@@ -13,6 +15,8 @@ pub(crate) enum PoolName {
     Invalidator,
     #[cfg(any(feature = "sync", feature = "future"))]
     RemovalNotifier,
+    #[cfg(feature = "sync")]
+    Refresher,
 }
 
 impl PoolName {
@@ -23,6 +27,8 @@ impl PoolName {
             PoolName::Invalidator => "moka-invalidator-{}",
             #[cfg(any(feature = "sync", feature = "future"))]
             PoolName::RemovalNotifier => "moka-notifier-{}",
+            #[cfg(feature = "sync")]
+            PoolName::Refresher => "moka-refresher-{}",
         }
     }
 }
@@ -44,6 +50,40 @@ impl ThreadPool {
     }
 }
 
+/// The default `Executor`, which runs the tasks in a thread pool of the
+/// `ThreadPoolRegistry`. The pool is released when the executor is dropped.
+pub(crate) struct PoolExecutor {
+    thread_pool: Arc<ThreadPool>,
+}
+
+impl Drop for PoolExecutor {
+    fn drop(&mut self) {
+        ThreadPoolRegistry::release_pool(&self.thread_pool);
+    }
+}
+
+impl PoolExecutor {
+    /// Returns the given executor, or acquires the pool for `name` from the
+    /// registry if it is `None`.
+    pub(crate) fn or_acquire(executor: Option<ExecutorArc>, name: PoolName) -> ExecutorArc {
+        executor.unwrap_or_else(|| {
+            Arc::new(Self {
+                thread_pool: ThreadPoolRegistry::acquire_pool(name),
+            })
+        })
+    }
+}
+
+impl Executor for PoolExecutor {
+    fn execute(&self, task: Task) {
+        self.thread_pool.pool.execute(task);
+    }
+
+    fn execute_after(&self, delay: Duration, task: Task) {
+        self.thread_pool.pool.execute_after(delay, task);
+    }
+}
+
 pub(crate) struct ThreadPoolRegistry {
     pools: RwLock<HashMap<PoolName, Arc<ThreadPool>>>,
 }
@@ -0,0 +1,483 @@
+// A hierarchical timer wheel for the per-entry (variable) expiration.
+//
+// The design is based on the following paper and the implementation in Caffeine:
+//
+// - "Hashed and Hierarchical Timing Wheels: Data Structures for the Efficient
+//   Implementation of a Timer Facility" by George Varghese and Tony Lauck.
+//   http://www.cs.columbia.edu/~nahum/w6998/papers/ton97-timing-wheels.pdf
+// - https://github.com/ben-manes/caffeine/blob/master/caffeine/src/main/java/com/github/benmanes/caffeine/cache/TimerWheel.java
+//
+// The wheel consists of several levels of buckets. Each level covers a longer
+// span of time with coarser buckets than the previous one. An entry is placed in
+// the bucket that covers its expiration time. When the wheel is advanced, the
+// buckets whose time has passed are visited; the expired entries are moved to the
+// expired queue, and the others are cascaded down to the finer buckets. Both
+// scheduling and expiring an entry take O(1) time.
+
+use super::{entry_info::EntryInfo, KeyHash, ValueEntry};
+use crate::common::{
+    deque::{DeqNode, Deque},
+    time::{CheckedTimeOps, Instant},
+    CacheRegion,
+};
+
+use std::{convert::TryFrom, ptr::NonNull, time::Duration};
+use triomphe::Arc as TrioArc;
+
+/// The numbers of buckets in the levels.
+const BUCKET_COUNTS: [u64; 5] = [
+    64, // roughly a second per bucket (2^30 nanos), 1.14 minutes in total.
+    64, // roughly a minute per bucket (2^36 nanos), 1.22 hours in total.
+    32, // roughly an hour per bucket (2^42 nanos), 1.63 days in total.
+    4,  // roughly a day per bucket (2^47 nanos), 6.5 days in total.
+    1,  // the overflow bucket.
+];
+
+/// The spans of a bucket in the levels in nanoseconds. The last element is the
+/// upper bound of the level before the overflow bucket.
+const SPANS: [u64; 6] = [
+    1 << 30, // 1.07 seconds
+    1 << 36, // 1.14 minutes
+    1 << 42, // 1.22 hours
+    1 << 47, // 1.63 days
+    1 << 49, // 6.51 days (BUCKET_COUNTS[3] * SPANS[3])
+    1 << 49,
+];
+
+/// The shift amounts to convert nanoseconds to the ticks of the levels.
+const SHIFTS: [u32; 5] = [30, 36, 42, 47, 49];
+
+/// The level used for the nodes in the expired queue.
+const EXPIRED_LEVEL: u8 = u8::MAX;
+
+// DeqNode for the timer wheel.
+pub(crate) type TimerDeqNode<K> = NonNull<DeqNode<TimerNode<K>>>;
+
+// A bucket of the timer wheel.
+type Bucket<K> = Deque<TimerNode<K>>;
+
+pub(crate) struct TimerNode<K> {
+    level: u8,
+    index: u8,
+    key_hash: KeyHash<K>,
+    entry_info: TrioArc<EntryInfo>,
+    deq_nodes: TrioArc<parking_lot::Mutex<super::DeqNodes<K>>>,
+}
+
+impl<K> TimerNode<K> {
+    fn expiration_time(&self) -> Option<Instant> {
+        self.entry_info.expiration_time()
+    }
+}
+
+pub(crate) struct TimerWheel<K> {
+    /// The buckets of the levels. Empty until the wheel is enabled.
+    wheels: Box<[Box<[Bucket<K>]>]>,
+    /// The nodes whose expiration time has passed. They will be removed by
+    /// `pop_expired`.
+    expired: Bucket<K>,
+    /// The time when the wheel was enabled.
+    origin: Option<Instant>,
+    /// The time of the last advance, in nanoseconds since the origin.
+    current: u64,
+}
+
+impl<K> Default for TimerWheel<K> {
+    fn default() -> Self {
+        Self {
+            wheels: Default::default(),
+            expired: Deque::new(CacheRegion::Other),
+            origin: None,
+            current: 0,
+        }
+    }
+}
+
+impl<K> TimerWheel<K> {
+    #[inline]
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    /// Allocates the buckets. Does nothing if the wheel has been already enabled.
+    pub(crate) fn enable(&mut self, now: Instant) {
+        if self.is_enabled() {
+            return;
+        }
+
+        self.wheels = BUCKET_COUNTS
+            .iter()
+            .map(|&count| {
+                (0..count)
+                    .map(|_| Deque::new(CacheRegion::Other))
+                    .collect::<Vec<_>>()
+                    .into_boxed_slice()
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        self.origin = Some(now);
+        self.current = 0;
+    }
+
+    /// Schedules a timer for the entry, or reschedules it if the entry already has
+    /// one. If the entry has no expiration time, its timer (if any) is removed.
+    pub(crate) fn schedule<V>(&mut self, key_hash: KeyHash<K>, entry: &ValueEntry<K, V>) {
+        debug_assert!(self.is_enabled());
+
+        if entry.timer_node().is_some() {
+            self.reschedule(entry);
+            return;
+        }
+
+        if let Some(time) = entry.entry_info().expiration_time() {
+            let node = TimerNode {
+                level: 0,
+                index: 0,
+                key_hash,
+                entry_info: TrioArc::clone(entry.entry_info()),
+                deq_nodes: TrioArc::clone(&entry.nodes),
+            };
+            self.push_to_bucket(Box::new(DeqNode::new(node)), time);
+        }
+    }
+
+    /// Moves the timer of the entry to the bucket for its current expiration
+    /// time. Does nothing if the entry does not have a timer.
+    pub(crate) fn reschedule<V>(&mut self, entry: &ValueEntry<K, V>) {
+        if let Some(node) = entry.timer_node() {
+            if let Some(time) = entry.entry_info().expiration_time() {
+                let node = unsafe { self.unlink(node) };
+                self.push_to_bucket(node, time);
+            } else {
+                self.deschedule(entry);
+            }
+        }
+    }
+
+    /// Removes the timer of the entry (if any).
+    pub(crate) fn deschedule<V>(&mut self, entry: &ValueEntry<K, V>) {
+        if let Some(node) = entry.take_timer_node() {
+            unsafe { std::mem::drop(self.unlink(node)) };
+        }
+    }
+
+    /// Advances the wheel to `now`. The timers whose expiration time has passed are
+    /// moved to the expired queue, and the others in the visited buckets are moved
+    /// to the buckets for their expiration time.
+    pub(crate) fn advance(&mut self, now: Instant) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let previous = self.current;
+        let current = self.nanos_since_origin(now);
+        if current <= previous {
+            return;
+        }
+        self.current = current;
+
+        for (level, &shift) in SHIFTS.iter().enumerate() {
+            let previous_ticks = previous >> shift;
+            let current_ticks = current >> shift;
+            if current_ticks <= previous_ticks {
+                break;
+            }
+            self.expire(level, previous_ticks, current_ticks - previous_ticks, now);
+        }
+    }
+
+    /// Removes a timer from the expired queue and returns the key of the entry.
+    pub(crate) fn pop_expired(&mut self) -> Option<KeyHash<K>> {
+        self.expired.pop_front().map(|node| {
+            node.element.deq_nodes.lock().timer_node = None;
+            node.element.key_hash.clone()
+        })
+    }
+
+    /// Returns the earliest time when `advance` will have a timer to expire or to
+    /// cascade, that is, the end of the first non-empty bucket of each level. Since
+    /// a bucket is visited only after its span has passed, this can be later than
+    /// the expiration time of the timer by up to the span of a level-0 bucket.
+    ///
+    /// Returns the time of the last advance if the expired queue is not empty, or
+    /// `None` if there is no timer.
+    pub(crate) fn next_advance_time(&self) -> Option<Instant> {
+        let origin = self.origin?;
+        let nanos = if self.expired.len() > 0 {
+            Some(self.current)
+        } else {
+            SHIFTS
+                .iter()
+                .zip(BUCKET_COUNTS.iter())
+                .enumerate()
+                .filter_map(|(level, (&shift, &count))| {
+                    let current_ticks = self.current >> shift;
+                    (0..count)
+                        .map(|i| current_ticks + i)
+                        .find(|&ticks| {
+                            let index = (ticks & (count - 1)) as usize;
+                            self.wheels[level][index].len() > 0
+                        })
+                        .map(|ticks| (ticks + 1).saturating_mul(1 << shift))
+                })
+                .min()
+        };
+        nanos.and_then(|n| origin.checked_add(Duration::from_nanos(n)))
+    }
+
+    /// Returns the number of the timers in the wheel, including the expired ones.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        let scheduled: usize = self
+            .wheels
+            .iter()
+            .flat_map(|buckets| buckets.iter())
+            .map(|bucket| bucket.len())
+            .sum();
+        scheduled + self.expired.len()
+    }
+}
+
+// Private methods.
+impl<K> TimerWheel<K> {
+    fn nanos_since_origin(&self, time: Instant) -> u64 {
+        let origin = self.origin.expect("The timer wheel is not enabled");
+        let nanos = time.saturating_duration_since(origin).as_nanos();
+        // A u64 can hold 584 years in nanoseconds.
+        u64::try_from(nanos).unwrap_or(u64::MAX)
+    }
+
+    /// Visits `delta` buckets (at most all buckets) of the `level` starting from
+    /// the bucket at `previous_ticks`.
+    fn expire(&mut self, level: usize, previous_ticks: u64, delta: u64, now: Instant) {
+        let count = BUCKET_COUNTS[level];
+        let mask = count - 1;
+        let steps = count.min(delta + 1);
+        let start = previous_ticks & mask;
+
+        for i in start..(start + steps) {
+            let index = (i & mask) as usize;
+            // Take the current nodes out of the bucket, so that the nodes pushed
+            // back to the same bucket will not be visited again.
+            let len = self.wheels[level][index].len();
+            for _ in 0..len {
+                let mut node = match self.wheels[level][index].pop_front() {
+                    Some(node) => node,
+                    None => break,
+                };
+                match node.element.expiration_time() {
+                    Some(time) if time > now => {
+                        self.push_to_bucket(node, time);
+                    }
+                    // Expired or no longer has an expiration time. Move it to the
+                    // expired queue and let the cache check the entry.
+                    _ => {
+                        node.element.level = EXPIRED_LEVEL;
+                        let ptr = self.expired.push_back(node);
+                        unsafe { self.set_timer_node(ptr) };
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pushes the node to the bucket for the given time, and stores the pointer
+    /// to the node in the entry.
+    fn push_to_bucket(&mut self, mut node: Box<DeqNode<TimerNode<K>>>, time: Instant) {
+        let (level, index) = self.bucket_for(time);
+        node.element.level = level as u8;
+        node.element.index = index as u8;
+        let ptr = self.wheels[level][index].push_back(node);
+        unsafe { self.set_timer_node(ptr) };
+    }
+
+    /// Returns the level and the index of the bucket for the given time.
+    fn bucket_for(&self, time: Instant) -> (usize, usize) {
+        // A past time goes to the bucket for the current time, which will be
+        // visited by the next advance.
+        let time = self.nanos_since_origin(time).max(self.current);
+        let duration = time - self.current;
+        let last = BUCKET_COUNTS.len() - 1;
+        for level in 0..last {
+            if duration < SPANS[level + 1] {
+                let ticks = time >> SHIFTS[level];
+                let index = ticks & (BUCKET_COUNTS[level] - 1);
+                return (level, index as usize);
+            }
+        }
+        (last, 0)
+    }
+
+    /// Unlinks the node from the bucket or the expired queue where it lives.
+    unsafe fn unlink(&mut self, node: TimerDeqNode<K>) -> Box<DeqNode<TimerNode<K>>> {
+        let (level, index) = {
+            let element = &node.as_ref().element;
+            (element.level, element.index)
+        };
+        let deq = if level == EXPIRED_LEVEL {
+            &mut self.expired
+        } else {
+            &mut self.wheels[level as usize][index as usize]
+        };
+        deq.unlink(node);
+        Box::from_raw(node.as_ptr())
+    }
+
+    unsafe fn set_timer_node(&self, node: TimerDeqNode<K>) {
+        node.as_ref().element.deq_nodes.lock().timer_node = Some(node);
+    }
+}
+
+#[cfg(feature = "future")]
+// TODO: https://github.com/moka-rs/moka/issues/54
+#[allow(clippy::non_send_fields_in_send_ty)]
+// Multi-threaded async runtimes require base_cache::Inner to be Send, but it will
+// not be without this `unsafe impl`. This is because DeqNodes have NonNull
+// pointers.
+unsafe impl<K> Send for TimerWheel<K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimerWheel, SPANS};
+    use crate::common::{
+        concurrent::{entry_info::EntryInfo, KeyHash, ValueEntry},
+        time::{CheckedTimeOps, Clock, Instant},
+    };
+
+    use std::{sync::Arc, time::Duration};
+    use triomphe::Arc as TrioArc;
+
+    fn entry(now: Instant, ttl: Duration) -> ValueEntry<u32, ()> {
+        let info = TrioArc::new(EntryInfo::new(now, 1));
+        info.set_expiration_time(now.checked_add(ttl));
+        ValueEntry::new((), info)
+    }
+
+    fn pop_all_expired(timer: &mut TimerWheel<u32>) -> Vec<u32> {
+        let mut keys = Vec::new();
+        while let Some(kh) = timer.pop_expired() {
+            keys.push(*kh.key);
+        }
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn schedule_and_advance() {
+        let (clock, mock) = Clock::mock();
+        let now = || Instant::new(clock.now());
+        let mut timer = TimerWheel::default();
+        timer.enable(now());
+
+        // Deadlines on every level of the wheel.
+        let ttls = [
+            Duration::from_secs(5),
+            Duration::from_secs(90),
+            Duration::from_secs(2 * 60 * 60),
+            Duration::from_secs(3 * 24 * 60 * 60),
+            Duration::from_nanos(SPANS[5] * 2),
+        ];
+        let entries = ttls
+            .iter()
+            .enumerate()
+            .map(|(i, ttl)| {
+                let e = entry(now(), *ttl);
+                timer.schedule(KeyHash::new(Arc::new(i as u32), i as u64), &e);
+                e
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(timer.len(), 5);
+
+        let mut elapsed = Duration::default();
+        for (i, ttl) in ttls.iter().enumerate() {
+            // Not expired yet.
+            let before = *ttl - elapsed - Duration::from_millis(1);
+            mock.increment(before);
+            elapsed += before;
+            timer.advance(now());
+            assert!(pop_all_expired(&mut timer).is_empty(), "i: {}", i);
+            assert!(entries[i].entry_info().expiration_time().unwrap() > now());
+
+            // Expired.
+            mock.increment(Duration::from_secs(2));
+            elapsed += Duration::from_secs(2);
+            timer.advance(now());
+            assert_eq!(pop_all_expired(&mut timer), vec![i as u32]);
+            assert!(entries[i].timer_node().is_none());
+        }
+        assert_eq!(timer.len(), 0);
+    }
+
+    #[test]
+    fn next_advance_time() {
+        let (clock, mock) = Clock::mock();
+        let now = || Instant::new(clock.now());
+        let mut timer = TimerWheel::default();
+        assert!(timer.next_advance_time().is_none());
+
+        let origin = now();
+        timer.enable(origin);
+        assert!(timer.next_advance_time().is_none());
+
+        // A level-0 bucket spans SPANS[0] nanos. The timer will be expired by the
+        // advance after the end of its bucket.
+        let span = Duration::from_nanos(SPANS[0]);
+        let e1 = entry(now(), span * 3 + span / 2);
+        timer.schedule(KeyHash::new(Arc::new(1), 1), &e1);
+        assert!(timer.next_advance_time() == origin.checked_add(span * 4));
+
+        // An earlier timer.
+        let e2 = entry(now(), span / 2);
+        timer.schedule(KeyHash::new(Arc::new(2), 2), &e2);
+        assert!(timer.next_advance_time() == origin.checked_add(span));
+
+        // A timer on level 1 does not change the result.
+        let e3 = entry(now(), Duration::from_nanos(SPANS[1] * 2));
+        timer.schedule(KeyHash::new(Arc::new(3), 3), &e3);
+        assert!(timer.next_advance_time() == origin.checked_add(span));
+
+        // The expired queue is not empty.
+        mock.increment(span);
+        timer.advance(now());
+        assert!(timer.next_advance_time() == Some(now()));
+        assert_eq!(pop_all_expired(&mut timer), vec![2]);
+        assert!(timer.next_advance_time() == origin.checked_add(span * 4));
+
+        timer.deschedule(&e1);
+        timer.deschedule(&e3);
+        assert!(timer.next_advance_time().is_none());
+    }
+
+    #[test]
+    fn reschedule_and_deschedule() {
+        let (clock, mock) = Clock::mock();
+        let now = || Instant::new(clock.now());
+        let mut timer = TimerWheel::default();
+        timer.enable(now());
+
+        let e0 = entry(now(), Duration::from_secs(10));
+        let e1 = entry(now(), Duration::from_secs(10));
+        timer.schedule(KeyHash::new(Arc::new(0), 0), &e0);
+        timer.schedule(KeyHash::new(Arc::new(1), 1), &e1);
+
+        // Extend e0 and remove e1.
+        e0.entry_info()
+            .set_expiration_time(now().checked_add(Duration::from_secs(60)));
+        timer.reschedule(&e0);
+        timer.deschedule(&e1);
+        assert!(e1.timer_node().is_none());
+        assert_eq!(timer.len(), 1);
+
+        mock.increment(Duration::from_secs(30));
+        timer.advance(now());
+        assert!(pop_all_expired(&mut timer).is_empty());
+
+        // Shorten the deadline of e0 without rescheduling. The wheel will find it
+        // when it reaches the original bucket.
+        e0.entry_info()
+            .set_expiration_time(now().checked_add(Duration::from_secs(1)));
+        mock.increment(Duration::from_secs(31));
+        timer.advance(now());
+        assert_eq!(pop_all_expired(&mut timer), vec![0]);
+    }
+}
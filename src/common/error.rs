@@ -19,3 +19,27 @@ pub enum PredicateError {
     )]
     InvalidationClosuresDisabled,
 }
+
+/// The error type for taking and restoring a snapshot of a cache by
+/// [`Cache::snapshot_to`][snapshot-to] and
+/// [`CacheBuilder::restore_from`][restore-from] methods.
+///
+/// [snapshot-to]: ./sync/struct.Cache.html#method.snapshot_to
+/// [restore-from]: ./sync/struct.CacheBuilder.html#method.restore_from
+#[cfg(feature = "serde")]
+#[derive(thiserror::Error, Debug)]
+pub enum SnapshotError {
+    /// An I/O error occurred while writing or reading the snapshot.
+    #[error("I/O error on the snapshot: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Failed to serialize a key or value, or the data read is not a snapshot
+    /// taken from a cache with the same key and value types.
+    #[error("Failed to serialize or deserialize the snapshot: {0}")]
+    Serialization(Box<dyn std::error::Error + Send + Sync>),
+
+    /// The snapshot was taken by a version of this crate with an incompatible
+    /// snapshot format.
+    #[error("Unsupported snapshot format version: {0}")]
+    UnsupportedVersion(u32),
+}
@@ -109,6 +109,13 @@ impl FrequencySketch {
         };
     }
 
+    /// Returns the number of increments after which the counters will be halved.
+    /// (aging)
+    #[cfg(any(feature = "sync", feature = "future"))]
+    pub(crate) fn sample_size(&self) -> u32 {
+        self.sample_size
+    }
+
     /// Takes the hash value of an element, and returns the estimated number of
     /// occurrences of the element, up to the maximum (15).
     pub(crate) fn frequency(&self, hash: u64) -> u8 {
@@ -151,6 +158,27 @@ impl FrequencySketch {
         }
     }
 
+    /// Takes the hash value of an element and increments the popularity of the
+    /// element until it reaches the given `frequency`. Unlike `increment`, this
+    /// does not count toward the aging process, so it can be used to restore the
+    /// popularity of elements saved in a snapshot.
+    #[cfg(all(any(feature = "sync", feature = "future"), feature = "serde"))]
+    pub(crate) fn restore_frequency(&mut self, hash: u64, frequency: u8) {
+        if self.table.is_empty() {
+            return;
+        }
+
+        let start = ((hash & 3) << 2) as u8;
+        for i in 0..4 {
+            let index = self.index_of(hash, i);
+            let offset = ((start + i) as usize) << 2;
+            let count = (self.table[index] >> offset & 0xF) as u8;
+            let new_count = count.max(frequency.min(15));
+            self.table[index] =
+                (self.table[index] & !(0xF_u64 << offset)) | ((new_count as u64) << offset);
+        }
+    }
+
     /// Takes a table index (each entry has 16 counters) and counter index, and
     /// increments the counter by 1 if it is not already at the maximum value
     /// (15). Returns `true` if incremented.
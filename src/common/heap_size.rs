@@ -0,0 +1,230 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem::{size_of, size_of_val},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Estimates the number of bytes a value owns on the heap.
+///
+/// The `max_memory_bytes` method of the cache builders uses this trait to weigh
+/// the entries by their memory usage. The weight of an entry is the
+/// [`size_of`][size-of] its key and value plus their `heap_size`.
+///
+/// The returned size should not include `size_of::<Self>()` itself, only the
+/// memory allocated for the data that `self` points to. It does not have to be
+/// exact, but it should be cheap to calculate as it is called on every insert.
+///
+/// The implementations for `Arc` and `Rc` count the whole shared allocation, so
+/// the data shared by multiple entries will be counted for each of them.
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{sync::Cache, HeapSize};
+///
+/// #[derive(Clone)]
+/// struct User {
+///     name: String,
+///     groups: Vec<String>,
+/// }
+///
+/// impl HeapSize for User {
+///     fn heap_size(&self) -> usize {
+///         self.name.heap_size() + self.groups.heap_size()
+///     }
+/// }
+///
+/// // A cache that will hold the entries up to about 32MiB.
+/// let cache: Cache<u64, User> = Cache::builder()
+///     .max_memory_bytes(32 * 1024 * 1024)
+///     .build();
+/// ```
+///
+/// [size-of]: https://doc.rust-lang.org/std/mem/fn.size_of.html
+pub trait HeapSize {
+    /// Returns the number of bytes owned by `self` on the heap.
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_zero_heap_size {
+    ($($t:ty),*) => {
+        $(
+            impl HeapSize for $t {
+                #[inline]
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_zero_heap_size!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    str,
+    Duration
+);
+
+impl<T: ?Sized> HeapSize for &T {
+    /// Always returns zero as a reference does not own the data.
+    #[inline]
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for String {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize + ?Sized> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        size_of_val(&**self) + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize + ?Sized> HeapSize for Arc<T> {
+    fn heap_size(&self) -> usize {
+        // The strong and weak reference counts, and the data.
+        2 * size_of::<usize>() + size_of_val(&**self) + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize + ?Sized> HeapSize for Rc<T> {
+    fn heap_size(&self) -> usize {
+        // The strong and weak reference counts, and the data.
+        2 * size_of::<usize>() + size_of_val(&**self) + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for [T] {
+    fn heap_size(&self) -> usize {
+        self.iter().map(T::heap_size).sum()
+    }
+}
+
+impl<T: HeapSize, const N: usize> HeapSize for [T; N] {
+    fn heap_size(&self) -> usize {
+        self[..].heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self[..].heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for VecDeque<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        // Each slot of the table has one control byte.
+        self.capacity() * (size_of::<(K, V)>() + 1)
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl<T: HeapSize, S> HeapSize for HashSet<T, S> {
+    fn heap_size(&self) -> usize {
+        // Each slot of the table has one control byte.
+        self.capacity() * (size_of::<T>() + 1) + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+macro_rules! impl_tuple_heap_size {
+    ($($name:ident),+) => {
+        impl<$($name: HeapSize),+> HeapSize for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn heap_size(&self) -> usize {
+                let ($($name,)+) = self;
+                0 $(+ $name.heap_size())+
+            }
+        }
+    };
+}
+
+impl_tuple_heap_size!(A);
+impl_tuple_heap_size!(A, B);
+impl_tuple_heap_size!(A, B, C);
+impl_tuple_heap_size!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::HeapSize;
+    use std::{mem::size_of, sync::Arc};
+
+    #[test]
+    fn primitives_and_strings() {
+        assert_eq!(42u64.heap_size(), 0);
+        assert_eq!("hello".heap_size(), 0);
+
+        let s = String::with_capacity(100);
+        assert_eq!(s.heap_size(), 100);
+        assert_eq!(Some(s).heap_size(), 100);
+        assert_eq!(None::<String>.heap_size(), 0);
+
+        let boxed: Box<str> = "hello".into();
+        assert_eq!(boxed.heap_size(), 5);
+    }
+
+    #[test]
+    fn collections() {
+        let mut v: Vec<String> = Vec::with_capacity(4);
+        v.push(String::with_capacity(10));
+        v.push(String::with_capacity(20));
+        assert_eq!(v.heap_size(), 4 * size_of::<String>() + 30);
+
+        let a = [String::with_capacity(3), String::with_capacity(5)];
+        assert_eq!(a.heap_size(), 8);
+
+        let t = (1u32, String::with_capacity(7), vec![0u8; 9]);
+        assert_eq!(t.heap_size(), 7 + 9);
+    }
+
+    #[test]
+    fn shared_pointers() {
+        let arc = Arc::new(String::with_capacity(16));
+        assert_eq!(
+            arc.heap_size(),
+            2 * size_of::<usize>() + size_of::<String>() + 16
+        );
+
+        let arc_str: Arc<str> = "hello".into();
+        assert_eq!(arc_str.heap_size(), 2 * size_of::<usize>() + 5);
+    }
+}
@@ -1,15 +1,63 @@
+use crate::ticker::TickerArc;
+
 use std::time::Duration;
 
 #[cfg_attr(feature = "quanta", path = "time/clock_quanta.rs")]
 #[cfg_attr(not(feature = "quanta"), path = "time/clock_compat.rs")]
 pub(crate) mod clock;
 
-pub(crate) use clock::Clock;
-
-#[cfg(test)]
 #[cfg(all(test, feature = "sync"))]
 pub(crate) use clock::Mock;
 
+/// The clock of a cache, used instead of the system clock when set. It reads the
+/// time from the `Ticker` given via the cache builder, or from a mock clock in the
+/// unit tests.
+pub(crate) enum Clock {
+    Ticker {
+        ticker: TickerArc,
+        /// The time of the system clock and the ticker when this clock was created.
+        origin: clock::Instant,
+        origin_elapsed: Duration,
+    },
+    #[cfg(test)]
+    Mock(clock::Clock),
+}
+
+impl Clock {
+    pub(crate) fn from_ticker(ticker: TickerArc) -> Self {
+        let origin_elapsed = ticker.read();
+        Self::Ticker {
+            ticker,
+            origin: clock::Instant::now(),
+            origin_elapsed,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn mock() -> (Self, std::sync::Arc<clock::Mock>) {
+        let (clock, mock) = clock::Clock::mock();
+        (Self::Mock(clock), mock)
+    }
+
+    pub(crate) fn now(&self) -> clock::Instant {
+        match self {
+            Self::Ticker {
+                ticker,
+                origin,
+                origin_elapsed,
+            } => {
+                let elapsed = ticker
+                    .read()
+                    .checked_sub(*origin_elapsed)
+                    .unwrap_or_default();
+                origin.checked_add(elapsed).expect("Timestamp overflow")
+            }
+            #[cfg(test)]
+            Self::Mock(clock) => clock.now(),
+        }
+    }
+}
+
 /// a wrapper type over Instant to force checked additions and prevent
 /// unintentional overflow. The type preserve the Copy semantics for the wrapped
 #[derive(PartialEq, PartialOrd, Clone, Copy)]
@@ -30,6 +78,15 @@ impl Instant {
         Instant(clock::Instant::now())
     }
 
+    pub(crate) fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.0.saturating_duration_since(earlier.0)
+    }
+
+    #[cfg(all(any(feature = "sync", feature = "future"), feature = "serde"))]
+    pub(crate) fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_sub(duration).map(Instant)
+    }
+
     #[cfg(feature = "quanta")]
     pub(crate) fn inner_clock(&self) -> clock::Instant {
         self.0
@@ -1,18 +1,19 @@
-use std::{sync::Arc, time::Instant as StdInstant};
+use std::time::Instant as StdInstant;
 
 #[cfg(test)]
-use std::time::Duration;
-
 use parking_lot::RwLock;
+#[cfg(test)]
+use std::{sync::Arc, time::Duration};
 
 pub(crate) type Instant = StdInstant;
 
+#[cfg(test)]
 pub(crate) struct Clock {
     mock: Option<Arc<Mock>>,
 }
 
+#[cfg(test)]
 impl Clock {
-    #[cfg(test)]
     pub(crate) fn mock() -> (Clock, Arc<Mock>) {
         let mock = Arc::new(Mock::default());
         let clock = Clock {
@@ -30,10 +31,12 @@ impl Clock {
     }
 }
 
+#[cfg(test)]
 pub(crate) struct Mock {
     now: RwLock<Instant>,
 }
 
+#[cfg(test)]
 impl Default for Mock {
     fn default() -> Self {
         Self {
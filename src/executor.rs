@@ -0,0 +1,98 @@
+//! Provides the [`Executor`](./trait.Executor.html) trait to run the background
+//! tasks of a cache on your own runtime or thread pool.
+
+use std::{sync::Arc, time::Duration};
+
+pub(crate) type ExecutorArc = Arc<dyn Executor>;
+
+/// A task submitted by a cache to an [`Executor`](./trait.Executor.html).
+pub type Task = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs the background tasks of the `sync` and `future` caches.
+///
+/// By default, these tasks are executed by the global thread pools created by
+/// this crate (`moka-housekeeper-*`, `moka-invalidator-*`, `moka-notifier-*` and
+/// `moka-refresher-*`). Implement this trait and give it to a cache via the
+/// `executor` method of the cache builder to run them on your own runtime or thread
+/// pool instead. The tasks are:
+///
+/// - The housekeeping tasks, which apply the recorded reads and writes to the
+///   cache policy, remove expired entries and evict entries.
+/// - The scanning tasks of the invalidation closures.
+/// - The delivery of the queued eviction notifications.
+/// - The reloads of the entries for `refresh_after_write` (`sync` caches only;
+///   `future::Cache` drives them by its `run_pending_tasks`).
+///
+/// # Blocking
+///
+/// The tasks are _blocking_; they acquire locks and call the eviction listener and
+/// the reloader, both of which can take time. If you run them on an async runtime,
+/// run them on the runtime's blocking thread pool (e.g. `tokio::task::spawn_blocking`)
+/// rather than on its async worker threads.
+///
+/// # Shutdown
+///
+/// An executor may drop the submitted tasks without running them, for example
+/// when its runtime is shutting down. When a cache is dropped, it does not wait
+/// for the pending tasks; the tasks run after that will do nothing. It only waits
+/// for a housekeeping task that is already running, so do not drop the cache from
+/// inside the eviction listener.
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{
+///     executor::{Executor, Task},
+///     sync::Cache,
+/// };
+/// use std::{sync::Arc, time::Duration};
+///
+/// // An executor spawning a thread for each task.
+/// struct ThreadPerTask;
+///
+/// impl Executor for ThreadPerTask {
+///     fn execute(&self, task: Task) {
+///         std::thread::spawn(task);
+///     }
+///
+///     fn execute_after(&self, delay: Duration, task: Task) {
+///         std::thread::spawn(move || {
+///             std::thread::sleep(delay);
+///             task();
+///         });
+///     }
+/// }
+///
+/// // An executor can be shared by multiple caches by wrapping it with an `Arc`.
+/// let executor = Arc::new(ThreadPerTask);
+///
+/// let cache1 = Cache::builder().executor(Arc::clone(&executor)).build();
+/// let cache2 = Cache::builder().executor(executor).build();
+///
+/// cache1.insert(1, "one");
+/// cache2.insert(2, "two");
+/// ```
+pub trait Executor: Send + Sync {
+    /// Runs the task in the background as soon as possible.
+    fn execute(&self, task: Task);
+
+    /// Runs the task in the background after the given delay.
+    ///
+    /// The delay does not have to be precise; running the task a bit later is fine.
+    /// A cache uses this method to schedule its periodical housekeeping task, which
+    /// schedules the next run when it finishes.
+    fn execute_after(&self, delay: Duration, task: Task);
+}
+
+impl<E> Executor for Arc<E>
+where
+    E: Executor + ?Sized,
+{
+    fn execute(&self, task: Task) {
+        (**self).execute(task)
+    }
+
+    fn execute_after(&self, delay: Duration, task: Task) {
+        (**self).execute_after(delay, task)
+    }
+}
@@ -3,17 +3,22 @@
 //!
 //! To use this module, enable a crate feature called "future".
 
+use crate::sync_base::refresher::Refresher;
+
+use futures_util::future::BoxFuture;
 use std::{hash::Hash, sync::Arc};
 
 mod builder;
 mod cache;
 mod entry_selector;
+mod loading_cache;
 mod value_initializer;
 
 pub use {
     builder::CacheBuilder,
     cache::{BlockingOp, Cache},
     entry_selector::{OwnedKeyEntrySelector, RefKeyEntrySelector},
+    loading_cache::{CacheLoader, LoadingCache},
 };
 
 /// The type of the unique ID to identify a predicate used by
@@ -27,6 +32,11 @@ pub type PredicateId = String;
 // Empty struct to be used in InitResult::InitErr to represent the Option None.
 struct OptionallyNone;
 
+pub(crate) type Reloader<K, V> =
+    Arc<dyn Fn(&K, &V) -> BoxFuture<'static, Option<V>> + Send + Sync + 'static>;
+
+pub(crate) type RefresherArc<K, V> = Arc<Refresher<Reloader<K, V>>>;
+
 pub struct Iter<'i, K, V>(crate::sync_base::iter::Iter<'i, K, V>);
 
 impl<'i, K, V> Iter<'i, K, V> {
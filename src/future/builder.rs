@@ -1,13 +1,30 @@
-use super::Cache;
+use super::{Cache, CacheLoader, LoadingCache, Reloader};
 use crate::{
-    common::{builder_utils, concurrent::Weigher},
-    notification::{self, DeliveryMode, EvictionListener, RemovalCause},
+    common::{
+        builder_utils,
+        concurrent::{self, ExpiryArc, Weigher},
+    },
+    executor::{Executor, ExecutorArc},
+    notification::{self, DeliveryMode, Listener, ListenerFuture, OverflowPolicy, RemovalCause},
+    stats::{ConcurrentStatsCounter, StatsCounter, StatsCounterArc},
+    ticker::{Ticker, TickerArc},
+    Expiry, HeapSize,
 };
 
+#[cfg(feature = "serde")]
+use crate::SnapshotError;
+#[cfg(feature = "serde")]
+use serde_crate::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use std::io::Read;
+
+use futures_util::FutureExt;
 use std::{
     collections::hash_map::RandomState,
+    future::Future,
     hash::{BuildHasher, Hash},
     marker::PhantomData,
+    mem::size_of,
     sync::Arc,
     time::Duration,
 };
@@ -58,11 +75,21 @@ pub struct CacheBuilder<K, V, C> {
     max_capacity: Option<u64>,
     initial_capacity: Option<usize>,
     weigher: Option<Weigher<K, V>>,
-    eviction_listener: Option<EvictionListener<K, V>>,
+    eviction_listener: Option<Listener<K, V>>,
     eviction_listener_conf: Option<notification::Configuration>,
     time_to_live: Option<Duration>,
     time_to_idle: Option<Duration>,
+    expiry: Option<ExpiryArc<K, V>>,
     invalidator_enabled: bool,
+    stats_counter: Option<StatsCounterArc>,
+    refresh_after_write: Option<Duration>,
+    reloader: Option<Reloader<K, V>>,
+    thread_pool_enabled: bool,
+    executor: Option<ExecutorArc>,
+    expiration_scheduling_enabled: bool,
+    ticker: Option<TickerArc>,
+    deterministic_mode: bool,
+    include_entry_overhead: bool,
     cache_type: PhantomData<C>,
 }
 
@@ -81,7 +108,17 @@ where
             eviction_listener_conf: None,
             time_to_live: None,
             time_to_idle: None,
+            expiry: None,
             invalidator_enabled: false,
+            stats_counter: None,
+            refresh_after_write: None,
+            reloader: None,
+            thread_pool_enabled: true,
+            executor: None,
+            expiration_scheduling_enabled: false,
+            ticker: None,
+            deterministic_mode: false,
+            include_entry_overhead: false,
             cache_type: Default::default(),
         }
     }
@@ -108,24 +145,99 @@ where
     /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
     /// 1000 years. This is done to protect against overflow when computing key
     /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
     pub fn build(self) -> Cache<K, V, RandomState> {
         let build_hasher = RandomState::default();
         builder_utils::ensure_expirations_or_panic(self.time_to_live, self.time_to_idle);
+        let weigher = self.weigher_with_overhead();
+        let housekeeper_conf = builder_utils::async_housekeeper_conf(
+            self.thread_pool_enabled,
+            self.executor,
+            self.deterministic_mode,
+        )
+        .with_expiration_scheduling(self.expiration_scheduling_enabled);
         Cache::with_everything(
             self.name,
             self.max_capacity,
             self.initial_capacity,
             build_hasher,
-            self.weigher,
+            weigher,
             self.eviction_listener,
             self.eviction_listener_conf,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
             self.invalidator_enabled,
-            builder_utils::housekeeper_conf(true),
+            self.stats_counter,
+            builder_utils::refresher_without_executor(self.refresh_after_write, self.reloader),
+            self.ticker,
+            housekeeper_conf,
         )
     }
 
+    /// Builds a `Cache<K, V>` and restores the entries from a snapshot written by
+    /// [`Cache::snapshot_to`][snapshot-to].
+    ///
+    /// The entries are restored with their remaining time-to-live, time-to-idle
+    /// and per-entry expiration times, their access order and their historic
+    /// popularity. The configuration of the cache is not a part of the snapshot,
+    /// so configure this builder in the same way as the cache the snapshot was
+    /// taken from. If the cache does not have enough capacity for all entries, the
+    /// least recently used ones will be evicted.
+    ///
+    /// This method blocks the current thread while reading from the `reader`.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`][snapshot-error] if it fails to read from the
+    /// `reader`, or the data read is not a snapshot of a cache with the same key
+    /// and value types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
+    /// 1000 years. This is done to protect against overflow when computing key
+    /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
+    ///
+    /// [snapshot-to]: ./struct.Cache.html#method.snapshot_to
+    /// [snapshot-error]: ../enum.SnapshotError.html
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn restore_from<R>(self, reader: R) -> Result<Cache<K, V, RandomState>, SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        R: Read,
+    {
+        let cache = self.build();
+        cache.restore_snapshot(reader)?;
+        Ok(cache)
+    }
+
+    /// Builds a [`LoadingCache<K, V, L>`][loading-cache] that loads the missing
+    /// values by the given `loader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
+    /// 1000 years. This is done to protect against overflow when computing key
+    /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
+    ///
+    /// [loading-cache]: ./struct.LoadingCache.html
+    pub fn build_with_loader<L>(self, loader: L) -> LoadingCache<K, V, L, RandomState>
+    where
+        L: CacheLoader<K, V>,
+    {
+        LoadingCache::new(self.build(), loader)
+    }
+
     /// Builds a `Cache<K, V, S>`, with the given `hasher`.
     ///
     /// # Panics
@@ -133,23 +245,36 @@ where
     /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
     /// 1000 years. This is done to protect against overflow when computing key
     /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
     pub fn build_with_hasher<S>(self, hasher: S) -> Cache<K, V, S>
     where
         S: BuildHasher + Clone + Send + Sync + 'static,
     {
         builder_utils::ensure_expirations_or_panic(self.time_to_live, self.time_to_idle);
+        let weigher = self.weigher_with_overhead();
+        let housekeeper_conf = builder_utils::async_housekeeper_conf(
+            self.thread_pool_enabled,
+            self.executor,
+            self.deterministic_mode,
+        )
+        .with_expiration_scheduling(self.expiration_scheduling_enabled);
         Cache::with_everything(
             self.name,
             self.max_capacity,
             self.initial_capacity,
             hasher,
-            self.weigher,
+            weigher,
             self.eviction_listener,
             self.eviction_listener_conf,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
             self.invalidator_enabled,
-            builder_utils::housekeeper_conf(true),
+            self.stats_counter,
+            builder_utils::refresher_without_executor(self.refresh_after_write, self.reloader),
+            self.ticker,
+            housekeeper_conf,
         )
     }
 }
@@ -183,14 +308,71 @@ impl<K, V, C> CacheBuilder<K, V, C> {
     /// Sets the weigher closure to the cache.
     ///
     /// The closure should take `&K` and `&V` as the arguments and returns a `u32`
-    /// representing the relative size of the entry.
+    /// representing the relative size of the entry. To use weights larger than
+    /// `u32::MAX`, use [`weigher_u64`](#method.weigher_u64) instead.
     pub fn weigher(self, weigher: impl Fn(&K, &V) -> u32 + Send + Sync + 'static) -> Self {
+        Self {
+            weigher: Some(Arc::new(move |k, v| u64::from(weigher(k, v)))),
+            ..self
+        }
+    }
+
+    /// Sets the weigher closure returning a `u64` weight to the cache.
+    ///
+    /// This is the same as [`weigher`](#method.weigher), but the closure can
+    /// return a weight larger than `u32::MAX`, for example the size of the entry in
+    /// bytes.
+    pub fn weigher_u64(self, weigher: impl Fn(&K, &V) -> u64 + Send + Sync + 'static) -> Self {
         Self {
             weigher: Some(Arc::new(weigher)),
             ..self
         }
     }
 
+    /// Bounds the cache by the estimated memory usage of the entries in bytes.
+    ///
+    /// This sets `max_capacity` to `bytes`, and the weigher to the one that returns
+    /// the [`size_of`][size-of] the key and value plus their
+    /// [`HeapSize::heap_size`][heap-size]. Then `weighted_size` of the cache
+    /// returns the estimated memory usage in bytes. Call
+    /// [`include_entry_overhead`](#method.include_entry_overhead) to also count the
+    /// memory used by the cache to store the entries.
+    ///
+    /// [size-of]: https://doc.rust-lang.org/std/mem/fn.size_of.html
+    /// [heap-size]: ../trait.HeapSize.html#tymethod.heap_size
+    pub fn max_memory_bytes(self, bytes: u64) -> Self
+    where
+        K: HeapSize + 'static,
+        V: HeapSize + 'static,
+    {
+        Self {
+            max_capacity: Some(bytes),
+            ..self
+        }
+        .weigher_u64(|k, v| {
+            (size_of::<K>() + k.heap_size() + size_of::<V>() + v.heap_size()) as u64
+        })
+    }
+
+    /// Specify whether or not to add the estimated per-entry memory overhead of the
+    /// cache to the weight of every entry. (Default: `false`)
+    ///
+    /// The overhead includes the internal hash table bucket, the bookkeeping data
+    /// such as the timestamps and the nodes of the internal queues, but not the key
+    /// and value themselves. It is calculated from the types of the key and value
+    /// and the expiration settings of the cache.
+    ///
+    /// Use it with [`max_memory_bytes`](#method.max_memory_bytes) or a weigher
+    /// returning the size of the entry in bytes, so that `weighted_size` of the
+    /// cache reflects the memory used by the cache. If no weigher is set, the weight
+    /// of an entry will be the `size_of` the key and value plus the overhead.
+    pub fn include_entry_overhead(self, v: bool) -> Self {
+        Self {
+            include_entry_overhead: v,
+            ..self
+        }
+    }
+
     /// Sets the eviction listener closure to the cache.
     ///
     /// The closure should take `Arc<K>`, `V` and [`RemovalCause`][removal-cause] as
@@ -214,7 +396,173 @@ impl<K, V, C> CacheBuilder<K, V, C> {
             .delivery_mode(DeliveryMode::Queued)
             .build();
         Self {
-            eviction_listener: Some(Arc::new(listener)),
+            eviction_listener: Some(Listener::Sync(Arc::new(listener))),
+            eviction_listener_conf: Some(conf),
+            ..self
+        }
+    }
+
+    /// Sets the eviction listener closure to the cache with a custom
+    /// [`Configuration`][conf]. Use this method if you want to change the queue
+    /// capacity or the overflow policy of the [queued delivery mode][queued-mode].
+    ///
+    /// The closure should take `Arc<K>`, `V` and [`RemovalCause`][removal-cause] as
+    /// the arguments. The queued delivery mode is always used for the listener,
+    /// so the delivery mode of the `conf` is ignored.
+    ///
+    /// # Panics
+    ///
+    /// It is very important to make the listener closure not to panic. Otherwise,
+    /// the cache will stop calling the listener after a panic. This is an intended
+    /// behavior because the cache cannot know whether is is memory safe or not to
+    /// call the panicked lister again.
+    ///
+    /// [removal-cause]: ../notification/enum.RemovalCause.html
+    /// [conf]: ../notification/struct.Configuration.html
+    /// [queued-mode]: ../notification/enum.DeliveryMode.html#variant.Queued
+    pub fn eviction_listener_with_conf(
+        self,
+        listener: impl Fn(Arc<K>, V, RemovalCause) + Send + Sync + 'static,
+        conf: notification::Configuration,
+    ) -> Self {
+        let conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Queued)
+            .queue_capacity(conf.queue_capacity())
+            .overflow_policy(conf.overflow_policy())
+            .build();
+        Self {
+            eviction_listener: Some(Listener::Sync(Arc::new(listener))),
+            eviction_listener_conf: Some(conf),
+            ..self
+        }
+    }
+
+    /// Sets the async eviction listener closure to the cache.
+    ///
+    /// The closure should take `Arc<K>`, `V` and [`RemovalCause`][removal-cause] as
+    /// the arguments, and return a [`ListenerFuture`][listener-future]. Unlike
+    /// [`eviction_listener_with_queued_delivery_mode`][queued-listener], the
+    /// listener is not called by a notifier thread pool. Instead, the returned
+    /// futures are awaited by the async methods of the cache that write to it, such
+    /// as `insert`, `get_with` and `invalidate`. So the listener can write to async
+    /// sinks such as async channels and async database clients.
+    ///
+    /// The [immediate delivery mode][immediate-mode] is used for the listener. When
+    /// a write method returns, the notifications for the entries it has removed or
+    /// replaced have been delivered, and the notifications for a key are delivered
+    /// in the order of the write operations on the key. The notifications for the
+    /// entries evicted or expired by the housekeeping are queued, and delivered by
+    /// the next call of an async method, including the read methods such as
+    /// `get_with` and `run_pending_tasks`. The non-async `get` does not deliver
+    /// them.
+    ///
+    /// The queue holds up to 1,024 notifications. When it is full, the oldest
+    /// notification is dropped. The number of the dropped notifications is returned
+    /// by [`Cache::dropped_notification_count`][dropped-count]. Use
+    /// [`async_eviction_listener_with_conf`][async-listener-conf] to change them.
+    ///
+    /// The listener must not call the write methods of the same cache, or the
+    /// notification delivery will deadlock.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros", "sync"] }
+    /// // futures-util = "0.3"
+    /// use futures_util::FutureExt;
+    /// use moka::{future::Cache, notification::ListenerFuture};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    ///
+    ///     let listener = move |k, v, cause| -> ListenerFuture {
+    ///         let tx = tx.clone();
+    ///         async move {
+    ///             // Send the notification to the async channel.
+    ///             let _ = tx.send((k, v, cause)).await;
+    ///         }
+    ///         .boxed()
+    ///     };
+    ///
+    ///     let cache = Cache::builder()
+    ///         .max_capacity(100)
+    ///         .async_eviction_listener(listener)
+    ///         .build();
+    ///
+    ///     cache.insert("alice", 1).await;
+    ///     cache.invalidate(&"alice").await;
+    ///
+    ///     // The notification has been sent when `invalidate` returns.
+    ///     let (k, v, _cause) = rx.try_recv().unwrap();
+    ///     assert_eq!((*k, v), ("alice", 1));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// It is very important to make the listener closure and the returned future
+    /// not to panic. Otherwise, the cache will stop calling the listener after a
+    /// panic. This is an intended behavior because the cache cannot know whether is
+    /// is memory safe or not to call the panicked lister again.
+    ///
+    /// [removal-cause]: ../notification/enum.RemovalCause.html
+    /// [listener-future]: ../notification/type.ListenerFuture.html
+    /// [queued-listener]: #method.eviction_listener_with_queued_delivery_mode
+    /// [immediate-mode]: ../notification/enum.DeliveryMode.html#variant.Immediate
+    /// [dropped-count]: ./struct.Cache.html#method.dropped_notification_count
+    /// [async-listener-conf]: #method.async_eviction_listener_with_conf
+    pub fn async_eviction_listener(
+        self,
+        listener: impl Fn(Arc<K>, V, RemovalCause) -> ListenerFuture + Send + Sync + 'static,
+    ) -> Self {
+        let conf = notification::Configuration::builder()
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .build();
+        self.async_eviction_listener_with_conf(listener, conf)
+    }
+
+    /// Sets the async eviction listener closure to the cache with a custom
+    /// [`Configuration`][conf]. Use this method if you want to change the capacity
+    /// of the queue for the notifications of the entries evicted or expired by the
+    /// housekeeping, or the overflow policy of the queue.
+    ///
+    /// See [`async_eviction_listener`][async-listener] for how the notifications
+    /// are delivered. The [immediate delivery mode][immediate-mode] is always used
+    /// for the listener, so the delivery mode of the `conf` is ignored.
+    ///
+    /// When the queue is full, the new notification is dropped with
+    /// [`OverflowPolicy::DropNewest`][drop-newest]. With any other policy, the
+    /// oldest notification is dropped, because the housekeeping can neither wait
+    /// for the async listener nor call it by itself.
+    ///
+    /// # Panics
+    ///
+    /// It is very important to make the listener closure and the returned future
+    /// not to panic. Otherwise, the cache will stop calling the listener after a
+    /// panic. This is an intended behavior because the cache cannot know whether is
+    /// is memory safe or not to call the panicked lister again.
+    ///
+    /// [conf]: ../notification/struct.Configuration.html
+    /// [async-listener]: #method.async_eviction_listener
+    /// [immediate-mode]: ../notification/enum.DeliveryMode.html#variant.Immediate
+    /// [drop-newest]: ../notification/enum.OverflowPolicy.html#variant.DropNewest
+    pub fn async_eviction_listener_with_conf(
+        self,
+        listener: impl Fn(Arc<K>, V, RemovalCause) -> ListenerFuture + Send + Sync + 'static,
+        conf: notification::Configuration,
+    ) -> Self {
+        let conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Immediate)
+            .queue_capacity(conf.queue_capacity())
+            .overflow_policy(conf.overflow_policy())
+            .build();
+        Self {
+            eviction_listener: Some(Listener::Async(Arc::new(listener))),
             eviction_listener_conf: Some(conf),
             ..self
         }
@@ -254,6 +602,22 @@ impl<K, V, C> CacheBuilder<K, V, C> {
         }
     }
 
+    /// Sets the given `expiry` to the cache to calculate the expiration time of
+    /// each entry.
+    ///
+    /// The per-entry expiration works together with `time_to_live` and
+    /// `time_to_idle`; an entry will be expired when any of them has passed.
+    ///
+    /// See [`Expiry`][expiry-trait] for more details.
+    ///
+    /// [expiry-trait]: ../trait.Expiry.html
+    pub fn expire_after(self, expiry: impl Expiry<K, V> + Send + Sync + 'static) -> Self {
+        Self {
+            expiry: Some(Arc::new(expiry)),
+            ..self
+        }
+    }
+
     /// Enables support for [Cache::invalidate_entries_if][cache-invalidate-if]
     /// method.
     ///
@@ -267,6 +631,232 @@ impl<K, V, C> CacheBuilder<K, V, C> {
             ..self
         }
     }
+
+    /// Sets the refresh-after-write duration of the cache. Requires a
+    /// [`reloader`](#method.reloader).
+    ///
+    /// When an entry is read by `get` (or `get_with` family methods) after the
+    /// specified duration has passed since the entry was inserted or updated, the
+    /// cache returns the current value immediately and queues a reload of a new
+    /// value. Concurrent reads of the same key trigger only one reload. The queued
+    /// reloads are driven by [`Cache::run_pending_tasks`][run-pending-tasks] on the
+    /// calling task's runtime, so call it periodically, e.g. from a timer task.
+    ///
+    /// The reloaded value replaces the current one only if the entry has not been
+    /// updated, invalidated or expired while it was being reloaded.
+    ///
+    /// Unlike `time_to_live`, the entry is not removed after the duration; use
+    /// `refresh_after_write` together with a longer `time_to_live` to bound how old a
+    /// value can be when it is not read.
+    ///
+    /// [run-pending-tasks]: ./struct.Cache.html#method.run_pending_tasks
+    pub fn refresh_after_write(self, duration: Duration) -> Self {
+        Self {
+            refresh_after_write: Some(duration),
+            ..self
+        }
+    }
+
+    /// Sets the reloader closure of the cache, which is called to reload the value
+    /// of an entry when the entry needs to be refreshed. See
+    /// [`refresh_after_write`](#method.refresh_after_write) for more details.
+    ///
+    /// The closure takes the key and the current value, and returns a future that
+    /// resolves to `Some(value)` to replace the current value, or `None` to keep
+    /// it. If the future panics, the current value is kept too.
+    ///
+    /// The future is driven by [`Cache::run_pending_tasks`][run-pending-tasks] on
+    /// the calling task's runtime, so it can use the runtime's timers and I/O types.
+    ///
+    /// [run-pending-tasks]: ./struct.Cache.html#method.run_pending_tasks
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::future::Cache;
+    /// use std::time::Duration;
+    ///
+    /// let cache = Cache::builder()
+    ///     .refresh_after_write(Duration::from_secs(60))
+    ///     .reloader(|key: &u32, _old_value: &String| {
+    ///         let key = *key;
+    ///         async move { Some(format!("value of {}", key)) }
+    ///     })
+    ///     .build();
+    /// # drop(cache);
+    /// ```
+    pub fn reloader<F, Fut>(self, reloader: F) -> Self
+    where
+        F: Fn(&K, &V) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<V>> + Send + 'static,
+    {
+        let reloader: Reloader<K, V> = Arc::new(move |k, v| reloader(k, v).boxed());
+        Self {
+            reloader: Some(reloader),
+            ..self
+        }
+    }
+
+    /// Enables the statistics of the cache with the built-in
+    /// [`ConcurrentStatsCounter`][concurrent-stats-counter]. (Default: disabled)
+    ///
+    /// The cache will record the hit and miss counts, the load success and
+    /// failure counts, the total load time, and the eviction count and weight.
+    /// Use [`Cache::stats`][cache-stats] method to get a snapshot of them.
+    ///
+    /// Recording the statistics has a small performance penalty on every cache
+    /// read and load.
+    ///
+    /// [concurrent-stats-counter]: ../stats/struct.ConcurrentStatsCounter.html
+    /// [cache-stats]: ./struct.Cache.html#method.stats
+    pub fn record_stats(self) -> Self {
+        Self {
+            stats_counter: Some(Arc::new(ConcurrentStatsCounter::default())),
+            ..self
+        }
+    }
+
+    /// Enables the statistics of the cache with the given
+    /// [`StatsCounter`][stats-counter]. Use this method instead of `record_stats`
+    /// to forward the statistics to your metrics system.
+    ///
+    /// The `stats` method of the cache will return the snapshot created by the
+    /// `snapshot` method of the given counter.
+    ///
+    /// [stats-counter]: ../stats/trait.StatsCounter.html
+    pub fn stats_counter(self, counter: impl StatsCounter + 'static) -> Self {
+        Self {
+            stats_counter: Some(Arc::new(counter)),
+            ..self
+        }
+    }
+
+    /// Specify whether or not to enable the thread pool for housekeeping tasks.
+    /// These tasks include removing expired entries and updating the LRU queue and
+    /// LFU filter. `true` to enable and `false` to disable. (Default: `true`)
+    ///
+    /// If disabled, the housekeeping tasks will be executed by a client task when
+    /// necessary. Note that they are blocking; they acquire a blocking lock and do
+    /// not yield to the runtime, so they may block the async executor thread for a
+    /// while. The same goes for the scanning tasks of the invalidation closures and
+    /// a non-async eviction listener when they are run by the client tasks.
+    ///
+    /// If disabled and no [`executor`](#method.executor) is set, the cache will
+    /// have no background threads at all. The scanning tasks of the invalidation
+    /// closures and the queued eviction notifications will also be executed by the
+    /// client tasks and [`Cache::run_pending_tasks`][run-pending-tasks]. When the
+    /// notification queue is full with `OverflowPolicy::Block`, the client task
+    /// delivers the oldest queued notifications by itself to make room.
+    ///
+    /// [run-pending-tasks]: ./struct.Cache.html#method.run_pending_tasks
+    pub fn thread_pool_enabled(self, v: bool) -> Self {
+        Self {
+            thread_pool_enabled: v,
+            ..self
+        }
+    }
+
+    /// Sets the executor to run the background tasks of the cache, instead of the
+    /// global thread pools of this crate.
+    ///
+    /// The tasks include the housekeeping tasks and the scanning tasks of the
+    /// invalidation closures. If
+    /// `thread_pool_enabled` is set to `false`, the housekeeping tasks will be
+    /// executed by a client task as described there, but the other tasks still run
+    /// on the executor.
+    ///
+    /// The tasks are blocking. When running them on an async runtime, use its
+    /// blocking thread pool such as `tokio::task::spawn_blocking`. See
+    /// [`Executor`][executor-trait] for details.
+    ///
+    /// [executor-trait]: ../executor/trait.Executor.html
+    pub fn executor(self, executor: impl Executor + 'static) -> Self {
+        Self {
+            executor: Some(Arc::new(executor)),
+            ..self
+        }
+    }
+
+    /// Specify whether or not to schedule the housekeeping at the next expiration
+    /// time of the cache. (Default: `false`)
+    ///
+    /// By default, expired entries are removed by the housekeeping that runs
+    /// periodically, or when the cache is read or written, so an eviction listener
+    /// may be notified some time after the entries have expired. If enabled, the
+    /// housekeeping is also scheduled at the time when the next entry will expire,
+    /// so the listener is notified close to the real deadline, e.g. when a session
+    /// or a lease times out.
+    ///
+    /// The next expiration time is found when the housekeeping applies the
+    /// pending writes to the cache, so a newly inserted entry is taken into
+    /// account after the next housekeeping. The entries with per-entry expiration
+    /// times (`expire_after`) are tracked with a granularity of about one second.
+    ///
+    /// This option requires the housekeeping to run in the background, and has no
+    /// effect if `thread_pool_enabled` is set to `false`.
+    pub fn expiration_scheduling_enabled(self, v: bool) -> Self {
+        Self {
+            expiration_scheduling_enabled: v,
+            ..self
+        }
+    }
+
+    /// Sets the [`Ticker`][ticker-trait] to read the current time from, instead of
+    /// the system clock.
+    ///
+    /// Together with a [`MockTicker`][mock-ticker] and the
+    /// [`deterministic_mode`](#method.deterministic_mode), you can write fast and
+    /// reproducible tests of the expirations without sleeping.
+    ///
+    /// [ticker-trait]: ../ticker/trait.Ticker.html
+    /// [mock-ticker]: ../ticker/struct.MockTicker.html
+    pub fn ticker(self, ticker: impl Ticker + 'static) -> Self {
+        Self {
+            ticker: Some(Arc::new(ticker)),
+            ..self
+        }
+    }
+
+    /// Specify whether or not to run the cache in the deterministic mode, which is
+    /// intended for tests. (Default: `false`)
+    ///
+    /// In the deterministic mode, the cache has no background threads. All pending
+    /// maintenance tasks, including the housekeeping, the scanning tasks of the
+    /// invalidation closures and the delivery of the queued eviction notifications,
+    /// are run by the calling task until they are done when
+    /// [`ConcurrentCacheExt::sync`][sync-method] is called. Call
+    /// [`run_pending_tasks`][run-pending-tasks] instead to also run the reloads for
+    /// `refresh_after_write`.
+    ///
+    /// This mode takes precedence over `thread_pool_enabled`, `executor` and
+    /// `expiration_scheduling_enabled`.
+    ///
+    /// [sync-method]: ./trait.ConcurrentCacheExt.html#tymethod.sync
+    /// [run-pending-tasks]: ./struct.Cache.html#method.run_pending_tasks
+    pub fn deterministic_mode(self, v: bool) -> Self {
+        Self {
+            deterministic_mode: v,
+            ..self
+        }
+    }
+}
+
+impl<K, V, C> CacheBuilder<K, V, C>
+where
+    K: 'static,
+    V: 'static,
+{
+    fn weigher_with_overhead(&self) -> Option<Weigher<K, V>> {
+        let overhead = if self.include_entry_overhead {
+            Some(concurrent::entry_overhead::<K, V>(
+                self.time_to_live.is_some() || self.invalidator_enabled,
+                self.expiry.is_some(),
+            ))
+        } else {
+            None
+        };
+        builder_utils::weigher(self.weigher.clone(), overhead)
+    }
 }
 
 #[cfg(test)]
@@ -1,29 +1,40 @@
 use super::{
     value_initializer::{InitResult, ValueInitializer},
     CacheBuilder, ConcurrentCacheExt, Iter, OwnedKeyEntrySelector, PredicateId,
-    RefKeyEntrySelector,
+    RefKeyEntrySelector, RefresherArc,
 };
 use crate::{
     common::{
         concurrent::{
-            constants::{MAX_SYNC_REPEATS, WRITE_RETRY_INTERVAL_MICROS},
+            constants::WRITE_RETRY_INTERVAL_MICROS,
             housekeeper::{self, InnerSync},
-            Weigher, WriteOp,
+            ExpiryArc, KvEntry, Weigher, WriteOp,
         },
         time::Instant,
     },
-    notification::{self, EvictionListener},
+    notification::{self, Listener, RemovalCause},
+    ops::compute::Op,
+    stats::{CacheStats, StatsCounterArc},
     sync_base::base_cache::{BaseCache, HouseKeeperArc},
+    ticker::TickerArc,
     Entry, Policy, PredicateError,
 };
 
 #[cfg(feature = "unstable-debug-counters")]
 use crate::common::concurrent::debug_counters::CacheDebugStats;
 
+#[cfg(feature = "serde")]
+use crate::{sync_base::snapshot, SnapshotError};
+#[cfg(feature = "serde")]
+use serde_crate::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "serde")]
+use std::io::{Read, Write};
+
 use crossbeam_channel::{Sender, TrySendError};
+use parking_lot::Mutex;
 use std::{
     borrow::Borrow,
-    collections::hash_map::RandomState,
+    collections::{hash_map::RandomState, HashMap, HashSet},
     fmt,
     future::Future,
     hash::{BuildHasher, Hash},
@@ -445,23 +456,23 @@ use std::{
 /// ## Delivery Modes for Eviction Listener
 ///
 /// The [`DeliveryMode`][delivery-mode] specifies how and when an eviction
-/// notification should be delivered to an eviction listener. Currently, the
-/// `future::Cache` supports only one delivery mode: `Queued` mode.
-///
-/// A future version of `future::Cache` will support `Immediate` mode, which will be
-/// easier to use in many use cases than queued mode. Unlike the `future::Cache`,
-/// the `sync::Cache` already supports it.
+/// notification should be delivered to an eviction listener. The `future::Cache`
+/// supports the both delivery modes, depending on the kind of the listener:
 ///
-/// Once `future::Cache` supports the immediate mode, the `eviction_listener` and
-/// `eviction_listener_with_conf` methods will be added to the
-/// `future::CacheBuilder`. The former will use the immediate mode, and the latter
-/// will take a custom configurations to specify the queued mode. The current method
-/// `eviction_listener_with_queued_delivery_mode` will be deprecated.
+/// - A listener closure set by
+///   [`eviction_listener_with_queued_delivery_mode`][queued-listener] uses the
+///   `Queued` mode. It is called by a notifier thread pool.
+/// - A listener closure set by [`async_eviction_listener`][async-listener] returns
+///   a future, and uses the `Immediate` mode. The futures are awaited by the async
+///   write methods of the cache, such as `insert` and `invalidate`, before they
+///   return.
 ///
 /// For more details about the delivery modes, see [this section][sync-delivery-modes]
 /// of `sync::Cache` documentation.
 ///
 /// [delivery-mode]: ../notification/enum.DeliveryMode.html
+/// [queued-listener]: ./struct.CacheBuilder.html#method.eviction_listener_with_queued_delivery_mode
+/// [async-listener]: ./struct.CacheBuilder.html#method.async_eviction_listener
 /// [sync-delivery-modes]: ../sync/struct.Cache.html#delivery-modes-for-eviction-listener
 ///
 /// # Thread Safety
@@ -509,8 +520,14 @@ use std::{
 pub struct Cache<K, V, S = RandomState> {
     base: BaseCache<K, V, S>,
     value_initializer: Arc<ValueInitializer<K, V, S>>,
+    refresher: Option<RefresherArc<K, V>>,
+    /// The reloads waiting for `run_pending_tasks` to drive them.
+    pending_reloads: Option<PendingReloads<K, V>>,
 }
 
+/// The entries to be reloaded and the hashes of their keys.
+type PendingReloads<K, V> = Arc<Mutex<Vec<(KvEntry<K, V>, u64)>>>;
+
 // TODO: https://github.com/moka-rs/moka/issues/54
 #[allow(clippy::non_send_fields_in_send_ty)]
 unsafe impl<K, V, S> Send for Cache<K, V, S>
@@ -539,6 +556,8 @@ impl<K, V, S> Clone for Cache<K, V, S> {
         Self {
             base: self.base.clone(),
             value_initializer: Arc::clone(&self.value_initializer),
+            refresher: self.refresher.as_ref().map(Arc::clone),
+            pending_reloads: self.pending_reloads.as_ref().map(Arc::clone),
         }
     }
 }
@@ -632,6 +651,34 @@ impl<K, V, S> Cache<K, V, S> {
         self.base.weighted_size()
     }
 
+    /// Returns a snapshot of the statistics of this cache.
+    ///
+    /// The statistics are recorded only when the cache was built with
+    /// [`CacheBuilder::record_stats`][builder-record-stats]. Otherwise, all counters
+    /// in the returned `CacheStats` are zero.
+    ///
+    /// The eviction counts are updated by the pending internal tasks, so they can be
+    /// behind the actual numbers. Performing a `sync()` first will mitigate this.
+    ///
+    /// [builder-record-stats]: ./struct.CacheBuilder.html#method.record_stats
+    pub fn stats(&self) -> CacheStats {
+        self.base.stats()
+    }
+
+    /// Returns the number of the eviction notifications dropped because the queue
+    /// of the [`Queued`][delivery-mode-queued] delivery mode or the queue of the
+    /// async eviction listener was full.
+    ///
+    /// For the queued delivery mode, the notifications are dropped only with the
+    /// `DropNewest` or `DropOldest` [`OverflowPolicy`][overflow-policy]. Unlike [`stats`](#method.stats), this
+    /// count is kept even if the cache was built without `record_stats`.
+    ///
+    /// [delivery-mode-queued]: ../notification/enum.DeliveryMode.html#variant.Queued
+    /// [overflow-policy]: ../notification/enum.OverflowPolicy.html
+    pub fn dropped_notification_count(&self) -> u64 {
+        self.base.dropped_notification_count()
+    }
+
     #[cfg(feature = "unstable-debug-counters")]
     #[cfg_attr(docsrs, doc(cfg(feature = "unstable-debug-counters")))]
     pub fn debug_stats(&self) -> CacheDebugStats {
@@ -662,8 +709,12 @@ where
             None,
             None,
             None,
+            None,
             false,
-            housekeeper::Configuration::new_thread_pool(true),
+            None,
+            None,
+            None,
+            housekeeper::Configuration::new_thread_pool(true, None),
         )
     }
 
@@ -690,28 +741,39 @@ where
         initial_capacity: Option<usize>,
         build_hasher: S,
         weigher: Option<Weigher<K, V>>,
-        eviction_listener: Option<EvictionListener<K, V>>,
+        eviction_listener: Option<Listener<K, V>>,
         eviction_listener_conf: Option<notification::Configuration>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryArc<K, V>>,
         invalidator_enabled: bool,
+        stats_counter: Option<StatsCounterArc>,
+        refresher: Option<RefresherArc<K, V>>,
+        ticker: Option<TickerArc>,
         housekeeper_conf: housekeeper::Configuration,
     ) -> Self {
+        let base = BaseCache::new(
+            name,
+            max_capacity,
+            initial_capacity,
+            build_hasher.clone(),
+            weigher,
+            eviction_listener,
+            eviction_listener_conf,
+            time_to_live,
+            time_to_idle,
+            expiry,
+            invalidator_enabled,
+            stats_counter,
+            ticker,
+            housekeeper_conf,
+        );
+        let pending_reloads = refresher.as_ref().map(|_| Default::default());
         Self {
-            base: BaseCache::new(
-                name,
-                max_capacity,
-                initial_capacity,
-                build_hasher.clone(),
-                weigher,
-                eviction_listener,
-                eviction_listener_conf,
-                time_to_live,
-                time_to_idle,
-                invalidator_enabled,
-                housekeeper_conf,
-            ),
+            base,
             value_initializer: Arc::new(ValueInitializer::with_hasher(build_hasher)),
+            refresher,
+            pending_reloads,
         }
     }
 
@@ -731,6 +793,14 @@ where
         self.base.contains_key_with_hash(key, self.base.hash(key))
     }
 
+    pub(crate) fn hash<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.base.hash(key)
+    }
+
     /// Returns a _clone_ of the value corresponding to the key.
     ///
     /// If you want to store values that will be expensive to clone, wrap them by
@@ -746,11 +816,59 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.base
-            .get_with_hash(key, self.base.hash(key), false)
+        self.get_with_hash(key, self.base.hash(key), false)
             .map(Entry::into_value)
     }
 
+    fn get_with_hash<Q>(&self, key: &Q, hash: u64, need_key: bool) -> Option<Entry<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.base.get_with_hash(key, hash, need_key);
+        if entry.is_some() {
+            self.refresh_if_stale(key, hash);
+        }
+        entry
+    }
+
+    /// Returns a `HashMap` of the keys and _clones_ of the values for the given
+    /// keys. The keys that do not exist in the cache are not included.
+    ///
+    /// Each key is looked up like [`get`](#method.get) method, so the lookups are
+    /// recorded as cache reads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(100);
+    ///     cache.insert(1, "one").await;
+    ///     cache.insert(2, "two").await;
+    ///
+    ///     let values = cache.get_all(vec![1, 2, 3]);
+    ///     assert_eq!(values.len(), 2);
+    ///     assert_eq!(values.get(&1), Some(&"one"));
+    ///     assert_eq!(values.get(&2), Some(&"two"));
+    /// }
+    /// ```
+    pub fn get_all<I>(&self, keys: I) -> HashMap<K, V>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        keys.into_iter()
+            .filter_map(|key| self.get(&key).map(|value| (key, value)))
+            .collect()
+    }
+
     /// Takes a key `K` and returns an [`OwnedKeyEntrySelector`] that can be used to
     /// select or insert an entry.
     ///
@@ -1197,6 +1315,93 @@ where
             .map(Entry::into_value)
     }
 
+    /// Returns a `HashMap` of the keys and _clones_ of the values for the given
+    /// keys. The values of the keys that do not exist in the cache are loaded in a
+    /// batch by resolving the future returned by the `init` closure, and inserted to
+    /// the cache.
+    ///
+    /// The `init` closure takes the missing keys and must return a future that
+    /// resolves to their values in the same order. As the future cannot borrow
+    /// the keys, clone them if the future needs them.
+    ///
+    /// # Concurrent calls on the same key
+    ///
+    /// Like [`get_with`](#method.get_with), this method guarantees that concurrent
+    /// calls on the same missing key are coalesced into one evaluation. If some of
+    /// the missing keys are being loaded by other async tasks (by `get_with` or
+    /// `get_all_with`), they are not passed to the `init` closure, and this method
+    /// waits for and returns the values loaded by the other tasks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(100);
+    ///     cache.insert(1, "one".to_string()).await;
+    ///
+    ///     let values = cache
+    ///         .get_all_with(vec![1, 2, 3], |keys| {
+    ///             // Only the keys 2 and 3 are loaded, e.g. by a single database query.
+    ///             assert_eq!(keys.len(), 2);
+    ///             let keys = keys.iter().map(|k| **k).collect::<Vec<u32>>();
+    ///             async move { keys.iter().map(|k| format!("value of {}", k)).collect() }
+    ///         })
+    ///         .await;
+    ///     assert_eq!(values.len(), 3);
+    ///     assert_eq!(values[&1], "one");
+    ///     assert_eq!(values[&2], "value of 2");
+    ///     assert!(cache.contains_key(&3));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method panics when the `init` future has panicked, or it has resolved
+    /// to a `Vec` whose length differs from the number of the given keys. When it
+    /// happens, only the caller whose `init` future failed will get the panic.
+    /// Other calls waiting for the keys will retry.
+    pub async fn get_all_with<I, F, Fut>(&self, keys: I, mut init: F) -> HashMap<K, V>
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(&[&K]) -> Fut,
+        Fut: Future<Output = Vec<V>>,
+        K: Clone,
+    {
+        use futures_util::FutureExt;
+
+        let (mut found, missing) = self.get_all_or_collect_missing(keys);
+        if missing.is_empty() {
+            self.base.run_pending_notifications().await;
+            return found;
+        }
+
+        let get = |key: &Arc<K>| self.get_with_arc_key_but_no_recording(key);
+        let insert = |key: Arc<K>, v| {
+            let hash = self.base.hash(&*key);
+            self.insert_with_hash(key, hash, v).boxed()
+        };
+        let init = |keys: Vec<Arc<K>>| {
+            let keys = keys.iter().map(|k| &**k).collect::<Vec<_>>();
+            self.base.load_with_stats_async(init(&keys), |_| true)
+        };
+
+        let entries = self
+            .value_initializer
+            .init_or_read_all(missing, get, init, insert)
+            .await;
+        crossbeam_epoch::pin().flush();
+        found.extend(entries.into_iter().map(|(k, v)| (K::clone(&k), v)));
+        found
+    }
+
     /// Inserts a key-value pair into the cache.
     ///
     /// If the cache has this key present, the value is updated.
@@ -1206,93 +1411,606 @@ where
         self.insert_with_hash(key, hash, value).await
     }
 
-    fn do_blocking_insert(&self, key: K, value: V) {
+    /// Inserts the key-value pairs into the cache.
+    ///
+    /// This is faster than calling [`insert`](#method.insert) for each pair, as
+    /// the pending writes are applied to the cache policy (admission and eviction)
+    /// once for the batch, or once whenever the internal write log is full, rather
+    /// than checked for each pair. Use it for example to warm up a cache.
+    ///
+    /// If the cache has a key present, the value is updated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(10_000);
+    ///     cache.insert_all((0..5_000).map(|i| (i, i * 2))).await;
+    ///     assert_eq!(cache.get(&100), Some(200));
+    /// }
+    /// ```
+    pub async fn insert_all<I>(&self, entries: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let ops = entries.into_iter().map(|(key, value)| {
+            let hash = self.base.hash(&key);
+            let (op, _now) = self
+                .base
+                .do_insert_with_hash(Arc::new(key), hash, value, None);
+            op
+        });
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_ops(self.base.inner.as_ref(), &self.base.write_op_ch, ops, hk)
+            .await
+            .expect("Failed to insert");
+        self.base.run_pending_notifications().await;
+    }
+
+    /// Inserts a key-value pair into the cache only if the key is absent. Returns
+    /// `None` if the value was inserted, or `Some` with a clone of the existing
+    /// value if the key was present. The check and the insertion are done
+    /// atomically.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(100);
+    ///     assert_eq!(cache.insert_if_absent("session1", "alice").await, None);
+    ///     assert_eq!(cache.insert_if_absent("session1", "bob").await, Some("alice"));
+    ///     assert_eq!(cache.get(&"session1"), Some("alice"));
+    /// }
+    /// ```
+    pub async fn insert_if_absent(&self, key: K, value: V) -> Option<V> {
         let hash = self.base.hash(&key);
         let key = Arc::new(key);
-        let (op, now) = self.base.do_insert_with_hash(key, hash, value);
+        let (op, now) = match self.base.do_insert_if_absent_with_hash(key, hash, value) {
+            Ok(op_and_now) => op_and_now,
+            Err(existing) => return Some(existing),
+        };
         let hk = self.base.housekeeper.as_ref();
-        Self::blocking_schedule_write_op(
+        Self::schedule_write_op(
             self.base.inner.as_ref(),
             &self.base.write_op_ch,
             op,
             now,
             hk,
         )
+        .await
         .expect("Failed to insert");
+        self.base.run_pending_notifications().await;
+        None
     }
 
-    /// Discards any cached value for the key.
+    /// Replaces the value of the key with `new` only if the current value is equal
+    /// to `old`. Returns `true` if the value was replaced. The comparison and the
+    /// replacement are done atomically.
+    ///
+    /// If the eviction listener is set, it will be notified of the old value with
+    /// [`RemovalCause::Replaced`][removal-cause-replaced].
     ///
     /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
     /// on the borrowed form _must_ match those for the key type.
-    pub async fn invalidate<Q>(&self, key: &Q)
+    ///
+    /// [removal-cause-replaced]: ../notification/enum.RemovalCause.html#variant.Replaced
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(100);
+    ///     cache.insert("session1", 1).await;
+    ///
+    ///     assert!(!cache.replace(&"session1", &0, 2).await);
+    ///     assert!(cache.replace(&"session1", &1, 2).await);
+    ///     assert_eq!(cache.get(&"session1"), Some(2));
+    /// }
+    /// ```
+    pub async fn replace<Q>(&self, key: &Q, old: &V, new: V) -> bool
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
+        V: PartialEq,
     {
         let hash = self.base.hash(key);
-        if let Some(kv) = self.base.remove_entry(key, hash) {
-            if self.base.is_removal_notifier_enabled() {
-                self.base.notify_invalidate(&kv.key, &kv.entry)
-            }
-            let op = WriteOp::Remove(kv);
-            let now = self.base.current_time_from_expiration_clock();
-            let hk = self.base.housekeeper.as_ref();
-            Self::schedule_write_op(
-                self.base.inner.as_ref(),
-                &self.base.write_op_ch,
-                op,
-                now,
-                hk,
-            )
+        let (op, now) = match self.base.do_replace_with_hash(key, hash, old, new) {
+            Some(op_and_now) => op_and_now,
+            None => return false,
+        };
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            op,
+            now,
+            hk,
+        )
+        .await
+        .expect("Failed to insert");
+        self.base.run_pending_notifications().await;
+        true
+    }
+
+    /// Inserts a key-value pair into the cache with its own time-to-live.
+    ///
+    /// The entry will expire after the given `ttl` from now, regardless of the
+    /// `time_to_live` and `expire_after` settings of the cache. If the cache has
+    /// this key present, the value and its expiration time are updated. A later
+    /// `insert` without a TTL puts the entry back under the expiration policies of
+    /// the cache.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.10", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    ///
+    /// use moka::future::Cache;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::builder()
+    ///         .time_to_live(Duration::from_secs(60 * 60))
+    ///         .build();
+    ///
+    ///     // This entry will expire after 5 minutes instead of an hour.
+    ///     cache
+    ///         .insert_with_ttl("session", "token", Duration::from_secs(5 * 60))
+    ///         .await;
+    ///     assert_eq!(cache.get(&"session"), Some("token"));
+    /// }
+    /// ```
+    pub async fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        let hash = self.base.hash(&key);
+        let key = Arc::new(key);
+        self.insert_with_hash_and_ttl(key, hash, value, Some(ttl))
             .await
-            .expect("Failed to remove");
-            crossbeam_epoch::pin().flush();
-        }
     }
 
-    fn do_blocking_invalidate<Q>(&self, key: &Q)
+    fn do_blocking_insert(&self, key: K, value: V, ttl: Option<Duration>) {
+        let hash = self.base.hash(&key);
+        let key = Arc::new(key);
+        let (op, now) = self.base.do_insert_with_hash(key, hash, value, ttl);
+        let hk = self.base.housekeeper.as_ref();
+        Self::blocking_schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            op,
+            now,
+            hk,
+        )
+        .expect("Failed to insert");
+    }
+
+    /// Discards any cached value for the key.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub async fn invalidate<Q>(&self, key: &Q)
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
         let hash = self.base.hash(key);
-        if let Some(kv) = self.base.remove_entry(key, hash) {
-            let op = WriteOp::Remove(kv);
-            let now = self.base.current_time_from_expiration_clock();
-            let hk = self.base.housekeeper.as_ref();
-            Self::blocking_schedule_write_op(
-                self.base.inner.as_ref(),
-                &self.base.write_op_ch,
-                op,
-                now,
-                hk,
-            )
-            .expect("Failed to remove");
-        }
+        self.do_invalidate_with_hash(key, hash, false).await;
     }
 
-    /// Discards all cached values.
+    /// Discards any cached value for the key and returns a _clone_ of the value.
     ///
-    /// This method returns immediately and a background thread will evict all the
-    /// cached values inserted before the time when this method was called. It is
-    /// guaranteed that the `get` method must not return these invalidated values
-    /// even if they have not been evicted.
+    /// Returns `None` if the key does not exist, or the entry has been expired or
+    /// invalidated (the entry will still be removed from the cache).
     ///
-    /// Like the `invalidate` method, this method does not clear the historic
-    /// popularity estimator of keys so that it retains the client activities of
-    /// trying to retrieve an item.
-    pub fn invalidate_all(&self) {
-        self.base.invalidate_all();
-    }
-
-    /// Discards cached values that satisfy a predicate.
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
     ///
-    /// `invalidate_entries_if` takes a closure that returns `true` or `false`. This
-    /// method returns immediately and a background thread will apply the closure to
-    /// each cached value inserted before the time when `invalidate_entries_if` was
-    /// called. If the closure returns `true` on a value, that value will be evicted
-    /// from the cache.
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(100);
+    ///     cache.insert("upload1", vec![1u8, 2, 3]).await;
+    ///
+    ///     assert_eq!(cache.remove(&"upload1").await, Some(vec![1, 2, 3]));
+    ///     assert_eq!(cache.remove(&"upload1").await, None);
+    /// }
+    /// ```
+    pub async fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).await.map(|(_k, v)| v)
+    }
+
+    /// Works like [`remove`](#method.remove), but returns the key along with the
+    /// value.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub async fn remove_entry<Q>(&self, key: &Q) -> Option<(Arc<K>, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.base.hash(key);
+        self.do_invalidate_with_hash(key, hash, true).await
+    }
+
+    /// Removes the entry of the key. If `need_value` is `true`, returns the key and
+    /// a clone of the value if the entry was neither expired nor invalidated.
+    async fn do_invalidate_with_hash<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        need_value: bool,
+    ) -> Option<(Arc<K>, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let kv = self.remove_with_hash_and_fun(key, hash, RemovalCause::Explicit, || {
+            self.base.remove_entry(key, hash)
+        })?;
+        let now = self.base.current_time_from_expiration_clock();
+        let maybe_entry = if need_value && self.base.is_valid_entry(&kv.key, &kv.entry, now) {
+            Some((Arc::clone(&kv.key), kv.entry.value.clone()))
+        } else {
+            None
+        };
+
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            WriteOp::Remove(kv),
+            now,
+            hk,
+        )
+        .await
+        .expect("Failed to remove");
+        self.base.run_pending_notifications().await;
+        crossbeam_epoch::pin().flush();
+        maybe_entry
+    }
+
+    /// Discards the cached value for the key only if the `predicate` returns `true`
+    /// for the value. Returns the removed value, or `None` if the key was absent or
+    /// the `predicate` returned `false`. The check and the removal are done
+    /// atomically.
+    ///
+    /// If the eviction listener is set, it will be notified of the removed value
+    /// with [`RemovalCause::Explicit`][removal-cause-explicit].
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    ///
+    /// [removal-cause-explicit]: ../notification/enum.RemovalCause.html#variant.Explicit
+    pub async fn remove_if<Q, F>(&self, key: &Q, predicate: F) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnMut(&V) -> bool,
+    {
+        let hash = self.base.hash(key);
+        let kv = self.remove_with_hash_and_fun(key, hash, RemovalCause::Explicit, || {
+            self.base.remove_valid_entry_if(key, hash, predicate)
+        })?;
+        let value = kv.entry.value.clone();
+        let now = self.base.current_time_from_expiration_clock();
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            WriteOp::Remove(kv),
+            now,
+            hk,
+        )
+        .await
+        .expect("Failed to remove");
+        self.base.run_pending_notifications().await;
+        crossbeam_epoch::pin().flush();
+        Some(value)
+    }
+
+    /// Discards any cached values for the keys.
+    ///
+    /// Like [`insert_all`](#method.insert_all), this is faster than calling
+    /// [`invalidate`](#method.invalidate) for each key, as the removals are
+    /// applied to the cache policy in bulk.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub async fn invalidate_all_keys<'a, I, Q>(&self, keys: I)
+    where
+        I: IntoIterator<Item = &'a Q>,
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+    {
+        self.invalidate_keys_with_cause(keys, RemovalCause::Explicit)
+            .await
+    }
+
+    async fn invalidate_keys_with_cause<'a, I, Q>(&self, keys: I, cause: RemovalCause)
+    where
+        I: IntoIterator<Item = &'a Q>,
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+    {
+        let ops = keys.into_iter().filter_map(|key| {
+            let hash = self.base.hash(key);
+            let kv = self
+                .remove_with_hash_and_fun(key, hash, cause, || self.base.remove_entry(key, hash))?;
+            Some(WriteOp::Remove(kv))
+        });
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_ops(self.base.inner.as_ref(), &self.base.write_op_ch, ops, hk)
+            .await
+            .expect("Failed to remove");
+        self.base.run_pending_notifications().await;
+        crossbeam_epoch::pin().flush();
+    }
+
+    /// Removes the entry by calling the `remove` closure, and queues the removal
+    /// notification if the entry was removed.
+    ///
+    /// This is not an async method, so that the key lock for the async eviction
+    /// listener is never held across an `.await`.
+    fn remove_with_hash_and_fun<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        cause: RemovalCause,
+        remove: impl FnOnce() -> Option<KvEntry<K, V>>,
+    ) -> Option<KvEntry<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // Lock the key for removal if the async eviction listener is set, so that
+        // the notifications for the key are queued in the order of the writes.
+        let mut kl = None;
+        let mut klg = None;
+        if self.base.is_removal_notifier_enabled() && self.base.is_blocking_removal_notification() {
+            if let Some(arc_key) = self.base.get_key_with_hash(key, hash) {
+                kl = self.base.maybe_key_lock(&arc_key);
+                klg = kl.as_ref().map(|kl| kl.lock());
+            }
+        }
+
+        let kv = remove()?;
+        if self.base.is_removal_notifier_enabled() {
+            self.base.notify_invalidate(&kv.key, &kv.entry, cause)
+        }
+        std::mem::drop(klg);
+        std::mem::drop(kl);
+        Some(kv)
+    }
+
+    fn do_blocking_invalidate<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.base.hash(key);
+        if let Some(kv) = self.base.remove_entry(key, hash) {
+            let op = WriteOp::Remove(kv);
+            let now = self.base.current_time_from_expiration_clock();
+            let hk = self.base.housekeeper.as_ref();
+            Self::blocking_schedule_write_op(
+                self.base.inner.as_ref(),
+                &self.base.write_op_ch,
+                op,
+                now,
+                hk,
+            )
+            .expect("Failed to remove");
+        }
+    }
+
+    /// Discards all cached values.
+    ///
+    /// This method returns immediately and a background thread will evict all the
+    /// cached values inserted before the time when this method was called. It is
+    /// guaranteed that the `get` method must not return these invalidated values
+    /// even if they have not been evicted.
+    ///
+    /// Like the `invalidate` method, this method does not clear the historic
+    /// popularity estimator of keys so that it retains the client activities of
+    /// trying to retrieve an item.
+    pub fn invalidate_all(&self) {
+        self.base.invalidate_all();
+    }
+
+    /// Performs the pending maintenance tasks of the cache in the calling task,
+    /// and waits for them to finish.
+    ///
+    /// The tasks include applying the recorded reads and writes to the cache
+    /// policy, removing expired entries, evicting entries to keep the max capacity,
+    /// and delivering the eviction notifications.
+    ///
+    /// This method also drives the reloads queued for `refresh_after_write` as
+    /// futures on the calling task's runtime. The reloads only run by this method,
+    /// so call it periodically, e.g. from a timer task of your runtime, when the
+    /// cache is built with a [`reloader`][builder-reloader].
+    ///
+    /// If the cache was built with `thread_pool_enabled(false)` and without an
+    /// [`executor`][builder-executor], the cache has no background threads and
+    /// this method also runs the scanning tasks of the invalidation closures and
+    /// the queued eviction notifications. Such a cache also does these tasks when
+    /// needed while it is being read or written.
+    ///
+    /// # Blocking
+    ///
+    /// Only the reloads and the deliveries to an async eviction listener are
+    /// awaited. The other tasks are run synchronously in the calling task without
+    /// yielding to the runtime: applying the recorded operations acquires a
+    /// blocking lock, a scanning task of the invalidation closures can visit all
+    /// the entries, and a non-async eviction listener is called directly. So this
+    /// method can block the runtime's worker thread for a while on a large cache
+    /// or with a slow listener. If that matters, call it from a blocking thread
+    /// (e.g. `tokio::task::spawn_blocking` with a `block_on` of the runtime
+    /// handle), or keep the thread pools or an [`executor`][builder-executor]
+    /// enabled.
+    ///
+    /// [builder-executor]: ./struct.CacheBuilder.html#method.executor
+    /// [builder-reloader]: ./struct.CacheBuilder.html#method.reloader
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     // A cache without any background threads.
+    ///     let cache = Cache::builder()
+    ///         .max_capacity(100)
+    ///         .support_invalidation_closures()
+    ///         .thread_pool_enabled(false)
+    ///         .build();
+    ///
+    ///     cache.insert("alice", 1).await;
+    ///     cache.insert("bob", 2).await;
+    ///     cache
+    ///         .invalidate_entries_if(|_k, v| *v == 1)
+    ///         .expect("Invalidation closures are supported");
+    ///
+    ///     cache.run_pending_tasks().await;
+    ///     assert_eq!(cache.entry_count(), 1);
+    /// }
+    /// ```
+    pub async fn run_pending_tasks(&self) {
+        self.base.run_pending_tasks();
+
+        if let Some(reloads) = &self.pending_reloads {
+            let reloads = std::mem::take(&mut *reloads.lock());
+            let reloads = reloads.into_iter().map(|(kv, hash)| self.reload(kv, hash));
+            futures_util::future::join_all(reloads).await;
+        }
+
+        self.base.run_pending_notifications().await;
+    }
+
+    /// Discards all cached values, and waits until all eviction notifications,
+    /// including the ones queued before this call, have been delivered to the
+    /// eviction listener.
+    ///
+    /// Dropping a cache drops its entries without notifying the eviction listener.
+    /// Call this method before dropping the cache if the listener should be
+    /// notified of every entry. The entries are notified with
+    /// [`RemovalCause::Shutdown`][removal-cause-shutdown], or `Expired` if they
+    /// have already expired.
+    ///
+    /// Unlike `invalidate_all`, this method does the removals in the calling task.
+    /// The cache can still be used after calling this method.
+    ///
+    /// If the eviction listener panics, the notifications not delivered by then are
+    /// discarded.
+    ///
+    /// [removal-cause-shutdown]: ../notification/enum.RemovalCause.html#variant.Shutdown
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::{future::Cache, notification::RemovalCause};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let evicted = Arc::new(Mutex::new(Vec::new()));
+    ///     let evicted1 = Arc::clone(&evicted);
+    ///
+    ///     let cache = Cache::builder()
+    ///         .max_capacity(100)
+    ///         .eviction_listener_with_queued_delivery_mode(move |k, _v, cause| {
+    ///             evicted1.lock().unwrap().push((k, cause));
+    ///         })
+    ///         .build();
+    ///
+    ///     cache.insert("alice", 1).await;
+    ///     cache.shutdown().await;
+    ///
+    ///     assert_eq!(
+    ///         *evicted.lock().unwrap(),
+    ///         vec![(Arc::new("alice"), RemovalCause::Shutdown)]
+    ///     );
+    ///     assert_eq!(cache.get(&"alice"), None);
+    /// }
+    /// ```
+    pub async fn shutdown(&self) {
+        let keys = self.base.all_keys();
+        self.invalidate_keys_with_cause(keys.iter().map(|k| &**k), RemovalCause::Shutdown)
+            .await;
+        self.base.run_pending_tasks();
+
+        loop {
+            self.base.run_pending_notifications().await;
+            if !self.base.has_pending_notifications() {
+                break;
+            }
+            if self.base.has_task_queue() {
+                // No background thread will deliver the queued notifications.
+                self.base.run_pending_tasks();
+            } else {
+                async_io::Timer::after(Duration::from_millis(1)).await;
+            }
+        }
+    }
+
+    /// Discards cached values that satisfy a predicate.
+    ///
+    /// `invalidate_entries_if` takes a closure that returns `true` or `false`. This
+    /// method returns immediately and a background thread will apply the closure to
+    /// each cached value inserted before the time when `invalidate_entries_if` was
+    /// called. If the closure returns `true` on a value, that value will be evicted
+    /// from the cache.
     ///
     /// Also the `get` method will apply the closure to a value to determine if it
     /// should have been invalidated. Therefore, it is guaranteed that the `get`
@@ -1389,6 +2107,89 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<K, V, S> Cache<K, V, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    /// Writes a snapshot of this cache to the `writer`, so that the cache can be
+    /// restored later by [`CacheBuilder::restore_from`][restore-from], for example
+    /// after restarting the process.
+    ///
+    /// The snapshot contains the keys and values of the entries, their remaining
+    /// time-to-live and time-to-idle, and their per-entry expiration times. It also
+    /// contains the access order of the entries and their historic popularity, so
+    /// that the restored cache will admit and evict entries as this cache would do.
+    ///
+    /// The pending reads and writes are applied to the cache before taking the
+    /// snapshot. Expired and invalidated entries are not included. Entries
+    /// inserted, updated or removed while taking the snapshot may or may not be
+    /// reflected.
+    ///
+    /// This method blocks the current thread while writing to the `writer`. To
+    /// write to an async destination, write the snapshot to a `Vec<u8>` first.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`][snapshot-error] if it fails to serialize a key or
+    /// a value, or to write to the `writer`.
+    ///
+    /// [restore-from]: ./struct.CacheBuilder.html#method.restore_from
+    /// [snapshot-error]: ../enum.SnapshotError.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future", "serde"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::future::Cache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = Cache::new(100);
+    ///     cache.insert("alice".to_string(), 1).await;
+    ///
+    ///     let mut snapshot = Vec::new();
+    ///     cache.snapshot_to(&mut snapshot).unwrap();
+    ///
+    ///     let restored: Cache<String, u32> = Cache::builder()
+    ///         .max_capacity(100)
+    ///         .restore_from(&snapshot[..])
+    ///         .unwrap();
+    ///     assert_eq!(restored.get("alice"), Some(1));
+    /// }
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn snapshot_to<W>(&self, writer: W) -> Result<(), SnapshotError>
+    where
+        K: Serialize,
+        V: Serialize,
+        W: Write,
+    {
+        let snapshot = self.base.snapshot();
+        snapshot::write_snapshot(writer, &[snapshot.as_ref()])
+    }
+
+    pub(crate) fn restore_snapshot<R>(&self, reader: R) -> Result<(), SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        R: Read,
+    {
+        for snapshot in snapshot::read_snapshot(reader)? {
+            self.base.restore(snapshot);
+        }
+        Ok(())
+    }
+}
+
 impl<'a, K, V, S> IntoIterator for &'a Cache<K, V, S>
 where
     K: Hash + Eq + Send + Sync + 'static,
@@ -1411,7 +2212,7 @@ where
     S: BuildHasher + Clone + Send + Sync + 'static,
 {
     fn sync(&self) {
-        self.base.inner.sync(MAX_SYNC_REPEATS);
+        self.base.run_pending_tasks();
     }
 }
 
@@ -1436,6 +2237,9 @@ where
             self.base
                 .get_with_hash_but_ignore_if(&key, hash, replace_if.as_mut(), need_key);
         if let Some(entry) = maybe_entry {
+            self.refresh_if_stale(&key, hash);
+            // Deliver the notifications queued by the housekeeping.
+            self.base.run_pending_notifications().await;
             entry
         } else {
             self.insert_with_hash_and_fun(key, hash, init, replace_if, need_key)
@@ -1459,6 +2263,8 @@ where
             self.base
                 .get_with_hash_but_ignore_if(key, hash, replace_if.as_mut(), need_key);
         if let Some(entry) = maybe_entry {
+            self.refresh_if_stale(key, hash);
+            self.base.run_pending_notifications().await;
             entry
         } else {
             let key = Arc::new(key.to_owned());
@@ -1482,6 +2288,7 @@ where
                 .get_with_hash_but_no_recording(&key, hash, replace_if.as_mut())
         };
         let insert = |v| self.insert_with_hash(key.clone(), hash, v).boxed();
+        let init = self.base.load_with_stats_async(init, |_| true);
 
         let k = if need_key {
             Some(Arc::clone(&key))
@@ -1503,39 +2310,128 @@ where
         }
     }
 
-    pub(crate) async fn get_or_insert_with_hash(
+    /// Evaluates the compute closure `f` with the current entry of the key, and
+    /// applies the `Op` resolved by the returned future to the cache. Returns the
+    /// entry after the operation, or `None` if the entry does not exist.
+    ///
+    /// The `Op` is applied only if the entry has not been modified since `f` read
+    /// it. Otherwise, `f` is evaluated again with the new entry.
+    pub(crate) async fn compute_with_hash_and_fun<F, Fut>(
         &self,
         key: Arc<K>,
         hash: u64,
-        init: impl FnOnce() -> V,
-    ) -> Entry<K, V> {
-        match self.base.get_with_hash(&key, hash, true) {
-            Some(entry) => entry,
-            None => {
-                let value = init();
-                self.insert_with_hash(Arc::clone(&key), hash, value.clone())
-                    .await;
-                Entry::new(Some(key), value, true)
-            }
-        }
-    }
+        mut f: F,
+    ) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Fut,
+        Fut: Future<Output = Op<V>>,
+    {
+        loop {
+            let now = self.base.current_time_from_expiration_clock();
+            let current = self.base.get_value_entry(&key, hash);
+            let current_value = current
+                .as_ref()
+                .filter(|entry| self.base.is_valid_entry(&key, entry, now))
+                .map(|entry| entry.value.clone());
+            let entry = current_value
+                .clone()
+                .map(|v| Entry::new(Some(Arc::clone(&key)), v, false));
+
+            match f(entry).await {
+                Op::Nop => return current_value.map(|v| Entry::new(Some(key), v, false)),
+                Op::Put(v) => {
+                    if let Some((op, now)) = self.base.do_compute_put_with_hash(
+                        Arc::clone(&key),
+                        hash,
+                        current.as_ref(),
+                        v.clone(),
+                    ) {
+                        let hk = self.base.housekeeper.as_ref();
+                        Self::schedule_write_op(
+                            self.base.inner.as_ref(),
+                            &self.base.write_op_ch,
+                            op,
+                            now,
+                            hk,
+                        )
+                        .await
+                        .expect("Failed to insert");
+                        self.base.run_pending_notifications().await;
+                        return Some(Entry::new(Some(key), v, true));
+                    }
+                }
+                Op::Remove => {
+                    let expected = match (&current, &current_value) {
+                        (Some(expected), Some(_)) => expected,
+                        // The entry does not exist, or has expired.
+                        _ => return None,
+                    };
+                    let kv =
+                        self.remove_with_hash_and_fun(&*key, hash, RemovalCause::Explicit, || {
+                            self.base.remove_entry_if_same(&key, hash, expected)
+                        });
+                    if let Some(kv) = kv {
+                        let hk = self.base.housekeeper.as_ref();
+                        Self::schedule_write_op(
+                            self.base.inner.as_ref(),
+                            &self.base.write_op_ch,
+                            WriteOp::Remove(kv),
+                            now,
+                            hk,
+                        )
+                        .await
+                        .expect("Failed to remove");
+                        self.base.run_pending_notifications().await;
+                        crossbeam_epoch::pin().flush();
+                        return None;
+                    }
+                }
+            }
+            // The entry has been modified by another write. Retry.
+        }
+    }
+
+    pub(crate) async fn get_or_insert_with_hash(
+        &self,
+        key: Arc<K>,
+        hash: u64,
+        init: impl FnOnce() -> V,
+        ttl: Option<Duration>,
+    ) -> Entry<K, V> {
+        match self.get_with_hash(&key, hash, true) {
+            Some(entry) => {
+                self.base.run_pending_notifications().await;
+                entry
+            }
+            None => {
+                let value = init();
+                self.insert_with_hash_and_ttl(Arc::clone(&key), hash, value.clone(), ttl)
+                    .await;
+                Entry::new(Some(key), value, true)
+            }
+        }
+    }
 
     pub(crate) async fn get_or_insert_with_hash_by_ref<Q>(
         &self,
         key: &Q,
         hash: u64,
         init: impl FnOnce() -> V,
+        ttl: Option<Duration>,
     ) -> Entry<K, V>
     where
         K: Borrow<Q>,
         Q: ToOwned<Owned = K> + Hash + Eq + ?Sized,
     {
-        match self.base.get_with_hash(key, hash, true) {
-            Some(entry) => entry,
+        match self.get_with_hash(key, hash, true) {
+            Some(entry) => {
+                self.base.run_pending_notifications().await;
+                entry
+            }
             None => {
                 let key = Arc::new(key.to_owned());
                 let value = init();
-                self.insert_with_hash(Arc::clone(&key), hash, value.clone())
+                self.insert_with_hash_and_ttl(Arc::clone(&key), hash, value.clone(), ttl)
                     .await;
                 Entry::new(Some(key), value, true)
             }
@@ -1552,8 +2448,9 @@ where
     where
         F: Future<Output = Option<V>>,
     {
-        let entry = self.base.get_with_hash(&key, hash, need_key);
+        let entry = self.get_with_hash(&key, hash, need_key);
         if entry.is_some() {
+            self.base.run_pending_notifications().await;
             return entry;
         }
 
@@ -1573,8 +2470,9 @@ where
         K: Borrow<Q>,
         Q: ToOwned<Owned = K> + Hash + Eq + ?Sized,
     {
-        let entry = self.base.get_with_hash(key, hash, need_key);
+        let entry = self.get_with_hash(key, hash, need_key);
         if entry.is_some() {
+            self.base.run_pending_notifications().await;
             return entry;
         }
 
@@ -1601,6 +2499,7 @@ where
                 .get_with_hash_but_no_recording(&key, hash, ignore_if)
         };
         let insert = |v| self.insert_with_hash(key.clone(), hash, v).boxed();
+        let init = self.base.load_with_stats_async(init, Option::is_some);
 
         let k = if need_key {
             Some(Arc::clone(&key))
@@ -1622,6 +2521,74 @@ where
         }
     }
 
+    /// Similar to `get_all_with`, but the `init` closure takes the missing keys as
+    /// `Arc<K>`s, so that the returned future can own them.
+    pub(super) async fn try_get_all_with<I, F, Fut, E>(
+        &self,
+        keys: I,
+        mut init: F,
+    ) -> Result<HashMap<K, V>, Arc<E>>
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(Vec<Arc<K>>) -> Fut,
+        Fut: Future<Output = Result<Vec<V>, E>>,
+        E: Send + Sync + 'static,
+        K: Clone,
+    {
+        use futures_util::FutureExt;
+
+        let (mut found, missing) = self.get_all_or_collect_missing(keys);
+        if missing.is_empty() {
+            return Ok(found);
+        }
+
+        let get = |key: &Arc<K>| self.get_with_arc_key_but_no_recording(key);
+        let insert = |key: Arc<K>, v| {
+            let hash = self.base.hash(&*key);
+            self.insert_with_hash(key, hash, v).boxed()
+        };
+        let init = |keys| self.base.load_with_stats_async(init(keys), Result::is_ok);
+
+        let result = self
+            .value_initializer
+            .try_init_or_read_all(missing, get, init, insert)
+            .await;
+        crossbeam_epoch::pin().flush();
+        found.extend(result?.into_iter().map(|(k, v)| (K::clone(&k), v)));
+        Ok(found)
+    }
+
+    /// Gets the values of the keys, and returns them with the keys that do not
+    /// exist in the cache. Duplicate keys are ignored.
+    fn get_all_or_collect_missing<I>(&self, keys: I) -> (HashMap<K, V>, Vec<Arc<K>>)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let mut found = HashMap::new();
+        let mut missing = HashSet::new();
+        for key in keys {
+            if found.contains_key(&key) || missing.contains(&key) {
+                continue;
+            }
+            let hash = self.base.hash(&key);
+            match self.get_with_hash(&key, hash, false) {
+                Some(entry) => {
+                    found.insert(key, entry.into_value());
+                }
+                None => {
+                    missing.insert(Arc::new(key));
+                }
+            }
+        }
+        (found, missing.into_iter().collect())
+    }
+
+    fn get_with_arc_key_but_no_recording(&self, key: &Arc<K>) -> Option<V> {
+        let ignore_if = None as Option<&mut fn(&V) -> bool>;
+        self.base
+            .get_with_hash_but_no_recording(key, self.base.hash(&**key), ignore_if)
+    }
+
     pub(super) async fn get_or_try_insert_with_hash_and_fun<F, E>(
         &self,
         key: Arc<K>,
@@ -1633,7 +2600,8 @@ where
         F: Future<Output = Result<V, E>>,
         E: Send + Sync + 'static,
     {
-        if let Some(entry) = self.base.get_with_hash(&key, hash, need_key) {
+        if let Some(entry) = self.get_with_hash(&key, hash, need_key) {
+            self.base.run_pending_notifications().await;
             return Ok(entry);
         }
 
@@ -1654,7 +2622,7 @@ where
         K: Borrow<Q>,
         Q: ToOwned<Owned = K> + Hash + Eq + ?Sized,
     {
-        if let Some(entry) = self.base.get_with_hash(key, hash, need_key) {
+        if let Some(entry) = self.get_with_hash_and_notify(key, hash, need_key).await {
             return Ok(entry);
         }
         let key = Arc::new(key.to_owned());
@@ -1662,7 +2630,27 @@ where
             .await
     }
 
-    async fn try_insert_with_hash_and_fun<F, E>(
+    /// Returns the entry of the key like `get_with_hash` does, and on a hit,
+    /// delivers the queued notifications to the async eviction listener like the
+    /// `get_with` family methods do.
+    pub(super) async fn get_with_hash_and_notify<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        need_key: bool,
+    ) -> Option<Entry<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.get_with_hash(key, hash, need_key);
+        if entry.is_some() {
+            self.base.run_pending_notifications().await;
+        }
+        entry
+    }
+
+    pub(super) async fn try_insert_with_hash_and_fun<F, E>(
         &self,
         key: Arc<K>,
         hash: u64,
@@ -1681,6 +2669,7 @@ where
                 .get_with_hash_but_no_recording(&key, hash, ignore_if)
         };
         let insert = |v| self.insert_with_hash(key.clone(), hash, v).boxed();
+        let init = self.base.load_with_stats_async(init, Result::is_ok);
 
         let k = if need_key {
             Some(Arc::clone(&key))
@@ -1705,8 +2694,91 @@ where
         }
     }
 
+    /// Queues a reload of the value of the key for `run_pending_tasks` if the entry
+    /// was written `refresh_after_write` or more ago, and no other reload for the
+    /// key is running.
+    fn refresh_if_stale<Q>(&self, key: &Q, hash: u64)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let refresher = match &self.refresher {
+            Some(refresher) => refresher,
+            None => return,
+        };
+        let duration = refresher.refresh_after_write();
+        let kv = match self.base.stale_entry_with_hash(key, hash, duration) {
+            Some(kv) => kv,
+            None => return,
+        };
+        if !self.value_initializer.try_start_refresh(&kv.key) {
+            // Already being reloaded.
+            return;
+        }
+
+        // The reload future will be driven by `run_pending_tasks` on the caller's
+        // runtime, as the reloader may depend on it.
+        if let Some(reloads) = &self.pending_reloads {
+            reloads.lock().push((kv, hash));
+        }
+    }
+
+    async fn reload(&self, kv: KvEntry<K, V>, hash: u64) {
+        use futures_util::FutureExt;
+        use std::panic::AssertUnwindSafe;
+
+        if let Some(refresher) = &self.refresher {
+            let reload = (refresher.reloader())(&kv.key, &kv.entry.value);
+            // Safety: It is safe to assert unwind safety here because the reloader
+            // will be called again only for a next reload.
+            let result = AssertUnwindSafe(self.base.load_with_stats_async(reload, Option::is_some))
+                .catch_unwind()
+                .await;
+            if let Ok(Some(value)) = result {
+                self.put_reloaded_value(&kv, hash, value).await;
+            }
+        }
+        self.value_initializer.finish_refresh(&kv.key);
+    }
+
+    /// Replaces the value of the reloaded entry only if the entry has neither been
+    /// modified nor removed during the reload, and it has not expired.
+    async fn put_reloaded_value(&self, kv: &KvEntry<K, V>, hash: u64, value: V) {
+        let now = self.base.current_time_from_expiration_clock();
+        if !self.base.is_valid_entry(&kv.key, &kv.entry, now) {
+            return;
+        }
+        let key = Arc::clone(&kv.key);
+        if let Some((op, now)) =
+            self.base
+                .do_compute_put_with_hash(key, hash, Some(&kv.entry), value)
+        {
+            let hk = self.base.housekeeper.as_ref();
+            Self::schedule_write_op(
+                self.base.inner.as_ref(),
+                &self.base.write_op_ch,
+                op,
+                now,
+                hk,
+            )
+            .await
+            .expect("Failed to insert");
+            self.base.run_pending_notifications().await;
+        }
+    }
+
     async fn insert_with_hash(&self, key: Arc<K>, hash: u64, value: V) {
-        let (op, now) = self.base.do_insert_with_hash(key, hash, value);
+        self.insert_with_hash_and_ttl(key, hash, value, None).await
+    }
+
+    async fn insert_with_hash_and_ttl(
+        &self,
+        key: Arc<K>,
+        hash: u64,
+        value: V,
+        ttl: Option<Duration>,
+    ) {
+        let (op, now) = self.base.do_insert_with_hash(key, hash, value, ttl);
         let hk = self.base.housekeeper.as_ref();
         Self::schedule_write_op(
             self.base.inner.as_ref(),
@@ -1717,6 +2789,7 @@ where
         )
         .await
         .expect("Failed to insert");
+        self.base.run_pending_notifications().await;
     }
 
     #[inline]
@@ -1746,6 +2819,44 @@ where
         Ok(())
     }
 
+    /// Sends the write ops to the channel in bulk. Unlike `schedule_write_op`, this
+    /// does not check if the pending ops should be applied before sending each op.
+    /// Instead, it applies the pending ops only when the channel is full, and once
+    /// after all the ops are sent.
+    async fn schedule_write_ops(
+        inner: &impl InnerSync,
+        ch: &Sender<WriteOp<K, V>>,
+        ops: impl IntoIterator<Item = WriteOp<K, V>>,
+        housekeeper: Option<&HouseKeeperArc<K, V, S>>,
+    ) -> Result<(), TrySendError<WriteOp<K, V>>> {
+        let mut is_sent = false;
+
+        for mut op in ops {
+            loop {
+                match ch.try_send(op) {
+                    Ok(()) => break,
+                    Err(TrySendError::Full(op1)) => {
+                        op = op1;
+                        if let Some(hk) = housekeeper {
+                            hk.try_sync(inner);
+                        }
+                        async_io::Timer::after(Duration::from_micros(WRITE_RETRY_INTERVAL_MICROS))
+                            .await;
+                    }
+                    Err(e @ TrySendError::Disconnected(_)) => return Err(e),
+                }
+            }
+            is_sent = true;
+        }
+
+        if is_sent {
+            if let Some(hk) = housekeeper {
+                hk.try_sync(inner);
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     fn blocking_schedule_write_op(
         inner: &impl InnerSync,
@@ -1810,7 +2921,17 @@ where
     /// This method is intended for use cases where you are inserting from
     /// synchronous code.
     pub fn insert(&self, key: K, value: V) {
-        self.0.do_blocking_insert(key, value)
+        self.0.do_blocking_insert(key, value, None)
+    }
+
+    /// Inserts a key-value pair into the cache with its own time-to-live. See
+    /// [`Cache::insert_with_ttl`](./struct.Cache.html#method.insert_with_ttl) for
+    /// details.
+    ///
+    /// This method is intended for use cases where you are inserting from
+    /// synchronous code.
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        self.0.do_blocking_insert(key, value, Some(ttl))
     }
 
     /// Discards any cached value for the key.
@@ -1833,7 +2954,11 @@ where
 #[cfg(test)]
 mod tests {
     use super::{Cache, ConcurrentCacheExt};
-    use crate::{common::time::Clock, notification::RemovalCause};
+    use crate::{
+        common::time::Clock,
+        notification::{PredicateHandle, RemovalCause},
+        Expiry,
+    };
 
     use async_io::Timer;
     use parking_lot::Mutex;
@@ -1966,6 +3091,45 @@ mod tests {
         assert_eq!(cache.get(&"b"), None);
     }
 
+    #[tokio::test]
+    async fn stats() {
+        let mut cache = Cache::builder().max_capacity(3).record_stats().build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice").await;
+        cache.insert("b", "bob").await;
+        cache.sync();
+
+        assert_eq!(cache.get(&"a"), Some("alice")); // hit
+        assert_eq!(cache.get(&"c"), None); // miss
+        assert_eq!(cache.get_with("c", async { "cindy" }).await, "cindy"); // miss, load
+        assert_eq!(cache.get_with("c", async { unreachable!() }).await, "cindy"); // hit
+        let result = cache.try_get_with("d", async { Err("error") }).await;
+        assert!(result.is_err()); // miss, load failure
+        let result = cache.optionally_get_with("d", async { None }).await;
+        assert_eq!(result, None); // miss, load failure
+        cache.sync();
+
+        let stats = cache.stats();
+        assert_eq!(stats.hit_count(), 2);
+        assert_eq!(stats.miss_count(), 4);
+        assert_eq!(stats.load_success_count(), 1);
+        assert_eq!(stats.load_failure_count(), 2);
+        assert_eq!(stats.eviction_count(), 0);
+
+        // The cache is full. Either "d" or one of the existing entries will be
+        // evicted.
+        cache.insert("d", "david").await;
+        cache.sync();
+
+        let stats = cache.stats();
+        assert_eq!(stats.eviction_count(), 1);
+        assert_eq!(stats.eviction_weight(), 1);
+    }
+
     #[tokio::test]
     async fn size_aware_eviction() {
         let weigher = |_k: &&str, v: &(&str, u32)| v.1;
@@ -2157,9 +3321,21 @@ mod tests {
         // https://github.com/moka-rs/moka/issues/155
 
         cache.invalidate_all();
-        expected.push((Arc::new("a"), "alice", RemovalCause::Explicit));
-        expected.push((Arc::new("b"), "bob", RemovalCause::Explicit));
-        expected.push((Arc::new("c"), "cindy", RemovalCause::Explicit));
+        expected.push((
+            Arc::new("a"),
+            "alice",
+            RemovalCause::Invalidated { predicate_id: None },
+        ));
+        expected.push((
+            Arc::new("b"),
+            "bob",
+            RemovalCause::Invalidated { predicate_id: None },
+        ));
+        expected.push((
+            Arc::new("c"),
+            "cindy",
+            RemovalCause::Invalidated { predicate_id: None },
+        ));
         cache.sync();
 
         cache.insert("d", "david").await;
@@ -2232,10 +3408,10 @@ mod tests {
         assert!(cache.contains_key(&2));
 
         let names = ["alice", "alex"].iter().cloned().collect::<HashSet<_>>();
-        cache.invalidate_entries_if(move |_k, &v| names.contains(v))?;
+        let id0 = cache.invalidate_entries_if(move |_k, &v| names.contains(v))?;
         assert_eq!(cache.invalidation_predicate_count(), 1);
-        expected.push((Arc::new(0), "alice", RemovalCause::Explicit));
-        expected.push((Arc::new(2), "alex", RemovalCause::Explicit));
+        expected.push((Arc::new(0), "alice", invalidated_by(&id0)));
+        expected.push((Arc::new(2), "alex", invalidated_by(&id0)));
 
         mock.increment(Duration::from_secs(5)); // 10 secs from the start.
 
@@ -2263,12 +3439,12 @@ mod tests {
 
         mock.increment(Duration::from_secs(5)); // 15 secs from the start.
 
-        cache.invalidate_entries_if(|_k, &v| v == "alice")?;
-        cache.invalidate_entries_if(|_k, &v| v == "bob")?;
+        let id1 = cache.invalidate_entries_if(|_k, &v| v == "alice")?;
+        let id2 = cache.invalidate_entries_if(|_k, &v| v == "bob")?;
         assert_eq!(cache.invalidation_predicate_count(), 2);
         // key 1 was inserted before key 3.
-        expected.push((Arc::new(1), "bob", RemovalCause::Explicit));
-        expected.push((Arc::new(3), "alice", RemovalCause::Explicit));
+        expected.push((Arc::new(1), "bob", invalidated_by(&id2)));
+        expected.push((Arc::new(3), "alice", invalidated_by(&id1)));
 
         // Run the invalidation task and wait for it to finish. (TODO: Need a better way than sleeping)
         cache.sync(); // To submit the invalidation task.
@@ -2452,105 +3628,320 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_iter() {
-        const NUM_KEYS: usize = 50;
-
-        fn make_value(key: usize) -> String {
-            format!("val: {}", key)
-        }
+    async fn expire_after() {
+        struct MyExpiry;
 
-        let cache = Cache::builder()
-            .max_capacity(100)
-            .time_to_idle(Duration::from_secs(10))
-            .build();
+        impl Expiry<&'static str, &'static str> for MyExpiry {
+            fn expire_after_create(&self, _key: &&str, _value: &&str) -> Option<Duration> {
+                Some(Duration::from_secs(10))
+            }
 
-        for key in 0..NUM_KEYS {
-            cache.insert(key, make_value(key)).await;
+            fn expire_after_read(
+                &self,
+                _key: &&str,
+                _value: &&str,
+                _current_duration: Option<Duration>,
+            ) -> Option<Duration> {
+                Some(Duration::from_secs(5))
+            }
         }
 
-        let mut key_set = std::collections::HashSet::new();
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
 
-        for (key, value) in &cache {
-            assert_eq!(value, make_value(*key));
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
 
-            key_set.insert(*key);
-        }
+        // Create a cache with the eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .expire_after(MyExpiry)
+            .eviction_listener_with_queued_delivery_mode(listener)
+            .build();
+        cache.reconfigure_for_testing();
 
-        // Ensure there are no missing or duplicate keys in the iteration.
-        assert_eq!(key_set.len(), NUM_KEYS);
-    }
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
 
-    /// Runs 16 async tasks at the same time and ensures no deadlock occurs.
-    ///
-    /// - Eight of the task will update key-values in the cache.
-    /// - Eight others will iterate the cache.
-    ///
-    #[tokio::test]
-    async fn test_iter_multi_async_tasks() {
-        use std::collections::HashSet;
+        // Make the cache exterior immutable.
+        let cache = cache;
 
-        const NUM_KEYS: usize = 1024;
-        const NUM_TASKS: usize = 16;
+        cache.insert("a", "alice").await;
+        cache.insert("b", "bob").await;
+        cache.sync();
 
-        fn make_value(key: usize) -> String {
-            format!("val: {}", key)
-        }
+        mock.increment(Duration::from_secs(8)); // 8 secs from the start.
+        cache.sync();
 
-        let cache = Cache::builder()
-            .max_capacity(2048)
-            .time_to_idle(Duration::from_secs(10))
-            .build();
+        // The read sets the remaining time of "a" to 5 secs.
+        assert_eq!(cache.get(&"a"), Some("alice"));
 
-        // Initialize the cache.
-        for key in 0..NUM_KEYS {
-            cache.insert(key, make_value(key)).await;
-        }
+        mock.increment(Duration::from_secs(2)); // 10 secs.
+        expected.push((Arc::new("b"), "bob", RemovalCause::Expired));
 
-        let rw_lock = Arc::new(tokio::sync::RwLock::<()>::default());
-        let write_lock = rw_lock.write().await;
+        assert_eq!(cache.get(&"b"), None);
+        // contains_key does not update the expiration time.
+        assert!(cache.contains_key(&"a"));
+        assert_eq!(cache.iter().count(), 1);
 
-        let tasks = (0..NUM_TASKS)
-            .map(|n| {
-                let cache = cache.clone();
-                let rw_lock = Arc::clone(&rw_lock);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
 
-                if n % 2 == 0 {
-                    // This thread will update the cache.
-                    tokio::spawn(async move {
-                        let read_lock = rw_lock.read().await;
-                        for key in 0..NUM_KEYS {
-                            // TODO: Update keys in a random order?
-                            cache.insert(key, make_value(key)).await;
-                        }
-                        std::mem::drop(read_lock);
-                    })
-                } else {
-                    // This thread will iterate the cache.
-                    tokio::spawn(async move {
-                        let read_lock = rw_lock.read().await;
-                        let mut key_set = HashSet::new();
-                        // let mut key_count = 0usize;
-                        for (key, value) in &cache {
-                            assert_eq!(value, make_value(*key));
-                            key_set.insert(*key);
-                            // key_count += 1;
-                        }
-                        // Ensure there are no missing or duplicate keys in the iteration.
-                        assert_eq!(key_set.len(), NUM_KEYS);
-                        std::mem::drop(read_lock);
-                    })
-                }
-            })
-            .collect::<Vec<_>>();
+        mock.increment(Duration::from_secs(3)); // 13 secs.
+        expected.push((Arc::new("a"), "alice", RemovalCause::Expired));
 
-        // Let these threads to run by releasing the write lock.
-        std::mem::drop(write_lock);
+        assert_eq!(cache.get(&"a"), None);
+        assert!(!cache.contains_key(&"a"));
 
-        let _ = futures_util::future::join_all(tasks).await;
+        cache.sync();
+        assert!(cache.is_table_empty());
 
-        // Ensure there are no missing or duplicate keys in the iteration.
-        let key_set = cache.iter().map(|(k, _v)| *k).collect::<HashSet<_>>();
-        assert_eq!(key_set.len(), NUM_KEYS);
+        verify_notification_vec(&cache, actual, &expected);
+    }
+
+    #[tokio::test]
+    async fn insert_with_ttl() {
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice").await;
+        cache
+            .insert_with_ttl("b", "bob", Duration::from_secs(30))
+            .await;
+        cache
+            .blocking()
+            .insert_with_ttl("c", "cindy", Duration::from_secs(3));
+        let entry = cache
+            .entry("d")
+            .or_insert_with_ttl("david", Duration::from_secs(20))
+            .await;
+        assert!(entry.is_fresh());
+        // The TTL is not applied to an existing entry.
+        let entry = cache
+            .entry_by_ref(&"a")
+            .or_insert_with_ttl("anna", Duration::from_secs(1))
+            .await;
+        assert!(!entry.is_fresh());
+        cache.sync();
+
+        mock.increment(Duration::from_secs(4)); // 4 secs from the start.
+        assert_eq!(cache.get(&"c"), None);
+        assert_eq!(cache.get(&"a"), Some("alice"));
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 3);
+
+        // "a" expires by the time-to-live of the cache, but "b" and "d" do not.
+        mock.increment(Duration::from_secs(7)); // 11 secs.
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some("bob"));
+        assert_eq!(cache.get(&"d"), Some("david"));
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+
+        // Updating without a TTL puts "d" back under the time-to-live of the cache.
+        cache.insert("d", "dennis").await;
+        cache.sync();
+
+        mock.increment(Duration::from_secs(10)); // 21 secs.
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.get(&"b"), Some("bob"));
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
+
+        mock.increment(Duration::from_secs(10)); // 31 secs.
+        assert_eq!(cache.get(&"b"), None);
+
+        cache.sync();
+        assert!(cache.is_table_empty());
+    }
+
+    #[tokio::test]
+    async fn refresh_after_write() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let reload_count = Arc::new(AtomicU32::new(0));
+        let count = Arc::clone(&reload_count);
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .refresh_after_write(Duration::from_secs(10))
+            .reloader(move |key: &&str, value: &u32| {
+                count.fetch_add(1, Ordering::AcqRel);
+                // Keep the current value of "b".
+                let new_value = if *key == "b" { None } else { Some(*value + 1) };
+                async move { new_value }
+            })
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", 1).await;
+        cache.insert("b", 10).await;
+        cache.sync();
+
+        mock.increment(Duration::from_secs(5)); // 5 secs from the start.
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(reload_count.load(Ordering::Acquire), 0);
+
+        // "a" is stale. The old value is returned and only one reload is started.
+        mock.increment(Duration::from_secs(6)); // 11 secs.
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert!(cache.get(&"a").is_some());
+        assert!(cache.get_with("a", async { unreachable!() }).await > 0);
+
+        // The reload is driven by `run_pending_tasks`.
+        assert_eq!(reload_count.load(Ordering::Acquire), 0);
+        cache.run_pending_tasks().await;
+        assert_eq!(reload_count.load(Ordering::Acquire), 1);
+        assert_eq!(cache.get(&"a"), Some(2));
+        assert!(cache.contains_key(&"a"));
+
+        // The reloader returns `None` for "b", so the value is kept.
+        assert_eq!(cache.get(&"b"), Some(10));
+        cache.run_pending_tasks().await;
+        assert_eq!(reload_count.load(Ordering::Acquire), 2);
+        assert_eq!(cache.get(&"b"), Some(10));
+
+        // "b" is still stale, so the last read queued another reload.
+        cache.run_pending_tasks().await;
+        assert_eq!(reload_count.load(Ordering::Acquire), 3);
+
+        // "a" is updated while its reload is pending. The reloaded value must not
+        // overwrite the new value.
+        mock.increment(Duration::from_secs(11)); // 22 secs.
+        assert_eq!(cache.get(&"a"), Some(2));
+        cache.insert("a", 100).await;
+        cache.run_pending_tasks().await;
+        assert_eq!(reload_count.load(Ordering::Acquire), 4);
+        assert_eq!(cache.get(&"a"), Some(100));
+
+        // "a" is invalidated while its reload is pending. The reloaded value must
+        // not be put back.
+        mock.increment(Duration::from_secs(11)); // 33 secs.
+        assert_eq!(cache.get(&"a"), Some(100));
+        cache.invalidate(&"a").await;
+        cache.run_pending_tasks().await;
+        assert_eq!(reload_count.load(Ordering::Acquire), 5);
+        assert!(!cache.contains_key(&"a"));
+    }
+    #[tokio::test]
+    async fn test_iter() {
+        const NUM_KEYS: usize = 50;
+
+        fn make_value(key: usize) -> String {
+            format!("val: {}", key)
+        }
+
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_idle(Duration::from_secs(10))
+            .build();
+
+        for key in 0..NUM_KEYS {
+            cache.insert(key, make_value(key)).await;
+        }
+
+        let mut key_set = std::collections::HashSet::new();
+
+        for (key, value) in &cache {
+            assert_eq!(value, make_value(*key));
+
+            key_set.insert(*key);
+        }
+
+        // Ensure there are no missing or duplicate keys in the iteration.
+        assert_eq!(key_set.len(), NUM_KEYS);
+    }
+
+    /// Runs 16 async tasks at the same time and ensures no deadlock occurs.
+    ///
+    /// - Eight of the task will update key-values in the cache.
+    /// - Eight others will iterate the cache.
+    ///
+    #[tokio::test]
+    async fn test_iter_multi_async_tasks() {
+        use std::collections::HashSet;
+
+        const NUM_KEYS: usize = 1024;
+        const NUM_TASKS: usize = 16;
+
+        fn make_value(key: usize) -> String {
+            format!("val: {}", key)
+        }
+
+        let cache = Cache::builder()
+            .max_capacity(2048)
+            .time_to_idle(Duration::from_secs(10))
+            .build();
+
+        // Initialize the cache.
+        for key in 0..NUM_KEYS {
+            cache.insert(key, make_value(key)).await;
+        }
+
+        let rw_lock = Arc::new(tokio::sync::RwLock::<()>::default());
+        let write_lock = rw_lock.write().await;
+
+        let tasks = (0..NUM_TASKS)
+            .map(|n| {
+                let cache = cache.clone();
+                let rw_lock = Arc::clone(&rw_lock);
+
+                if n % 2 == 0 {
+                    // This thread will update the cache.
+                    tokio::spawn(async move {
+                        let read_lock = rw_lock.read().await;
+                        for key in 0..NUM_KEYS {
+                            // TODO: Update keys in a random order?
+                            cache.insert(key, make_value(key)).await;
+                        }
+                        std::mem::drop(read_lock);
+                    })
+                } else {
+                    // This thread will iterate the cache.
+                    tokio::spawn(async move {
+                        let read_lock = rw_lock.read().await;
+                        let mut key_set = HashSet::new();
+                        // let mut key_count = 0usize;
+                        for (key, value) in &cache {
+                            assert_eq!(value, make_value(*key));
+                            key_set.insert(*key);
+                            // key_count += 1;
+                        }
+                        // Ensure there are no missing or duplicate keys in the iteration.
+                        assert_eq!(key_set.len(), NUM_KEYS);
+                        std::mem::drop(read_lock);
+                    })
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Let these threads to run by releasing the write lock.
+        std::mem::drop(write_lock);
+
+        let _ = futures_util::future::join_all(tasks).await;
+
+        // Ensure there are no missing or duplicate keys in the iteration.
+        let key_set = cache.iter().map(|(k, _v)| *k).collect::<HashSet<_>>();
+        assert_eq!(key_set.len(), NUM_KEYS);
     }
 
     #[tokio::test]
@@ -2611,26 +4002,307 @@ mod tests {
         let task4 = {
             let cache4 = cache.clone();
             async move {
-                // Wait for 200 ms before calling `get`.
-                Timer::after(Duration::from_millis(200)).await;
-                let maybe_v = cache4.get(&KEY);
-                assert!(maybe_v.is_none());
+                // Wait for 200 ms before calling `get`.
+                Timer::after(Duration::from_millis(200)).await;
+                let maybe_v = cache4.get(&KEY);
+                assert!(maybe_v.is_none());
+            }
+        };
+
+        // Task5 will call `get` for the same key. It will call after task1's async
+        // block finished, so it will get the value insert by task1's async block.
+        let task5 = {
+            let cache5 = cache.clone();
+            async move {
+                // Wait for 400 ms before calling `get`.
+                Timer::after(Duration::from_millis(400)).await;
+                let maybe_v = cache5.get(&KEY);
+                assert_eq!(maybe_v, Some("task1"));
+            }
+        };
+
+        futures_util::join!(task1, task2, task3, task4, task5);
+    }
+
+    #[tokio::test]
+    async fn conditional_writes() {
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        // Create a cache with the eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .eviction_listener_with_queued_delivery_mode(listener)
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        assert_eq!(cache.insert_if_absent("a", "alice").await, None);
+        assert_eq!(cache.insert_if_absent("a", "anna").await, Some("alice"));
+
+        assert!(!cache.replace(&"a", &"anna", "amy").await);
+        assert!(cache.replace(&"a", &"alice", "amy").await);
+        expected.push((Arc::new("a"), "alice", RemovalCause::Replaced));
+        assert_eq!(cache.get(&"a"), Some("amy"));
+
+        // `replace` does not insert a missing key.
+        assert!(!cache.replace(&"b", &"bob", "bill").await);
+        assert!(!cache.contains_key(&"b"));
+
+        assert_eq!(cache.remove_if(&"a", |v| *v == "alice").await, None);
+        assert!(cache.contains_key(&"a"));
+        assert_eq!(cache.remove_if(&"a", |v| *v == "amy").await, Some("amy"));
+        expected.push((Arc::new("a"), "amy", RemovalCause::Explicit));
+        assert!(!cache.contains_key(&"a"));
+
+        cache.insert("c", "cindy").await;
+        cache.sync();
+
+        mock.increment(Duration::from_secs(10)); // 10 secs from the start.
+
+        // An expired entry is treated as absent.
+        assert!(!cache.replace(&"c", &"cindy", "carl").await);
+        assert_eq!(cache.remove_if(&"c", |_| true).await, None);
+        assert_eq!(cache.insert_if_absent("c", "carl").await, None);
+        expected.push((Arc::new("c"), "cindy", RemovalCause::Expired));
+        assert_eq!(cache.get(&"c"), Some("carl"));
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
+
+        verify_notification_vec(&cache, actual, &expected);
+    }
+
+    #[tokio::test]
+    async fn remove_and_remove_entry() {
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        // Create a cache with the eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .eviction_listener_with_queued_delivery_mode(listener)
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice").await;
+        cache.insert("b", "bob").await;
+        assert_eq!(cache.remove(&"a").await, Some("alice"));
+        expected.push((Arc::new("a"), "alice", RemovalCause::Explicit));
+        assert_eq!(cache.remove(&"a").await, None);
+        assert_eq!(cache.remove_entry(&"b").await, Some((Arc::new("b"), "bob")));
+        expected.push((Arc::new("b"), "bob", RemovalCause::Explicit));
+        assert!(!cache.contains_key(&"b"));
+
+        // An expired value is not returned, but the entry is removed.
+        cache.insert("c", "cindy").await;
+        cache.sync();
+        mock.increment(Duration::from_secs(10)); // 10 secs from the start.
+        assert_eq!(cache.remove(&"c").await, None);
+        expected.push((Arc::new("c"), "cindy", RemovalCause::Expired));
+
+        cache.sync();
+        assert!(cache.is_table_empty());
+        verify_notification_vec(&cache, actual, &expected);
+
+        // Only one task can take the value.
+        let cache = Cache::new(100);
+        cache.insert(0, 0).await;
+        let tasks = (0..16)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.remove(&0).await.is_some() })
+            })
+            .collect::<Vec<_>>();
+        let taken = futures_util::future::join_all(tasks)
+            .await
+            .into_iter()
+            .map(|r| r.expect("Failed to join"))
+            .filter(|taken| *taken)
+            .count();
+        assert_eq!(taken, 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_conditional_writes() {
+        const NUM_TASKS: usize = 16;
+        let cache = Cache::new(100);
+
+        // Only one task can insert the value.
+        let tasks = (0..NUM_TASKS)
+            .map(|i| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.insert_if_absent(0, i).await.is_none() })
+            })
+            .collect::<Vec<_>>();
+        let inserted = futures_util::future::join_all(tasks)
+            .await
+            .into_iter()
+            .map(|r| r.expect("Failed to join"))
+            .filter(|inserted| *inserted)
+            .count();
+        assert_eq!(inserted, 1);
+
+        // Increment the value with compare-and-swap. No increment will be lost.
+        cache.insert(0, 0).await;
+        let tasks = (0..NUM_TASKS)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    for _ in 0..100 {
+                        loop {
+                            let v = cache.get(&0).unwrap();
+                            if cache.replace(&0, &v, v + 1).await {
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        futures_util::future::join_all(tasks).await;
+        assert_eq!(cache.get(&0), Some(NUM_TASKS * 100));
+    }
+
+    #[tokio::test]
+    async fn insert_all_and_invalidate_all_keys() {
+        let mut cache = Cache::new(10_000);
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        // Insert more entries than the write op channel can hold, so that the
+        // pending ops are applied while the entries are being inserted.
+        cache.insert_all((0..5_000).map(|i| (i, i * 10))).await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 5_000);
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&4_999), Some(49_990));
+
+        // Invalidate the even keys. The key 10_000 does not exist.
+        let keys = (0..5_000)
+            .step_by(2)
+            .chain(Some(10_000))
+            .collect::<Vec<_>>();
+        cache.invalidate_all_keys(&keys).await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2_500);
+        assert_eq!(cache.get(&0), None);
+        assert_eq!(cache.get(&1), Some(10));
+        assert!(!cache.contains_key(&4_998));
+        assert!(cache.contains_key(&4_999));
+
+        // The max capacity is still enforced.
+        let mut cache = Cache::new(100);
+        cache.reconfigure_for_testing();
+        let cache = cache;
+
+        cache.insert_all((0..1_000).map(|i| (i, i))).await;
+        cache.sync();
+        assert!(cache.entry_count() <= 100);
+    }
+
+    #[tokio::test]
+    async fn get_all_with() {
+        let cache = Cache::new(100);
+        cache.insert(0, "zero".to_string()).await;
+
+        // Duplicate keys and the existing key are not passed to the init closure.
+        let values = cache
+            .get_all_with(vec![0, 1, 2, 2], |keys| {
+                let mut sorted = keys.iter().map(|k| **k).collect::<Vec<u32>>();
+                sorted.sort_unstable();
+                assert_eq!(sorted, vec![1, 2]);
+                let values = keys.iter().map(|k| format!("value {}", k)).collect();
+                async { values }
+            })
+            .await;
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[&0], "zero");
+        assert_eq!(values[&1], "value 1");
+        assert_eq!(values[&2], "value 2");
+
+        let values = cache.get_all(vec![1, 2, 3]);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[&2], "value 2");
+
+        // This test will resolve three async tasks concurrently:
+        //
+        // Task1 will be the first task to call `get_all_with` for the keys 10 and
+        // 11, so its init future will be resolved for both keys.
+        let task1 = {
+            let cache1 = cache.clone();
+            async move {
+                let values = cache1
+                    .get_all_with(vec![10, 11], |keys| {
+                        assert_eq!(keys.len(), 2);
+                        let values = keys.iter().map(|k| format!("task1 {}", k)).collect();
+                        async move {
+                            // Wait for 300 ms and return the values.
+                            Timer::after(Duration::from_millis(300)).await;
+                            values
+                        }
+                    })
+                    .await;
+                assert_eq!(values[&10], "task1 10");
+                assert_eq!(values[&11], "task1 11");
+            }
+        };
+
+        // Task2 will call `get_with` for the key 11 while task1's init future is
+        // being resolved. It will get the value loaded by task1.
+        let task2 = {
+            let cache2 = cache.clone();
+            async move {
+                Timer::after(Duration::from_millis(100)).await;
+                let v = cache2.get_with(11, async { unreachable!() }).await;
+                assert_eq!(v, "task1 11");
             }
         };
 
-        // Task5 will call `get` for the same key. It will call after task1's async
-        // block finished, so it will get the value insert by task1's async block.
-        let task5 = {
-            let cache5 = cache.clone();
+        // Task3 will call `get_all_with` for the keys 11 and 12 while task1's init
+        // future is being resolved. Its init future will be resolved only for the
+        // key 12.
+        let task3 = {
+            let cache3 = cache.clone();
             async move {
-                // Wait for 400 ms before calling `get`.
-                Timer::after(Duration::from_millis(400)).await;
-                let maybe_v = cache5.get(&KEY);
-                assert_eq!(maybe_v, Some("task1"));
+                Timer::after(Duration::from_millis(100)).await;
+                let values = cache3
+                    .get_all_with(vec![11, 12], |keys| {
+                        assert_eq!(keys, &[&12]);
+                        async { vec!["task3 12".to_string()] }
+                    })
+                    .await;
+                assert_eq!(values[&11], "task1 11");
+                assert_eq!(values[&12], "task3 12");
             }
         };
 
-        futures_util::join!(task1, task2, task3, task4, task5);
+        futures_util::join!(task1, task2, task3);
     }
 
     #[tokio::test]
@@ -2713,6 +4385,127 @@ mod tests {
         futures_util::join!(task1, task2, task3, task4, task5);
     }
 
+    #[tokio::test]
+    async fn entry_and_compute_with() {
+        use crate::{ops::compute::Op, Entry};
+
+        let cache: Cache<u32, u32> = Cache::new(100);
+        const KEY: u32 = 0;
+
+        // `Op::Nop` on a missing entry does not insert one.
+        let entry = cache
+            .entry(KEY)
+            .and_compute_with(|maybe_entry| async move {
+                assert!(maybe_entry.is_none());
+                Op::Nop
+            })
+            .await;
+        assert!(entry.is_none());
+        assert!(!cache.contains_key(&KEY));
+
+        // `and_compute_if_present` does not evaluate the closure for a missing entry.
+        let entry = cache
+            .entry_by_ref(&KEY)
+            .and_compute_if_present(|_entry| async { unreachable!() })
+            .await;
+        assert!(entry.is_none());
+
+        // `Op::Put` inserts the value.
+        let entry = cache
+            .entry(KEY)
+            .and_compute_with(|_| async { Op::Put(1) })
+            .await
+            .unwrap();
+        assert!(entry.is_fresh());
+        assert_eq!(entry.into_value(), 1);
+        assert_eq!(cache.get(&KEY), Some(1));
+
+        // `Op::Nop` on an existing entry returns the entry as is.
+        let entry = cache
+            .entry(KEY)
+            .and_compute_with(|maybe_entry| async move {
+                assert_eq!(maybe_entry.map(Entry::into_value), Some(1));
+                Op::Nop
+            })
+            .await
+            .unwrap();
+        assert!(!entry.is_fresh());
+        assert_eq!(entry.into_value(), 1);
+
+        // Increment the value from eight tasks. As a compute closure is evaluated
+        // again when the entry has been modified by another task, no increment will
+        // be lost.
+        let tasks = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    for _ in 0..100 {
+                        cache
+                            .entry(KEY)
+                            .and_upsert_with(|maybe_entry| async move {
+                                // Yield to let other tasks run.
+                                Timer::after(Duration::from_micros(10)).await;
+                                maybe_entry.unwrap().into_value() + 1
+                            })
+                            .await;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        futures_util::future::join_all(tasks).await;
+        assert_eq!(cache.get(&KEY), Some(801));
+
+        // Neither is a value inserted while the future is being resolved.
+        let mut calls = 0;
+        let entry = cache
+            .entry(KEY)
+            .and_compute_with(|maybe_entry| {
+                calls += 1;
+                let is_first = calls == 1;
+                let cache = &cache;
+                async move {
+                    if is_first {
+                        cache.insert(KEY, 1000).await;
+                    }
+                    Op::Put(maybe_entry.unwrap().into_value() + 1)
+                }
+            })
+            .await;
+        assert_eq!(calls, 2);
+        assert_eq!(entry.map(Entry::into_value), Some(1001));
+        assert_eq!(cache.get(&KEY), Some(1001));
+
+        // `Op::Remove` does not remove a value inserted while the future is being
+        // resolved without evaluating the closure again.
+        let mut calls = 0;
+        let entry = cache
+            .entry(KEY)
+            .and_compute_with(|maybe_entry| {
+                calls += 1;
+                let is_first = calls == 1;
+                let cache = &cache;
+                async move {
+                    if is_first {
+                        cache.insert(KEY, 2000).await;
+                        return Op::Remove;
+                    }
+                    assert_eq!(maybe_entry.map(Entry::into_value), Some(2000));
+                    Op::Nop
+                }
+            })
+            .await;
+        assert_eq!(calls, 2);
+        assert_eq!(entry.map(Entry::into_value), Some(2000));
+
+        // `Op::Remove` removes the entry.
+        let entry = cache
+            .entry_by_ref(&KEY)
+            .and_compute_if_present(|_entry| async { Op::Remove })
+            .await;
+        assert!(entry.is_none());
+        assert!(!cache.contains_key(&KEY));
+    }
+
     #[tokio::test]
     async fn entry_or_insert_with_if() {
         let cache = Cache::new(100);
@@ -3629,6 +5422,62 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn removal_notifications_of_invalidated_entries() {
+        use crate::notification::ListenerFuture;
+        use futures_util::FutureExt;
+
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an async eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| -> ListenerFuture {
+            a1.lock().push((k, v, cause));
+            async {}.boxed()
+        };
+
+        // Create a cache with the async eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .support_invalidation_closures()
+            .async_eviction_listener(listener)
+            .build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("alice", "a0").await;
+        cache.insert("bob", "b0").await;
+        cache.insert("cindy", "c0").await;
+        cache.insert("david", "d0").await;
+        cache.sync();
+
+        // Remove and replace the entries matched by the predicate before the
+        // invalidation task removes them. They should be notified with the handle
+        // of the predicate.
+        let id = cache
+            .invalidate_entries_if(|k, _v| *k == "alice" || *k == "bob")
+            .unwrap();
+        cache.invalidate(&"alice").await;
+        expected.push((Arc::new("alice"), "a0", invalidated_by(&id)));
+        cache.insert("bob", "b1").await;
+        expected.push((Arc::new("bob"), "b0", invalidated_by(&id)));
+        cache.invalidate(&"cindy").await;
+        expected.push((Arc::new("cindy"), "c0", RemovalCause::Explicit));
+        assert_eq!(*actual.lock(), expected);
+
+        // Remove an entry invalidated by `invalidate_all` before the cache removes
+        // it.
+        cache.invalidate_all();
+        cache.invalidate(&"david").await;
+        let cause = RemovalCause::Invalidated { predicate_id: None };
+        expected.push((Arc::new("david"), "d0", cause));
+        assert_eq!(*actual.lock(), expected);
+    }
+
     #[tokio::test]
     async fn test_removal_notifications() {
         // The following `Vec`s will hold actual and expected notifications.
@@ -3688,105 +5537,527 @@ mod tests {
         verify_notification_vec(&cache, actual, &expected);
     }
 
-    #[tokio::test]
-    async fn test_removal_notifications_with_updates() {
-        // The following `Vec`s will hold actual and expected notifications.
+    #[tokio::test]
+    async fn test_removal_notifications_with_updates() {
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        // Create a cache with the eviction listener and also TTL and TTI.
+        let mut cache = Cache::builder()
+            .eviction_listener_with_queued_delivery_mode(listener)
+            .time_to_live(Duration::from_secs(7))
+            .time_to_idle(Duration::from_secs(5))
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("alice", "a0").await;
+        cache.sync();
+
+        // Now alice (a0) has been expired by the idle timeout (TTI).
+        mock.increment(Duration::from_secs(6));
+        expected.push((Arc::new("alice"), "a0", RemovalCause::Expired));
+        assert_eq!(cache.get(&"alice"), None);
+
+        // We have not ran sync after the expiration of alice (a0), so it is
+        // still in the cache.
+        assert_eq!(cache.entry_count(), 1);
+
+        // Re-insert alice with a different value. Since alice (a0) is still
+        // in the cache, this is actually a replace operation rather than an
+        // insert operation. We want to verify that the RemovalCause of a0 is
+        // Expired, not Replaced.
+        cache.insert("alice", "a1").await;
+        cache.sync();
+
+        mock.increment(Duration::from_secs(4));
+        assert_eq!(cache.get(&"alice"), Some("a1"));
+        cache.sync();
+
+        // Now alice has been expired by time-to-live (TTL).
+        mock.increment(Duration::from_secs(4));
+        expected.push((Arc::new("alice"), "a1", RemovalCause::Expired));
+        assert_eq!(cache.get(&"alice"), None);
+
+        // But, again, it is still in the cache.
+        assert_eq!(cache.entry_count(), 1);
+
+        // Re-insert alice with a different value and verify that the
+        // RemovalCause of a1 is Expired (not Replaced).
+        cache.insert("alice", "a2").await;
+        cache.sync();
+
+        assert_eq!(cache.entry_count(), 1);
+
+        // Now alice (a2) has been expired by the idle timeout.
+        mock.increment(Duration::from_secs(6));
+        expected.push((Arc::new("alice"), "a2", RemovalCause::Expired));
+        assert_eq!(cache.get(&"alice"), None);
+        assert_eq!(cache.entry_count(), 1);
+
+        // This invalidate will internally remove alice (a2).
+        cache.invalidate(&"alice").await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 0);
+
+        // Re-insert, and this time, make it expired by the TTL.
+        cache.insert("alice", "a3").await;
+        cache.sync();
+        mock.increment(Duration::from_secs(4));
+        assert_eq!(cache.get(&"alice"), Some("a3"));
+        cache.sync();
+        mock.increment(Duration::from_secs(4));
+        expected.push((Arc::new("alice"), "a3", RemovalCause::Expired));
+        assert_eq!(cache.get(&"alice"), None);
+        assert_eq!(cache.entry_count(), 1);
+
+        // This invalidate will internally remove alice (a2).
+        cache.invalidate(&"alice").await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 0);
+
+        verify_notification_vec(&cache, actual, &expected);
+    }
+
+    // NOTE: To enable the panic logging, run the following command:
+    //
+    // RUST_LOG=moka=info cargo test --features 'future, logging' -- \
+    //   future::cache::tests::recover_from_panicking_eviction_listener --exact --nocapture
+    //
+    #[tokio::test]
+    async fn recover_from_panicking_eviction_listener() {
+        #[cfg(feature = "logging")]
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener that panics when it see
+        // a value "panic now!".
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| {
+            if v == "panic now!" {
+                panic!("Panic now!");
+            }
+            a1.lock().push((k, v, cause))
+        };
+
+        // Create a cache with the eviction listener.
+        let mut cache = Cache::builder()
+            .name("My Future Cache")
+            .eviction_listener_with_queued_delivery_mode(listener)
+            .build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        // Insert an okay value.
+        cache.insert("alice", "a0").await;
+        cache.sync();
+
+        // Insert a value that will cause the eviction listener to panic.
+        cache.insert("alice", "panic now!").await;
+        expected.push((Arc::new("alice"), "a0", RemovalCause::Replaced));
+        cache.sync();
+
+        // Insert an okay value. This will replace the previous
+        // value "panic now!" so the eviction listener will panic.
+        cache.insert("alice", "a2").await;
+        cache.sync();
+        // No more removal notification should be sent.
+
+        // Invalidate the okay value.
+        cache.invalidate(&"alice").await;
+        cache.sync();
+
+        verify_notification_vec(&cache, actual, &expected);
+    }
+
+    #[tokio::test]
+    async fn async_eviction_listener() {
+        use crate::notification::ListenerFuture;
+        use futures_util::FutureExt;
+
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an async eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| -> ListenerFuture {
+            let a2 = Arc::clone(&a1);
+            async move {
+                tokio::task::yield_now().await;
+                a2.lock().push((k, v, cause));
+            }
+            .boxed()
+        };
+
+        // Create a cache with the async eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .async_eviction_listener(listener)
+            .support_invalidation_closures()
+            .build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        // The notifications for the writes should have been delivered when the
+        // write methods return.
+        cache.insert("alice", "a0").await;
+        cache.insert("alice", "a1").await;
+        expected.push((Arc::new("alice"), "a0", RemovalCause::Replaced));
+        assert_eq!(*actual.lock(), expected);
+
+        cache.invalidate(&"alice").await;
+        expected.push((Arc::new("alice"), "a1", RemovalCause::Explicit));
+        assert_eq!(*actual.lock(), expected);
+
+        cache.insert("bob", "b0").await;
+        assert!(cache.replace(&"bob", &"b0", "b1").await);
+        expected.push((Arc::new("bob"), "b0", RemovalCause::Replaced));
+        assert_eq!(cache.remove_if(&"bob", |v| *v == "b1").await, Some("b1"));
+        expected.push((Arc::new("bob"), "b1", RemovalCause::Explicit));
+        assert_eq!(*actual.lock(), expected);
+
+        // The notifications for the removals by the housekeeping are queued, and
+        // delivered by the next async method call.
+        cache.insert("cindy", "c0").await;
+        cache.sync();
+        cache.invalidate_all();
+        cache.sync();
+        assert_eq!(*actual.lock(), expected);
+
+        cache.insert("david", "d0").await;
+        expected.push((
+            Arc::new("cindy"),
+            "c0",
+            RemovalCause::Invalidated { predicate_id: None },
+        ));
+        assert_eq!(*actual.lock(), expected);
+
+        // The async read methods also deliver them.
+        cache.insert("emily", "e0").await;
+        cache.sync();
+        let id = cache.invalidate_entries_if(|k, _v| *k == "david").unwrap();
+        // Run the invalidation task and wait for it to finish.
+        cache.sync(); // To submit the invalidation task.
+        std::thread::sleep(Duration::from_millis(200));
+        cache.sync(); // To process the task result.
+        assert!(!cache.contains_key(&"david"));
+        assert_eq!(*actual.lock(), expected);
+
+        assert_eq!(
+            cache.get_with("emily", async { unreachable!() }).await,
+            "e0"
+        );
+        expected.push((
+            Arc::new("david"),
+            "d0",
+            RemovalCause::Invalidated {
+                predicate_id: Some(PredicateHandle::parse(&id)),
+            },
+        ));
+        assert_eq!(*actual.lock(), expected);
+    }
+
+    #[tokio::test]
+    async fn async_eviction_listener_queue_overflow() {
+        use crate::notification::ListenerFuture;
+        use futures_util::FutureExt;
+
+        const KEYS: u64 = 1_100;
+        // The default capacity of the notification queue.
+        const QUEUE_CAPACITY: u64 = 1_024;
+
         let actual = Arc::new(Mutex::new(Vec::new()));
-        let mut expected = Vec::new();
-
-        // Create an eviction listener.
         let a1 = Arc::clone(&actual);
-        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+        let listener = move |k, _v, _cause| -> ListenerFuture {
+            a1.lock().push(k);
+            async {}.boxed()
+        };
 
-        // Create a cache with the eviction listener and also TTL and TTI.
         let mut cache = Cache::builder()
-            .eviction_listener_with_queued_delivery_mode(listener)
-            .time_to_live(Duration::from_secs(7))
-            .time_to_idle(Duration::from_secs(5))
+            .max_capacity(KEYS)
+            .async_eviction_listener(listener)
             .build();
         cache.reconfigure_for_testing();
 
-        let (clock, mock) = Clock::mock();
-        cache.set_expiration_clock(Some(clock));
-
         // Make the cache exterior immutable.
         let cache = cache;
 
-        cache.insert("alice", "a0").await;
+        for i in 0..KEYS {
+            cache.insert(i, i).await;
+        }
         cache.sync();
 
-        // Now alice (a0) has been expired by the idle timeout (TTI).
-        mock.increment(Duration::from_secs(6));
-        expected.push((Arc::new("alice"), "a0", RemovalCause::Expired));
-        assert_eq!(cache.get(&"alice"), None);
+        // Queue the notifications for all the entries, overflowing the queue. A
+        // housekeeping run removes a limited number of the invalidated entries.
+        cache.invalidate_all();
+        for _ in 0..10 {
+            cache.sync();
+        }
+        assert_eq!(cache.entry_count(), 0);
+        assert!(actual.lock().is_empty());
+        assert_eq!(cache.dropped_notification_count(), KEYS - QUEUE_CAPACITY);
 
-        // We have not ran sync after the expiration of alice (a0), so it is
-        // still in the cache.
-        assert_eq!(cache.entry_count(), 1);
+        cache.run_pending_tasks().await;
+        assert_eq!(actual.lock().len() as u64, QUEUE_CAPACITY);
+    }
 
-        // Re-insert alice with a different value. Since alice (a0) is still
-        // in the cache, this is actually a replace operation rather than an
-        // insert operation. We want to verify that the RemovalCause of a0 is
-        // Expired, not Replaced.
-        cache.insert("alice", "a1").await;
-        cache.sync();
+    #[tokio::test]
+    async fn async_eviction_listener_with_conf() {
+        use crate::notification::{ListenerFuture, OverflowPolicy};
+        use futures_util::FutureExt;
 
-        mock.increment(Duration::from_secs(4));
-        assert_eq!(cache.get(&"alice"), Some("a1"));
-        cache.sync();
+        const KEYS: u64 = 10;
+        const QUEUE_CAPACITY: u64 = 4;
+
+        run_test(OverflowPolicy::DropNewest).await;
+        run_test(OverflowPolicy::DropOldest).await;
+        // The housekeeping cannot wait for the async listener or call it, so the
+        // oldest notification is dropped.
+        run_test(OverflowPolicy::Block).await;
+
+        async fn run_test(policy: OverflowPolicy) {
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            let a1 = Arc::clone(&actual);
+            let listener = move |k: Arc<u64>, _v, _cause| -> ListenerFuture {
+                a1.lock().push(*k);
+                async {}.boxed()
+            };
+            let listener_conf = crate::notification::Configuration::builder()
+                .queue_capacity(QUEUE_CAPACITY as usize)
+                .overflow_policy(policy)
+                .build();
+
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .record_stats()
+                .async_eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            // Insert and evict the entries one by one, so that the order of the
+            // notifications is known. Use the blocking insert, which does not
+            // deliver the queued notifications.
+            for i in 0..KEYS {
+                cache.blocking().insert(i, i);
+                cache.sync();
+                cache.invalidate_all();
+                cache.sync();
+            }
+            assert!(actual.lock().is_empty());
+            let dropped = KEYS - QUEUE_CAPACITY;
+            assert_eq!(cache.dropped_notification_count(), dropped, "{:?}", policy);
+            assert_eq!(cache.stats().dropped_notification_count(), dropped);
+
+            cache.run_pending_tasks().await;
+            let expected = match policy {
+                OverflowPolicy::DropNewest => (0..QUEUE_CAPACITY).collect::<Vec<_>>(),
+                _ => (dropped..KEYS).collect(),
+            };
+            assert_eq!(*actual.lock(), expected, "{:?}", policy);
+        }
+    }
 
-        // Now alice has been expired by time-to-live (TTL).
-        mock.increment(Duration::from_secs(4));
-        expected.push((Arc::new("alice"), "a1", RemovalCause::Expired));
-        assert_eq!(cache.get(&"alice"), None);
+    #[tokio::test]
+    async fn queued_notification_overflow_policies() {
+        use crate::notification::OverflowPolicy;
+        use std::{
+            sync::atomic::{AtomicBool, Ordering},
+            thread::{self, ThreadId},
+        };
 
-        // But, again, it is still in the cache.
-        assert_eq!(cache.entry_count(), 1);
+        const KEYS: u32 = 10;
+
+        run_test(OverflowPolicy::DropNewest).await;
+        run_test(OverflowPolicy::DropOldest).await;
+        run_test(OverflowPolicy::SpillToCaller).await;
+
+        async fn run_test(policy: OverflowPolicy) {
+            // The listener records the key and whether it was called by this thread.
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            // The listener called by the notifier thread is blocked until this gate
+            // is opened, so that the notification queue will be full. Do not use a
+            // mutex for it, as its guard would be held across the awaits.
+            let is_gate_open = Arc::new(AtomicBool::new(false));
+            let test_thread: ThreadId = thread::current().id();
+
+            let a1 = Arc::clone(&actual);
+            let g1 = Arc::clone(&is_gate_open);
+            let listener = move |k: Arc<u32>, _v, _cause| {
+                let is_caller = thread::current().id() == test_thread;
+                while !is_caller && !g1.load(Ordering::Acquire) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                a1.lock().push((*k, is_caller));
+            };
+            // The delivery mode is ignored; the queued mode is always used.
+            let listener_conf = crate::notification::Configuration::builder()
+                .queue_capacity(1)
+                .overflow_policy(policy)
+                .build();
+
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .record_stats()
+                .eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            for i in 0..KEYS {
+                cache.insert(i, i).await;
+            }
+            cache.sync();
 
-        // Re-insert alice with a different value and verify that the
-        // RemovalCause of a1 is Expired (not Replaced).
-        cache.insert("alice", "a2").await;
-        cache.sync();
+            for i in 0..KEYS {
+                cache.invalidate(&i).await;
+                // Give the notifier thread a chance to receive the notification.
+                thread::sleep(Duration::from_millis(10));
+            }
+            is_gate_open.store(true, Ordering::Release);
+            cache.shutdown().await;
+
+            let actual = actual.lock().clone();
+            let dropped = cache.dropped_notification_count();
+            assert_eq!(actual.len() as u64 + dropped, KEYS as u64, "{:?}", policy);
+            assert_eq!(cache.stats().dropped_notification_count(), dropped);
+
+            // At most two notifications (one being delivered and one in the queue)
+            // can be held by the blocked notifier thread.
+            match policy {
+                OverflowPolicy::DropNewest => {
+                    assert!(dropped >= KEYS as u64 - 2, "{:?}", policy);
+                    assert!(!actual.contains(&(KEYS - 1, false)));
+                }
+                OverflowPolicy::DropOldest => {
+                    assert!(dropped >= KEYS as u64 - 2, "{:?}", policy);
+                    // The newest notification is never dropped.
+                    assert!(actual.contains(&(KEYS - 1, false)));
+                }
+                OverflowPolicy::SpillToCaller => {
+                    assert_eq!(dropped, 0);
+                    let spilled = actual.iter().filter(|(_, is_caller)| *is_caller).count();
+                    assert!(spilled >= KEYS as usize - 2);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
 
-        assert_eq!(cache.entry_count(), 1);
+    // Without the thread pool, the notification task is queued in a `TaskQueue` and
+    // run by the client task after the housekeeping. Make sure a housekeeping run
+    // evicting more entries than the queue capacity does not wait forever for the
+    // queue to have room with the `Block` policy.
+    #[tokio::test]
+    async fn queued_notification_block_without_thread_pool() {
+        const MAX_CAPACITY: u64 = 100;
+        const KEYS: u64 = 3_000;
 
-        // Now alice (a2) has been expired by the idle timeout.
-        mock.increment(Duration::from_secs(6));
-        expected.push((Arc::new("alice"), "a2", RemovalCause::Expired));
-        assert_eq!(cache.get(&"alice"), None);
-        assert_eq!(cache.entry_count(), 1);
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        // The queue capacity (1,024) is smaller than the number of the evictions.
+        let cache = Cache::builder()
+            .max_capacity(MAX_CAPACITY)
+            .eviction_listener_with_queued_delivery_mode(move |k: Arc<u64>, _v, cause| {
+                a1.lock().push((*k, cause))
+            })
+            .thread_pool_enabled(false)
+            .build();
 
-        // This invalidate will internally remove alice (a2).
-        cache.invalidate(&"alice").await;
-        cache.sync();
-        assert_eq!(cache.entry_count(), 0);
+        cache.insert_all((0..KEYS).map(|i| (i, i))).await;
+        cache.run_pending_tasks().await;
+        assert_eq!(cache.entry_count(), MAX_CAPACITY);
 
-        // Re-insert, and this time, make it expired by the TTL.
-        cache.insert("alice", "a3").await;
-        cache.sync();
-        mock.increment(Duration::from_secs(4));
-        assert_eq!(cache.get(&"alice"), Some("a3"));
-        cache.sync();
-        mock.increment(Duration::from_secs(4));
-        expected.push((Arc::new("alice"), "a3", RemovalCause::Expired));
-        assert_eq!(cache.get(&"alice"), None);
-        assert_eq!(cache.entry_count(), 1);
+        let actual = actual.lock();
+        assert_eq!(actual.len() as u64, KEYS - MAX_CAPACITY);
+        assert!(actual.iter().all(|(_, cause)| *cause == RemovalCause::Size));
+        assert_eq!(cache.dropped_notification_count(), 0);
+    }
 
-        // This invalidate will internally remove alice (a2).
-        cache.invalidate(&"alice").await;
-        cache.sync();
-        assert_eq!(cache.entry_count(), 0);
+    #[tokio::test]
+    async fn shutdown() {
+        use crate::notification::ListenerFuture;
+        use futures_util::FutureExt;
 
-        verify_notification_vec(&cache, actual, &expected);
+        // The following `Vec`s will hold actual notifications.
+        let actual1 = Arc::new(Mutex::new(Vec::new()));
+        let actual2 = Arc::new(Mutex::new(Vec::new()));
+
+        // Create a cache with a queued eviction listener, and another cache with
+        // an async eviction listener.
+        let a1 = Arc::clone(&actual1);
+        let mut cache1 = Cache::builder()
+            .max_capacity(100)
+            .eviction_listener_with_queued_delivery_mode(move |k, v, cause| {
+                a1.lock().push((k, v, cause))
+            })
+            .build();
+        cache1.reconfigure_for_testing();
+
+        let a2 = Arc::clone(&actual2);
+        let mut cache2 = Cache::builder()
+            .max_capacity(100)
+            .async_eviction_listener(move |k, v, cause| -> ListenerFuture {
+                let a3 = Arc::clone(&a2);
+                async move { a3.lock().push((k, v, cause)) }.boxed()
+            })
+            .build();
+        cache2.reconfigure_for_testing();
+
+        let expected = (0..50)
+            .map(|i| {
+                let cause = RemovalCause::Invalidated { predicate_id: None };
+                (Arc::new(i), i * 10, cause)
+            })
+            .collect::<Vec<_>>();
+
+        for (cache, actual) in [(cache1, actual1), (cache2, actual2)].iter() {
+            for i in 0..50 {
+                cache.insert(i, i * 10).await;
+            }
+            cache.sync();
+            // The entries invalidated by `invalidate_all` should be notified too.
+            cache.invalidate_all();
+
+            // All notifications should have been delivered when `shutdown` returns,
+            // so do not use `verify_notification_vec` here.
+            cache.shutdown().await;
+            assert_eq!(cache.entry_count(), 0);
+            assert!(cache.is_table_empty());
+
+            let mut actual = actual.lock().clone();
+            actual.sort_by_key(|(k, _, _)| **k);
+            assert_eq!(actual, expected);
+        }
     }
 
-    // NOTE: To enable the panic logging, run the following command:
-    //
-    // RUST_LOG=moka=info cargo test --features 'future, logging' -- \
-    //   future::cache::tests::recover_from_panicking_eviction_listener --exact --nocapture
-    //
     #[tokio::test]
-    async fn recover_from_panicking_eviction_listener() {
+    async fn recover_from_panicking_async_eviction_listener() {
+        use crate::notification::ListenerFuture;
+        use futures_util::FutureExt;
+
         #[cfg(feature = "logging")]
         let _ = env_logger::builder().is_test(true).try_init();
 
@@ -3794,20 +6065,24 @@ mod tests {
         let actual = Arc::new(Mutex::new(Vec::new()));
         let mut expected = Vec::new();
 
-        // Create an eviction listener that panics when it see
-        // a value "panic now!".
+        // Create an async eviction listener whose future panics when it see a
+        // value "panic now!".
         let a1 = Arc::clone(&actual);
-        let listener = move |k, v, cause| {
-            if v == "panic now!" {
-                panic!("Panic now!");
+        let listener = move |k, v, cause| -> ListenerFuture {
+            let a2 = Arc::clone(&a1);
+            async move {
+                if v == "panic now!" {
+                    panic!("Panic now!");
+                }
+                a2.lock().push((k, v, cause))
             }
-            a1.lock().push((k, v, cause))
+            .boxed()
         };
 
-        // Create a cache with the eviction listener.
+        // Create a cache with the async eviction listener.
         let mut cache = Cache::builder()
             .name("My Future Cache")
-            .eviction_listener_with_queued_delivery_mode(listener)
+            .async_eviction_listener(listener)
             .build();
         cache.reconfigure_for_testing();
 
@@ -3816,24 +6091,21 @@ mod tests {
 
         // Insert an okay value.
         cache.insert("alice", "a0").await;
-        cache.sync();
 
         // Insert a value that will cause the eviction listener to panic.
         cache.insert("alice", "panic now!").await;
         expected.push((Arc::new("alice"), "a0", RemovalCause::Replaced));
-        cache.sync();
 
-        // Insert an okay value. This will replace the previous
-        // value "panic now!" so the eviction listener will panic.
+        // Insert an okay value. This will replace the previous value "panic now!"
+        // so the eviction listener will panic.
         cache.insert("alice", "a2").await;
-        cache.sync();
         // No more removal notification should be sent.
 
         // Invalidate the okay value.
         cache.invalidate(&"alice").await;
-        cache.sync();
 
-        verify_notification_vec(&cache, actual, &expected);
+        assert_eq!(*actual.lock(), expected);
+        assert!(cache.get(&"alice").is_none());
     }
 
     // This test ensures that the `contains_key`, `get` and `invalidate` can use
@@ -3975,6 +6247,259 @@ mod tests {
         assert!(debug_str.ends_with('}'));
     }
 
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn snapshot_and_restore_to_smaller_cache() {
+        let cache = Cache::new(10);
+        for i in 0..10u32 {
+            cache.insert(i, i * 10).await;
+        }
+        cache.sync();
+
+        let mut snapshot = Vec::new();
+        cache.snapshot_to(&mut snapshot).unwrap();
+
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        // Restore the snapshot to a cache with a smaller capacity. The LRU entries
+        // should be evicted.
+        let restored: Cache<u32, u32> = Cache::builder()
+            .max_capacity(5)
+            .eviction_listener_with_queued_delivery_mode(listener)
+            .restore_from(&snapshot[..])
+            .unwrap();
+        for i in 0..5u32 {
+            expected.push((Arc::new(i), i * 10, RemovalCause::Size));
+        }
+
+        assert_eq!(restored.entry_count(), 5);
+        for i in 0..5u32 {
+            assert!(!restored.contains_key(&i));
+        }
+        for i in 5..10u32 {
+            assert_eq!(restored.get(&i), Some(i * 10));
+        }
+
+        verify_notification_vec(&restored, actual, &expected);
+    }
+
+    /// Returns the removal cause of an entry invalidated by the predicate.
+    fn invalidated_by(predicate_id: &str) -> RemovalCause {
+        RemovalCause::Invalidated {
+            predicate_id: Some(PredicateHandle::parse(predicate_id)),
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_executor_and_disabled_thread_pool() {
+        use crate::executor::{Executor, Task};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // An executor running each task on a new thread, which counts the tasks.
+        #[derive(Default)]
+        struct ThreadPerTaskExecutor {
+            tasks: AtomicUsize,
+            delayed_tasks: AtomicUsize,
+        }
+
+        impl Executor for ThreadPerTaskExecutor {
+            fn execute(&self, task: Task) {
+                self.tasks.fetch_add(1, Ordering::AcqRel);
+                std::thread::spawn(task);
+            }
+
+            fn execute_after(&self, delay: Duration, task: Task) {
+                self.delayed_tasks.fetch_add(1, Ordering::AcqRel);
+                std::thread::spawn(move || {
+                    std::thread::sleep(delay);
+                    task();
+                });
+            }
+        }
+
+        let executor = Arc::new(ThreadPerTaskExecutor::default());
+
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .support_invalidation_closures()
+            .thread_pool_enabled(false)
+            .executor(Arc::clone(&executor))
+            .build();
+
+        cache.insert("alice", 0).await;
+        cache.insert("bob", 1).await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+
+        // The housekeeping tasks are executed by the client, so no task should
+        // have been submitted to the executor.
+        assert_eq!(executor.tasks.load(Ordering::Acquire), 0);
+        assert_eq!(executor.delayed_tasks.load(Ordering::Acquire), 0);
+
+        cache.invalidate_entries_if(|k, _v| *k == "alice").unwrap();
+
+        // Run the invalidation task and wait for it to finish.
+        cache.sync(); // To submit the invalidation task.
+        Timer::after(Duration::from_millis(200)).await;
+        cache.sync(); // To process the task result.
+
+        assert!(cache.get(&"alice").is_none());
+        assert_eq!(cache.get(&"bob"), Some(1));
+
+        // The invalidation task ran on the executor.
+        assert!(executor.tasks.load(Ordering::Acquire) >= 1);
+        assert_eq!(executor.delayed_tasks.load(Ordering::Acquire), 0);
+    }
+
+    #[tokio::test]
+    async fn run_pending_tasks_without_background_threads() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        let reload_count = Arc::new(AtomicU32::new(0));
+        let count = Arc::clone(&reload_count);
+
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .support_invalidation_closures()
+            .eviction_listener_with_queued_delivery_mode(move |k, v, cause| {
+                a1.lock().push((k, v, cause))
+            })
+            .refresh_after_write(Duration::from_secs(10))
+            .reloader(move |_key: &&str, value: &u32| {
+                count.fetch_add(1, Ordering::AcqRel);
+                let new_value = *value + 1;
+                async move { Some(new_value) }
+            })
+            .thread_pool_enabled(false)
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("alice", 1).await;
+        cache.insert("bob", 10).await;
+        cache.run_pending_tasks().await;
+        assert_eq!(cache.entry_count(), 2);
+
+        // The invalidation task and the notification task are run by
+        // run_pending_tasks, so no need to wait for background threads.
+        let id = cache.invalidate_entries_if(|k, _v| *k == "bob").unwrap();
+        cache.run_pending_tasks().await;
+        assert!(!cache.contains_key(&"bob"));
+        assert_eq!(cache.entry_count(), 1);
+        assert_eq!(
+            *actual.lock(),
+            vec![(
+                Arc::new("bob"),
+                10,
+                RemovalCause::Invalidated {
+                    predicate_id: Some(PredicateHandle::parse(&id))
+                }
+            )]
+        );
+        actual.lock().clear();
+
+        // "alice" is stale. The reload is not started until run_pending_tasks is
+        // called.
+        mock.increment(Duration::from_secs(11));
+        assert_eq!(cache.get(&"alice"), Some(1));
+        assert_eq!(cache.get(&"alice"), Some(1));
+        assert_eq!(reload_count.load(Ordering::Acquire), 0);
+
+        cache.run_pending_tasks().await;
+        assert_eq!(reload_count.load(Ordering::Acquire), 1);
+        assert_eq!(cache.get(&"alice"), Some(2));
+
+        cache.run_pending_tasks().await;
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new("alice"), 1, RemovalCause::Replaced)]
+        );
+    }
+
+    #[tokio::test]
+    async fn ticker_and_deterministic_mode() {
+        use crate::ticker::MockTicker;
+
+        let ticker = Arc::new(MockTicker::new());
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+
+        // Deterministic mode takes precedence over the thread pool.
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_idle(Duration::from_secs(10))
+            .eviction_listener_with_queued_delivery_mode(move |k, v, cause| {
+                a1.lock().push((k, v, cause))
+            })
+            .thread_pool_enabled(true)
+            .ticker(Arc::clone(&ticker))
+            .deterministic_mode(true)
+            .build();
+
+        cache.insert("alice", 1).await;
+        cache.insert("bob", 10).await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+
+        ticker.advance(Duration::from_secs(9));
+        assert_eq!(cache.get(&"alice"), Some(1));
+        // Apply the read to the idle time of "alice".
+        cache.sync();
+
+        // "bob" has expired, but "alice" has not as it was read.
+        ticker.advance(Duration::from_secs(1));
+        assert_eq!(cache.get(&"bob"), None);
+        assert_eq!(cache.get(&"alice"), Some(1));
+
+        // The expired entry is removed and the listener is notified by sync.
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new("bob"), 10, RemovalCause::Expired)]
+        );
+    }
+
+    #[tokio::test]
+    async fn max_memory_bytes_with_entry_overhead() {
+        use crate::common::concurrent::entry_overhead;
+        use std::mem::size_of;
+
+        let overhead = entry_overhead::<u32, Vec<u8>>(false, false);
+        let weight = (size_of::<u32>() + size_of::<Vec<u8>>() + 1000) as u64 + overhead;
+
+        let cache = Cache::builder()
+            .max_memory_bytes(2 * weight)
+            .include_entry_overhead(true)
+            .deterministic_mode(true)
+            .build();
+
+        cache.insert(0u32, vec![0u8; 1000]).await;
+        cache.insert(1, vec![1; 1000]).await;
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+        assert_eq!(cache.weighted_size(), 2 * weight);
+
+        // A larger value will not fit even if the other entries are evicted.
+        cache.insert(2, vec![2; 3000]).await;
+        cache.sync();
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.weighted_size(), 2 * weight);
+    }
+
     type NotificationTuple<K, V> = (Arc<K>, V, RemovalCause);
 
     fn verify_notification_vec<K, V, S>(
@@ -1,4 +1,4 @@
-use crate::Entry;
+use crate::{ops::compute::Op, Entry};
 
 use super::Cache;
 
@@ -7,6 +7,7 @@ use std::{
     future::Future,
     hash::{BuildHasher, Hash},
     sync::Arc,
+    time::Duration,
 };
 
 /// Provides advanced methods to select or insert an entry of the cache.
@@ -78,7 +79,7 @@ where
     {
         let key = Arc::new(self.owned_key);
         self.cache
-            .get_or_insert_with_hash(key, self.hash, Default::default)
+            .get_or_insert_with_hash(key, self.hash, Default::default, None)
             .await
     }
 
@@ -119,7 +120,47 @@ where
         let key = Arc::new(self.owned_key);
         let init = || default;
         self.cache
-            .get_or_insert_with_hash(key, self.hash, init)
+            .get_or_insert_with_hash(key, self.hash, init, None)
+            .await
+    }
+
+    /// Works like [`or_insert`](#method.or_insert), but the inserted entry will
+    /// expire after the given `ttl`, regardless of the `time_to_live` and
+    /// `expire_after` settings of the cache. The TTL is not applied if the entry
+    /// already exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.10", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    ///
+    /// use moka::future::Cache;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache: Cache<String, u32> = Cache::new(100);
+    ///     let key = "key1".to_string();
+    ///
+    ///     let ttl = Duration::from_secs(30);
+    ///     let entry = cache.entry(key.clone()).or_insert_with_ttl(3, ttl).await;
+    ///     assert!(entry.is_fresh());
+    ///     assert_eq!(entry.into_value(), 3);
+    ///
+    ///     let entry = cache.entry(key).or_insert_with_ttl(6, ttl).await;
+    ///     assert!(!entry.is_fresh());
+    ///     assert_eq!(entry.into_value(), 3);
+    /// }
+    /// ```
+    pub async fn or_insert_with_ttl(self, default: V, ttl: Duration) -> Entry<K, V> {
+        let key = Arc::new(self.owned_key);
+        let init = || default;
+        self.cache
+            .get_or_insert_with_hash(key, self.hash, init, Some(ttl))
             .await
     }
 
@@ -340,6 +381,116 @@ where
             .get_or_try_insert_with_hash_and_fun(key, self.hash, init, true)
             .await
     }
+
+    /// Performs a compute operation on the cached entry by evaluating the closure
+    /// `f` and resolving the returned future. The closure takes the current entry
+    /// (`None` if the entry does not exist), and the future resolves to an [`Op`]
+    /// telling what to do with the entry:
+    ///
+    /// - `Op::Put(V)`: Inserts the value, or replaces the existing value with it.
+    /// - `Op::Remove`: Removes the entry.
+    /// - `Op::Nop`: Leaves the entry as it is.
+    ///
+    /// Returns the entry after the operation, or `None` if the entry does not exist
+    /// (e.g. it has been removed). The returned entry is fresh only if the value was
+    /// put by `f`.
+    ///
+    /// [`Op`]: ../ops/compute/enum.Op.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::{future::Cache, ops::compute::Op, Entry};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache: Cache<String, u32> = Cache::new(100);
+    ///     let key = "key1".to_string();
+    ///
+    ///     // Increment the counter, or remove it when it reaches 2.
+    ///     let incr = |maybe_entry: Option<Entry<String, u32>>| async move {
+    ///         match maybe_entry {
+    ///             Some(entry) if *entry.value() >= 2 => Op::Remove,
+    ///             Some(entry) => Op::Put(entry.into_value() + 1),
+    ///             None => Op::Put(1),
+    ///         }
+    ///     };
+    ///
+    ///     let entry = cache.entry(key.clone()).and_compute_with(incr).await;
+    ///     assert_eq!(entry.map(|e| e.into_value()), Some(1));
+    ///     let entry = cache.entry(key.clone()).and_compute_with(incr).await;
+    ///     assert_eq!(entry.map(|e| e.into_value()), Some(2));
+    ///     let entry = cache.entry(key.clone()).and_compute_with(incr).await;
+    ///     assert!(entry.is_none());
+    ///     assert!(!cache.contains_key(&key));
+    /// }
+    /// ```
+    ///
+    /// # Concurrent calls on the same key
+    ///
+    /// The compute operation is atomic: the `Op` resolved by the future is applied
+    /// only if the entry has not been modified by another write, such as `insert`,
+    /// `invalidate` or another compute operation, since `f` read it. Otherwise, `f`
+    /// is evaluated again with the new entry and the new future is resolved.
+    /// Therefore, `f` can be evaluated more than once and should not have side
+    /// effects.
+    pub async fn and_compute_with<F, Fut>(self, f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Fut,
+        Fut: Future<Output = Op<V>>,
+    {
+        let key = Arc::new(self.owned_key);
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, f)
+            .await
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the closure
+    /// `f` is evaluated only when the entry exists. If the entry does not exist,
+    /// returns `None` without doing anything.
+    pub async fn and_compute_if_present<F, Fut>(self, mut f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Entry<K, V>) -> Fut,
+        Fut: Future<Output = Op<V>>,
+    {
+        let key = Arc::new(self.owned_key);
+        let f = |maybe_entry: Option<Entry<K, V>>| {
+            let fut = maybe_entry.map(&mut f);
+            async move {
+                match fut {
+                    Some(fut) => fut.await,
+                    None => Op::Nop,
+                }
+            }
+        };
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, f)
+            .await
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the future
+    /// returned by `f` always resolves to a value to insert or to replace the
+    /// existing value with. Returns the entry holding the new value.
+    pub async fn and_upsert_with<F, Fut>(self, mut f: F) -> Entry<K, V>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let key = Arc::new(self.owned_key);
+        let f = |maybe_entry| {
+            let fut = f(maybe_entry);
+            async move { Op::Put(fut.await) }
+        };
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, f)
+            .await
+            .expect("Bug: The upserted entry is None")
+    }
 }
 
 /// Provides advanced methods to select or insert an entry of the cache.
@@ -415,7 +566,7 @@ where
         V: Default,
     {
         self.cache
-            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, Default::default)
+            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, Default::default, None)
             .await
     }
 
@@ -455,7 +606,46 @@ where
     pub async fn or_insert(self, default: V) -> Entry<K, V> {
         let init = || default;
         self.cache
-            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, init)
+            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, init, None)
+            .await
+    }
+
+    /// Works like [`or_insert`](#method.or_insert), but the inserted entry will
+    /// expire after the given `ttl`, regardless of the `time_to_live` and
+    /// `expire_after` settings of the cache. The TTL is not applied if the entry
+    /// already exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.10", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    ///
+    /// use moka::future::Cache;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache: Cache<String, u32> = Cache::new(100);
+    ///     let key = "key1".to_string();
+    ///
+    ///     let ttl = Duration::from_secs(30);
+    ///     let entry = cache.entry_by_ref(&key).or_insert_with_ttl(3, ttl).await;
+    ///     assert!(entry.is_fresh());
+    ///     assert_eq!(entry.into_value(), 3);
+    ///
+    ///     let entry = cache.entry_by_ref(&key).or_insert_with_ttl(6, ttl).await;
+    ///     assert!(!entry.is_fresh());
+    ///     assert_eq!(entry.into_value(), 3);
+    /// }
+    /// ```
+    pub async fn or_insert_with_ttl(self, default: V, ttl: Duration) -> Entry<K, V> {
+        let init = || default;
+        self.cache
+            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, init, Some(ttl))
             .await
     }
 
@@ -677,4 +867,114 @@ where
             .get_or_try_insert_with_hash_by_ref_and_fun(self.ref_key, self.hash, init, true)
             .await
     }
+
+    /// Performs a compute operation on the cached entry by evaluating the closure
+    /// `f` and resolving the returned future. The closure takes the current entry
+    /// (`None` if the entry does not exist), and the future resolves to an [`Op`]
+    /// telling what to do with the entry:
+    ///
+    /// - `Op::Put(V)`: Inserts the value, or replaces the existing value with it.
+    /// - `Op::Remove`: Removes the entry.
+    /// - `Op::Nop`: Leaves the entry as it is.
+    ///
+    /// Returns the entry after the operation, or `None` if the entry does not exist
+    /// (e.g. it has been removed). The returned entry is fresh only if the value was
+    /// put by `f`.
+    ///
+    /// [`Op`]: ../ops/compute/enum.Op.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["future"] }
+    /// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+    /// use moka::{future::Cache, ops::compute::Op, Entry};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache: Cache<String, u32> = Cache::new(100);
+    ///     let key = "key1".to_string();
+    ///
+    ///     // Increment the counter, or remove it when it reaches 2.
+    ///     let incr = |maybe_entry: Option<Entry<String, u32>>| async move {
+    ///         match maybe_entry {
+    ///             Some(entry) if *entry.value() >= 2 => Op::Remove,
+    ///             Some(entry) => Op::Put(entry.into_value() + 1),
+    ///             None => Op::Put(1),
+    ///         }
+    ///     };
+    ///
+    ///     let entry = cache.entry_by_ref(&key).and_compute_with(incr).await;
+    ///     assert_eq!(entry.map(|e| e.into_value()), Some(1));
+    ///     let entry = cache.entry_by_ref(&key).and_compute_with(incr).await;
+    ///     assert_eq!(entry.map(|e| e.into_value()), Some(2));
+    ///     let entry = cache.entry_by_ref(&key).and_compute_with(incr).await;
+    ///     assert!(entry.is_none());
+    ///     assert!(!cache.contains_key(&key));
+    /// }
+    /// ```
+    ///
+    /// # Concurrent calls on the same key
+    ///
+    /// The compute operation is atomic: the `Op` resolved by the future is applied
+    /// only if the entry has not been modified by another write, such as `insert`,
+    /// `invalidate` or another compute operation, since `f` read it. Otherwise, `f`
+    /// is evaluated again with the new entry and the new future is resolved.
+    /// Therefore, `f` can be evaluated more than once and should not have side
+    /// effects.
+    pub async fn and_compute_with<F, Fut>(self, f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Fut,
+        Fut: Future<Output = Op<V>>,
+    {
+        let key = Arc::new(self.ref_key.to_owned());
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, f)
+            .await
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the closure
+    /// `f` is evaluated only when the entry exists. If the entry does not exist,
+    /// returns `None` without doing anything.
+    pub async fn and_compute_if_present<F, Fut>(self, mut f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Entry<K, V>) -> Fut,
+        Fut: Future<Output = Op<V>>,
+    {
+        let key = Arc::new(self.ref_key.to_owned());
+        let f = |maybe_entry: Option<Entry<K, V>>| {
+            let fut = maybe_entry.map(&mut f);
+            async move {
+                match fut {
+                    Some(fut) => fut.await,
+                    None => Op::Nop,
+                }
+            }
+        };
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, f)
+            .await
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the future
+    /// returned by `f` always resolves to a value to insert or to replace the
+    /// existing value with. Returns the entry holding the new value.
+    pub async fn and_upsert_with<F, Fut>(self, mut f: F) -> Entry<K, V>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let key = Arc::new(self.ref_key.to_owned());
+        let f = |maybe_entry| {
+            let fut = f(maybe_entry);
+            async move { Op::Put(fut.await) }
+        };
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, f)
+            .await
+            .expect("Bug: The upserted entry is None")
+    }
 }
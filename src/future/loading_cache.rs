@@ -0,0 +1,430 @@
+use super::Cache;
+
+use futures_util::future::{BoxFuture, FutureExt};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashMap},
+    fmt,
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+};
+
+/// Loads the values of the keys that are not in a [`LoadingCache`][loading-cache].
+///
+/// The methods return boxed futures, so that the trait can be implemented without
+/// an extra crate for `async fn` in traits. Create one by calling `boxed()` of
+/// [`FutureExt`][future-ext] on an `async` block.
+///
+/// [loading-cache]: ./struct.LoadingCache.html
+/// [future-ext]: https://docs.rs/futures-util/0.3/futures_util/future/trait.FutureExt.html
+pub trait CacheLoader<K, V> {
+    /// The type of the error returned by the loader.
+    type Error: Send + Sync + 'static;
+
+    /// Loads the value of the given key.
+    fn load<'a>(&'a self, key: &'a K) -> BoxFuture<'a, Result<V, Self::Error>>;
+
+    /// Loads the values of the given keys. The returned `Vec` must have the values
+    /// in the same order as the `keys`.
+    ///
+    /// The default implementation calls [`load`](#tymethod.load) for each key one by
+    /// one. Override it if the values can be loaded more efficiently in a batch
+    /// (e.g. by a single database query).
+    fn load_all<'a>(&'a self, keys: &'a [&'a K]) -> BoxFuture<'a, Result<Vec<V>, Self::Error>>
+    where
+        Self: Sync,
+        K: Sync,
+        V: Send,
+    {
+        async move {
+            let mut values = Vec::with_capacity(keys.len());
+            for key in keys {
+                values.push(self.load(key).await?);
+            }
+            Ok(values)
+        }
+        .boxed()
+    }
+}
+
+/// A [`Cache`][cache-struct] that loads the missing values by a
+/// [`CacheLoader`][cache-loader] registered to the cache.
+///
+/// Build a `LoadingCache` by
+/// [`CacheBuilder::build_with_loader`][build-with-loader] method.
+///
+/// `get` method returns the cached value of the key, or loads the value by the
+/// loader and inserts it to the cache. Like [`Cache::try_get_with`][try-get-with],
+/// concurrent calls on the same missing key are coalesced into one `load` call.
+/// An error returned by the loader is wrapped by an `Arc` and returned to all the
+/// calls, and nothing is inserted to the cache.
+///
+/// Other methods of the `Cache` are available via [`as_cache`](#method.as_cache)
+/// method.
+///
+/// # Example
+///
+/// ```rust
+/// // Cargo.toml
+/// //
+/// // [dependencies]
+/// // moka = { version = "0.9", features = ["future"] }
+/// // futures-util = "0.3"
+/// // tokio = { version = "1", features = ["rt-multi-thread", "macros" ] }
+/// use moka::future::{Cache, CacheLoader};
+/// use futures_util::future::{BoxFuture, FutureExt};
+///
+/// struct Loader;
+///
+/// impl CacheLoader<u32, String> for Loader {
+///     type Error = String;
+///
+///     fn load<'a>(&'a self, key: &'a u32) -> BoxFuture<'a, Result<String, Self::Error>> {
+///         async move {
+///             // Load the value from a slow data source, e.g. a database.
+///             if *key < 100 {
+///                 Ok(format!("value of {}", key))
+///             } else {
+///                 Err(format!("key {} not found", key))
+///             }
+///         }
+///         .boxed()
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let cache = Cache::builder()
+///         .max_capacity(100)
+///         .build_with_loader(Loader);
+///
+///     assert_eq!(cache.get(&1).await.unwrap(), "value of 1");
+///     assert!(cache.get(&1000).await.is_err());
+///
+///     assert!(cache.contains_key(&1));
+///     assert!(!cache.contains_key(&1000));
+/// }
+/// ```
+///
+/// [cache-struct]: ./struct.Cache.html
+/// [cache-loader]: ./trait.CacheLoader.html
+/// [build-with-loader]: ./struct.CacheBuilder.html#method.build_with_loader
+/// [try-get-with]: ./struct.Cache.html#method.try_get_with
+pub struct LoadingCache<K, V, L, S = RandomState> {
+    cache: Cache<K, V, S>,
+    loader: Arc<L>,
+}
+
+impl<K, V, L, S> Clone for LoadingCache<K, V, L, S> {
+    /// Makes a clone of this shared cache.
+    ///
+    /// This operation is cheap as it only creates thread-safe reference counted
+    /// pointers to the shared internal data structures.
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+            loader: Arc::clone(&self.loader),
+        }
+    }
+}
+
+impl<K, V, L, S> fmt::Debug for LoadingCache<K, V, L, S>
+where
+    K: fmt::Debug + Eq + Hash + Send + Sync + 'static,
+    V: fmt::Debug + Clone + Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.cache.fmt(f)
+    }
+}
+
+impl<K, V, L, S> LoadingCache<K, V, L, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+    L: CacheLoader<K, V>,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    pub(crate) fn new(cache: Cache<K, V, S>, loader: L) -> Self {
+        Self {
+            cache,
+            loader: Arc::new(loader),
+        }
+    }
+
+    /// Returns a _clone_ of the value corresponding to the key. If the value does
+    /// not exist, loads it by the `CacheLoader` and inserts it to the cache.
+    ///
+    /// If the loader returns an error, this method returns the error wrapped by an
+    /// `Arc` and does not insert anything to the cache. Concurrent calls on the
+    /// same key are coalesced into one `load` call, and all of them get the same
+    /// value or error.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub async fn get<Q>(&self, key: &Q) -> Result<V, Arc<L::Error>>
+    where
+        K: Borrow<Q>,
+        Q: ToOwned<Owned = K> + Hash + Eq + ?Sized,
+    {
+        let hash = self.cache.hash(key);
+        if let Some(entry) = self.cache.get_with_hash_and_notify(key, hash, false).await {
+            return Ok(entry.into_value());
+        }
+
+        // Allocate the owned key only when the value needs to be loaded.
+        let key = Arc::new(key.to_owned());
+        let k = Arc::clone(&key);
+        self.cache
+            .try_insert_with_hash_and_fun(k, hash, self.loader.load(&key), false)
+            .await
+            .map(|entry| entry.into_value())
+    }
+
+    /// Returns a `HashMap` of the keys and _clones_ of the values for the given
+    /// keys. The values of the keys that do not exist in the cache are loaded by
+    /// one [`load_all`](./trait.CacheLoader.html#method.load_all) call of the
+    /// `CacheLoader`, and inserted to the cache.
+    ///
+    /// If some of the missing keys are being loaded by other async tasks, they are
+    /// not passed to `load_all`, and this method waits for the values loaded by
+    /// the other tasks. See [`Cache::get_all_with`][get-all-with] for more details.
+    ///
+    /// If the loader returns an error, this method returns the error wrapped by an
+    /// `Arc` and does not insert any of the values loaded by the failed call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `load_all` returns a `Vec` whose length differs from the number of
+    /// the given keys.
+    ///
+    /// [get-all-with]: ./struct.Cache.html#method.get_all_with
+    pub async fn get_all<I>(&self, keys: I) -> Result<HashMap<K, V>, Arc<L::Error>>
+    where
+        I: IntoIterator<Item = K>,
+        K: Clone,
+        L: Sync,
+    {
+        let loader = &self.loader;
+        let load_all = |keys: Vec<Arc<K>>| async move {
+            let keys = keys.iter().map(|k| &**k).collect::<Vec<_>>();
+            loader.load_all(&keys).await
+        };
+        self.cache.try_get_all_with(keys, load_all).await
+    }
+
+    /// Returns a _clone_ of the value corresponding to the key, without loading
+    /// it when the value does not exist.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub fn get_if_present<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.get(key)
+    }
+
+    /// Returns `true` if the cache contains a value for the key.
+    ///
+    /// See [`Cache::contains_key`](./struct.Cache.html#method.contains_key) for
+    /// more details.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.contains_key(key)
+    }
+
+    /// Inserts a key-value pair into the cache, without calling the loader.
+    ///
+    /// If the cache has this key present, the value is updated.
+    pub async fn insert(&self, key: K, value: V) {
+        self.cache.insert(key, value).await
+    }
+
+    /// Discards any cached value for the key.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub async fn invalidate<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.invalidate(key).await
+    }
+
+    /// Discards all cached values.
+    ///
+    /// See [`Cache::invalidate_all`](./struct.Cache.html#method.invalidate_all)
+    /// for more details.
+    pub fn invalidate_all(&self) {
+        self.cache.invalidate_all()
+    }
+
+    /// Returns a reference to the `CacheLoader` of this cache.
+    pub fn loader(&self) -> &L {
+        &self.loader
+    }
+
+    /// Returns a reference to the underlying `Cache`.
+    ///
+    /// Values inserted through the returned `Cache` are visible to this
+    /// `LoadingCache`, and vice versa.
+    pub fn as_cache(&self) -> &Cache<K, V, S> {
+        &self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CacheLoader;
+    use crate::future::{Cache, ConcurrentCacheExt};
+
+    use async_io::Timer;
+    use futures_util::future::{BoxFuture, FutureExt};
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[derive(Default)]
+    struct SlowLoader {
+        load_count: AtomicU32,
+    }
+
+    impl CacheLoader<u32, String> for SlowLoader {
+        type Error = String;
+
+        fn load<'a>(&'a self, key: &'a u32) -> BoxFuture<'a, Result<String, Self::Error>> {
+            async move {
+                self.load_count.fetch_add(1, Ordering::AcqRel);
+                Timer::after(Duration::from_millis(300)).await;
+                if *key % 2 == 0 {
+                    Ok(format!("value {}", key))
+                } else {
+                    Err(format!("odd key {}", key))
+                }
+            }
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn basic_single_async_task() {
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .build_with_loader(SlowLoader::default());
+
+        assert_eq!(cache.get_if_present(&0), None);
+        assert_eq!(cache.get(&0).await, Ok("value 0".to_string()));
+        assert_eq!(cache.get_if_present(&0), Some("value 0".to_string()));
+        assert_eq!(cache.loader().load_count.load(Ordering::Acquire), 1);
+
+        // An error is returned but not cached.
+        assert_eq!(cache.get(&1).await, Err(Arc::new("odd key 1".to_string())));
+        assert!(!cache.contains_key(&1));
+        assert_eq!(cache.loader().load_count.load(Ordering::Acquire), 2);
+
+        // Inserted values are returned without loading.
+        cache.insert(1, "one".to_string()).await;
+        assert_eq!(cache.get(&1).await, Ok("one".to_string()));
+        assert_eq!(cache.loader().load_count.load(Ordering::Acquire), 2);
+
+        cache.invalidate(&0).await;
+        assert_eq!(cache.get_if_present(&0), None);
+        assert_eq!(cache.get(&0).await, Ok("value 0".to_string()));
+        assert_eq!(cache.loader().load_count.load(Ordering::Acquire), 3);
+
+        cache.as_cache().sync();
+        assert_eq!(cache.as_cache().entry_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_loads_are_coalesced() {
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .build_with_loader(SlowLoader::default());
+
+        let tasks = (0..8u32).map(|i| {
+            let cache = cache.clone();
+            // Four tasks get the key 2 and others get the key 3.
+            let key = 2 + i % 2;
+            async move { cache.get(&key).await }
+        });
+        let results = futures_util::future::join_all(tasks).await;
+
+        for (i, result) in results.into_iter().enumerate() {
+            if i % 2 == 0 {
+                assert_eq!(result, Ok("value 2".to_string()));
+            } else {
+                assert_eq!(result, Err(Arc::new("odd key 3".to_string())));
+            }
+        }
+
+        // Only one load per key.
+        assert_eq!(cache.loader().load_count.load(Ordering::Acquire), 2);
+    }
+
+    #[derive(Default)]
+    struct BatchLoader {
+        batch_count: AtomicU32,
+    }
+
+    impl CacheLoader<u32, u32> for BatchLoader {
+        type Error = String;
+
+        fn load<'a>(&'a self, _key: &'a u32) -> BoxFuture<'a, Result<u32, Self::Error>> {
+            unreachable!()
+        }
+
+        fn load_all<'a>(
+            &'a self,
+            keys: &'a [&'a u32],
+        ) -> BoxFuture<'a, Result<Vec<u32>, Self::Error>> {
+            async move {
+                self.batch_count.fetch_add(1, Ordering::AcqRel);
+                if keys.iter().any(|k| **k >= 100) {
+                    Err("too large".to_string())
+                } else {
+                    Ok(keys.iter().map(|k| **k * 10).collect())
+                }
+            }
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn get_all() {
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .build_with_loader(BatchLoader::default());
+        cache.insert(1, 1).await;
+
+        let values = cache.get_all(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[&1], 1);
+        assert_eq!(values[&2], 20);
+        assert_eq!(values[&3], 30);
+        assert_eq!(cache.loader().batch_count.load(Ordering::Acquire), 1);
+
+        // All keys exist.
+        assert_eq!(cache.get_all(vec![2, 3]).await.unwrap().len(), 2);
+        assert_eq!(cache.loader().batch_count.load(Ordering::Acquire), 1);
+
+        // An error is returned and nothing is inserted.
+        assert_eq!(
+            cache.get_all(vec![4, 100]).await,
+            Err(Arc::new("too large".to_string()))
+        );
+        assert!(!cache.contains_key(&4));
+        assert_eq!(cache.loader().batch_count.load(Ordering::Acquire), 2);
+    }
+}
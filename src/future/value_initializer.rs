@@ -14,6 +14,9 @@ const WAITER_MAP_NUM_SEGMENTS: usize = 64;
 
 type ErrorObject = Arc<dyn Any + Send + Sync + 'static>;
 
+// Empty struct to be used as the type ID of the waiters for reloading values.
+struct Refreshing;
+
 pub(crate) enum InitResult<V, E> {
     Initialized(V),
     ReadExisting(V),
@@ -243,6 +246,67 @@ where
             .await
     }
 
+    /// Initializes the values of the keys in batches, and returns the pairs of the
+    /// keys and values. The waiters share the type ID with `init_or_read`, so
+    /// that concurrent `get_with` calls and batch calls on the same key are
+    /// deduplicated.
+    ///
+    /// # Panics
+    /// Panics if the `init` future has been panicked, or it returned a wrong
+    /// number of values.
+    pub(crate) async fn init_or_read_all<'a, Fut>(
+        &'a self,
+        keys: Vec<Arc<K>>,
+        get: impl FnMut(&Arc<K>) -> Option<V>,
+        mut init: impl FnMut(Vec<Arc<K>>) -> Fut,
+        insert: impl FnMut(Arc<K>, V) -> BoxFuture<'a, ()>,
+    ) -> Vec<(Arc<K>, V)>
+    where
+        Fut: Future<Output = Vec<V>>,
+    {
+        let type_id = TypeId::of::<()>();
+        let init = |keys| init(keys).map(Ok::<_, ()>);
+        match self.do_try_init_all(keys, type_id, get, init, insert).await {
+            Ok(entries) => entries,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// # Panics
+    /// Panics if the `init` future has been panicked, or it returned a wrong
+    /// number of values.
+    pub(crate) async fn try_init_or_read_all<'a, Fut, E>(
+        &'a self,
+        keys: Vec<Arc<K>>,
+        get: impl FnMut(&Arc<K>) -> Option<V>,
+        init: impl FnMut(Vec<Arc<K>>) -> Fut,
+        insert: impl FnMut(Arc<K>, V) -> BoxFuture<'a, ()>,
+    ) -> Result<Vec<(Arc<K>, V)>, Arc<E>>
+    where
+        Fut: Future<Output = Result<Vec<V>, E>>,
+        E: Send + Sync + 'static,
+    {
+        let type_id = TypeId::of::<E>();
+        self.do_try_init_all(keys, type_id, get, init, insert).await
+    }
+
+    /// Registers a waiter for reloading the value of the key, so that at most one
+    /// reload runs for a key at a time. Returns `false` if the value of the key is
+    /// already being reloaded. The caller must call `finish_refresh` after the
+    /// reload.
+    pub(crate) fn try_start_refresh(&self, key: &Arc<K>) -> bool {
+        let type_id = TypeId::of::<Refreshing>();
+        let (cht_key, hash) = cht_key_hash(&self.waiters, key, type_id);
+        let waiter = TrioArc::new(RwLock::new(WaiterValue::Computing));
+        try_insert_waiter(&self.waiters, cht_key, hash, &waiter).is_none()
+    }
+
+    pub(crate) fn finish_refresh(&self, key: &Arc<K>) {
+        let type_id = TypeId::of::<Refreshing>();
+        let (cht_key, hash) = cht_key_hash(&self.waiters, key, type_id);
+        remove_waiter(&self.waiters, cht_key, hash);
+    }
+
     /// # Panics
     /// Panics if the `init` future has been panicked.
     async fn do_try_init<'a, O, E>(
@@ -345,6 +409,156 @@ where
             }
         }
     }
+
+    /// # Panics
+    /// Panics if the `init` future has been panicked, or it returned a wrong
+    /// number of values.
+    async fn do_try_init_all<'a, Fut, E>(
+        &'a self,
+        keys: Vec<Arc<K>>,
+        type_id: TypeId,
+        // Closure to get an existing value from cache.
+        mut get: impl FnMut(&Arc<K>) -> Option<V>,
+        // Closure to create a future to resolve the values of the keys in a batch.
+        mut init: impl FnMut(Vec<Arc<K>>) -> Fut,
+        // Closure to insert a new value into cache.
+        mut insert: impl FnMut(Arc<K>, V) -> BoxFuture<'a, ()>,
+    ) -> Result<Vec<(Arc<K>, V)>, Arc<E>>
+    where
+        Fut: Future<Output = Result<Vec<V>, E>>,
+        E: Send + Sync + 'static,
+    {
+        use std::panic::{resume_unwind, AssertUnwindSafe};
+
+        const MAX_RETRIES: usize = 200;
+        let mut retries = 0;
+
+        let mut entries = Vec::with_capacity(keys.len());
+        let mut remaining = keys;
+
+        while !remaining.is_empty() {
+            let waiters = remaining
+                .iter()
+                .map(|_| TrioArc::new(RwLock::new(WaiterValue::Computing)))
+                .collect::<Vec<Waiter<V>>>();
+            // The keys whose waiters we inserted, and the keys whose waiters had
+            // been inserted by other async tasks.
+            let mut ours = Vec::new();
+            let mut others = Vec::new();
+
+            // Insert our waiters. We must not wait for other tasks' waiters until
+            // all of ours are unlocked, otherwise two tasks could wait for each
+            // other forever.
+            for (key, waiter) in remaining.iter().zip(&waiters) {
+                let (cht_key, hash) = cht_key_hash(&self.waiters, key, type_id);
+                let lock = waiter.write().await;
+                match try_insert_waiter(&self.waiters, cht_key.clone(), hash, waiter) {
+                    None => {
+                        // Our waiter was inserted. Create a guard to remove it when
+                        // the enclosing future has been aborted.
+                        let mut waiter_guard = WaiterGuard::new(
+                            cht_key.clone(),
+                            hash,
+                            TrioArc::clone(&self.waiters),
+                            lock,
+                        );
+                        // Check if the value has already been inserted by other task.
+                        if let Some(value) = get(key) {
+                            waiter_guard.set_waiter_value(WaiterValue::Ready(Ok(value.clone())));
+                            remove_waiter(&self.waiters, cht_key, hash);
+                            entries.push((Arc::clone(key), value));
+                        } else {
+                            ours.push((key, cht_key, hash, waiter_guard));
+                        }
+                    }
+                    Some(res) => {
+                        std::mem::drop(lock);
+                        others.push((key, res));
+                    }
+                }
+            }
+
+            if !ours.is_empty() {
+                let init_keys = ours.iter().map(|(k, ..)| Arc::clone(k)).collect();
+                // Catching panic is safe here as we do not try to resolve the future
+                // again.
+                match AssertUnwindSafe(init(init_keys)).catch_unwind().await {
+                    // Resolved.
+                    Ok(Ok(values)) if values.len() == ours.len() => {
+                        for ((key, cht_key, hash, mut waiter_guard), value) in
+                            ours.into_iter().zip(values)
+                        {
+                            insert(Arc::clone(key), value.clone()).await;
+                            waiter_guard.set_waiter_value(WaiterValue::Ready(Ok(value.clone())));
+                            remove_waiter(&self.waiters, cht_key, hash);
+                            entries.push((Arc::clone(key), value));
+                        }
+                    }
+                    Ok(Ok(values)) => {
+                        let len = ours.len();
+                        for (_key, cht_key, hash, mut waiter_guard) in ours {
+                            waiter_guard.set_waiter_value(WaiterValue::InitFuturePanicked);
+                            // Remove the waiter so that others can retry.
+                            remove_waiter(&self.waiters, cht_key, hash);
+                        }
+                        panic!(
+                            "The `init` future returned {} values for {} keys",
+                            values.len(),
+                            len
+                        );
+                    }
+                    Ok(Err(e)) => {
+                        let err: ErrorObject = Arc::new(e);
+                        for (_key, cht_key, hash, mut waiter_guard) in ours {
+                            waiter_guard
+                                .set_waiter_value(WaiterValue::Ready(Err(Arc::clone(&err))));
+                            remove_waiter(&self.waiters, cht_key, hash);
+                        }
+                        return Err(err.downcast().unwrap());
+                    }
+                    // Panicked.
+                    Err(payload) => {
+                        for (_key, cht_key, hash, mut waiter_guard) in ours {
+                            waiter_guard.set_waiter_value(WaiterValue::InitFuturePanicked);
+                            // Remove the waiter so that others can retry.
+                            remove_waiter(&self.waiters, cht_key, hash);
+                        }
+                        resume_unwind(payload);
+                    }
+                } // The locks will be unlocked here.
+            }
+
+            // Wait for the read locks of others' waiters to become available.
+            let mut retry_keys = Vec::new();
+            for (key, waiter) in others {
+                match &*waiter.read().await {
+                    WaiterValue::Ready(Ok(value)) => entries.push((Arc::clone(key), value.clone())),
+                    WaiterValue::Ready(Err(e)) => return Err(Arc::clone(e).downcast().unwrap()),
+                    // Somebody else's init future has been panicked.
+                    WaiterValue::InitFuturePanicked => {
+                        retries += 1;
+                        panic_if_retry_exhausted_for_panicking(retries, MAX_RETRIES);
+                        retry_keys.push(Arc::clone(key));
+                    }
+                    // Somebody else (a future containing `get_with`/`try_get_with`)
+                    // has been aborted.
+                    WaiterValue::EnclosingFutureAborted => {
+                        retries += 1;
+                        panic_if_retry_exhausted_for_aborting(retries, MAX_RETRIES);
+                        retry_keys.push(Arc::clone(key));
+                    }
+                    // Unexpected state.
+                    WaiterValue::Computing => panic!(
+                        "Got unexpected state `Computing` after resolving `init` future. \
+                    This might be a bug in Moka"
+                    ),
+                }
+            }
+            remaining = retry_keys;
+        }
+
+        Ok(entries)
+    }
 }
 
 #[inline]
@@ -27,6 +27,7 @@
 //! - A cache can be bounded by one of the followings:
 //!     - The maximum number of entries.
 //!     - The total weighted size of entries. (Size aware eviction)
+//!     - The estimated memory usage of entries. (See [`HeapSize`](./trait.HeapSize.html))
 //! - Maintains good hit rate by using entry replacement algorithms inspired by
 //!   [Caffeine][caffeine-git]:
 //!     - Admission to a cache is controlled by the Least Frequently Used (LFU) policy.
@@ -34,8 +35,11 @@
 //! - Supports expiration policies:
 //!     - Time to live
 //!     - Time to idle
+//!     - Per-entry variable expiration (See [`Expiry`](./trait.Expiry.html))
 //! - Supports eviction listener, a callback function that will be called when an entry
 //!   is removed from the cache.
+//! - Optionally records cache statistics such as hit rate, load time and eviction
+//!   count.
 //!
 //! # Examples
 //!
@@ -44,12 +48,17 @@
 //! - Thread-safe, synchronous caches:
 //!     - [`sync::Cache`][sync-cache-struct]
 //!     - [`sync::SegmentedCache`][sync-seg-cache-struct]
+//!     - [`sync::LoadingCache`][sync-loading-cache-struct]
 //! - An asynchronous (futures aware) cache:
 //!     - [`future::Cache`][future-cache-struct] (Requires "future" feature)
+//!     - [`future::LoadingCache`][future-loading-cache-struct] (Requires "future"
+//!       feature)
 //!
 //! [future-cache-struct]: ./future/struct.Cache.html
+//! [future-loading-cache-struct]: ./future/struct.LoadingCache.html
 //! [sync-cache-struct]: ./sync/struct.Cache.html
 //! [sync-seg-cache-struct]: ./sync/struct.SegmentedCache.html
+//! [sync-loading-cache-struct]: ./sync/struct.LoadingCache.html
 //!
 //! **NOTE:** The following caches have been moved to a separate crate called
 //! "[mini-moka][mini-moka-crate]"".
@@ -119,22 +128,35 @@
 //! retained in a historic popularity estimator. This estimator has a tiny memory
 //! footprint as it uses hashing to probabilistically estimate an item's frequency.
 //!
-//! All caches employ [TinyLFU] (Least Frequently Used) as the admission policy. When
-//! a new entry is inserted to the cache, it is temporary admitted to the cache, and
-//! a recording of this insertion is added to the write queue. When the write queue
-//! is drained and the main space of the cache is already full, then the historic
-//! popularity estimator determines to evict one of the following entries:
+//! All caches employ Window TinyLFU (W-TinyLFU) policy, which uses [TinyLFU]
+//! (Least Frequently Used) as the admission policy. W-TinyLFU has a small admission
+//! window in front of the main space. A new entry starts in the admission window and
+//! remains there as long as it has high temporal locality (recency). When the write
+//! queue is drained and the admission window is full, the least recently used entry
+//! in the window slips off from the window. If the main space of the cache is
+//! already full, then the historic popularity estimator determines to evict one of
+//! the following entries:
 //!
-//! - The temporary admitted entry.
+//! - The entry slipped off from the window.
 //! - Or, an entry that is selected from the main cache space by LRU (Least Recently
 //!   Used) eviction policy.
 //!
-//! In a future release of this crate, TinyLFU admission policy will be replaced by
-//! Window TinyLFU (W-TinyLFU) policy. W-TinyLFU has an admission window in front of
-//! the main space. A new entry starts in the admission window and remains there as
-//! long as it has high temporal locality (recency). Eventually an entry will slip
-//! off from the window, then TinyLFU comes in play to determine whether or not to
-//! admit the entry to the main space based on its popularity (frequency).
+//! The main space is divided into the probation and protected segments. An entry
+//! admitted to the main space starts in the probation segment, and is promoted to
+//! the protected segment when it is accessed again. The LRU entries are selected
+//! from the probation segment first, so the frequently accessed entries in the
+//! protected segment are less likely to be evicted.
+//!
+//! The admission window initially takes 1% of the max capacity of the cache, and the
+//! protected segment takes 80% of the main space. A cache whose max capacity is less
+//! than 100 does not have the admission window and uses TinyLFU policy with a single
+//! LRU main space.
+//!
+//! The `sync` and `future` caches adapt the size of the admission window at runtime
+//! by the hill climbing algorithm; the window grows when recency-biased workload
+//! benefits from it, and it shrinks when frequency-biased workload does. The current
+//! split is available via `window_capacity` and `main_capacity` methods of the
+//! [`Policy`](./struct.Policy.html).
 //!
 //! [TinyLFU]: https://dl.acm.org/citation.cfm?id=3149371
 //!
@@ -144,17 +166,18 @@
 //!
 //! - The time-to-live policy
 //! - The time-to-idle policy
-//!
-//! A future release will support the following:
-//!
 //! - The variable expiration (which allows to set different expiration on each
-//!   cached entry)
+//!   cached entry via the [`Expiry`](./trait.Expiry.html) trait)
 //!
 //! These policies are provided with _O(1)_ time complexity:
 //!
 //! - The time-to-live policy uses a write-order queue.
 //! - The time-to-idle policy uses an access-order queue.
-//! - The variable expiration will use a [hierarchical timer wheel][timer-wheel] (*1).
+//! - The variable expiration uses a [hierarchical timer wheel][timer-wheel] (*1)
+//!   in the `sync` and `future` caches.
+//!
+//! The `unsync` cache scans the access-order queue incrementally for the variable
+//! expiration.
 //!
 //! *1: If you get 404 page not found when you click on the link to the hierarchical
 //! timer wheel paper, try to change the URL from `https:` to `http:`.
@@ -173,8 +196,18 @@ pub mod future;
 
 #[cfg(any(feature = "sync", feature = "future"))]
 #[cfg_attr(docsrs, doc(cfg(any(feature = "sync", feature = "future"))))]
+pub mod executor;
+
 pub mod notification;
 
+#[cfg(any(feature = "sync", feature = "future"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "sync", feature = "future"))))]
+pub mod ops;
+
+#[cfg(any(feature = "sync", feature = "future"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "sync", feature = "future"))))]
+pub mod stats;
+
 #[cfg(feature = "sync")]
 #[cfg_attr(docsrs, doc(cfg(feature = "sync")))]
 pub mod sync;
@@ -182,13 +215,24 @@ pub mod sync;
 #[cfg(any(feature = "sync", feature = "future"))]
 pub(crate) mod sync_base;
 
+pub mod ticker;
+
 #[cfg(any(feature = "sync", feature = "future"))]
 pub use common::error::PredicateError;
 
+#[cfg(all(any(feature = "sync", feature = "future"), feature = "serde"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(any(feature = "sync", feature = "future"), feature = "serde")))
+)]
+pub use common::error::SnapshotError;
+
 #[cfg(any(feature = "sync", feature = "future"))]
 pub use common::entry::Entry;
 
-pub use policy::Policy;
+pub use common::heap_size::HeapSize;
+
+pub use policy::{Expiry, Policy};
 
 #[cfg(feature = "dash")]
 compile_error!(
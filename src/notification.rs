@@ -1,28 +1,68 @@
 //! Common data types for notifications.
 
+#[cfg(any(feature = "sync", feature = "future"))]
 pub(crate) mod notifier;
 
+use std::fmt;
+
+#[cfg(any(feature = "sync", feature = "future"))]
 use std::sync::Arc;
 
+#[cfg(feature = "future")]
+use std::{future::Future, pin::Pin};
+
+#[cfg(any(feature = "sync", feature = "future"))]
 pub(crate) type EvictionListener<K, V> =
     Arc<dyn Fn(Arc<K>, V, RemovalCause) + Send + Sync + 'static>;
 
-pub(crate) type EvictionListenerRef<'a, K, V> =
-    &'a Arc<dyn Fn(Arc<K>, V, RemovalCause) + Send + Sync + 'static>;
+/// The future returned by an async eviction listener of `future::Cache`.
+///
+/// To create it from an `async` block, call `boxed` method of the
+/// `futures_util::FutureExt` trait, or wrap it with `Box::pin`.
+#[cfg(feature = "future")]
+#[cfg_attr(docsrs, doc(cfg(feature = "future")))]
+pub type ListenerFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+#[cfg(feature = "future")]
+pub(crate) type AsyncEvictionListener<K, V> =
+    Arc<dyn Fn(Arc<K>, V, RemovalCause) -> ListenerFuture + Send + Sync + 'static>;
+
+/// An eviction listener registered to a concurrent cache.
+#[cfg(any(feature = "sync", feature = "future"))]
+pub(crate) enum Listener<K, V> {
+    /// A listener closure that is called in a blocking manner.
+    Sync(EvictionListener<K, V>),
+    /// A listener closure that returns a future. Only `future::Cache` supports it.
+    #[cfg(feature = "future")]
+    Async(AsyncEvictionListener<K, V>),
+}
+
+// NOTE: Dropping the cache will drop all entries without sending notifications.
+// Calling `shutdown` method of the cache before dropping it will remove all entries
+// and wait for their notifications to be delivered.
 
-// NOTE: Currently, dropping the cache will drop all entries without sending
-// notifications. Calling `invalidate_all` method of the cache will trigger
-// the notifications, but currently there is no way to know when all entries
-// have been invalidated and their notifications have been sent.
+/// The default capacity of the queue of the queued delivery mode.
+pub(crate) const DEFAULT_QUEUE_CAPACITY: usize = 1_024;
 
 /// Configuration for an eviction listener of a cache.
 ///
-/// Currently only setting the [`DeliveryMode`][delivery-mode] is supported.
+/// It sets the [`DeliveryMode`][delivery-mode], and the capacity and the
+/// [`OverflowPolicy`][overflow-policy] of the notification queue of the queued
+/// delivery mode.
 ///
 /// [delivery-mode]: ./enum.DeliveryMode.html
-#[derive(Clone, Debug, Default)]
+/// [overflow-policy]: ./enum.OverflowPolicy.html
+#[derive(Clone, Debug)]
 pub struct Configuration {
     mode: DeliveryMode,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        ConfigurationBuilder::default().build()
+    }
 }
 
 impl Configuration {
@@ -33,26 +73,138 @@ impl Configuration {
     pub fn delivery_mode(&self) -> DeliveryMode {
         self.mode
     }
+
+    /// Returns the capacity of the notification queue of the queued delivery mode.
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+
+    /// Returns the policy applied when the notification queue of the queued
+    /// delivery mode is full.
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
 }
 
 /// Builds a [`Configuration`][conf] with some configuration knobs.
 ///
-/// Currently only setting the [`DeliveryMode`][delivery-mode] is supported.
-///
 /// [conf]: ./struct.Configuration.html
-/// [delivery-mode]: ./enum.DeliveryMode.html
-#[derive(Default)]
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{
+///     notification::{self, DeliveryMode, OverflowPolicy},
+///     sync::Cache,
+/// };
+///
+/// let listener_conf = notification::Configuration::builder()
+///     .delivery_mode(DeliveryMode::Queued)
+///     .queue_capacity(10_000)
+///     .overflow_policy(OverflowPolicy::DropOldest)
+///     .build();
+///
+/// let listener = |k, _v, cause| println!("{} was evicted. ({:?})", k, cause);
+///
+/// let cache: Cache<u32, String> = Cache::builder()
+///     .max_capacity(100)
+///     .eviction_listener_with_conf(listener, listener_conf)
+///     .build();
+///
+/// // The number of the notifications dropped due to the full queue.
+/// let _dropped = cache.dropped_notification_count();
+/// ```
 pub struct ConfigurationBuilder {
     mode: DeliveryMode,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl Default for ConfigurationBuilder {
+    fn default() -> Self {
+        Self {
+            mode: DeliveryMode::default(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
 }
 
 impl ConfigurationBuilder {
     pub fn build(self) -> Configuration {
-        Configuration { mode: self.mode }
+        Configuration {
+            mode: self.mode,
+            queue_capacity: self.queue_capacity,
+            overflow_policy: self.overflow_policy,
+        }
     }
 
     pub fn delivery_mode(self, mode: DeliveryMode) -> Self {
-        Self { mode }
+        Self { mode, ..self }
+    }
+
+    /// Sets the capacity of the notification queue of the queued delivery mode,
+    /// in the number of notifications. The default is 1,024.
+    ///
+    /// The notifications of the entries removed together by a cache maintenance
+    /// task are queued one by one, and the overflow policy is applied to each of
+    /// them.
+    ///
+    /// This has no effect on the immediate delivery mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn queue_capacity(self, capacity: usize) -> Self {
+        assert!(capacity > 0, "queue_capacity must be greater than zero");
+        Self {
+            queue_capacity: capacity,
+            ..self
+        }
+    }
+
+    /// Sets the policy applied when the notification queue of the queued delivery
+    /// mode is full. The default is [`OverflowPolicy::Block`][block].
+    ///
+    /// This has no effect on the immediate delivery mode.
+    ///
+    /// [block]: ./enum.OverflowPolicy.html#variant.Block
+    pub fn overflow_policy(self, policy: OverflowPolicy) -> Self {
+        Self {
+            overflow_policy: policy,
+            ..self
+        }
+    }
+}
+
+/// Specifies what to do with a new notification when the notification queue of
+/// the queued delivery mode is full, i.e. when the eviction listener cannot keep
+/// up with the evictions.
+///
+/// The number of the notifications dropped by `DropNewest` and `DropOldest` is
+/// returned by the `dropped_notification_count` method of the cache. It is also
+/// recorded as the `dropped_notification_count` of the cache statistics when the
+/// statistics are enabled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Blocks the cache operation that removed the entry until the queue has room
+    /// for the notification. No notification is lost, but a slow listener will
+    /// slow down the cache operations, including the ones in other threads that
+    /// are waiting for the blocked one.
+    Block,
+    /// Drops the new notification.
+    DropNewest,
+    /// Drops the oldest notification in the queue to make room for the new one.
+    DropOldest,
+    /// Delivers the new notification by calling the listener in the thread of the
+    /// cache operation that removed the entry, instead of queueing it. No
+    /// notification is lost, but it can be delivered before the queued ones.
+    SpillToCaller,
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        Self::Block
     }
 }
 
@@ -95,24 +247,154 @@ impl Default for DeliveryMode {
 
 /// Indicates the reason why a cached entry was removed.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum RemovalCause {
     /// The entry's expiration timestamp has passed.
     Expired,
-    /// The entry was manually removed by the user.
+    /// The entry was manually removed by the user, e.g. by `invalidate` or
+    /// `remove`.
     Explicit,
+    /// The entry was invalidated by the user in bulk. `predicate_id` is the handle
+    /// of the predicate of `invalidate_entries_if` that matched the entry, or
+    /// `None` if the entry was invalidated by `invalidate_all`. (`unsync::Cache`
+    /// does not assign an ID to a predicate, so it is always `None` for it)
+    ///
+    /// An entry that has already been invalidated but not yet removed by the
+    /// cache is also reported with this cause when it is removed or replaced by
+    /// the user, e.g. by `invalidate` or `insert`.
+    Invalidated {
+        predicate_id: Option<PredicateHandle>,
+    },
     /// The entry itself was not actually removed, but its value was replaced by
     /// the user.
     Replaced,
+    /// The entry was removed by the `shutdown` method of the cache.
+    Shutdown,
     /// The entry was evicted due to size constraints.
     Size,
 }
 
 impl RemovalCause {
+    /// Returns `true` if the entry was removed by the cache policy (`Expired` or
+    /// `Size`), rather than by the user.
     pub fn was_evicted(&self) -> bool {
         matches!(self, Self::Expired | Self::Size)
     }
 }
 
+/// A handle of a predicate registered by `invalidate_entries_if` method of the
+/// `sync` and `future` caches, carried by [`RemovalCause::Invalidated`].
+///
+/// It is a `Copy` form of the `PredicateId` string returned by
+/// `invalidate_entries_if`. It can be compared with the `PredicateId` by `==`,
+/// and its `to_string` returns the same string.
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{notification::RemovalCause, sync::Cache};
+/// use std::sync::{Arc, Mutex};
+///
+/// let causes = Arc::new(Mutex::new(Vec::new()));
+/// let causes1 = Arc::clone(&causes);
+///
+/// let cache = Cache::builder()
+///     .eviction_listener(move |_k, _v, cause| causes1.lock().unwrap().push(cause))
+///     .support_invalidation_closures()
+///     .build();
+/// cache.insert(1, "one");
+///
+/// let id = cache.invalidate_entries_if(|_k, _v| true).unwrap();
+/// // Invalidate the entry explicitly before the invalidation task removes it.
+/// cache.invalidate(&1);
+///
+/// let cause = causes.lock().unwrap()[0];
+/// match cause {
+///     RemovalCause::Invalidated {
+///         predicate_id: Some(handle),
+///     } => {
+///         assert_eq!(handle, id);
+///         assert_eq!(handle.to_string(), id);
+///     }
+///     cause => panic!("Unexpected cause: {:?}", cause),
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PredicateHandle(u128);
+
+impl PredicateHandle {
+    /// Creates a handle from the UUID of a `PredicateId`.
+    #[cfg(any(feature = "sync", feature = "future"))]
+    pub(crate) fn new(uuid: u128) -> Self {
+        Self(uuid)
+    }
+
+    /// Creates a handle from a `PredicateId` string.
+    #[cfg(all(test, any(feature = "sync", feature = "future")))]
+    pub(crate) fn parse(id: &str) -> Self {
+        Self(
+            uuid::Uuid::parse_str(id)
+                .expect("Invalid PredicateId")
+                .as_u128(),
+        )
+    }
+
+    /// Returns the hyphenated, lowercase form of the UUID, which is the same as
+    /// the `PredicateId` string.
+    fn to_hyphenated(self) -> [u8; 36] {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let mut buf = [b'-'; 36];
+        let mut uuid = self.0;
+        for i in (0..buf.len()).rev() {
+            if matches!(i, 8 | 13 | 18 | 23) {
+                continue;
+            }
+            buf[i] = HEX[(uuid & 0xf) as usize];
+            uuid >>= 4;
+        }
+        buf
+    }
+}
+
+impl fmt::Display for PredicateHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buf = self.to_hyphenated();
+        f.write_str(std::str::from_utf8(&buf).expect("Bug: Not an ASCII string"))
+    }
+}
+
+impl fmt::Debug for PredicateHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PredicateHandle")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl PartialEq<str> for PredicateHandle {
+    fn eq(&self, other: &str) -> bool {
+        self.to_hyphenated()[..] == *other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for PredicateHandle {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for PredicateHandle {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<PredicateHandle> for String {
+    fn eq(&self, other: &PredicateHandle) -> bool {
+        other == self
+    }
+}
+
 #[cfg(all(test, feature = "sync"))]
 pub(crate) mod macros {
 
@@ -9,41 +9,89 @@ use std::{
 use crate::{
     common::concurrent::{
         constants::WRITE_RETRY_INTERVAL_MICROS,
-        thread_pool::{PoolName, ThreadPool, ThreadPoolRegistry},
+        thread_pool::{PoolExecutor, PoolName},
     },
-    notification::{self, DeliveryMode, EvictionListener, EvictionListenerRef, RemovalCause},
+    executor::ExecutorArc,
+    notification::{self, DeliveryMode, EvictionListener, Listener, OverflowPolicy, RemovalCause},
+    stats::StatsCounterArc,
 };
 
+#[cfg(feature = "future")]
+use crate::notification::AsyncEvictionListener;
+
 use crossbeam_channel::{Receiver, Sender, TrySendError};
+use crossbeam_utils::atomic::AtomicCell;
 use parking_lot::Mutex;
 
-const CHANNEL_CAPACITY: usize = 1_024;
 const SUBMIT_TASK_THRESHOLD: usize = 100;
 const MAX_NOTIFICATIONS_PER_TASK: u16 = 5_000;
 
 pub(crate) enum RemovalNotifier<K, V> {
     Blocking(BlockingRemovalNotifier<K, V>),
     ThreadPool(ThreadPoolRemovalNotifier<K, V>),
+    #[cfg(feature = "future")]
+    Async(AsyncRemovalNotifier<K, V>),
 }
 
 impl<K, V> RemovalNotifier<K, V> {
+    /// Creates a notifier for the listener. An async listener always uses the
+    /// immediate delivery mode, so the delivery mode in the `conf` is ignored for
+    /// it. See `AsyncRemovalNotifier` for how its queue applies the overflow policy.
+    ///
+    /// The `stats_counter` records the notifications dropped by the overflow
+    /// policy. The queued notifications are delivered on the `executor`, or on the
+    /// global thread pool if it is `None`. Set `has_task_queue` to `true` if the
+    /// `executor` is a `TaskQueue`.
     pub(crate) fn new(
-        listener: EvictionListener<K, V>,
+        listener: Listener<K, V>,
         conf: notification::Configuration,
+        stats_counter: Option<StatsCounterArc>,
         cache_name: Option<String>,
+        executor: Option<ExecutorArc>,
+        has_task_queue: bool,
     ) -> Self {
-        match conf.delivery_mode() {
-            DeliveryMode::Immediate => {
+        match (listener, conf.delivery_mode()) {
+            (Listener::Sync(listener), DeliveryMode::Immediate) => {
                 Self::Blocking(BlockingRemovalNotifier::new(listener, cache_name))
             }
-            DeliveryMode::Queued => {
-                Self::ThreadPool(ThreadPoolRemovalNotifier::new(listener, cache_name))
+            (Listener::Sync(listener), DeliveryMode::Queued) => {
+                Self::ThreadPool(ThreadPoolRemovalNotifier::new(
+                    listener,
+                    &conf,
+                    stats_counter,
+                    cache_name,
+                    executor,
+                    has_task_queue,
+                ))
             }
+            #[cfg(feature = "future")]
+            (Listener::Async(listener), _) => Self::Async(AsyncRemovalNotifier::new(
+                listener,
+                &conf,
+                stats_counter,
+                cache_name,
+            )),
         }
     }
 
+    /// Returns `true` if the notifications are delivered in the immediate mode,
+    /// which requires the key-level locks to keep the notifications and the write
+    /// operations for a key in order.
     pub(crate) fn is_blocking(&self) -> bool {
-        matches!(self, RemovalNotifier::Blocking(_))
+        match self {
+            RemovalNotifier::Blocking(_) => true,
+            RemovalNotifier::ThreadPool(_) => false,
+            #[cfg(feature = "future")]
+            RemovalNotifier::Async(_) => true,
+        }
+    }
+
+    #[cfg(feature = "future")]
+    pub(crate) fn as_async(&self) -> Option<&AsyncRemovalNotifier<K, V>> {
+        match self {
+            RemovalNotifier::Async(notifier) => Some(notifier),
+            _ => None,
+        }
     }
 
     pub(crate) fn is_batching_supported(&self) -> bool {
@@ -60,6 +108,8 @@ impl<K, V> RemovalNotifier<K, V> {
             RemovalNotifier::ThreadPool(notifier) => {
                 notifier.add_single_notification(key, value, cause)
             }
+            #[cfg(feature = "future")]
+            RemovalNotifier::Async(notifier) => notifier.add_notification(key, value, cause),
         }
     }
 
@@ -69,8 +119,19 @@ impl<K, V> RemovalNotifier<K, V> {
         V: Send + Sync + 'static,
     {
         match self {
-            RemovalNotifier::Blocking(_) => unreachable!(),
             RemovalNotifier::ThreadPool(notifier) => notifier.add_multiple_notifications(entries),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the number of the notifications dropped by the overflow policy of
+    /// the queued delivery mode, or by the full queue of the async listener.
+    pub(crate) fn dropped_notification_count(&self) -> u64 {
+        match self {
+            RemovalNotifier::Blocking(_) => 0,
+            RemovalNotifier::ThreadPool(notifier) => notifier.dropped_count.load(),
+            #[cfg(feature = "future")]
+            RemovalNotifier::Async(notifier) => notifier.dropped_count.load(),
         }
     }
 
@@ -80,8 +141,27 @@ impl<K, V> RemovalNotifier<K, V> {
         V: Send + Sync + 'static,
     {
         match self {
-            RemovalNotifier::Blocking(_) => unreachable!(),
             RemovalNotifier::ThreadPool(notifier) => notifier.submit_task(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns `true` if there are notifications that have been queued but not
+    /// delivered yet. For the queued delivery mode, this also submits a
+    /// notification task to deliver them if no task is running.
+    ///
+    /// Returns `false` if the listener has been disabled by a panic, as the queued
+    /// notifications will never be delivered.
+    pub(crate) fn has_pending_notifications(&self) -> bool
+    where
+        K: Send + Sync + 'static,
+        V: Send + Sync + 'static,
+    {
+        match self {
+            RemovalNotifier::Blocking(_) => false,
+            RemovalNotifier::ThreadPool(notifier) => notifier.has_pending_notifications(),
+            #[cfg(feature = "future")]
+            RemovalNotifier::Async(notifier) => notifier.has_pending_notifications(),
         }
     }
 }
@@ -123,10 +203,153 @@ impl<K, V> BlockingRemovalNotifier<K, V> {
     }
 }
 
+/// A notifier for an async eviction listener. It queues the notifications, and the
+/// async methods of `future::Cache` deliver them by awaiting the futures returned
+/// by the listener.
+///
+/// The queue is bounded. When it is full, the new notification is dropped with
+/// `OverflowPolicy::DropNewest`. Otherwise, the oldest notification is dropped to
+/// make room for the new one, because the notifications are queued by the
+/// housekeeping, which can neither wait for an async method to deliver them
+/// (`Block`) nor call the async listener by itself (`SpillToCaller`).
+#[cfg(feature = "future")]
+pub(crate) struct AsyncRemovalNotifier<K, V> {
+    listener: AsyncEvictionListener<K, V>,
+    snd: Sender<RemovedEntry<K, V>>,
+    rcv: Receiver<RemovedEntry<K, V>>,
+    overflow_policy: OverflowPolicy,
+    // Only one task can deliver the notifications at a time to keep them in order.
+    delivery_lock: async_lock::Mutex<()>,
+    is_enabled: AtomicBool,
+    stats_counter: Option<StatsCounterArc>,
+    // Counted even if the stats are not recorded.
+    dropped_count: AtomicCell<u64>,
+    #[cfg(feature = "logging")]
+    cache_name: Option<String>,
+}
+
+#[cfg(feature = "future")]
+impl<K, V> AsyncRemovalNotifier<K, V> {
+    fn new(
+        listener: AsyncEvictionListener<K, V>,
+        conf: &notification::Configuration,
+        stats_counter: Option<StatsCounterArc>,
+        _cache_name: Option<String>,
+    ) -> Self {
+        let (snd, rcv) = crossbeam_channel::bounded(conf.queue_capacity());
+        let overflow_policy = match conf.overflow_policy() {
+            OverflowPolicy::DropNewest => OverflowPolicy::DropNewest,
+            _ => OverflowPolicy::DropOldest,
+        };
+        Self {
+            listener,
+            snd,
+            rcv,
+            overflow_policy,
+            delivery_lock: Default::default(),
+            is_enabled: AtomicBool::new(true),
+            stats_counter,
+            dropped_count: Default::default(),
+            #[cfg(feature = "logging")]
+            cache_name: _cache_name,
+        }
+    }
+
+    fn has_pending_notifications(&self) -> bool {
+        self.is_enabled.load(Ordering::Acquire) && !self.rcv.is_empty()
+    }
+
+    fn add_notification(&self, key: Arc<K>, value: V, cause: RemovalCause) {
+        if !self.is_enabled.load(Ordering::Acquire) {
+            return;
+        }
+        let mut entry = RemovedEntry::new(key, value, cause);
+        loop {
+            match self.snd.try_send(entry) {
+                Ok(()) => break,
+                Err(TrySendError::Full(_))
+                    if self.overflow_policy == OverflowPolicy::DropNewest =>
+                {
+                    self.record_dropped();
+                    break;
+                }
+                Err(TrySendError::Full(entry1)) => {
+                    // A delivering task may have received it first. In that case,
+                    // just retry.
+                    if self.rcv.try_recv().is_ok() {
+                        self.record_dropped();
+                    }
+                    entry = entry1;
+                }
+                Err(TrySendError::Disconnected(_)) => panic!("Failed to send notification"),
+            }
+        }
+    }
+
+    fn record_dropped(&self) {
+        self.dropped_count.fetch_add(1);
+        if let Some(stats) = &self.stats_counter {
+            stats.record_dropped_notifications(1);
+        }
+    }
+
+    /// Delivers the notifications queued so far to the listener, one at a time.
+    ///
+    /// If another task is delivering, waits for it to finish first. Therefore,
+    /// when this method returns, the notifications queued before the call have been
+    /// delivered.
+    pub(crate) async fn run_pending_notifications(&self) {
+        use futures_util::FutureExt;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // Take the lock before checking the queue. Otherwise, this could return
+        // while another task has received a notification but not delivered it yet.
+        let _guard = self.delivery_lock.lock().await;
+
+        // Deliver at most the notifications queued by now, so that a busy cache
+        // will not keep this task delivering forever.
+        for _ in 0..self.rcv.len() {
+            if !self.is_enabled.load(Ordering::Acquire) {
+                break;
+            }
+            let RemovedEntry { key, value, cause } = match self.rcv.try_recv() {
+                Ok(entry) => entry,
+                Err(_) => break,
+            };
+
+            // Safety: It is safe to assert unwind safety here because we will not
+            // call the listener again if it has been panicked.
+            let result = match catch_unwind(AssertUnwindSafe(|| (self.listener)(key, value, cause)))
+            {
+                Ok(fut) => AssertUnwindSafe(fut).catch_unwind().await,
+                Err(payload) => Err(payload),
+            };
+
+            if let Err(_payload) = result {
+                self.is_enabled.store(false, Ordering::Release);
+                #[cfg(feature = "logging")]
+                log_panic(&*_payload, self.cache_name.as_deref());
+                // Drop the remaining notifications.
+                while self.rcv.try_recv().is_ok() {}
+            }
+        }
+    }
+}
+
 pub(crate) struct ThreadPoolRemovalNotifier<K, V> {
-    snd: Sender<RemovedEntries<K, V>>,
+    // The queue holds one notification per item, so that its capacity bounds the
+    // number of the queued notifications.
+    snd: Sender<RemovedEntry<K, V>>,
     state: Arc<NotifierState<K, V>>,
-    thread_pool: Arc<ThreadPool>,
+    executor: ExecutorArc,
+    // If true, the notification task does not run until the clients run the
+    // pending tasks of the `TaskQueue`.
+    has_task_queue: bool,
+    overflow_policy: OverflowPolicy,
+    submit_task_threshold: usize,
+    stats_counter: Option<StatsCounterArc>,
+    // Counted even if the stats are not recorded.
+    dropped_count: AtomicCell<u64>,
 }
 
 impl<K, V> Drop for ThreadPoolRemovalNotifier<K, V> {
@@ -139,15 +362,21 @@ impl<K, V> Drop for ThreadPoolRemovalNotifier<K, V> {
         while state.is_running() {
             std::thread::sleep(Duration::from_millis(1));
         }
-
-        ThreadPoolRegistry::release_pool(&self.thread_pool);
     }
 }
 
 impl<K, V> ThreadPoolRemovalNotifier<K, V> {
-    fn new(listener: EvictionListener<K, V>, _cache_name: Option<String>) -> Self {
-        let (snd, rcv) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
-        let thread_pool = ThreadPoolRegistry::acquire_pool(PoolName::RemovalNotifier);
+    fn new(
+        listener: EvictionListener<K, V>,
+        conf: &notification::Configuration,
+        stats_counter: Option<StatsCounterArc>,
+        _cache_name: Option<String>,
+        executor: Option<ExecutorArc>,
+        has_task_queue: bool,
+    ) -> Self {
+        let queue_capacity = conf.queue_capacity();
+        let (snd, rcv) = crossbeam_channel::bounded(queue_capacity);
+        let executor = PoolExecutor::or_acquire(executor, PoolName::RemovalNotifier);
         let state = NotifierState {
             task_lock: Default::default(),
             rcv,
@@ -161,7 +390,13 @@ impl<K, V> ThreadPoolRemovalNotifier<K, V> {
         Self {
             snd,
             state: Arc::new(state),
-            thread_pool,
+            executor,
+            has_task_queue,
+            overflow_policy: conf.overflow_policy(),
+            // Do not wait for the queue to be full to start a notification task.
+            submit_task_threshold: SUBMIT_TASK_THRESHOLD.min(queue_capacity),
+            stats_counter,
+            dropped_count: Default::default(),
         }
     }
 }
@@ -172,54 +407,110 @@ where
     V: Send + Sync + 'static,
 {
     fn add_single_notification(&self, key: Arc<K>, value: V, cause: RemovalCause) {
-        let entry = RemovedEntries::new_single(key, value, cause);
-        self.send_entries(entry)
-            .expect("Failed to send notification");
+        let entry = RemovedEntry::new(key, value, cause);
+        self.send_entry(entry).expect("Failed to send notification");
     }
 
     fn add_multiple_notifications(&self, entries: Vec<RemovedEntry<K, V>>) {
-        let entries = RemovedEntries::new_multi(entries);
-        self.send_entries(entries)
-            .expect("Failed to send notification");
+        // Split the batch, so that the overflow policy is applied to each
+        // notification.
+        for entry in entries {
+            self.send_entry(entry).expect("Failed to send notification");
+        }
     }
 
-    fn send_entries(
+    fn send_entry(
         &self,
-        entries: RemovedEntries<K, V>,
-    ) -> Result<(), TrySendError<RemovedEntries<K, V>>> {
-        let mut entries = entries;
+        entry: RemovedEntry<K, V>,
+    ) -> Result<(), TrySendError<RemovedEntry<K, V>>> {
+        let mut entry = entry;
         loop {
+            // The queued notifications will never be delivered once the listener has
+            // been disabled by a panic or the notifier is shutting down. Drop the
+            // notification rather than waiting for the queue to have room.
+            if !self.state.is_enabled() || self.state.is_shutting_down() {
+                break;
+            }
             self.submit_task_if_necessary();
-            match self.snd.try_send(entries) {
+            match self.snd.try_send(entry) {
                 Ok(()) => break,
-                Err(TrySendError::Full(entries1)) => {
-                    entries = entries1;
-                    std::thread::sleep(Duration::from_millis(WRITE_RETRY_INTERVAL_MICROS));
-                }
+                Err(TrySendError::Full(entry1)) => match self.overflow_policy {
+                    OverflowPolicy::Block => {
+                        entry = entry1;
+                        // A notification task queued in a `TaskQueue` will not run
+                        // until this thread returns, so waiting would never end.
+                        // Deliver the oldest notification here to make room.
+                        if !(self.has_task_queue && self.state.notify_oldest()) {
+                            std::thread::sleep(Duration::from_micros(WRITE_RETRY_INTERVAL_MICROS));
+                        }
+                    }
+                    OverflowPolicy::DropNewest => {
+                        self.record_dropped();
+                        break;
+                    }
+                    OverflowPolicy::DropOldest => {
+                        // The notification task may have received it first. In that
+                        // case, just retry.
+                        if self.state.rcv.try_recv().is_ok() {
+                            self.record_dropped();
+                        }
+                        entry = entry1;
+                    }
+                    OverflowPolicy::SpillToCaller => {
+                        self.state.notify_one(entry1);
+                        break;
+                    }
+                },
                 Err(e @ TrySendError::Disconnected(_)) => return Err(e),
             }
         }
         Ok(())
     }
 
+    fn record_dropped(&self) {
+        self.dropped_count.fetch_add(1);
+        if let Some(stats) = &self.stats_counter {
+            stats.record_dropped_notifications(1);
+        }
+    }
+
     fn submit_task(&self) {
         // TODO: Use compare and exchange to ensure it was false.
 
         let state = &self.state;
 
-        if state.is_running() || !state.is_enabled() || state.is_shutting_down() {
+        // Do not submit a task if there is nothing to deliver. (A task submitted to
+        // a `TaskQueue` would be run by the clients for nothing)
+        if self.snd.is_empty()
+            || state.is_running()
+            || !state.is_enabled()
+            || state.is_shutting_down()
+        {
             return;
         }
         state.set_running(true);
 
         let task = NotificationTask::new(state);
-        self.thread_pool.pool.execute(move || {
+        self.executor.execute(Box::new(move || {
             task.execute();
-        });
+        }));
+    }
+
+    fn has_pending_notifications(&self) -> bool {
+        let state = &self.state;
+        if !state.is_enabled() {
+            return false;
+        }
+        if self.snd.is_empty() {
+            // A running task may still be calling the listener.
+            return state.is_running();
+        }
+        self.submit_task();
+        true
     }
 
     fn submit_task_if_necessary(&self) {
-        if self.snd.len() >= SUBMIT_TASK_THRESHOLD && !self.state.is_running() {
+        if self.snd.len() >= self.submit_task_threshold && !self.state.is_running() {
             self.submit_task(); // TODO: Error handling?
         }
     }
@@ -246,30 +537,13 @@ impl<K, V> NotificationTask<K, V> {
             return;
         }
 
-        while let Ok(entries) = self.state.rcv.try_recv() {
-            match entries {
-                RemovedEntries::Single(entry) => {
-                    let result = self.notify(&self.state.listener, entry);
-                    if result.is_err() {
-                        is_enabled = false;
-                        break;
-                    }
-                    count += 1;
-                }
-                RemovedEntries::Multi(entries) => {
-                    for entry in entries {
-                        let result = self.notify(&self.state.listener, entry);
-                        if result.is_err() {
-                            is_enabled = false;
-                            break;
-                        }
-                        if self.state.is_shutting_down() {
-                            break;
-                        }
-                        count += 1;
-                    }
-                }
+        while let Ok(entry) = self.state.rcv.try_recv() {
+            let result = self.state.notify(entry);
+            if result.is_err() {
+                is_enabled = false;
+                break;
             }
+            count += 1;
 
             if count > MAX_NOTIFICATIONS_PER_TASK || self.state.is_shutting_down() {
                 break;
@@ -283,38 +557,11 @@ impl<K, V> NotificationTask<K, V> {
         std::mem::drop(task_lock);
         self.state.set_running(false);
     }
-
-    /// Returns `Ok(())` when calling the listener succeeded. Returns
-    /// `Err(panic_payload)` when the listener panicked.
-    fn notify(
-        &self,
-        listener: EvictionListenerRef<'_, K, V>,
-        entry: RemovedEntry<K, V>,
-    ) -> Result<(), Box<dyn std::any::Any + Send>> {
-        use std::panic::{catch_unwind, AssertUnwindSafe};
-
-        let RemovedEntry { key, value, cause } = entry;
-        let listener_clo = || (listener)(key, value, cause);
-
-        // Safety: It is safe to assert unwind safety here because we will not
-        // call the listener again if it has been panicked.
-        //
-        #[allow(clippy::let_and_return)]
-        // https://rust-lang.github.io/rust-clippy/master/index.html#let_and_return
-        let result = catch_unwind(AssertUnwindSafe(listener_clo));
-        #[cfg(feature = "logging")]
-        {
-            if let Err(payload) = &result {
-                log_panic(&**payload, self.state.cache_name.as_deref());
-            }
-        }
-        result
-    }
 }
 
 struct NotifierState<K, V> {
     task_lock: Mutex<()>,
-    rcv: Receiver<RemovedEntries<K, V>>,
+    rcv: Receiver<RemovedEntry<K, V>>,
     listener: EvictionListener<K, V>,
     #[cfg(feature = "logging")]
     cache_name: Option<String>,
@@ -347,6 +594,54 @@ impl<K, V> NotifierState<K, V> {
     fn shutdown(&self) {
         self.is_shutting_down.store(true, Ordering::Release);
     }
+
+    /// Returns `Ok(())` when calling the listener succeeded. Returns
+    /// `Err(panic_payload)` when the listener panicked.
+    fn notify(&self, entry: RemovedEntry<K, V>) -> Result<(), Box<dyn std::any::Any + Send>> {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let RemovedEntry { key, value, cause } = entry;
+        let listener_clo = || (self.listener)(key, value, cause);
+
+        // Safety: It is safe to assert unwind safety here because we will not
+        // call the listener again if it has been panicked.
+        //
+        #[allow(clippy::let_and_return)]
+        // https://rust-lang.github.io/rust-clippy/master/index.html#let_and_return
+        let result = catch_unwind(AssertUnwindSafe(listener_clo));
+        #[cfg(feature = "logging")]
+        {
+            if let Err(payload) = &result {
+                log_panic(&**payload, self.cache_name.as_deref());
+            }
+        }
+        result
+    }
+
+    /// Delivers the notification in the current thread. Disables the listener if
+    /// it panics.
+    fn notify_one(&self, entry: RemovedEntry<K, V>) {
+        if self.is_enabled() && self.notify(entry).is_err() {
+            self.set_enabled(false);
+        }
+    }
+
+    /// Delivers the oldest queued notification in the current thread, unless a
+    /// notification task is delivering them. Returns `true` if it has been
+    /// delivered.
+    fn notify_oldest(&self) -> bool {
+        let _task_lock = match self.task_lock.try_lock() {
+            Some(lock) => lock,
+            None => return false,
+        };
+        match self.rcv.try_recv() {
+            Ok(entry) => {
+                self.notify_one(entry);
+                true
+            }
+            Err(_) => false,
+        }
+    }
 }
 
 pub(crate) struct RemovedEntry<K, V> {
@@ -361,21 +656,6 @@ impl<K, V> RemovedEntry<K, V> {
     }
 }
 
-enum RemovedEntries<K, V> {
-    Single(RemovedEntry<K, V>),
-    Multi(Vec<RemovedEntry<K, V>>),
-}
-
-impl<K, V> RemovedEntries<K, V> {
-    fn new_single(key: Arc<K>, value: V, cause: RemovalCause) -> Self {
-        Self::Single(RemovedEntry::new(key, value, cause))
-    }
-
-    fn new_multi(entries: Vec<RemovedEntry<K, V>>) -> Self {
-        Self::Multi(entries)
-    }
-}
-
 #[cfg(feature = "logging")]
 fn log_panic(payload: &(dyn std::any::Any + Send + 'static), cache_name: Option<&str>) {
     // Try to downcast the payload into &str or String.
@@ -0,0 +1,3 @@
+//! Cache operations.
+
+pub mod compute;
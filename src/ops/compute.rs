@@ -0,0 +1,24 @@
+//! Types for the compute operations on an entry of the cache.
+//!
+//! See the following methods of the entry selectors for more information:
+//!
+//! - `sync::OwnedKeyEntrySelector`:
+//!     - [`and_compute_with`](../../sync/struct.OwnedKeyEntrySelector.html#method.and_compute_with)
+//!     - [`and_compute_if_present`](../../sync/struct.OwnedKeyEntrySelector.html#method.and_compute_if_present)
+//!     - [`and_upsert_with`](../../sync/struct.OwnedKeyEntrySelector.html#method.and_upsert_with)
+//! - `future::OwnedKeyEntrySelector`:
+//!     - [`and_compute_with`](../../future/struct.OwnedKeyEntrySelector.html#method.and_compute_with)
+//!     - [`and_compute_if_present`](../../future/struct.OwnedKeyEntrySelector.html#method.and_compute_if_present)
+//!     - [`and_upsert_with`](../../future/struct.OwnedKeyEntrySelector.html#method.and_upsert_with)
+
+/// Instructs the cache what to do with an entry after a compute closure has been
+/// evaluated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op<V> {
+    /// Leaves the entry as it is. If the entry does not exist, does not insert one.
+    Nop,
+    /// Inserts the value, or replaces the existing value with it.
+    Put(V),
+    /// Removes the entry. Does nothing if the entry does not exist.
+    Remove,
+}
@@ -1,5 +1,107 @@
 use std::time::Duration;
 
+/// Calculates when cache entries expire. A single expiration time is retained on
+/// each entry so that the lifetime of an entry may be extended or reduced by
+/// subsequent evaluations.
+///
+/// Each method returns the duration from the current time until the entry
+/// expires, or `None` if the entry should not expire by this policy. The
+/// per-entry expiration works together with the `time_to_live` and `time_to_idle`
+/// of the cache; an entry expires when any of them has passed.
+///
+/// The methods are called while the cache is processing a `get` or `insert`
+/// operation, so they should be cheap and must not panic.
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{sync::Cache, Expiry};
+/// use std::time::Duration;
+///
+/// // A DNS record with its own time to live.
+/// #[derive(Clone)]
+/// struct DnsRecord {
+///     address: std::net::IpAddr,
+///     ttl: Duration,
+/// }
+///
+/// struct DnsExpiry;
+///
+/// impl Expiry<String, DnsRecord> for DnsExpiry {
+///     fn expire_after_create(&self, _key: &String, value: &DnsRecord) -> Option<Duration> {
+///         Some(value.ttl)
+///     }
+///
+///     // Reset the deadline when the record is updated.
+///     fn expire_after_update(
+///         &self,
+///         _key: &String,
+///         value: &DnsRecord,
+///         _current_duration: Option<Duration>,
+///     ) -> Option<Duration> {
+///         Some(value.ttl)
+///     }
+/// }
+///
+/// let cache = Cache::builder()
+///     .max_capacity(10_000)
+///     .expire_after(DnsExpiry)
+///     .build();
+///
+/// let record = DnsRecord {
+///     address: [192, 0, 2, 1].into(),
+///     ttl: Duration::from_secs(300),
+/// };
+/// cache.insert("example.com".to_string(), record);
+/// ```
+pub trait Expiry<K, V> {
+    /// Specifies that the entry should expire after the returned duration from
+    /// its creation. Returning `None` means the entry does not expire by this
+    /// policy.
+    ///
+    /// The default implementation returns `None`.
+    #[allow(unused_variables)]
+    fn expire_after_create(&self, key: &K, value: &V) -> Option<Duration> {
+        None
+    }
+
+    /// Specifies that the entry should expire after the returned duration from
+    /// its last read. `current_duration` is the remaining duration until the
+    /// current expiration time of the entry, or `None` if it does not have one.
+    ///
+    /// The default implementation returns `current_duration`, which keeps the
+    /// current expiration time.
+    #[allow(unused_variables)]
+    fn expire_after_read(
+        &self,
+        key: &K,
+        value: &V,
+        current_duration: Option<Duration>,
+    ) -> Option<Duration> {
+        current_duration
+    }
+
+    /// Specifies that the entry should expire after the returned duration from
+    /// the replacement of its value. `current_duration` is the remaining duration
+    /// until the current expiration time of the entry, or `None` if it does not
+    /// have one.
+    ///
+    /// The default implementation returns `current_duration`, which keeps the
+    /// current expiration time.
+    ///
+    /// Note that this method is not called when the old value has already expired;
+    /// `expire_after_create` is called instead.
+    #[allow(unused_variables)]
+    fn expire_after_update(
+        &self,
+        key: &K,
+        value: &V,
+        current_duration: Option<Duration>,
+    ) -> Option<Duration> {
+        current_duration
+    }
+}
+
 #[derive(Clone, Debug)]
 /// The policy of a cache.
 pub struct Policy {
@@ -7,6 +109,8 @@ pub struct Policy {
     num_segments: usize,
     time_to_live: Option<Duration>,
     time_to_idle: Option<Duration>,
+    window_capacity: Option<u64>,
+    protected_capacity: Option<u64>,
 }
 
 impl Policy {
@@ -21,6 +125,8 @@ impl Policy {
             num_segments,
             time_to_live,
             time_to_idle,
+            window_capacity: None,
+            protected_capacity: None,
         }
     }
 
@@ -53,4 +159,43 @@ impl Policy {
     pub fn time_to_idle(&self) -> Option<Duration> {
         self.time_to_idle
     }
+
+    /// Returns the max weighted size of the admission window of the cache, or
+    /// `None` if the cache does not have the admission window.
+    ///
+    /// The `sync` and `future` caches adapt the split between the admission window
+    /// and the main space at runtime, so the returned value is a snapshot at the
+    /// time when this `Policy` was created.
+    pub fn window_capacity(&self) -> Option<u64> {
+        self.window_capacity
+    }
+
+    /// Returns the max weighted size of the main space (the probation and protected
+    /// segments) of the cache, or `None` if the cache does not have the admission
+    /// window.
+    ///
+    /// See [`window_capacity`](#method.window_capacity) for the adaptation.
+    pub fn main_capacity(&self) -> Option<u64> {
+        self.max_capacity
+            .zip(self.window_capacity)
+            .map(|(max, window)| max.saturating_sub(window))
+    }
+
+    /// Returns the max weighted size of the protected segment of the main space, or
+    /// `None` if the cache does not have the admission window.
+    ///
+    /// See [`window_capacity`](#method.window_capacity) for the adaptation.
+    pub fn protected_capacity(&self) -> Option<u64> {
+        self.protected_capacity
+    }
+
+    pub(crate) fn set_window_and_protected_capacities(&mut self, window: u64, protected: u64) {
+        if window > 0 {
+            self.window_capacity = Some(window);
+            self.protected_capacity = Some(protected);
+        } else {
+            self.window_capacity = None;
+            self.protected_capacity = None;
+        }
+    }
 }
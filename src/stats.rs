@@ -0,0 +1,487 @@
+//! Provides the statistics of a cache.
+
+use crate::notification::RemovalCause;
+
+use crossbeam_utils::atomic::AtomicCell;
+use std::{sync::Arc, time::Duration};
+
+/// A snapshot of the statistics of a cache.
+///
+/// The statistics are recorded only when the cache was built with
+/// `CacheBuilder::record_stats` or `CacheBuilder::stats_counter` method.
+/// Otherwise, all counters in the snapshot are zero.
+///
+/// The counters are monotonically increasing while the cache is alive, and they
+/// are updated without any synchronization with the cache operations. So a
+/// snapshot can be slightly out of date.
+///
+/// # Example
+///
+/// ```rust
+/// use moka::sync::Cache;
+///
+/// let cache = Cache::builder()
+///     .max_capacity(100)
+///     .record_stats()
+///     .build();
+///
+/// cache.insert(0, "zero");
+/// cache.get(&0); // Hit
+/// cache.get(&1); // Miss
+/// cache.get_with(1, || "one"); // Miss, and then load.
+///
+/// let stats = cache.stats();
+/// assert_eq!(stats.hit_count(), 1);
+/// assert_eq!(stats.miss_count(), 2);
+/// assert_eq!(stats.load_success_count(), 1);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    hit_count: u64,
+    miss_count: u64,
+    load_success_count: u64,
+    load_failure_count: u64,
+    total_load_time_nanos: u64,
+    eviction_count: u64,
+    eviction_weight: u64,
+    dropped_notification_count: u64,
+}
+
+impl CacheStats {
+    /// Creates a new `CacheStats`. This is useful when implementing the `snapshot`
+    /// method of your own [`StatsCounter`][stats-counter].
+    ///
+    /// [stats-counter]: ./trait.StatsCounter.html
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        hit_count: u64,
+        miss_count: u64,
+        load_success_count: u64,
+        load_failure_count: u64,
+        total_load_time: Duration,
+        eviction_count: u64,
+        eviction_weight: u64,
+        dropped_notification_count: u64,
+    ) -> Self {
+        Self {
+            hit_count,
+            miss_count,
+            load_success_count,
+            load_failure_count,
+            total_load_time_nanos: saturating_nanos(total_load_time),
+            eviction_count,
+            eviction_weight,
+            dropped_notification_count,
+        }
+    }
+
+    /// Returns the number of times the cache lookup methods returned a cached
+    /// value.
+    pub fn hit_count(&self) -> u64 {
+        self.hit_count
+    }
+
+    /// Returns the number of times the cache lookup methods did not find a cached
+    /// value. This includes the lookups done by `get_with` and friends before
+    /// they load a new value.
+    pub fn miss_count(&self) -> u64 {
+        self.miss_count
+    }
+
+    /// Returns the number of times the cache lookup methods were called. This is
+    /// `hit_count + miss_count`.
+    pub fn request_count(&self) -> u64 {
+        self.hit_count.saturating_add(self.miss_count)
+    }
+
+    /// Returns the ratio of the hits to the requests. Returns `1.0` when there
+    /// is no request.
+    pub fn hit_rate(&self) -> f64 {
+        let requests = self.request_count();
+        if requests == 0 {
+            1.0
+        } else {
+            self.hit_count as f64 / requests as f64
+        }
+    }
+
+    /// Returns the ratio of the misses to the requests. Returns `0.0` when there
+    /// is no request.
+    pub fn miss_rate(&self) -> f64 {
+        let requests = self.request_count();
+        if requests == 0 {
+            0.0
+        } else {
+            self.miss_count as f64 / requests as f64
+        }
+    }
+
+    /// Returns the number of times the `init` closure (or future) of `get_with`
+    /// and friends successfully loaded a new value.
+    ///
+    /// The `init` of `try_get_with` is considered as failed when it returns an
+    /// `Err`, and the `init` of `optionally_get_with` is considered as failed
+    /// when it returns `None`.
+    pub fn load_success_count(&self) -> u64 {
+        self.load_success_count
+    }
+
+    /// Returns the number of times the `init` closure (or future) of `get_with`
+    /// and friends failed to load a new value.
+    pub fn load_failure_count(&self) -> u64 {
+        self.load_failure_count
+    }
+
+    /// Returns the number of times the cache tried to load a new value. This is
+    /// `load_success_count + load_failure_count`.
+    pub fn load_count(&self) -> u64 {
+        self.load_success_count
+            .saturating_add(self.load_failure_count)
+    }
+
+    /// Returns the ratio of the failed loads to the loads. Returns `0.0` when
+    /// there is no load.
+    pub fn load_failure_rate(&self) -> f64 {
+        let loads = self.load_count();
+        if loads == 0 {
+            0.0
+        } else {
+            self.load_failure_count as f64 / loads as f64
+        }
+    }
+
+    /// Returns the total time spent to load new values.
+    pub fn total_load_time(&self) -> Duration {
+        Duration::from_nanos(self.total_load_time_nanos)
+    }
+
+    /// Returns the average time spent to load a new value. Returns zero when there
+    /// is no load.
+    pub fn average_load_penalty(&self) -> Duration {
+        self.total_load_time_nanos
+            .checked_div(self.load_count())
+            .map(Duration::from_nanos)
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of entries evicted from the cache by the size
+    /// constraint or the expiration.
+    pub fn eviction_count(&self) -> u64 {
+        self.eviction_count
+    }
+
+    /// Returns the sum of the weights of the entries evicted from the cache. If
+    /// the cache does not have a weigher, this will be the same to
+    /// `eviction_count`.
+    pub fn eviction_weight(&self) -> u64 {
+        self.eviction_weight
+    }
+
+    /// Returns the number of eviction notifications dropped because the
+    /// notification queue of the eviction listener was full. This is non-zero only
+    /// when the listener uses the queued delivery mode with the `DropNewest` or
+    /// `DropOldest` overflow policy.
+    pub fn dropped_notification_count(&self) -> u64 {
+        self.dropped_notification_count
+    }
+
+    /// Returns a new `CacheStats` that is the sum of this and the `other`
+    /// statistics.
+    pub fn plus(&self, other: &CacheStats) -> CacheStats {
+        CacheStats {
+            hit_count: self.hit_count.saturating_add(other.hit_count),
+            miss_count: self.miss_count.saturating_add(other.miss_count),
+            load_success_count: self
+                .load_success_count
+                .saturating_add(other.load_success_count),
+            load_failure_count: self
+                .load_failure_count
+                .saturating_add(other.load_failure_count),
+            total_load_time_nanos: self
+                .total_load_time_nanos
+                .saturating_add(other.total_load_time_nanos),
+            eviction_count: self.eviction_count.saturating_add(other.eviction_count),
+            eviction_weight: self.eviction_weight.saturating_add(other.eviction_weight),
+            dropped_notification_count: self
+                .dropped_notification_count
+                .saturating_add(other.dropped_notification_count),
+        }
+    }
+
+    /// Returns a new `CacheStats` that is the difference between this and the
+    /// `other` statistics. Useful to get the statistics of an interval from two
+    /// snapshots. Each counter will be floored at zero.
+    pub fn minus(&self, other: &CacheStats) -> CacheStats {
+        CacheStats {
+            hit_count: self.hit_count.saturating_sub(other.hit_count),
+            miss_count: self.miss_count.saturating_sub(other.miss_count),
+            load_success_count: self
+                .load_success_count
+                .saturating_sub(other.load_success_count),
+            load_failure_count: self
+                .load_failure_count
+                .saturating_sub(other.load_failure_count),
+            total_load_time_nanos: self
+                .total_load_time_nanos
+                .saturating_sub(other.total_load_time_nanos),
+            eviction_count: self.eviction_count.saturating_sub(other.eviction_count),
+            eviction_weight: self.eviction_weight.saturating_sub(other.eviction_weight),
+            dropped_notification_count: self
+                .dropped_notification_count
+                .saturating_sub(other.dropped_notification_count),
+        }
+    }
+}
+
+/// A sink of the statistics of a cache.
+///
+/// The cache calls the methods of this trait on its hot paths (e.g. every `get`),
+/// so the implementation should be cheap and must not block. It also must not
+/// panic.
+///
+/// Use [`ConcurrentStatsCounter`][concurrent-stats-counter] if you only need to
+/// read the statistics via the `stats` method of the cache. Implement this trait
+/// if you want to forward the statistics to your metrics system (e.g. Prometheus
+/// or OpenTelemetry), and set it to the cache with the `stats_counter` method of
+/// the `CacheBuilder`.
+///
+/// [concurrent-stats-counter]: ./struct.ConcurrentStatsCounter.html
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{
+///     notification::RemovalCause,
+///     stats::{CacheStats, ConcurrentStatsCounter, StatsCounter},
+///     sync::Cache,
+/// };
+/// use std::time::Duration;
+///
+/// // A counter that prints the evictions, and keeps all statistics in a
+/// // `ConcurrentStatsCounter`.
+/// struct MyStatsCounter {
+///     cache_name: String,
+///     counter: ConcurrentStatsCounter,
+/// }
+///
+/// impl StatsCounter for MyStatsCounter {
+///     fn record_hits(&self, count: u32) {
+///         self.counter.record_hits(count);
+///     }
+///
+///     fn record_misses(&self, count: u32) {
+///         self.counter.record_misses(count);
+///     }
+///
+///     fn record_load_success(&self, load_time: Duration) {
+///         self.counter.record_load_success(load_time);
+///     }
+///
+///     fn record_load_failure(&self, load_time: Duration) {
+///         self.counter.record_load_failure(load_time);
+///     }
+///
+///     fn record_eviction(&self, weight: u64, cause: RemovalCause) {
+///         println!("{}: evicted an entry. cause: {:?}", self.cache_name, cause);
+///         self.counter.record_eviction(weight, cause);
+///     }
+///
+///     fn snapshot(&self) -> CacheStats {
+///         self.counter.snapshot()
+///     }
+/// }
+///
+/// let name = "my-cache";
+/// let cache = Cache::builder()
+///     .name(name)
+///     .max_capacity(100)
+///     .stats_counter(MyStatsCounter {
+///         cache_name: name.to_string(),
+///         counter: Default::default(),
+///     })
+///     .build();
+///
+/// cache.insert(0, "zero");
+/// cache.get(&0);
+/// assert_eq!(cache.stats().hit_count(), 1);
+/// ```
+pub trait StatsCounter: Send + Sync {
+    /// Records cache hits. This is called when a cache lookup method returned a
+    /// cached value.
+    fn record_hits(&self, count: u32);
+
+    /// Records cache misses. This is called when a cache lookup method did not
+    /// find a cached value.
+    fn record_misses(&self, count: u32);
+
+    /// Records the successful load of a new value, and the time spent to load it.
+    fn record_load_success(&self, load_time: Duration);
+
+    /// Records the failed load of a new value, and the time spent until it failed.
+    fn record_load_failure(&self, load_time: Duration);
+
+    /// Records the eviction of an entry from the cache. The `cause` will be
+    /// either `RemovalCause::Size` or `RemovalCause::Expired`.
+    fn record_eviction(&self, weight: u64, cause: RemovalCause);
+
+    /// Records eviction notifications dropped because the notification queue of
+    /// the eviction listener was full.
+    ///
+    /// The default implementation does nothing.
+    fn record_dropped_notifications(&self, _count: u32) {}
+
+    /// Returns a snapshot of the recorded statistics. This will be returned by
+    /// the `stats` method of the cache.
+    ///
+    /// The default implementation returns a `CacheStats` whose counters are all
+    /// zero.
+    fn snapshot(&self) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+// Allows to share a counter between the cache and the application.
+impl<T: StatsCounter + ?Sized> StatsCounter for Arc<T> {
+    fn record_hits(&self, count: u32) {
+        (**self).record_hits(count);
+    }
+
+    fn record_misses(&self, count: u32) {
+        (**self).record_misses(count);
+    }
+
+    fn record_load_success(&self, load_time: Duration) {
+        (**self).record_load_success(load_time);
+    }
+
+    fn record_load_failure(&self, load_time: Duration) {
+        (**self).record_load_failure(load_time);
+    }
+
+    fn record_eviction(&self, weight: u64, cause: RemovalCause) {
+        (**self).record_eviction(weight, cause);
+    }
+
+    fn record_dropped_notifications(&self, count: u32) {
+        (**self).record_dropped_notifications(count);
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        (**self).snapshot()
+    }
+}
+
+pub(crate) type StatsCounterArc = Arc<dyn StatsCounter + 'static>;
+
+/// A thread-safe [`StatsCounter`][stats-counter] that keeps the statistics in
+/// atomic counters. This is used by a cache built with the `record_stats` method
+/// of the `CacheBuilder`.
+///
+/// [stats-counter]: ./trait.StatsCounter.html
+#[derive(Default)]
+pub struct ConcurrentStatsCounter {
+    hit_count: AtomicCell<u64>,
+    miss_count: AtomicCell<u64>,
+    load_success_count: AtomicCell<u64>,
+    load_failure_count: AtomicCell<u64>,
+    total_load_time_nanos: AtomicCell<u64>,
+    eviction_count: AtomicCell<u64>,
+    eviction_weight: AtomicCell<u64>,
+    dropped_notification_count: AtomicCell<u64>,
+}
+
+impl ConcurrentStatsCounter {
+    fn add_load_time(&self, load_time: Duration) {
+        self.total_load_time_nanos
+            .fetch_add(saturating_nanos(load_time));
+    }
+}
+
+impl StatsCounter for ConcurrentStatsCounter {
+    fn record_hits(&self, count: u32) {
+        self.hit_count.fetch_add(count as u64);
+    }
+
+    fn record_misses(&self, count: u32) {
+        self.miss_count.fetch_add(count as u64);
+    }
+
+    fn record_load_success(&self, load_time: Duration) {
+        self.load_success_count.fetch_add(1);
+        self.add_load_time(load_time);
+    }
+
+    fn record_load_failure(&self, load_time: Duration) {
+        self.load_failure_count.fetch_add(1);
+        self.add_load_time(load_time);
+    }
+
+    fn record_eviction(&self, weight: u64, _cause: RemovalCause) {
+        self.eviction_count.fetch_add(1);
+        self.eviction_weight.fetch_add(weight);
+    }
+
+    fn record_dropped_notifications(&self, count: u32) {
+        self.dropped_notification_count.fetch_add(count as u64);
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hit_count: self.hit_count.load(),
+            miss_count: self.miss_count.load(),
+            load_success_count: self.load_success_count.load(),
+            load_failure_count: self.load_failure_count.load(),
+            total_load_time_nanos: self.total_load_time_nanos.load(),
+            eviction_count: self.eviction_count.load(),
+            eviction_weight: self.eviction_weight.load(),
+            dropped_notification_count: self.dropped_notification_count.load(),
+        }
+    }
+}
+
+// Saturates at u64::MAX nanoseconds (about 584 years).
+fn saturating_nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConcurrentStatsCounter, StatsCounter};
+    use crate::notification::RemovalCause;
+
+    use std::time::Duration;
+
+    #[test]
+    fn rates_and_penalty() {
+        let counter = ConcurrentStatsCounter::default();
+        let stats = counter.snapshot();
+        assert_eq!(stats.hit_rate(), 1.0);
+        assert_eq!(stats.miss_rate(), 0.0);
+        assert_eq!(stats.load_failure_rate(), 0.0);
+        assert_eq!(stats.average_load_penalty(), Duration::default());
+
+        counter.record_hits(3);
+        counter.record_misses(1);
+        counter.record_load_success(Duration::from_millis(10));
+        counter.record_load_failure(Duration::from_millis(20));
+        counter.record_eviction(5, RemovalCause::Size);
+        counter.record_dropped_notifications(2);
+
+        let stats = counter.snapshot();
+        assert_eq!(stats.request_count(), 4);
+        assert_eq!(stats.hit_rate(), 0.75);
+        assert_eq!(stats.miss_rate(), 0.25);
+        assert_eq!(stats.load_count(), 2);
+        assert_eq!(stats.load_failure_rate(), 0.5);
+        assert_eq!(stats.total_load_time(), Duration::from_millis(30));
+        assert_eq!(stats.average_load_penalty(), Duration::from_millis(15));
+        assert_eq!(stats.eviction_count(), 1);
+        assert_eq!(stats.eviction_weight(), 5);
+        assert_eq!(stats.dropped_notification_count(), 2);
+
+        let sum = stats.plus(&stats);
+        assert_eq!(sum.hit_count(), 6);
+        assert_eq!(sum.eviction_weight(), 10);
+        assert_eq!(sum.minus(&stats), stats);
+    }
+}
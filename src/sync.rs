@@ -3,14 +3,19 @@
 mod builder;
 mod cache;
 mod entry_selector;
+mod loading_cache;
 mod segment;
 mod value_initializer;
 
+use crate::sync_base::refresher::Refresher;
+use std::sync::Arc;
+
 pub use crate::sync_base::{iter::Iter, PredicateId};
 pub use {
     builder::CacheBuilder,
     cache::Cache,
     entry_selector::{OwnedKeyEntrySelector, RefKeyEntrySelector},
+    loading_cache::{CacheLoader, LoadingCache},
     segment::SegmentedCache,
 };
 
@@ -23,3 +28,7 @@ pub trait ConcurrentCacheExt<K, V> {
 // Empty internal struct to be used in optionally_get_with to represent the None
 // results.
 struct OptionallyNone;
+
+pub(crate) type Reloader<K, V> = Arc<dyn Fn(&K, &V) -> Option<V> + Send + Sync + 'static>;
+
+pub(crate) type RefresherArc<K, V> = Arc<Refresher<Reloader<K, V>>>;
@@ -1,13 +1,28 @@
-use super::{Cache, SegmentedCache};
+use super::{Cache, CacheLoader, LoadingCache, Reloader, SegmentedCache};
 use crate::{
-    common::{builder_utils, concurrent::Weigher},
+    common::{
+        builder_utils,
+        concurrent::{self, ExpiryArc, Weigher},
+    },
+    executor::{Executor, ExecutorArc},
     notification::{self, EvictionListener, RemovalCause},
+    stats::{ConcurrentStatsCounter, StatsCounter, StatsCounterArc},
+    ticker::{Ticker, TickerArc},
+    Expiry, HeapSize,
 };
 
+#[cfg(feature = "serde")]
+use crate::SnapshotError;
+#[cfg(feature = "serde")]
+use serde_crate::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use std::io::Read;
+
 use std::{
     collections::hash_map::RandomState,
     hash::{BuildHasher, Hash},
     marker::PhantomData,
+    mem::size_of,
     sync::Arc,
     time::Duration,
 };
@@ -55,8 +70,17 @@ pub struct CacheBuilder<K, V, C> {
     eviction_listener_conf: Option<notification::Configuration>,
     time_to_live: Option<Duration>,
     time_to_idle: Option<Duration>,
+    expiry: Option<ExpiryArc<K, V>>,
     invalidator_enabled: bool,
+    stats_counter: Option<StatsCounterArc>,
+    refresh_after_write: Option<Duration>,
+    reloader: Option<Reloader<K, V>>,
     thread_pool_enabled: bool,
+    executor: Option<ExecutorArc>,
+    expiration_scheduling_enabled: bool,
+    ticker: Option<TickerArc>,
+    deterministic_mode: bool,
+    include_entry_overhead: bool,
     cache_type: PhantomData<C>,
 }
 
@@ -76,9 +100,18 @@ where
             eviction_listener_conf: None,
             time_to_live: None,
             time_to_idle: None,
+            expiry: None,
             invalidator_enabled: false,
+            stats_counter: None,
+            refresh_after_write: None,
+            reloader: None,
             // TODO: Change this to `false` in Moka 0.10.0.
             thread_pool_enabled: true,
+            executor: None,
+            expiration_scheduling_enabled: false,
+            ticker: None,
+            deterministic_mode: false,
+            include_entry_overhead: false,
             cache_type: Default::default(),
         }
     }
@@ -119,8 +152,17 @@ where
             eviction_listener_conf: None,
             time_to_live: self.time_to_live,
             time_to_idle: self.time_to_idle,
+            expiry: self.expiry,
             invalidator_enabled: self.invalidator_enabled,
+            stats_counter: self.stats_counter,
+            refresh_after_write: self.refresh_after_write,
+            reloader: self.reloader,
             thread_pool_enabled: self.thread_pool_enabled,
+            executor: self.executor,
+            expiration_scheduling_enabled: self.expiration_scheduling_enabled,
+            ticker: self.ticker,
+            deterministic_mode: self.deterministic_mode,
+            include_entry_overhead: self.include_entry_overhead,
             cache_type: PhantomData::default(),
         }
     }
@@ -135,24 +177,103 @@ where
     /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
     /// 1000 years. This is done to protect against overflow when computing key
     /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
     pub fn build(self) -> Cache<K, V, RandomState> {
         let build_hasher = RandomState::default();
         builder_utils::ensure_expirations_or_panic(self.time_to_live, self.time_to_idle);
+        let weigher = self.weigher_with_overhead();
+        let housekeeper_conf = builder_utils::housekeeper_conf(
+            self.thread_pool_enabled,
+            self.executor,
+            self.deterministic_mode,
+        )
+        .with_expiration_scheduling(self.expiration_scheduling_enabled);
         Cache::with_everything(
             self.name,
             self.max_capacity,
             self.initial_capacity,
             build_hasher,
-            self.weigher,
+            weigher,
             self.eviction_listener,
             self.eviction_listener_conf,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
             self.invalidator_enabled,
-            builder_utils::housekeeper_conf(self.thread_pool_enabled),
+            self.stats_counter,
+            builder_utils::refresher(
+                self.refresh_after_write,
+                self.reloader,
+                housekeeper_conf.executor().cloned(),
+            ),
+            self.ticker,
+            housekeeper_conf,
         )
     }
 
+    /// Builds a `Cache<K, V>` and restores the entries from a snapshot written by
+    /// [`Cache::snapshot_to`][snapshot-to] or
+    /// [`SegmentedCache::snapshot_to`][seg-snapshot-to].
+    ///
+    /// The entries are restored with their remaining time-to-live, time-to-idle
+    /// and per-entry expiration times, their access order and their historic
+    /// popularity. The configuration of the cache is not a part of the snapshot,
+    /// so configure this builder in the same way as the cache the snapshot was
+    /// taken from. If the cache does not have enough capacity for all entries, the
+    /// least recently used ones will be evicted.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`][snapshot-error] if it fails to read from the
+    /// `reader`, or the data read is not a snapshot of a cache with the same key
+    /// and value types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
+    /// 1000 years. This is done to protect against overflow when computing key
+    /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
+    ///
+    /// [snapshot-to]: ./struct.Cache.html#method.snapshot_to
+    /// [seg-snapshot-to]: ./struct.SegmentedCache.html#method.snapshot_to
+    /// [snapshot-error]: ../enum.SnapshotError.html
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn restore_from<R>(self, reader: R) -> Result<Cache<K, V, RandomState>, SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        R: Read,
+    {
+        let cache = self.build();
+        cache.restore_snapshot(reader)?;
+        Ok(cache)
+    }
+
+    /// Builds a [`LoadingCache<K, V, L>`][loading-cache] that loads the missing
+    /// values by the given `loader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
+    /// 1000 years. This is done to protect against overflow when computing key
+    /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
+    ///
+    /// [loading-cache]: ./struct.LoadingCache.html
+    pub fn build_with_loader<L>(self, loader: L) -> LoadingCache<K, V, L, RandomState>
+    where
+        L: CacheLoader<K, V>,
+    {
+        LoadingCache::new(self.build(), loader)
+    }
+
     /// Builds a `Cache<K, V, S>`, with the given `hasher`.
     ///
     /// If you want to build a `SegmentedCache<K, V>`, call `segments` method  before
@@ -163,23 +284,40 @@ where
     /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
     /// 1000 years. This is done to protect against overflow when computing key
     /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
     pub fn build_with_hasher<S>(self, hasher: S) -> Cache<K, V, S>
     where
         S: BuildHasher + Clone + Send + Sync + 'static,
     {
         builder_utils::ensure_expirations_or_panic(self.time_to_live, self.time_to_idle);
+        let weigher = self.weigher_with_overhead();
+        let housekeeper_conf = builder_utils::housekeeper_conf(
+            self.thread_pool_enabled,
+            self.executor,
+            self.deterministic_mode,
+        )
+        .with_expiration_scheduling(self.expiration_scheduling_enabled);
         Cache::with_everything(
             self.name,
             self.max_capacity,
             self.initial_capacity,
             hasher,
-            self.weigher,
+            weigher,
             self.eviction_listener,
             self.eviction_listener_conf,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
             self.invalidator_enabled,
-            builder_utils::housekeeper_conf(self.thread_pool_enabled),
+            self.stats_counter,
+            builder_utils::refresher(
+                self.refresh_after_write,
+                self.reloader,
+                housekeeper_conf.executor().cloned(),
+            ),
+            self.ticker,
+            housekeeper_conf,
         )
     }
 }
@@ -199,25 +337,87 @@ where
     /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
     /// 1000 years. This is done to protect against overflow when computing key
     /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
     pub fn build(self) -> SegmentedCache<K, V, RandomState> {
         let build_hasher = RandomState::default();
         builder_utils::ensure_expirations_or_panic(self.time_to_live, self.time_to_idle);
+        let weigher = self.weigher_with_overhead();
+        let housekeeper_conf = builder_utils::housekeeper_conf(
+            self.thread_pool_enabled,
+            self.executor,
+            self.deterministic_mode,
+        )
+        .with_expiration_scheduling(self.expiration_scheduling_enabled);
         SegmentedCache::with_everything(
             self.name,
             self.max_capacity,
             self.initial_capacity,
             self.num_segments.unwrap(),
             build_hasher,
-            self.weigher,
+            weigher,
             self.eviction_listener,
             self.eviction_listener_conf,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
             self.invalidator_enabled,
-            builder_utils::housekeeper_conf(self.thread_pool_enabled),
+            self.stats_counter,
+            builder_utils::refresher(
+                self.refresh_after_write,
+                self.reloader,
+                housekeeper_conf.executor().cloned(),
+            ),
+            self.ticker,
+            housekeeper_conf,
         )
     }
 
+    /// Builds a `SegmentedCache<K, V>` and restores the entries from a snapshot
+    /// written by [`SegmentedCache::snapshot_to`][seg-snapshot-to] or
+    /// [`Cache::snapshot_to`][snapshot-to].
+    ///
+    /// See [`CacheBuilder::restore_from`][restore-from] for what will be restored.
+    /// If the snapshot was taken from a cache with a different number of
+    /// segments, the sizes of the admission windows adapted by the cache will not
+    /// be restored.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`][snapshot-error] if it fails to read from the
+    /// `reader`, or the data read is not a snapshot of a cache with the same key
+    /// and value types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
+    /// 1000 years. This is done to protect against overflow when computing key
+    /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
+    ///
+    /// [snapshot-to]: ./struct.Cache.html#method.snapshot_to
+    /// [seg-snapshot-to]: ./struct.SegmentedCache.html#method.snapshot_to
+    /// [restore-from]: ./struct.CacheBuilder.html#method.restore_from
+    /// [snapshot-error]: ../enum.SnapshotError.html
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn restore_from<R>(
+        self,
+        reader: R,
+    ) -> Result<SegmentedCache<K, V, RandomState>, SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        R: Read,
+    {
+        let cache = self.build();
+        cache.restore_snapshot(reader)?;
+        Ok(cache)
+    }
+
     /// Builds a `SegmentedCache<K, V, S>`, with the given `hasher`.
     ///
     /// If you want to build a `Cache<K, V>`, do not call `segments` method before
@@ -228,24 +428,38 @@ where
     /// Panics if configured with either `time_to_live` or `time_to_idle` higher than
     /// 1000 years. This is done to protect against overflow when computing key
     /// expiration.
+    ///
+    /// Panics if configured with `refresh_after_write` but without `reloader`.
     pub fn build_with_hasher<S>(self, hasher: S) -> SegmentedCache<K, V, S>
     where
         S: BuildHasher + Clone + Send + Sync + 'static,
     {
         builder_utils::ensure_expirations_or_panic(self.time_to_live, self.time_to_idle);
+        let weigher = self.weigher_with_overhead();
+        let housekeeper_conf =
+            builder_utils::housekeeper_conf(true, self.executor, self.deterministic_mode)
+                .with_expiration_scheduling(self.expiration_scheduling_enabled);
         SegmentedCache::with_everything(
             self.name,
             self.max_capacity,
             self.initial_capacity,
             self.num_segments.unwrap(),
             hasher,
-            self.weigher,
+            weigher,
             self.eviction_listener,
             self.eviction_listener_conf,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
             self.invalidator_enabled,
-            builder_utils::housekeeper_conf(true),
+            self.stats_counter,
+            builder_utils::refresher(
+                self.refresh_after_write,
+                self.reloader,
+                housekeeper_conf.executor().cloned(),
+            ),
+            self.ticker,
+            housekeeper_conf,
         )
     }
 }
@@ -279,14 +493,71 @@ impl<K, V, C> CacheBuilder<K, V, C> {
     /// Sets the weigher closure to the cache.
     ///
     /// The closure should take `&K` and `&V` as the arguments and returns a `u32`
-    /// representing the relative size of the entry.
+    /// representing the relative size of the entry. To use weights larger than
+    /// `u32::MAX`, use [`weigher_u64`](#method.weigher_u64) instead.
     pub fn weigher(self, weigher: impl Fn(&K, &V) -> u32 + Send + Sync + 'static) -> Self {
+        Self {
+            weigher: Some(Arc::new(move |k, v| u64::from(weigher(k, v)))),
+            ..self
+        }
+    }
+
+    /// Sets the weigher closure returning a `u64` weight to the cache.
+    ///
+    /// This is the same as [`weigher`](#method.weigher), but the closure can
+    /// return a weight larger than `u32::MAX`, for example the size of the entry in
+    /// bytes.
+    pub fn weigher_u64(self, weigher: impl Fn(&K, &V) -> u64 + Send + Sync + 'static) -> Self {
         Self {
             weigher: Some(Arc::new(weigher)),
             ..self
         }
     }
 
+    /// Bounds the cache by the estimated memory usage of the entries in bytes.
+    ///
+    /// This sets `max_capacity` to `bytes`, and the weigher to the one that returns
+    /// the [`size_of`][size-of] the key and value plus their
+    /// [`HeapSize::heap_size`][heap-size]. Then `weighted_size` of the cache
+    /// returns the estimated memory usage in bytes. Call
+    /// [`include_entry_overhead`](#method.include_entry_overhead) to also count the
+    /// memory used by the cache to store the entries.
+    ///
+    /// [size-of]: https://doc.rust-lang.org/std/mem/fn.size_of.html
+    /// [heap-size]: ../trait.HeapSize.html#tymethod.heap_size
+    pub fn max_memory_bytes(self, bytes: u64) -> Self
+    where
+        K: HeapSize + 'static,
+        V: HeapSize + 'static,
+    {
+        Self {
+            max_capacity: Some(bytes),
+            ..self
+        }
+        .weigher_u64(|k, v| {
+            (size_of::<K>() + k.heap_size() + size_of::<V>() + v.heap_size()) as u64
+        })
+    }
+
+    /// Specify whether or not to add the estimated per-entry memory overhead of the
+    /// cache to the weight of every entry. (Default: `false`)
+    ///
+    /// The overhead includes the internal hash table bucket, the bookkeeping data
+    /// such as the timestamps and the nodes of the internal queues, but not the key
+    /// and value themselves. It is calculated from the types of the key and value
+    /// and the expiration settings of the cache.
+    ///
+    /// Use it with [`max_memory_bytes`](#method.max_memory_bytes) or a weigher
+    /// returning the size of the entry in bytes, so that `weighted_size` of the
+    /// cache reflects the memory used by the cache. If no weigher is set, the weight
+    /// of an entry will be the `size_of` the key and value plus the overhead.
+    pub fn include_entry_overhead(self, v: bool) -> Self {
+        Self {
+            include_entry_overhead: v,
+            ..self
+        }
+    }
+
     /// Sets the eviction listener closure to the cache.
     ///
     /// The closure should take `Arc<K>`, `V` and [`RemovalCause`][removal-cause] as
@@ -375,6 +646,69 @@ impl<K, V, C> CacheBuilder<K, V, C> {
         }
     }
 
+    /// Sets the given `expiry` to the cache to calculate the expiration time of
+    /// each entry.
+    ///
+    /// The per-entry expiration works together with `time_to_live` and
+    /// `time_to_idle`; an entry will be expired when any of them has passed.
+    ///
+    /// See [`Expiry`][expiry-trait] for more details.
+    ///
+    /// [expiry-trait]: ../trait.Expiry.html
+    pub fn expire_after(self, expiry: impl Expiry<K, V> + Send + Sync + 'static) -> Self {
+        Self {
+            expiry: Some(Arc::new(expiry)),
+            ..self
+        }
+    }
+
+    /// Sets the refresh-after-write duration of the cache. Requires a
+    /// [`reloader`](#method.reloader).
+    ///
+    /// When an entry is read by `get` (or `get_with` family methods) after the
+    /// specified duration has passed since the entry was inserted or updated, the
+    /// cache returns the current value immediately and reloads a new value in a
+    /// background thread. Concurrent reads of the same key trigger only one reload.
+    ///
+    /// Unlike `time_to_live`, the entry is not removed after the duration; use
+    /// `refresh_after_write` together with a longer `time_to_live` to bound how old a
+    /// value can be when it is not read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    /// use std::time::Duration;
+    ///
+    /// let cache = Cache::builder()
+    ///     .refresh_after_write(Duration::from_secs(60))
+    ///     .reloader(|key: &u32, _old_value: &String| Some(format!("value of {}", key)))
+    ///     .time_to_live(Duration::from_secs(10 * 60))
+    ///     .build();
+    ///
+    /// cache.insert(1, "one".to_string());
+    /// ```
+    pub fn refresh_after_write(self, duration: Duration) -> Self {
+        Self {
+            refresh_after_write: Some(duration),
+            ..self
+        }
+    }
+
+    /// Sets the reloader closure of the cache, which is called to reload the value
+    /// of an entry when the entry needs to be refreshed. See
+    /// [`refresh_after_write`](#method.refresh_after_write) for more details.
+    ///
+    /// The closure takes the key and the current value, and returns `Some(value)`
+    /// to replace the current value, or `None` to keep it. If the closure panics,
+    /// the current value is kept too.
+    pub fn reloader(self, reloader: impl Fn(&K, &V) -> Option<V> + Send + Sync + 'static) -> Self {
+        Self {
+            reloader: Some(Arc::new(reloader)),
+            ..self
+        }
+    }
+
     /// Enables support for [Cache::invalidate_entries_if][cache-invalidate-if]
     /// method.
     ///
@@ -389,6 +723,40 @@ impl<K, V, C> CacheBuilder<K, V, C> {
         }
     }
 
+    /// Enables the statistics of the cache with the built-in
+    /// [`ConcurrentStatsCounter`][concurrent-stats-counter]. (Default: disabled)
+    ///
+    /// The cache will record the hit and miss counts, the load success and
+    /// failure counts, the total load time, and the eviction count and weight.
+    /// Use [`Cache::stats`][cache-stats] method to get a snapshot of them.
+    ///
+    /// Recording the statistics has a small performance penalty on every cache
+    /// read and load.
+    ///
+    /// [concurrent-stats-counter]: ../stats/struct.ConcurrentStatsCounter.html
+    /// [cache-stats]: ./struct.Cache.html#method.stats
+    pub fn record_stats(self) -> Self {
+        Self {
+            stats_counter: Some(Arc::new(ConcurrentStatsCounter::default())),
+            ..self
+        }
+    }
+
+    /// Enables the statistics of the cache with the given
+    /// [`StatsCounter`][stats-counter]. Use this method instead of `record_stats`
+    /// to forward the statistics to your metrics system.
+    ///
+    /// The `stats` method of the cache will return the snapshot created by the
+    /// `snapshot` method of the given counter.
+    ///
+    /// [stats-counter]: ../stats/trait.StatsCounter.html
+    pub fn stats_counter(self, counter: impl StatsCounter + 'static) -> Self {
+        Self {
+            stats_counter: Some(Arc::new(counter)),
+            ..self
+        }
+    }
+
     /// Specify whether or not to enable the thread pool for housekeeping tasks.
     /// These tasks include removing expired entries and updating the LRU queue and
     /// LFU filter. `true` to enable and `false` to disable. (Default: `true`)
@@ -404,6 +772,107 @@ impl<K, V, C> CacheBuilder<K, V, C> {
             ..self
         }
     }
+
+    /// Sets the executor to run the background tasks of the cache, instead of the
+    /// global thread pools of this crate.
+    ///
+    /// The tasks include the housekeeping tasks, the scanning tasks of the
+    /// invalidation closures, the delivery of the queued eviction notifications and
+    /// the reloads for `refresh_after_write`. If `thread_pool_enabled` is set to
+    /// `false`, the housekeeping tasks will be executed by a client thread as
+    /// described there, but the other tasks still run on the executor.
+    ///
+    /// To share an executor between caches, wrap it with an `Arc` and pass a clone
+    /// of the `Arc` to each builder. See [`Executor`][executor-trait] for details
+    /// and an example.
+    ///
+    /// [executor-trait]: ../executor/trait.Executor.html
+    pub fn executor(self, executor: impl Executor + 'static) -> Self {
+        Self {
+            executor: Some(Arc::new(executor)),
+            ..self
+        }
+    }
+
+    /// Specify whether or not to schedule the housekeeping at the next expiration
+    /// time of the cache. (Default: `false`)
+    ///
+    /// By default, expired entries are removed by the housekeeping that runs
+    /// periodically, or when the cache is read or written, so an eviction listener
+    /// may be notified some time after the entries have expired. If enabled, the
+    /// housekeeping is also scheduled at the time when the next entry will expire,
+    /// so the listener is notified close to the real deadline, e.g. when a session
+    /// or a lease times out.
+    ///
+    /// The next expiration time is found when the housekeeping applies the
+    /// pending writes to the cache, so a newly inserted entry is taken into
+    /// account after the next housekeeping. The entries with per-entry expiration
+    /// times (`expire_after`) are tracked with a granularity of about one second.
+    ///
+    /// This option requires the housekeeping to run in the background, and has no
+    /// effect if `thread_pool_enabled` is set to `false`.
+    pub fn expiration_scheduling_enabled(self, v: bool) -> Self {
+        Self {
+            expiration_scheduling_enabled: v,
+            ..self
+        }
+    }
+
+    /// Sets the [`Ticker`][ticker-trait] to read the current time from, instead of
+    /// the system clock.
+    ///
+    /// Together with a [`MockTicker`][mock-ticker] and the
+    /// [`deterministic_mode`](#method.deterministic_mode), you can write fast and
+    /// reproducible tests of the expirations without sleeping.
+    ///
+    /// [ticker-trait]: ../ticker/trait.Ticker.html
+    /// [mock-ticker]: ../ticker/struct.MockTicker.html
+    pub fn ticker(self, ticker: impl Ticker + 'static) -> Self {
+        Self {
+            ticker: Some(Arc::new(ticker)),
+            ..self
+        }
+    }
+
+    /// Specify whether or not to run the cache in the deterministic mode, which is
+    /// intended for tests. (Default: `false`)
+    ///
+    /// In the deterministic mode, the cache has no background threads. All pending
+    /// maintenance tasks, including the housekeeping, the scanning tasks of the
+    /// invalidation closures, the delivery of the queued eviction notifications and
+    /// the reloads for `refresh_after_write`, are run by the calling thread until
+    /// they are done when [`ConcurrentCacheExt::sync`][sync-method] is called. The
+    /// housekeeping may also be run by a client thread when necessary, as with
+    /// `thread_pool_enabled(false)`.
+    ///
+    /// This mode takes precedence over `thread_pool_enabled`, `executor` and
+    /// `expiration_scheduling_enabled`.
+    ///
+    /// [sync-method]: ./trait.ConcurrentCacheExt.html#tymethod.sync
+    pub fn deterministic_mode(self, v: bool) -> Self {
+        Self {
+            deterministic_mode: v,
+            ..self
+        }
+    }
+}
+
+impl<K, V, C> CacheBuilder<K, V, C>
+where
+    K: 'static,
+    V: 'static,
+{
+    fn weigher_with_overhead(&self) -> Option<Weigher<K, V>> {
+        let overhead = if self.include_entry_overhead {
+            Some(concurrent::entry_overhead::<K, V>(
+                self.time_to_live.is_some() || self.invalidator_enabled,
+                self.expiry.is_some(),
+            ))
+        } else {
+            None
+        };
+        builder_utils::weigher(self.weigher.clone(), overhead)
+    }
 }
 
 #[cfg(test)]
@@ -1,35 +1,49 @@
 use super::{
     value_initializer::{InitResult, ValueInitializer},
-    CacheBuilder, ConcurrentCacheExt, OwnedKeyEntrySelector, RefKeyEntrySelector,
+    CacheBuilder, ConcurrentCacheExt, OwnedKeyEntrySelector, RefKeyEntrySelector, RefresherArc,
 };
 use crate::{
     common::{
         concurrent::{
-            constants::{MAX_SYNC_REPEATS, WRITE_RETRY_INTERVAL_MICROS},
+            constants::WRITE_RETRY_INTERVAL_MICROS,
             housekeeper::{self, InnerSync},
-            Weigher, WriteOp,
+            ExpiryArc, KvEntry, Weigher, WriteOp,
         },
         time::Instant,
     },
-    notification::{self, EvictionListener},
+    notification::{self, EvictionListener, Listener, RemovalCause},
+    ops::compute::Op,
+    stats::{CacheStats, StatsCounterArc},
     sync::{Iter, PredicateId},
     sync_base::{
         base_cache::{BaseCache, HouseKeeperArc},
         iter::ScanningGet,
     },
+    ticker::TickerArc,
     Entry, Policy, PredicateError,
 };
 
+#[cfg(feature = "serde")]
+use crate::{
+    sync_base::snapshot::{self, CacheSnapshot},
+    SnapshotError,
+};
+
 use crossbeam_channel::{Sender, TrySendError};
 use std::{
     borrow::Borrow,
-    collections::hash_map::RandomState,
+    collections::{hash_map::RandomState, HashMap, HashSet},
     fmt,
     hash::{BuildHasher, Hash},
     sync::Arc,
     time::Duration,
 };
 
+#[cfg(feature = "serde")]
+use serde_crate::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "serde")]
+use std::io::{Read, Write};
+
 /// A thread-safe concurrent synchronous in-memory cache.
 ///
 /// `Cache` supports full concurrency of retrievals and a high expected concurrency
@@ -437,6 +451,12 @@ use std::{
 ///   notifications.
 /// - This mode adds almost no performance overhead to cache write operations as it
 ///   does not use the per-key lock.
+/// - The queue has 1,024 slots by default. When it is full, the cache write
+///   operations are blocked until the listener catches up. You can change the
+///   capacity and this behavior by the `queue_capacity` and `overflow_policy`
+///   methods of the [`ConfigurationBuilder`][conf-builder].
+///
+/// [conf-builder]: ../notification/struct.ConfigurationBuilder.html
 ///
 /// ### Example: `Queued` Delivery Mode
 ///
@@ -676,6 +696,7 @@ use std::{
 pub struct Cache<K, V, S = RandomState> {
     base: BaseCache<K, V, S>,
     value_initializer: Arc<ValueInitializer<K, V, S>>,
+    refresher: Option<RefresherArc<K, V>>,
 }
 
 // TODO: https://github.com/moka-rs/moka/issues/54
@@ -706,6 +727,7 @@ impl<K, V, S> Clone for Cache<K, V, S> {
         Self {
             base: self.base.clone(),
             value_initializer: Arc::clone(&self.value_initializer),
+            refresher: self.refresher.as_ref().map(Arc::clone),
         }
     }
 }
@@ -790,6 +812,33 @@ impl<K, V, S> Cache<K, V, S> {
     pub fn weighted_size(&self) -> u64 {
         self.base.weighted_size()
     }
+
+    /// Returns a snapshot of the statistics of this cache.
+    ///
+    /// The statistics are recorded only when the cache was built with
+    /// [`CacheBuilder::record_stats`][builder-record-stats]. Otherwise, all counters
+    /// in the returned `CacheStats` are zero.
+    ///
+    /// The eviction counts are updated by the pending internal tasks, so they can be
+    /// behind the actual numbers. Performing a `sync()` first will mitigate this.
+    ///
+    /// [builder-record-stats]: ./struct.CacheBuilder.html#method.record_stats
+    pub fn stats(&self) -> CacheStats {
+        self.base.stats()
+    }
+
+    /// Returns the number of the eviction notifications dropped because the queue
+    /// of the [`Queued`][delivery-mode-queued] delivery mode was full.
+    ///
+    /// The notifications are dropped only with the `DropNewest` or `DropOldest`
+    /// [`OverflowPolicy`][overflow-policy]. Unlike [`stats`](#method.stats), this
+    /// count is kept even if the cache was built without `record_stats`.
+    ///
+    /// [delivery-mode-queued]: ../notification/enum.DeliveryMode.html#variant.Queued
+    /// [overflow-policy]: ../notification/enum.OverflowPolicy.html
+    pub fn dropped_notification_count(&self) -> u64 {
+        self.base.dropped_notification_count()
+    }
 }
 
 impl<K, V> Cache<K, V, RandomState>
@@ -805,7 +854,7 @@ where
     /// [builder-struct]: ./struct.CacheBuilder.html
     pub fn new(max_capacity: u64) -> Self {
         let build_hasher = RandomState::default();
-        let housekeeper_conf = housekeeper::Configuration::new_thread_pool(true);
+        let housekeeper_conf = housekeeper::Configuration::new_thread_pool(true, None);
         Self::with_everything(
             None,
             Some(max_capacity),
@@ -816,7 +865,11 @@ where
             None,
             None,
             None,
+            None,
             false,
+            None,
+            None,
+            None,
             housekeeper_conf,
         )
     }
@@ -848,7 +901,11 @@ where
         eviction_listener_conf: Option<notification::Configuration>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryArc<K, V>>,
         invalidator_enabled: bool,
+        stats_counter: Option<StatsCounterArc>,
+        refresher: Option<RefresherArc<K, V>>,
+        ticker: Option<TickerArc>,
         housekeeper_conf: housekeeper::Configuration,
     ) -> Self {
         Self {
@@ -858,14 +915,18 @@ where
                 initial_capacity,
                 build_hasher.clone(),
                 weigher,
-                eviction_listener,
+                eviction_listener.map(Listener::Sync),
                 eviction_listener_conf,
                 time_to_live,
                 time_to_idle,
+                expiry,
                 invalidator_enabled,
+                stats_counter,
+                ticker,
                 housekeeper_conf,
             ),
             value_initializer: Arc::new(ValueInitializer::with_hasher(build_hasher)),
+            refresher,
         }
     }
 
@@ -885,6 +946,14 @@ where
         self.base.contains_key_with_hash(key, self.base.hash(key))
     }
 
+    pub(crate) fn hash<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.base.hash(key)
+    }
+
     pub(crate) fn contains_key_with_hash<Q>(&self, key: &Q, hash: u64) -> bool
     where
         K: Borrow<Q>,
@@ -908,8 +977,7 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.base
-            .get_with_hash(key, self.base.hash(key), false)
+        self.get_with_hash(key, self.base.hash(key), false)
             .map(Entry::into_value)
     }
 
@@ -918,7 +986,40 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.base.get_with_hash(key, hash, need_key)
+        let entry = self.base.get_with_hash(key, hash, need_key);
+        if entry.is_some() {
+            self.refresh_if_stale(key, hash);
+        }
+        entry
+    }
+
+    /// Returns a `HashMap` of the keys and _clones_ of the values for the given
+    /// keys. The keys that do not exist in the cache are not included.
+    ///
+    /// Each key is looked up like [`get`](#method.get) method, so the lookups are
+    /// recorded as cache reads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert(1, "one");
+    /// cache.insert(2, "two");
+    ///
+    /// let values = cache.get_all(vec![1, 2, 3]);
+    /// assert_eq!(values.len(), 2);
+    /// assert_eq!(values.get(&1), Some(&"one"));
+    /// assert_eq!(values.get(&2), Some(&"two"));
+    /// ```
+    pub fn get_all<I>(&self, keys: I) -> HashMap<K, V>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        keys.into_iter()
+            .filter_map(|key| self.get(&key).map(|value| (key, value)))
+            .collect()
     }
 
     /// Takes a key `K` and returns an [`OwnedKeyEntrySelector`] that can be used to
@@ -1103,9 +1204,16 @@ where
         mut replace_if: Option<impl FnMut(&V) -> bool>,
         need_key: bool,
     ) -> Entry<K, V> {
-        self.base
+        match self
+            .base
             .get_with_hash_but_ignore_if(&key, hash, replace_if.as_mut(), need_key)
-            .unwrap_or_else(|| self.insert_with_hash_and_fun(key, hash, init, replace_if, need_key))
+        {
+            Some(entry) => {
+                self.refresh_if_stale(&key, hash);
+                entry
+            }
+            None => self.insert_with_hash_and_fun(key, hash, init, replace_if, need_key),
+        }
     }
 
     // Need to create new function instead of using the existing
@@ -1125,12 +1233,19 @@ where
         K: Borrow<Q>,
         Q: ToOwned<Owned = K> + Hash + Eq + ?Sized,
     {
-        self.base
+        match self
+            .base
             .get_with_hash_but_ignore_if(key, hash, replace_if.as_mut(), need_key)
-            .unwrap_or_else(|| {
+        {
+            Some(entry) => {
+                self.refresh_if_stale(key, hash);
+                entry
+            }
+            None => {
                 let key = Arc::new(key.to_owned());
                 self.insert_with_hash_and_fun(key, hash, init, replace_if, need_key)
-            })
+            }
+        }
     }
 
     pub(crate) fn insert_with_hash_and_fun(
@@ -1146,6 +1261,7 @@ where
                 .get_with_hash_but_no_recording(&key, hash, replace_if.as_mut())
         };
         let insert = |v| self.insert_with_hash(key.clone(), hash, v);
+        let init = || self.base.load_with_stats(init, |_| true);
 
         let k = if need_key {
             Some(Arc::clone(&key))
@@ -1166,17 +1282,91 @@ where
         }
     }
 
+    /// Evaluates the compute closure `f` with the current entry of the key, and
+    /// applies the returned `Op` to the cache. Returns the entry after the
+    /// operation, or `None` if the entry does not exist.
+    ///
+    /// The `Op` is applied only if the entry has not been modified since `f` read
+    /// it. Otherwise, `f` is evaluated again with the new entry.
+    pub(crate) fn compute_with_hash_and_fun(
+        &self,
+        key: Arc<K>,
+        hash: u64,
+        mut f: impl FnMut(Option<Entry<K, V>>) -> Op<V>,
+    ) -> Option<Entry<K, V>> {
+        loop {
+            let now = self.base.current_time_from_expiration_clock();
+            let current = self.base.get_value_entry(&key, hash);
+            let current_value = current
+                .as_ref()
+                .filter(|entry| self.base.is_valid_entry(&key, entry, now))
+                .map(|entry| entry.value.clone());
+            let entry = current_value
+                .clone()
+                .map(|v| Entry::new(Some(Arc::clone(&key)), v, false));
+
+            match f(entry) {
+                Op::Nop => return current_value.map(|v| Entry::new(Some(key), v, false)),
+                Op::Put(v) => {
+                    if let Some((op, now)) = self.base.do_compute_put_with_hash(
+                        Arc::clone(&key),
+                        hash,
+                        current.as_ref(),
+                        v.clone(),
+                    ) {
+                        let hk = self.base.housekeeper.as_ref();
+                        Self::schedule_write_op(
+                            self.base.inner.as_ref(),
+                            &self.base.write_op_ch,
+                            op,
+                            now,
+                            hk,
+                        )
+                        .expect("Failed to insert");
+                        return Some(Entry::new(Some(key), v, true));
+                    }
+                }
+                Op::Remove => {
+                    let expected = match (&current, &current_value) {
+                        (Some(expected), Some(_)) => expected,
+                        // The entry does not exist, or has expired.
+                        _ => return None,
+                    };
+                    let kv =
+                        self.remove_with_hash_and_fun(&*key, hash, RemovalCause::Explicit, || {
+                            self.base.remove_entry_if_same(&key, hash, expected)
+                        });
+                    if let Some(kv) = kv {
+                        let hk = self.base.housekeeper.as_ref();
+                        Self::schedule_write_op(
+                            self.base.inner.as_ref(),
+                            &self.base.write_op_ch,
+                            WriteOp::Remove(kv),
+                            now,
+                            hk,
+                        )
+                        .expect("Failed to remove");
+                        crossbeam_epoch::pin().flush();
+                        return None;
+                    }
+                }
+            }
+            // The entry has been modified by another write. Retry.
+        }
+    }
+
     pub(crate) fn get_or_insert_with_hash(
         &self,
         key: Arc<K>,
         hash: u64,
         init: impl FnOnce() -> V,
+        ttl: Option<Duration>,
     ) -> Entry<K, V> {
-        match self.base.get_with_hash(&key, hash, true) {
+        match self.get_with_hash(&key, hash, true) {
             Some(entry) => entry,
             None => {
                 let value = init();
-                self.insert_with_hash(Arc::clone(&key), hash, value.clone());
+                self.insert_with_hash_and_ttl(Arc::clone(&key), hash, value.clone(), ttl);
                 Entry::new(Some(key), value, true)
             }
         }
@@ -1187,17 +1377,18 @@ where
         key: &Q,
         hash: u64,
         init: impl FnOnce() -> V,
+        ttl: Option<Duration>,
     ) -> Entry<K, V>
     where
         K: Borrow<Q>,
         Q: ToOwned<Owned = K> + Hash + Eq + ?Sized,
     {
-        match self.base.get_with_hash(key, hash, true) {
+        match self.get_with_hash(key, hash, true) {
             Some(entry) => entry,
             None => {
                 let key = Arc::new(key.to_owned());
                 let value = init();
-                self.insert_with_hash(Arc::clone(&key), hash, value.clone());
+                self.insert_with_hash_and_ttl(Arc::clone(&key), hash, value.clone(), ttl);
                 Entry::new(Some(key), value, true)
             }
         }
@@ -1368,6 +1559,7 @@ where
                 .get_with_hash_but_no_recording(&key, hash, ignore_if)
         };
         let insert = |v| self.insert_with_hash(key.clone(), hash, v);
+        let init = || self.base.load_with_stats(init, Option::is_some);
 
         let k = if need_key {
             Some(Arc::clone(&key))
@@ -1504,6 +1696,130 @@ where
             .map(Entry::into_value)
     }
 
+    /// Returns a `HashMap` of the keys and _clones_ of the values for the given
+    /// keys. The values of the keys that do not exist in the cache are loaded in a
+    /// batch by calling the `init` closure once, and inserted to the cache.
+    ///
+    /// The `init` closure takes the missing keys and must return their values in
+    /// the same order.
+    ///
+    /// # Concurrent calls on the same key
+    ///
+    /// Like [`get_with`](#method.get_with), this method guarantees that concurrent
+    /// calls on the same missing key are coalesced into one evaluation. If some of
+    /// the missing keys are being loaded by other threads (by `get_with` or
+    /// `get_all_with`), they are not passed to the `init` closure, and this method
+    /// waits for and returns the values loaded by the other threads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert(1, "one".to_string());
+    ///
+    /// let values = cache.get_all_with(vec![1, 2, 3], |keys| {
+    ///     // Only the keys 2 and 3 are loaded, e.g. by a single database query.
+    ///     assert_eq!(keys.len(), 2);
+    ///     keys.iter().map(|k| format!("value of {}", k)).collect()
+    /// });
+    /// assert_eq!(values.len(), 3);
+    /// assert_eq!(values[&1], "one");
+    /// assert_eq!(values[&2], "value of 2");
+    /// assert!(cache.contains_key(&3));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method panics when the `init` closure has panicked, or it has returned
+    /// a `Vec` whose length differs from the number of the given keys. When it
+    /// happens, only the caller whose `init` closure failed will get the panic.
+    /// Other calls waiting for the keys will retry.
+    pub fn get_all_with<I, F>(&self, keys: I, mut init: F) -> HashMap<K, V>
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(&[&K]) -> Vec<V>,
+        K: Clone,
+    {
+        let (mut found, missing) = self.get_all_or_collect_missing(keys);
+        if missing.is_empty() {
+            return found;
+        }
+
+        let get = |key: &Arc<K>| self.get_with_arc_key_but_no_recording(key);
+        let insert =
+            |key: &Arc<K>, v| self.insert_with_hash(Arc::clone(key), self.base.hash(&**key), v);
+        let init = |keys: &[&K]| self.base.load_with_stats(|| init(keys), |_| true);
+
+        let entries = self
+            .value_initializer
+            .init_or_read_all(missing, get, init, insert);
+        crossbeam_epoch::pin().flush();
+        found.extend(entries.into_iter().map(|(k, v)| (K::clone(&k), v)));
+        found
+    }
+
+    pub(crate) fn try_get_all_with<I, F, E>(
+        &self,
+        keys: I,
+        mut init: F,
+    ) -> Result<HashMap<K, V>, Arc<E>>
+    where
+        I: IntoIterator<Item = K>,
+        F: FnMut(&[&K]) -> Result<Vec<V>, E>,
+        E: Send + Sync + 'static,
+        K: Clone,
+    {
+        let (mut found, missing) = self.get_all_or_collect_missing(keys);
+        if missing.is_empty() {
+            return Ok(found);
+        }
+
+        let get = |key: &Arc<K>| self.get_with_arc_key_but_no_recording(key);
+        let insert =
+            |key: &Arc<K>, v| self.insert_with_hash(Arc::clone(key), self.base.hash(&**key), v);
+        let init = |keys: &[&K]| self.base.load_with_stats(|| init(keys), Result::is_ok);
+
+        let result = self
+            .value_initializer
+            .try_init_or_read_all(missing, get, init, insert);
+        crossbeam_epoch::pin().flush();
+        found.extend(result?.into_iter().map(|(k, v)| (K::clone(&k), v)));
+        Ok(found)
+    }
+
+    /// Gets the values of the keys, and returns them with the keys that do not
+    /// exist in the cache. Duplicate keys are ignored.
+    fn get_all_or_collect_missing<I>(&self, keys: I) -> (HashMap<K, V>, Vec<Arc<K>>)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let mut found = HashMap::new();
+        let mut missing = HashSet::new();
+        for key in keys {
+            if found.contains_key(&key) || missing.contains(&key) {
+                continue;
+            }
+            let hash = self.base.hash(&key);
+            match self.get_with_hash(&key, hash, false) {
+                Some(entry) => {
+                    found.insert(key, entry.into_value());
+                }
+                None => {
+                    missing.insert(Arc::new(key));
+                }
+            }
+        }
+        (found, missing.into_iter().collect())
+    }
+
+    fn get_with_arc_key_but_no_recording(&self, key: &Arc<K>) -> Option<V> {
+        let ignore_if = None as Option<&mut fn(&V) -> bool>;
+        self.base
+            .get_with_hash_but_no_recording(key, self.base.hash(&**key), ignore_if)
+    }
+
     pub(crate) fn get_or_try_insert_with_hash_and_fun<F, E>(
         &self,
         key: Arc<K>,
@@ -1560,6 +1876,7 @@ where
                 .get_with_hash_but_no_recording(&key, hash, ignore_if)
         };
         let insert = |v| self.insert_with_hash(key.clone(), hash, v);
+        let init = || self.base.load_with_stats(init, Result::is_ok);
 
         let k = if need_key {
             Some(Arc::clone(&key))
@@ -1592,69 +1909,130 @@ where
         self.insert_with_hash(key, hash, value)
     }
 
-    pub(crate) fn insert_with_hash(&self, key: Arc<K>, hash: u64, value: V) {
-        let (op, now) = self.base.do_insert_with_hash(key, hash, value);
+    /// Inserts the key-value pairs into the cache.
+    ///
+    /// This is faster than calling [`insert`](#method.insert) for each pair, as
+    /// the pending writes are applied to the cache policy (admission and eviction)
+    /// once for the batch, or once whenever the internal write log is full, rather
+    /// than checked for each pair. Use it for example to warm up a cache.
+    ///
+    /// If the cache has a key present, the value is updated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(10_000);
+    /// cache.insert_all((0..5_000).map(|i| (i, i * 2)));
+    /// assert_eq!(cache.get(&100), Some(200));
+    /// ```
+    pub fn insert_all<I>(&self, entries: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let entries = entries.into_iter().map(|(key, value)| {
+            let hash = self.base.hash(&key);
+            (Arc::new(key), hash, value)
+        });
+        self.insert_all_with_hash(entries);
+    }
+
+    pub(crate) fn insert_all_with_hash(&self, entries: impl IntoIterator<Item = (Arc<K>, u64, V)>) {
+        let ops = entries.into_iter().map(|(key, hash, value)| {
+            let (op, _now) = self.base.do_insert_with_hash(key, hash, value, None);
+            op
+        });
         let hk = self.base.housekeeper.as_ref();
-        Self::schedule_write_op(
-            self.base.inner.as_ref(),
-            &self.base.write_op_ch,
-            op,
-            now,
-            hk,
-        )
-        .expect("Failed to insert");
+        Self::schedule_write_ops(self.base.inner.as_ref(), &self.base.write_op_ch, ops, hk)
+            .expect("Failed to insert");
     }
 
-    /// Discards any cached value for the key.
+    /// Inserts a key-value pair into the cache with its own time-to-live.
     ///
-    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
-    /// on the borrowed form _must_ match those for the key type.
-    pub fn invalidate<Q>(&self, key: &Q)
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.base.hash(key);
-        self.invalidate_with_hash(key, hash);
+    /// The entry will expire after the given `ttl` from now, regardless of the
+    /// `time_to_live` and `expire_after` settings of the cache. If the cache has
+    /// this key present, the value and its expiration time are updated. A later
+    /// `insert` without a TTL puts the entry back under the expiration policies of
+    /// the cache.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    /// use std::time::Duration;
+    ///
+    /// let cache = Cache::builder()
+    ///     .time_to_live(Duration::from_secs(60 * 60))
+    ///     .build();
+    ///
+    /// // This entry will expire after 5 minutes instead of an hour.
+    /// cache.insert_with_ttl("session", "token", Duration::from_secs(5 * 60));
+    /// assert_eq!(cache.get(&"session"), Some("token"));
+    /// ```
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        let hash = self.base.hash(&key);
+        let key = Arc::new(key);
+        self.insert_with_hash_and_ttl(key, hash, value, Some(ttl))
     }
 
-    pub(crate) fn invalidate_with_hash<Q>(&self, key: &Q, hash: u64)
+    /// Starts reloading the value of the key in the background if the entry was
+    /// written `refresh_after_write` or more ago, and no other reload for the key
+    /// is running.
+    fn refresh_if_stale<Q>(&self, key: &Q, hash: u64)
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        // Lock the key for removal if blocking removal notification is enabled.
-        let mut kl = None;
-        let mut klg = None;
-        if self.base.is_removal_notifier_enabled() && self.base.is_blocking_removal_notification() {
-            // To lock the key, we have to get Arc<K> for key (&Q).
-            //
-            // TODO: Enhance this if possible. This is rather hack now because
-            // it cannot prevent race conditions like this:
-            //
-            // 1. We miss the key because it does not exist. So we do not lock
-            //    the key.
-            // 2. Somebody else (other thread) inserts the key.
-            // 3. We remove the entry for the key, but without the key lock!
-            //
-            if let Some(arc_key) = self.base.get_key_with_hash(key, hash) {
-                kl = self.base.maybe_key_lock(&arc_key);
-                klg = kl.as_ref().map(|kl| kl.lock());
-            }
+        let refresher = match &self.refresher {
+            Some(refresher) => refresher,
+            None => return,
+        };
+        let duration = refresher.refresh_after_write();
+        let kv = match self.base.stale_entry_with_hash(key, hash, duration) {
+            Some(kv) => kv,
+            None => return,
+        };
+        if !self.value_initializer.try_start_refresh(&kv.key) {
+            // Already being reloaded.
+            return;
         }
 
-        if let Some(kv) = self.base.remove_entry(key, hash) {
-            if self.base.is_removal_notifier_enabled() {
-                self.base.notify_invalidate(&kv.key, &kv.entry)
+        let cache = self.clone();
+        refresher.execute(move || cache.reload(kv, hash));
+    }
+
+    fn reload(&self, kv: KvEntry<K, V>, hash: u64) {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let key = &kv.key;
+        if let Some(refresher) = &self.refresher {
+            let reloader = refresher.reloader();
+            let reload = || reloader(key, &kv.entry.value);
+            // Safety: It is safe to assert unwind safety here because the reloader
+            // will be called again only for a next reload.
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                self.base.load_with_stats(reload, Option::is_some)
+            }));
+            if let Ok(Some(value)) = result {
+                self.put_reloaded_value(&kv, hash, value);
             }
-            // Drop the locks before scheduling write op to avoid a potential dead lock.
-            // (Scheduling write can do spin lock when the queue is full, and queue will
-            // be drained by the housekeeping thread that can lock the same key)
-            std::mem::drop(klg);
-            std::mem::drop(kl);
+        }
+        self.value_initializer.finish_refresh(key);
+    }
 
-            let op = WriteOp::Remove(kv);
-            let now = self.base.current_time_from_expiration_clock();
+    /// Replaces the value of the reloaded entry only if the entry has neither been
+    /// modified nor removed during the reload, and it has not expired.
+    fn put_reloaded_value(&self, kv: &KvEntry<K, V>, hash: u64, value: V) {
+        let now = self.base.current_time_from_expiration_clock();
+        if !self.base.is_valid_entry(&kv.key, &kv.entry, now) {
+            return;
+        }
+        let key = Arc::clone(&kv.key);
+        if let Some((op, now)) =
+            self.base
+                .do_compute_put_with_hash(key, hash, Some(&kv.entry), value)
+        {
             let hk = self.base.housekeeper.as_ref();
             Self::schedule_write_op(
                 self.base.inner.as_ref(),
@@ -1663,47 +2041,513 @@ where
                 now,
                 hk,
             )
-            .expect("Failed to remove");
-            crossbeam_epoch::pin().flush();
+            .expect("Failed to insert");
         }
     }
 
-    /// Discards all cached values.
+    pub(crate) fn insert_with_hash(&self, key: Arc<K>, hash: u64, value: V) {
+        self.insert_with_hash_and_ttl(key, hash, value, None)
+    }
+
+    pub(crate) fn insert_with_hash_and_ttl(
+        &self,
+        key: Arc<K>,
+        hash: u64,
+        value: V,
+        ttl: Option<Duration>,
+    ) {
+        let (op, now) = self.base.do_insert_with_hash(key, hash, value, ttl);
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            op,
+            now,
+            hk,
+        )
+        .expect("Failed to insert");
+    }
+
+    /// Inserts a key-value pair into the cache only if the key is absent. Returns
+    /// `None` if the value was inserted, or `Some` with a clone of the existing
+    /// value if the key was present. The check and the insertion are done
+    /// atomically.
     ///
-    /// This method returns immediately and a background thread will evict all the
-    /// cached values inserted before the time when this method was called. It is
-    /// guaranteed that the `get` method must not return these invalidated values
-    /// even if they have not been evicted.
+    /// # Example
     ///
-    /// Like the `invalidate` method, this method does not clear the historic
-    /// popularity estimator of keys so that it retains the client activities of
-    /// trying to retrieve an item.
-    pub fn invalidate_all(&self) {
-        self.base.invalidate_all();
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// assert_eq!(cache.insert_if_absent("session1", "alice"), None);
+    /// assert_eq!(cache.insert_if_absent("session1", "bob"), Some("alice"));
+    /// assert_eq!(cache.get(&"session1"), Some("alice"));
+    /// ```
+    pub fn insert_if_absent(&self, key: K, value: V) -> Option<V> {
+        let hash = self.base.hash(&key);
+        self.insert_if_absent_with_hash(Arc::new(key), hash, value)
     }
 
-    /// Discards cached values that satisfy a predicate.
+    pub(crate) fn insert_if_absent_with_hash(&self, key: Arc<K>, hash: u64, value: V) -> Option<V> {
+        let (op, now) = match self.base.do_insert_if_absent_with_hash(key, hash, value) {
+            Ok(op_and_now) => op_and_now,
+            Err(existing) => return Some(existing),
+        };
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            op,
+            now,
+            hk,
+        )
+        .expect("Failed to insert");
+        None
+    }
+
+    /// Replaces the value of the key with `new` only if the current value is equal
+    /// to `old`. Returns `true` if the value was replaced. The comparison and the
+    /// replacement are done atomically.
     ///
-    /// `invalidate_entries_if` takes a closure that returns `true` or `false`. This
-    /// method returns immediately and a background thread will apply the closure to
-    /// each cached value inserted before the time when `invalidate_entries_if` was
-    /// called. If the closure returns `true` on a value, that value will be evicted
-    /// from the cache.
+    /// If the eviction listener is set, it will be notified of the old value with
+    /// [`RemovalCause::Replaced`][removal-cause-replaced].
     ///
-    /// Also the `get` method will apply the closure to a value to determine if it
-    /// should have been invalidated. Therefore, it is guaranteed that the `get`
-    /// method must not return invalidated values.
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
     ///
-    /// Note that you must call
-    /// [`CacheBuilder::support_invalidation_closures`][support-invalidation-closures]
-    /// at the cache creation time as the cache needs to maintain additional internal
-    /// data structures to support this method. Otherwise, calling this method will
-    /// fail with a
-    /// [`PredicateError::InvalidationClosuresDisabled`][invalidation-disabled-error].
+    /// [removal-cause-replaced]: ../notification/enum.RemovalCause.html#variant.Replaced
     ///
-    /// Like the `invalidate` method, this method does not clear the historic
-    /// popularity estimator of keys so that it retains the client activities of
-    /// trying to retrieve an item.
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert("session1", 1);
+    ///
+    /// assert!(!cache.replace(&"session1", &0, 2));
+    /// assert_eq!(cache.get(&"session1"), Some(1));
+    ///
+    /// assert!(cache.replace(&"session1", &1, 2));
+    /// assert_eq!(cache.get(&"session1"), Some(2));
+    ///
+    /// // Does not insert the value if the key is absent.
+    /// assert!(!cache.replace(&"session2", &1, 2));
+    /// assert!(!cache.contains_key(&"session2"));
+    /// ```
+    pub fn replace<Q>(&self, key: &Q, old: &V, new: V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: PartialEq,
+    {
+        let hash = self.base.hash(key);
+        self.replace_with_hash(key, hash, old, new)
+    }
+
+    pub(crate) fn replace_with_hash<Q>(&self, key: &Q, hash: u64, old: &V, new: V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: PartialEq,
+    {
+        let (op, now) = match self.base.do_replace_with_hash(key, hash, old, new) {
+            Some(op_and_now) => op_and_now,
+            None => return false,
+        };
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            op,
+            now,
+            hk,
+        )
+        .expect("Failed to insert");
+        true
+    }
+
+    /// Discards any cached value for the key.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub fn invalidate<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.base.hash(key);
+        self.invalidate_with_hash(key, hash);
+    }
+
+    pub(crate) fn invalidate_with_hash<Q>(&self, key: &Q, hash: u64)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.do_invalidate_with_hash(key, hash, false);
+    }
+
+    /// Discards any cached value for the key and returns a _clone_ of the value.
+    ///
+    /// Returns `None` if the key does not exist, or the entry has been expired or
+    /// invalidated (the entry will still be removed from the cache).
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert("upload1", vec![1u8, 2, 3]);
+    ///
+    /// assert_eq!(cache.remove(&"upload1"), Some(vec![1, 2, 3]));
+    /// assert_eq!(cache.remove(&"upload1"), None);
+    /// ```
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_k, v)| v)
+    }
+
+    /// Works like [`remove`](#method.remove), but returns the key along with the
+    /// value.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub fn remove_entry<Q>(&self, key: &Q) -> Option<(Arc<K>, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.base.hash(key);
+        self.remove_entry_with_hash(key, hash)
+    }
+
+    pub(crate) fn remove_entry_with_hash<Q>(&self, key: &Q, hash: u64) -> Option<(Arc<K>, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.do_invalidate_with_hash(key, hash, true)
+    }
+
+    /// Removes the entry of the key. If `need_value` is `true`, returns the key and
+    /// a clone of the value if the entry was neither expired nor invalidated.
+    fn do_invalidate_with_hash<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        need_value: bool,
+    ) -> Option<(Arc<K>, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let kv = self.remove_with_hash_and_fun(key, hash, RemovalCause::Explicit, || {
+            self.base.remove_entry(key, hash)
+        })?;
+        let now = self.base.current_time_from_expiration_clock();
+        let maybe_entry = if need_value && self.base.is_valid_entry(&kv.key, &kv.entry, now) {
+            Some((Arc::clone(&kv.key), kv.entry.value.clone()))
+        } else {
+            None
+        };
+
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            WriteOp::Remove(kv),
+            now,
+            hk,
+        )
+        .expect("Failed to remove");
+        crossbeam_epoch::pin().flush();
+        maybe_entry
+    }
+
+    /// Discards the cached value for the key only if the `predicate` returns `true`
+    /// for the value. Returns the removed value, or `None` if the key was absent or
+    /// the `predicate` returned `false`. The check and the removal are done
+    /// atomically.
+    ///
+    /// If the eviction listener is set, it will be notified of the removed value
+    /// with [`RemovalCause::Explicit`][removal-cause-explicit].
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    ///
+    /// [removal-cause-explicit]: ../notification/enum.RemovalCause.html#variant.Explicit
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert("session1", 1);
+    ///
+    /// assert_eq!(cache.remove_if(&"session1", |v| *v > 1), None);
+    /// assert!(cache.contains_key(&"session1"));
+    ///
+    /// assert_eq!(cache.remove_if(&"session1", |v| *v == 1), Some(1));
+    /// assert!(!cache.contains_key(&"session1"));
+    /// ```
+    pub fn remove_if<Q, F>(&self, key: &Q, predicate: F) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnMut(&V) -> bool,
+    {
+        let hash = self.base.hash(key);
+        self.remove_if_with_hash(key, hash, predicate)
+    }
+
+    pub(crate) fn remove_if_with_hash<Q, F>(&self, key: &Q, hash: u64, predicate: F) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnMut(&V) -> bool,
+    {
+        let kv = self.remove_with_hash_and_fun(key, hash, RemovalCause::Explicit, || {
+            self.base.remove_valid_entry_if(key, hash, predicate)
+        })?;
+        let value = kv.entry.value.clone();
+        let now = self.base.current_time_from_expiration_clock();
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_op(
+            self.base.inner.as_ref(),
+            &self.base.write_op_ch,
+            WriteOp::Remove(kv),
+            now,
+            hk,
+        )
+        .expect("Failed to remove");
+        crossbeam_epoch::pin().flush();
+        Some(value)
+    }
+
+    /// Discards any cached values for the keys.
+    ///
+    /// Like [`insert_all`](#method.insert_all), this is faster than calling
+    /// [`invalidate`](#method.invalidate) for each key, as the removals are
+    /// applied to the cache policy in bulk.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert_all(vec![("a", 1), ("b", 2), ("c", 3)]);
+    ///
+    /// cache.invalidate_all_keys(&["a", "b"]);
+    /// assert_eq!(cache.get(&"a"), None);
+    /// assert_eq!(cache.get(&"b"), None);
+    /// assert_eq!(cache.get(&"c"), Some(3));
+    /// ```
+    pub fn invalidate_all_keys<'a, I, Q>(&self, keys: I)
+    where
+        I: IntoIterator<Item = &'a Q>,
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+    {
+        let keys = keys.into_iter().map(|key| (key, self.base.hash(key)));
+        self.invalidate_all_keys_with_hash(keys);
+    }
+
+    pub(crate) fn invalidate_all_keys_with_hash<'a, Q>(
+        &self,
+        keys: impl IntoIterator<Item = (&'a Q, u64)>,
+    ) where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+    {
+        self.invalidate_keys_with_hash_and_cause(keys, RemovalCause::Explicit);
+    }
+
+    fn invalidate_keys_with_hash_and_cause<'a, Q>(
+        &self,
+        keys: impl IntoIterator<Item = (&'a Q, u64)>,
+        cause: RemovalCause,
+    ) where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+    {
+        let ops = keys
+            .into_iter()
+            .filter_map(|(key, hash)| self.remove_with_hash_for_invalidation(key, hash, cause));
+        let hk = self.base.housekeeper.as_ref();
+        Self::schedule_write_ops(self.base.inner.as_ref(), &self.base.write_op_ch, ops, hk)
+            .expect("Failed to remove");
+        crossbeam_epoch::pin().flush();
+    }
+
+    /// Removes the entry of the key from the concurrent hash table and sends a
+    /// removal notification. Returns the write op to be applied to the cache
+    /// policy, or `None` if the key does not exist.
+    fn remove_with_hash_for_invalidation<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        cause: RemovalCause,
+    ) -> Option<WriteOp<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_with_hash_and_fun(key, hash, cause, || self.base.remove_entry(key, hash))
+            .map(WriteOp::Remove)
+    }
+
+    /// Removes the entry of the key by calling the `remove` closure, and sends a
+    /// removal notification if the entry was removed.
+    fn remove_with_hash_and_fun<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        cause: RemovalCause,
+        remove: impl FnOnce() -> Option<KvEntry<K, V>>,
+    ) -> Option<KvEntry<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // Lock the key for removal if blocking removal notification is enabled.
+        let mut kl = None;
+        let mut klg = None;
+        if self.base.is_removal_notifier_enabled() && self.base.is_blocking_removal_notification() {
+            // To lock the key, we have to get Arc<K> for key (&Q).
+            //
+            // TODO: Enhance this if possible. This is rather hack now because
+            // it cannot prevent race conditions like this:
+            //
+            // 1. We miss the key because it does not exist. So we do not lock
+            //    the key.
+            // 2. Somebody else (other thread) inserts the key.
+            // 3. We remove the entry for the key, but without the key lock!
+            //
+            if let Some(arc_key) = self.base.get_key_with_hash(key, hash) {
+                kl = self.base.maybe_key_lock(&arc_key);
+                klg = kl.as_ref().map(|kl| kl.lock());
+            }
+        }
+
+        if let Some(kv) = remove() {
+            if self.base.is_removal_notifier_enabled() {
+                self.base.notify_invalidate(&kv.key, &kv.entry, cause)
+            }
+            // Drop the locks before scheduling write op to avoid a potential dead lock.
+            // (Scheduling write can do spin lock when the queue is full, and queue will
+            // be drained by the housekeeping thread that can lock the same key)
+            std::mem::drop(klg);
+            std::mem::drop(kl);
+
+            Some(kv)
+        } else {
+            None
+        }
+    }
+
+    /// Discards all cached values.
+    ///
+    /// This method returns immediately and a background thread will evict all the
+    /// cached values inserted before the time when this method was called. It is
+    /// guaranteed that the `get` method must not return these invalidated values
+    /// even if they have not been evicted.
+    ///
+    /// Like the `invalidate` method, this method does not clear the historic
+    /// popularity estimator of keys so that it retains the client activities of
+    /// trying to retrieve an item.
+    pub fn invalidate_all(&self) {
+        self.base.invalidate_all();
+    }
+
+    /// Discards all cached values, and blocks until all eviction notifications,
+    /// including the ones queued before this call, have been delivered to the
+    /// eviction listener.
+    ///
+    /// Dropping a cache drops its entries without notifying the eviction listener.
+    /// Call this method before dropping the cache if the listener should be
+    /// notified of every entry. The entries are notified with
+    /// [`RemovalCause::Shutdown`][removal-cause-shutdown], or `Expired` if they
+    /// have already expired.
+    ///
+    /// Unlike `invalidate_all`, this method does the removals in the calling
+    /// thread. The cache can still be used after calling this method.
+    ///
+    /// If the eviction listener panics, the notifications not delivered by then are
+    /// discarded.
+    ///
+    /// [removal-cause-shutdown]: ../notification/enum.RemovalCause.html#variant.Shutdown
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::{notification::RemovalCause, sync::Cache};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let evicted = Arc::new(Mutex::new(Vec::new()));
+    /// let evicted1 = Arc::clone(&evicted);
+    ///
+    /// let cache = Cache::builder()
+    ///     .max_capacity(100)
+    ///     .eviction_listener(move |k, _v, cause| {
+    ///         evicted1.lock().unwrap().push((k, cause));
+    ///     })
+    ///     .build();
+    ///
+    /// cache.insert("alice", 1);
+    /// cache.shutdown();
+    ///
+    /// assert_eq!(
+    ///     *evicted.lock().unwrap(),
+    ///     vec![(Arc::new("alice"), RemovalCause::Shutdown)]
+    /// );
+    /// assert_eq!(cache.get(&"alice"), None);
+    /// ```
+    pub fn shutdown(&self) {
+        let keys = self.base.all_keys();
+        let keys = keys.iter().map(|k| (&**k, self.base.hash(k)));
+        self.invalidate_keys_with_hash_and_cause(keys, RemovalCause::Shutdown);
+        self.base.apply_pending_ops();
+
+        while self.base.has_pending_notifications() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Discards cached values that satisfy a predicate.
+    ///
+    /// `invalidate_entries_if` takes a closure that returns `true` or `false`. This
+    /// method returns immediately and a background thread will apply the closure to
+    /// each cached value inserted before the time when `invalidate_entries_if` was
+    /// called. If the closure returns `true` on a value, that value will be evicted
+    /// from the cache.
+    ///
+    /// Also the `get` method will apply the closure to a value to determine if it
+    /// should have been invalidated. Therefore, it is guaranteed that the `get`
+    /// method must not return invalidated values.
+    ///
+    /// Note that you must call
+    /// [`CacheBuilder::support_invalidation_closures`][support-invalidation-closures]
+    /// at the cache creation time as the cache needs to maintain additional internal
+    /// data structures to support this method. Otherwise, calling this method will
+    /// fail with a
+    /// [`PredicateError::InvalidationClosuresDisabled`][invalidation-disabled-error].
+    ///
+    /// Like the `invalidate` method, this method does not clear the historic
+    /// popularity estimator of keys so that it retains the client activities of
+    /// trying to retrieve an item.
     ///
     /// [support-invalidation-closures]: ./struct.CacheBuilder.html#method.support_invalidation_closures
     /// [invalidation-disabled-error]: ../enum.PredicateError.html#variant.InvalidationClosuresDisabled
@@ -1776,6 +2620,98 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<K, V, S> Cache<K, V, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    /// Writes a snapshot of this cache to the `writer`, so that the cache can be
+    /// restored later by [`CacheBuilder::restore_from`][restore-from], for example
+    /// after restarting the process.
+    ///
+    /// The snapshot contains the keys and values of the entries, their remaining
+    /// time-to-live and time-to-idle, and their per-entry expiration times. It also
+    /// contains the access order of the entries and their historic popularity, so
+    /// that the restored cache will admit and evict entries as this cache would do.
+    ///
+    /// The pending reads and writes are applied to the cache before taking the
+    /// snapshot. Expired and invalidated entries are not included. Entries
+    /// inserted, updated or removed while taking the snapshot may or may not be
+    /// reflected.
+    ///
+    /// The snapshot is written in a binary format specific to this crate. Many
+    /// small writes will be made, so consider wrapping the `writer` with a
+    /// [`BufWriter`][bufwriter].
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`][snapshot-error] if it fails to serialize a key or
+    /// a value, or to write to the `writer`.
+    ///
+    /// [restore-from]: ./struct.CacheBuilder.html#method.restore_from
+    /// [bufwriter]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+    /// [snapshot-error]: ../enum.SnapshotError.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Cargo.toml
+    /// //
+    /// // [dependencies]
+    /// // moka = { version = "0.9", features = ["serde"] }
+    ///
+    /// use moka::sync::Cache;
+    ///
+    /// let cache = Cache::new(100);
+    /// cache.insert("alice".to_string(), 1);
+    ///
+    /// let mut snapshot = Vec::new();
+    /// cache.snapshot_to(&mut snapshot).unwrap();
+    ///
+    /// let restored: Cache<String, u32> = Cache::builder()
+    ///     .max_capacity(100)
+    ///     .restore_from(&snapshot[..])
+    ///     .unwrap();
+    /// assert_eq!(restored.get("alice"), Some(1));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn snapshot_to<W>(&self, writer: W) -> Result<(), SnapshotError>
+    where
+        K: Serialize,
+        V: Serialize,
+        W: Write,
+    {
+        let snapshot = self.snapshot();
+        snapshot::write_snapshot(writer, &[snapshot.as_ref()])
+    }
+
+    pub(crate) fn snapshot(&self) -> CacheSnapshot<Arc<K>, V> {
+        self.base.snapshot()
+    }
+
+    /// Restores the entries from a snapshot written by `Cache::snapshot_to` or
+    /// `SegmentedCache::snapshot_to`.
+    pub(crate) fn restore_snapshot<R>(&self, reader: R) -> Result<(), SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        R: Read,
+    {
+        for snapshot in snapshot::read_snapshot(reader)? {
+            self.restore(snapshot);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn restore(&self, snapshot: CacheSnapshot<K, V>) {
+        self.base.restore(snapshot);
+    }
+}
+
 impl<'a, K, V, S> IntoIterator for &'a Cache<K, V, S>
 where
     K: Hash + Eq + Send + Sync + 'static,
@@ -1798,7 +2734,7 @@ where
     S: BuildHasher + Clone + Send + Sync + 'static,
 {
     fn sync(&self) {
-        self.base.inner.sync(MAX_SYNC_REPEATS);
+        self.base.run_pending_tasks();
     }
 }
 
@@ -1855,7 +2791,44 @@ where
                     op = op1;
                     std::thread::sleep(Duration::from_micros(WRITE_RETRY_INTERVAL_MICROS));
                 }
-                Err(e @ TrySendError::Disconnected(_)) => return Err(e),
+                Err(e @ TrySendError::Disconnected(_)) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the write ops to the channel in bulk. Unlike `schedule_write_op`, this
+    /// does not check if the pending ops should be applied before sending each op.
+    /// Instead, it applies the pending ops only when the channel is full, and once
+    /// after all the ops are sent.
+    fn schedule_write_ops(
+        inner: &impl InnerSync,
+        ch: &Sender<WriteOp<K, V>>,
+        ops: impl IntoIterator<Item = WriteOp<K, V>>,
+        housekeeper: Option<&HouseKeeperArc<K, V, S>>,
+    ) -> Result<(), TrySendError<WriteOp<K, V>>> {
+        let mut is_sent = false;
+
+        for mut op in ops {
+            loop {
+                match ch.try_send(op) {
+                    Ok(()) => break,
+                    Err(TrySendError::Full(op1)) => {
+                        op = op1;
+                        if let Some(hk) = housekeeper {
+                            hk.try_sync(inner);
+                        }
+                        std::thread::sleep(Duration::from_micros(WRITE_RETRY_INTERVAL_MICROS));
+                    }
+                    Err(e @ TrySendError::Disconnected(_)) => return Err(e),
+                }
+            }
+            is_sent = true;
+        }
+
+        if is_sent {
+            if let Some(hk) = housekeeper {
+                hk.try_sync(inner);
             }
         }
         Ok(())
@@ -1893,11 +2866,13 @@ mod tests {
     use super::{Cache, ConcurrentCacheExt};
     use crate::{
         common::time::Clock,
+        executor::{Executor, Task},
         notification::{
             self,
             macros::{assert_eq_with_mode, assert_with_mode},
-            DeliveryMode, RemovalCause,
+            DeliveryMode, PredicateHandle, RemovalCause,
         },
+        Expiry,
     };
 
     use parking_lot::Mutex;
@@ -1989,6 +2964,209 @@ mod tests {
         }
     }
 
+    #[test]
+    fn admission_window() {
+        // A cache with max capacity 100 has an admission window of size 1.
+        let mut cache = Cache::new(100);
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        for i in 0..100 {
+            cache.insert(i, i);
+        }
+        cache.sync();
+        assert_eq!(cache.entry_count(), 100);
+
+        for i in 0..100 {
+            assert_eq!(cache.get(&i), Some(i));
+            assert_eq!(cache.get(&i), Some(i));
+        }
+        cache.sync();
+        // counts: 0..100 -> 2
+
+        // 100 should be added to the window even though the cache is full and its
+        // frequency is lower than others. 99 was at the window, so it should be
+        // evicted because its frequency is not higher than the main space's.
+        cache.insert(100, 100);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 100);
+        assert!(cache.contains_key(&100));
+        assert!(!cache.contains_key(&99));
+
+        assert_eq!(cache.get(&100), Some(100));
+        assert_eq!(cache.get(&100), Some(100));
+        assert_eq!(cache.get(&100), Some(100));
+        cache.sync();
+        // counts: 100 -> 3
+
+        // 100 should be moved from the window to the main space because its
+        // frequency is higher than the main space's.
+        cache.insert(101, 101);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 100);
+        assert!(cache.contains_key(&100));
+        assert!(cache.contains_key(&101));
+    }
+
+    #[test]
+    fn adaptive_window() {
+        let mut cache = Cache::new(100);
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        let policy = cache.policy();
+        assert_eq!(policy.window_capacity(), Some(1));
+        assert_eq!(policy.main_capacity(), Some(99));
+        assert_eq!(policy.protected_capacity(), Some(79));
+
+        for i in 0..100 {
+            cache.insert(i, i);
+        }
+        cache.sync();
+
+        // The sample size of the hill climber is 1,280 for this cache. Complete a
+        // sample with a high hit rate. The climber tries to shrink the window first,
+        // but the window cannot be smaller than 1.
+        for i in 0..1_300 {
+            assert_eq!(cache.get(&(i % 100)), Some(i % 100));
+            if i % 100 == 99 {
+                cache.sync();
+            }
+        }
+        assert_eq!(cache.policy().window_capacity(), Some(1));
+
+        // Complete a sample with a low hit rate. The climber turns the direction
+        // and grows the window by 6 (6.25% of the max capacity).
+        for i in 0..1_300 {
+            assert_eq!(cache.get(&(i + 1_000)), None);
+            if i % 100 == 99 {
+                cache.sync();
+            }
+        }
+        let policy = cache.policy();
+        assert_eq!(policy.window_capacity(), Some(7));
+        assert_eq!(policy.main_capacity(), Some(93));
+        assert_eq!(policy.protected_capacity(), Some(73));
+    }
+
+    #[test]
+    fn stats() {
+        let mut cache = Cache::builder().max_capacity(3).record_stats().build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice");
+        cache.insert("b", "bob");
+        cache.sync();
+
+        assert_eq!(cache.get(&"a"), Some("alice")); // hit
+        assert_eq!(cache.get(&"c"), None); // miss
+        assert_eq!(cache.get_with("c", || "cindy"), "cindy"); // miss, load success
+        assert_eq!(cache.get_with("c", || unreachable!()), "cindy"); // hit
+        assert!(cache.try_get_with("d", || Err("error")).is_err()); // miss, load failure
+        assert_eq!(cache.optionally_get_with("d", || None), None); // miss, load failure
+        cache.sync();
+
+        let stats = cache.stats();
+        assert_eq!(stats.hit_count(), 2);
+        assert_eq!(stats.miss_count(), 4);
+        assert_eq!(stats.load_success_count(), 1);
+        assert_eq!(stats.load_failure_count(), 2);
+        assert_eq!(stats.eviction_count(), 0);
+
+        // The cache is full. Either "d" or one of the existing entries will be
+        // evicted.
+        cache.insert("d", "david");
+        cache.sync();
+
+        let stats = cache.stats();
+        assert_eq!(stats.eviction_count(), 1);
+        assert_eq!(stats.eviction_weight(), 1);
+
+        // The statistics are not recorded by default.
+        let cache = Cache::new(3);
+        cache.insert("a", "alice");
+        assert_eq!(cache.get(&"a"), Some("alice"));
+        assert_eq!(cache.stats(), Default::default());
+    }
+
+    #[test]
+    fn custom_stats_counter() {
+        use crate::stats::StatsCounter;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        #[derive(Default)]
+        struct MyStatsCounter {
+            hits: AtomicU32,
+            misses: AtomicU32,
+            evictions: Mutex<Vec<(u64, RemovalCause)>>,
+        }
+
+        // Do not override the `snapshot` method.
+        impl StatsCounter for MyStatsCounter {
+            fn record_hits(&self, count: u32) {
+                self.hits.fetch_add(count, Ordering::Relaxed);
+            }
+
+            fn record_misses(&self, count: u32) {
+                self.misses.fetch_add(count, Ordering::Relaxed);
+            }
+
+            fn record_load_success(&self, _load_time: Duration) {}
+
+            fn record_load_failure(&self, _load_time: Duration) {}
+
+            fn record_eviction(&self, weight: u64, cause: RemovalCause) {
+                self.evictions.lock().push((weight, cause));
+            }
+        }
+
+        let counter = Arc::new(MyStatsCounter::default());
+
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .stats_counter(Arc::clone(&counter))
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice");
+        cache.insert("b", "bob");
+        cache.insert("c", "cindy");
+        cache.sync();
+
+        assert_eq!(cache.get(&"a"), Some("alice"));
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(counter.hits.load(Ordering::Relaxed), 1);
+        assert_eq!(counter.misses.load(Ordering::Relaxed), 1);
+
+        // Invalidated entries are not evictions.
+        cache.invalidate(&"c");
+
+        mock.increment(Duration::from_secs(11));
+        cache.sync();
+
+        assert_eq!(
+            *counter.evictions.lock(),
+            vec![(1, RemovalCause::Expired), (1, RemovalCause::Expired)]
+        );
+
+        // `stats` returns the default snapshot of the counter.
+        assert_eq!(cache.stats(), Default::default());
+    }
+
     #[test]
     fn size_aware_eviction() {
         run_test(DeliveryMode::Immediate);
@@ -2089,61 +3267,330 @@ mod tests {
             assert_with_mode!(!cache.contains_key(&"c"), delivery_mode);
             assert_with_mode!(cache.contains_key(&"d"), delivery_mode);
 
-            // Update "b" with "bill" (w: 15 -> 20). This should evict "d" (w: 15).
-            cache.insert("b", bill);
-            expected.push((Arc::new("b"), bob, RemovalCause::Replaced));
-            expected.push((Arc::new("d"), dennis, RemovalCause::Size));
-            cache.sync();
-            assert_eq_with_mode!(cache.get(&"b"), Some(bill), delivery_mode);
-            assert_eq_with_mode!(cache.get(&"d"), None, delivery_mode);
-            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
-            assert_with_mode!(!cache.contains_key(&"d"), delivery_mode);
+            // Update "b" with "bill" (w: 15 -> 20). This should evict "d" (w: 15).
+            cache.insert("b", bill);
+            expected.push((Arc::new("b"), bob, RemovalCause::Replaced));
+            expected.push((Arc::new("d"), dennis, RemovalCause::Size));
+            cache.sync();
+            assert_eq_with_mode!(cache.get(&"b"), Some(bill), delivery_mode);
+            assert_eq_with_mode!(cache.get(&"d"), None, delivery_mode);
+            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"d"), delivery_mode);
+
+            // Re-add "a" (w: 10) and update "b" with "bob" (w: 20 -> 15).
+            cache.insert("a", alice);
+            cache.insert("b", bob);
+            expected.push((Arc::new("b"), bill, RemovalCause::Replaced));
+            cache.sync();
+            assert_eq_with_mode!(cache.get(&"a"), Some(alice), delivery_mode);
+            assert_eq_with_mode!(cache.get(&"b"), Some(bob), delivery_mode);
+            assert_eq_with_mode!(cache.get(&"d"), None, delivery_mode);
+            assert_with_mode!(cache.contains_key(&"a"), delivery_mode);
+            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"d"), delivery_mode);
+
+            // Verify the sizes.
+            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
+            assert_eq_with_mode!(cache.weighted_size(), 25, delivery_mode);
+
+            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        }
+    }
+
+    #[test]
+    fn basic_multi_threads() {
+        let num_threads = 4;
+        let cache = Cache::new(100);
+
+        // https://rust-lang.github.io/rust-clippy/master/index.html#needless_collect
+        #[allow(clippy::needless_collect)]
+        let handles = (0..num_threads)
+            .map(|id| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    cache.insert(10, format!("{}-100", id));
+                    cache.get(&10);
+                    cache.insert(20, format!("{}-200", id));
+                    cache.invalidate(&10);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter().for_each(|h| h.join().expect("Failed"));
+
+        assert!(cache.get(&10).is_none());
+        assert!(cache.get(&20).is_some());
+        assert!(!cache.contains_key(&10));
+        assert!(cache.contains_key(&20));
+    }
+
+    #[test]
+    fn shutdown() {
+        run_test(DeliveryMode::Immediate);
+        run_test(DeliveryMode::Queued);
+
+        fn run_test(delivery_mode: DeliveryMode) {
+            // The following `Vec`s will hold actual and expected notifications.
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            let mut expected = Vec::new();
+
+            // Create an eviction listener.
+            let a1 = Arc::clone(&actual);
+            let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+            let listener_conf = notification::Configuration::builder()
+                .delivery_mode(delivery_mode)
+                .build();
+
+            // Create a cache with the eviction listener.
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            for i in 0..50 {
+                cache.insert(i, i * 10);
+                let cause = RemovalCause::Invalidated { predicate_id: None };
+                expected.push((Arc::new(i), i * 10, cause));
+            }
+            cache.sync();
+            // The entries invalidated by `invalidate_all` should be notified too.
+            cache.invalidate_all();
+
+            // All notifications should have been delivered when `shutdown` returns,
+            // so do not use `verify_notification_vec` here.
+            cache.shutdown();
+            assert_eq_with_mode!(cache.entry_count(), 0, delivery_mode);
+            assert_with_mode!(cache.is_table_empty(), delivery_mode);
+
+            let mut actual = actual.lock().clone();
+            actual.sort_by_key(|(k, _, _)| **k);
+            assert_eq_with_mode!(actual, expected, delivery_mode);
+
+            // The cache can still be used.
+            cache.insert(0, 0);
+            assert_eq_with_mode!(cache.get(&0), Some(0), delivery_mode);
+        }
+    }
+
+    #[test]
+    fn queued_notification_overflow_policies() {
+        use notification::OverflowPolicy;
+        use std::thread::{self, ThreadId};
+
+        const KEYS: u32 = 10;
+
+        run_test(OverflowPolicy::DropNewest);
+        run_test(OverflowPolicy::DropOldest);
+        run_test(OverflowPolicy::SpillToCaller);
+
+        fn run_test(policy: OverflowPolicy) {
+            // The listener records the key and whether it was called by this thread.
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            // The listener called by the notifier thread is blocked until this gate
+            // is released, so that the notification queue will be full.
+            let gate = Arc::new(Mutex::new(()));
+            let test_thread: ThreadId = thread::current().id();
+
+            let a1 = Arc::clone(&actual);
+            let g1 = Arc::clone(&gate);
+            let listener = move |k: Arc<u32>, _v, _cause| {
+                let is_caller = thread::current().id() == test_thread;
+                if !is_caller {
+                    let _guard = g1.lock();
+                }
+                a1.lock().push((*k, is_caller));
+            };
+            let listener_conf = notification::Configuration::builder()
+                .delivery_mode(DeliveryMode::Queued)
+                .queue_capacity(1)
+                .overflow_policy(policy)
+                .build();
+
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .record_stats()
+                .eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            for i in 0..KEYS {
+                cache.insert(i, i);
+            }
+            cache.sync();
+
+            let guard = gate.lock();
+            for i in 0..KEYS {
+                cache.invalidate(&i);
+                // Give the notifier thread a chance to receive the notification.
+                thread::sleep(Duration::from_millis(10));
+            }
+            std::mem::drop(guard);
+            cache.shutdown();
+
+            let actual = actual.lock().clone();
+            let dropped = cache.dropped_notification_count();
+            assert_eq!(actual.len() as u64 + dropped, KEYS as u64, "{:?}", policy);
+            assert_eq!(cache.stats().dropped_notification_count(), dropped);
+
+            // At most two notifications (one being delivered and one in the queue)
+            // can be held by the blocked notifier thread.
+            match policy {
+                OverflowPolicy::DropNewest => {
+                    assert!(dropped >= KEYS as u64 - 2, "{:?}", policy);
+                    assert!(!actual.contains(&(KEYS - 1, false)));
+                }
+                OverflowPolicy::DropOldest => {
+                    assert!(dropped >= KEYS as u64 - 2, "{:?}", policy);
+                    // The newest notification is never dropped.
+                    assert!(actual.contains(&(KEYS - 1, false)));
+                }
+                OverflowPolicy::SpillToCaller => {
+                    assert_eq!(dropped, 0);
+                    let spilled = actual.iter().filter(|(_, is_caller)| *is_caller).count();
+                    assert!(spilled >= KEYS as usize - 2);
+                }
+                OverflowPolicy::Block => unreachable!(),
+            }
+        }
+    }
+
+    // The notifications of the entries expired by a housekeeping task are sent as
+    // a batch. Make sure the batch is split so that the queue capacity and the
+    // overflow policy are applied to each of them.
+    #[test]
+    fn queued_notification_batch_overflow() {
+        use notification::OverflowPolicy;
+
+        const KEYS: u32 = 10;
+
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let gate = Arc::new(Mutex::new(()));
+
+        let a1 = Arc::clone(&actual);
+        let g1 = Arc::clone(&gate);
+        let listener = move |k: Arc<u32>, _v, _cause| {
+            let _guard = g1.lock();
+            a1.lock().push(*k);
+        };
+        let listener_conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Queued)
+            .queue_capacity(1)
+            .overflow_policy(OverflowPolicy::DropNewest)
+            .build();
+
+        // Do not record the stats; the dropped notifications are counted anyway.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .eviction_listener_with_conf(listener, listener_conf)
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        for i in 0..KEYS {
+            cache.insert(i, i);
+        }
+        cache.sync();
+
+        let guard = gate.lock();
+        mock.increment(Duration::from_secs(11));
+        // Expires all entries at once.
+        cache.sync();
+        std::mem::drop(guard);
+        cache.shutdown();
+
+        let delivered = actual.lock().len() as u64;
+        let dropped = cache.dropped_notification_count();
+        assert_eq!(delivered + dropped, KEYS as u64);
+        // At most two notifications can be held by the blocked notifier thread.
+        assert!(dropped >= KEYS as u64 - 2);
+        assert_eq!(cache.stats().dropped_notification_count(), 0);
+    }
+
+    // The queued notifications will never be delivered after the listener has
+    // panicked. Make sure the `Block` policy does not wait for the full queue to
+    // have room forever.
+    #[test]
+    fn queued_notification_block_after_panic() {
+        use notification::OverflowPolicy;
+
+        let listener = move |_k, v, _cause| {
+            if v == "panic now!" {
+                panic!("Panic now!");
+            }
+        };
+        let listener_conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Queued)
+            .queue_capacity(1)
+            .overflow_policy(OverflowPolicy::Block)
+            .build();
+
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .eviction_listener_with_conf(listener, listener_conf)
+            .build();
+        cache.reconfigure_for_testing();
 
-            // Re-add "a" (w: 10) and update "b" with "bob" (w: 20 -> 15).
-            cache.insert("a", alice);
-            cache.insert("b", bob);
-            expected.push((Arc::new("b"), bill, RemovalCause::Replaced));
-            cache.sync();
-            assert_eq_with_mode!(cache.get(&"a"), Some(alice), delivery_mode);
-            assert_eq_with_mode!(cache.get(&"b"), Some(bob), delivery_mode);
-            assert_eq_with_mode!(cache.get(&"d"), None, delivery_mode);
-            assert_with_mode!(cache.contains_key(&"a"), delivery_mode);
-            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
-            assert_with_mode!(!cache.contains_key(&"d"), delivery_mode);
+        // Make the cache exterior immutable.
+        let cache = cache;
 
-            // Verify the sizes.
-            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
-            assert_eq_with_mode!(cache.weighted_size(), 25, delivery_mode);
+        cache.insert(0, "panic now!");
+        cache.invalidate(&0);
+        // Wait for the notifier thread to call the listener.
+        std::thread::sleep(Duration::from_millis(200));
 
-            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        // These should not block even though nothing will receive the
+        // notifications from the queue.
+        for i in 1..10 {
+            cache.insert(i, "a");
+            cache.invalidate(&i);
         }
+        assert!(cache.is_table_empty());
     }
 
+    // With the deterministic mode, the notification task is queued in a `TaskQueue`
+    // and run by the client thread after the housekeeping. Make sure a housekeeping
+    // run evicting more entries than the queue capacity does not wait forever for
+    // the queue to have room with the `Block` policy.
     #[test]
-    fn basic_multi_threads() {
-        let num_threads = 4;
-        let cache = Cache::new(100);
+    fn queued_notification_block_with_task_queue() {
+        const MAX_CAPACITY: u64 = 100;
+        const KEYS: u64 = 3_000;
 
-        // https://rust-lang.github.io/rust-clippy/master/index.html#needless_collect
-        #[allow(clippy::needless_collect)]
-        let handles = (0..num_threads)
-            .map(|id| {
-                let cache = cache.clone();
-                std::thread::spawn(move || {
-                    cache.insert(10, format!("{}-100", id));
-                    cache.get(&10);
-                    cache.insert(20, format!("{}-200", id));
-                    cache.invalidate(&10);
-                })
-            })
-            .collect::<Vec<_>>();
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        let listener = move |k: Arc<u64>, _v, cause| a1.lock().push((*k, cause));
+        // The queue capacity (1,024) is smaller than the number of the evictions.
+        let listener_conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Queued)
+            .build();
 
-        handles.into_iter().for_each(|h| h.join().expect("Failed"));
+        let cache = Cache::builder()
+            .max_capacity(MAX_CAPACITY)
+            .eviction_listener_with_conf(listener, listener_conf)
+            .deterministic_mode(true)
+            .build();
 
-        assert!(cache.get(&10).is_none());
-        assert!(cache.get(&20).is_some());
-        assert!(!cache.contains_key(&10));
-        assert!(cache.contains_key(&20));
+        cache.insert_all((0..KEYS).map(|i| (i, i)));
+        cache.sync();
+        assert_eq!(cache.entry_count(), MAX_CAPACITY);
+
+        let actual = actual.lock();
+        assert_eq!(actual.len() as u64, KEYS - MAX_CAPACITY);
+        assert!(actual.iter().all(|(_, cause)| *cause == RemovalCause::Size));
+        assert_eq!(cache.dropped_notification_count(), 0);
     }
 
     #[test]
@@ -2188,9 +3635,21 @@ mod tests {
             // https://github.com/moka-rs/moka/issues/155
 
             cache.invalidate_all();
-            expected.push((Arc::new("a"), "alice", RemovalCause::Explicit));
-            expected.push((Arc::new("b"), "bob", RemovalCause::Explicit));
-            expected.push((Arc::new("c"), "cindy", RemovalCause::Explicit));
+            expected.push((
+                Arc::new("a"),
+                "alice",
+                RemovalCause::Invalidated { predicate_id: None },
+            ));
+            expected.push((
+                Arc::new("b"),
+                "bob",
+                RemovalCause::Invalidated { predicate_id: None },
+            ));
+            expected.push((
+                Arc::new("c"),
+                "cindy",
+                RemovalCause::Invalidated { predicate_id: None },
+            ));
             cache.sync();
 
             cache.insert("d", "david");
@@ -2258,10 +3717,10 @@ mod tests {
             assert_with_mode!(cache.contains_key(&2), delivery_mode);
 
             let names = ["alice", "alex"].iter().cloned().collect::<HashSet<_>>();
-            cache.invalidate_entries_if(move |_k, &v| names.contains(v))?;
+            let id0 = cache.invalidate_entries_if(move |_k, &v| names.contains(v))?;
             assert_eq_with_mode!(cache.base.invalidation_predicate_count(), 1, delivery_mode);
-            expected.push((Arc::new(0), "alice", RemovalCause::Explicit));
-            expected.push((Arc::new(2), "alex", RemovalCause::Explicit));
+            expected.push((Arc::new(0), "alice", invalidated_by(&id0)));
+            expected.push((Arc::new(2), "alex", invalidated_by(&id0)));
 
             mock.increment(Duration::from_secs(5)); // 10 secs from the start.
 
@@ -2289,12 +3748,12 @@ mod tests {
 
             mock.increment(Duration::from_secs(5)); // 15 secs from the start.
 
-            cache.invalidate_entries_if(|_k, &v| v == "alice")?;
-            cache.invalidate_entries_if(|_k, &v| v == "bob")?;
+            let id1 = cache.invalidate_entries_if(|_k, &v| v == "alice")?;
+            let id2 = cache.invalidate_entries_if(|_k, &v| v == "bob")?;
             assert_eq_with_mode!(cache.invalidation_predicate_count(), 2, delivery_mode);
             // key 1 was inserted before key 3.
-            expected.push((Arc::new(1), "bob", RemovalCause::Explicit));
-            expected.push((Arc::new(3), "alice", RemovalCause::Explicit));
+            expected.push((Arc::new(1), "bob", invalidated_by(&id2)));
+            expected.push((Arc::new(3), "alice", invalidated_by(&id1)));
 
             // Run the invalidation task and wait for it to finish. (TODO: Need a better way than sleeping)
             cache.sync(); // To submit the invalidation task.
@@ -2402,100 +3861,404 @@ mod tests {
 
             assert_eq_with_mode!(cache.iter().count(), 0, delivery_mode);
 
-            cache.sync();
-            assert_with_mode!(cache.is_table_empty(), delivery_mode);
+            cache.sync();
+            assert_with_mode!(cache.is_table_empty(), delivery_mode);
+
+            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        }
+    }
+
+    #[test]
+    fn time_to_idle() {
+        run_test(DeliveryMode::Immediate);
+        run_test(DeliveryMode::Queued);
+
+        fn run_test(delivery_mode: DeliveryMode) {
+            // The following `Vec`s will hold actual and expected notifications.
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            let mut expected = Vec::new();
+
+            // Create an eviction listener.
+            let a1 = Arc::clone(&actual);
+            let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+            let listener_conf = notification::Configuration::builder()
+                .delivery_mode(delivery_mode)
+                .build();
+
+            // Create a cache with the eviction listener.
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .time_to_idle(Duration::from_secs(10))
+                .eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            let (clock, mock) = Clock::mock();
+            cache.set_expiration_clock(Some(clock));
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            cache.insert("a", "alice");
+            cache.sync();
+
+            mock.increment(Duration::from_secs(5)); // 5 secs from the start.
+            cache.sync();
+
+            assert_eq_with_mode!(cache.get(&"a"), Some("alice"), delivery_mode);
+
+            mock.increment(Duration::from_secs(5)); // 10 secs.
+            cache.sync();
+
+            cache.insert("b", "bob");
+            cache.sync();
+
+            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
+
+            mock.increment(Duration::from_secs(2)); // 12 secs.
+            cache.sync();
+
+            // contains_key does not reset the idle timer for the key.
+            assert_with_mode!(cache.contains_key(&"a"), delivery_mode);
+            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
+            cache.sync();
+
+            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
+
+            mock.increment(Duration::from_secs(3)); // 15 secs.
+            expected.push((Arc::new("a"), "alice", RemovalCause::Expired));
+
+            assert_eq_with_mode!(cache.get(&"a"), None, delivery_mode);
+            assert_eq_with_mode!(cache.get(&"b"), Some("bob"), delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"a"), delivery_mode);
+            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
+
+            assert_eq_with_mode!(cache.iter().count(), 1, delivery_mode);
+
+            cache.sync();
+            assert_eq_with_mode!(cache.entry_count(), 1, delivery_mode);
+
+            mock.increment(Duration::from_secs(10)); // 25 secs
+            expected.push((Arc::new("b"), "bob", RemovalCause::Expired));
+
+            assert_eq_with_mode!(cache.get(&"a"), None, delivery_mode);
+            assert_eq_with_mode!(cache.get(&"b"), None, delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"a"), delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"b"), delivery_mode);
+
+            assert_eq_with_mode!(cache.iter().count(), 0, delivery_mode);
+
+            cache.sync();
+            assert_with_mode!(cache.is_table_empty(), delivery_mode);
+
+            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        }
+    }
+
+    #[test]
+    fn expire_after() {
+        run_test(DeliveryMode::Immediate);
+        run_test(DeliveryMode::Queued);
+
+        struct MyExpiry;
+
+        impl Expiry<&'static str, &'static str> for MyExpiry {
+            fn expire_after_create(&self, key: &&str, _value: &&str) -> Option<Duration> {
+                if *key == "c" {
+                    None
+                } else {
+                    Some(Duration::from_secs(10))
+                }
+            }
+
+            fn expire_after_update(
+                &self,
+                _key: &&str,
+                _value: &&str,
+                _current_duration: Option<Duration>,
+            ) -> Option<Duration> {
+                Some(Duration::from_secs(3))
+            }
+        }
+
+        fn run_test(delivery_mode: DeliveryMode) {
+            // The following `Vec`s will hold actual and expected notifications.
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            let mut expected = Vec::new();
+
+            // Create an eviction listener.
+            let a1 = Arc::clone(&actual);
+            let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+            let listener_conf = notification::Configuration::builder()
+                .delivery_mode(delivery_mode)
+                .build();
+
+            // Create a cache with the eviction listener.
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .expire_after(MyExpiry)
+                .eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            let (clock, mock) = Clock::mock();
+            cache.set_expiration_clock(Some(clock));
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            cache.insert("a", "alice");
+            cache.insert("b", "bob");
+            cache.insert("c", "cindy");
+            cache.sync();
+
+            mock.increment(Duration::from_secs(5)); // 5 secs from the start.
+            cache.sync();
+
+            assert_eq_with_mode!(cache.get(&"a"), Some("alice"), delivery_mode);
+
+            // The update shortens the remaining time of "b" to 3 secs.
+            cache.insert("b", "bill");
+            expected.push((Arc::new("b"), "bob", RemovalCause::Replaced));
+            cache.sync();
+
+            mock.increment(Duration::from_secs(4)); // 9 secs.
+            expected.push((Arc::new("b"), "bill", RemovalCause::Expired));
+
+            assert_eq_with_mode!(cache.get(&"a"), Some("alice"), delivery_mode);
+            assert_eq_with_mode!(cache.get(&"b"), None, delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"b"), delivery_mode);
+            assert_eq_with_mode!(cache.iter().count(), 2, delivery_mode);
+
+            cache.sync();
+            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
+
+            mock.increment(Duration::from_secs(1)); // 10 secs.
+            expected.push((Arc::new("a"), "alice", RemovalCause::Expired));
+
+            assert_eq_with_mode!(cache.get(&"a"), None, delivery_mode);
+            assert_eq_with_mode!(cache.get(&"c"), Some("cindy"), delivery_mode);
+
+            cache.sync();
+            assert_eq_with_mode!(cache.entry_count(), 1, delivery_mode);
+
+            // "c" has no expiration time.
+            mock.increment(Duration::from_secs(1000)); // 1010 secs.
+            cache.sync();
+            assert_eq_with_mode!(cache.get(&"c"), Some("cindy"), delivery_mode);
+
+            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        }
+    }
+
+    #[test]
+    fn insert_with_ttl() {
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice");
+        cache.insert_with_ttl("b", "bob", Duration::from_secs(30));
+        cache.insert_with_ttl("c", "cindy", Duration::from_secs(3));
+        let entry = cache
+            .entry("d")
+            .or_insert_with_ttl("david", Duration::from_secs(20));
+        assert!(entry.is_fresh());
+        // The TTL is not applied to an existing entry.
+        let entry = cache
+            .entry_by_ref(&"a")
+            .or_insert_with_ttl("anna", Duration::from_secs(1));
+        assert!(!entry.is_fresh());
+        cache.sync();
+
+        mock.increment(Duration::from_secs(4)); // 4 secs from the start.
+        assert_eq!(cache.get(&"c"), None);
+        assert_eq!(cache.get(&"a"), Some("alice"));
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 3);
+
+        // "a" expires by the time-to-live of the cache, but "b" and "d" do not.
+        mock.increment(Duration::from_secs(7)); // 11 secs.
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some("bob"));
+        assert_eq!(cache.get(&"d"), Some("david"));
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+
+        // Updating without a TTL puts "d" back under the time-to-live of the cache.
+        cache.insert("d", "dennis");
+        cache.sync();
 
-            verify_notification_vec(&cache, actual, &expected, delivery_mode);
-        }
-    }
+        mock.increment(Duration::from_secs(10)); // 21 secs.
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.get(&"b"), Some("bob"));
 
-    #[test]
-    fn time_to_idle() {
-        run_test(DeliveryMode::Immediate);
-        run_test(DeliveryMode::Queued);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
 
-        fn run_test(delivery_mode: DeliveryMode) {
-            // The following `Vec`s will hold actual and expected notifications.
-            let actual = Arc::new(Mutex::new(Vec::new()));
-            let mut expected = Vec::new();
+        mock.increment(Duration::from_secs(10)); // 31 secs.
+        assert_eq!(cache.get(&"b"), None);
 
-            // Create an eviction listener.
-            let a1 = Arc::clone(&actual);
-            let listener = move |k, v, cause| a1.lock().push((k, v, cause));
-            let listener_conf = notification::Configuration::builder()
-                .delivery_mode(delivery_mode)
-                .build();
+        cache.sync();
+        assert!(cache.is_table_empty());
+    }
 
-            // Create a cache with the eviction listener.
-            let mut cache = Cache::builder()
-                .max_capacity(100)
-                .time_to_idle(Duration::from_secs(10))
-                .eviction_listener_with_conf(listener, listener_conf)
-                .build();
-            cache.reconfigure_for_testing();
+    #[test]
+    fn expire_after_and_insert_with_ttl() {
+        struct MyExpiry;
 
-            let (clock, mock) = Clock::mock();
-            cache.set_expiration_clock(Some(clock));
+        impl Expiry<&'static str, &'static str> for MyExpiry {
+            fn expire_after_create(&self, _key: &&str, _value: &&str) -> Option<Duration> {
+                Some(Duration::from_secs(10))
+            }
 
-            // Make the cache exterior immutable.
-            let cache = cache;
+            // Every read extends the entry life to 100 secs.
+            fn expire_after_read(
+                &self,
+                _key: &&str,
+                _value: &&str,
+                _current_duration: Option<Duration>,
+            ) -> Option<Duration> {
+                Some(Duration::from_secs(100))
+            }
+        }
 
-            cache.insert("a", "alice");
-            cache.sync();
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .expire_after(MyExpiry)
+            .build();
+        cache.reconfigure_for_testing();
 
-            mock.increment(Duration::from_secs(5)); // 5 secs from the start.
-            cache.sync();
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
 
-            assert_eq_with_mode!(cache.get(&"a"), Some("alice"), delivery_mode);
+        // Make the cache exterior immutable.
+        let cache = cache;
 
-            mock.increment(Duration::from_secs(5)); // 10 secs.
-            cache.sync();
+        cache.insert("a", "alice");
+        cache.insert_with_ttl("b", "bob", Duration::from_secs(5));
+        cache
+            .entry("c")
+            .or_insert_with_ttl("cindy", Duration::from_secs(5));
+        cache.sync();
 
-            cache.insert("b", "bob");
-            cache.sync();
+        mock.increment(Duration::from_secs(1)); // 1 sec from the start.
+        assert_eq!(cache.get(&"a"), Some("alice"));
+        assert_eq!(cache.get(&"b"), Some("bob"));
+        assert_eq!(cache.get(&"c"), Some("cindy"));
+        cache.sync();
 
-            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
+        // The reads extended "a", but not "b" and "c" as they have their own TTLs.
+        mock.increment(Duration::from_secs(4)); // 5 secs.
+        assert_eq!(cache.get(&"a"), Some("alice"));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"c"), None);
 
-            mock.increment(Duration::from_secs(2)); // 12 secs.
-            cache.sync();
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
+    }
 
-            // contains_key does not reset the idle timer for the key.
-            assert_with_mode!(cache.contains_key(&"a"), delivery_mode);
-            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
-            cache.sync();
+    #[test]
+    fn refresh_after_write() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let reload_count = Arc::new(AtomicU32::new(0));
+        let count = Arc::clone(&reload_count);
+        // Held by the test to keep a reload running while it writes to the cache.
+        let gate = Arc::new(Mutex::new(()));
+        let gate1 = Arc::clone(&gate);
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .refresh_after_write(Duration::from_secs(10))
+            .reloader(move |key: &&str, value: &u32| {
+                let _guard = gate1.lock();
+                count.fetch_add(1, Ordering::AcqRel);
+                // Keep the current value of "b".
+                if *key == "b" {
+                    None
+                } else {
+                    Some(*value + 1)
+                }
+            })
+            .build();
+        cache.reconfigure_for_testing();
 
-            assert_eq_with_mode!(cache.entry_count(), 2, delivery_mode);
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
 
-            mock.increment(Duration::from_secs(3)); // 15 secs.
-            expected.push((Arc::new("a"), "alice", RemovalCause::Expired));
+        // Make the cache exterior immutable.
+        let cache = cache;
 
-            assert_eq_with_mode!(cache.get(&"a"), None, delivery_mode);
-            assert_eq_with_mode!(cache.get(&"b"), Some("bob"), delivery_mode);
-            assert_with_mode!(!cache.contains_key(&"a"), delivery_mode);
-            assert_with_mode!(cache.contains_key(&"b"), delivery_mode);
+        cache.insert("a", 1);
+        cache.insert("b", 10);
+        cache.sync();
 
-            assert_eq_with_mode!(cache.iter().count(), 1, delivery_mode);
+        mock.increment(Duration::from_secs(5)); // 5 secs from the start.
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(reload_count.load(Ordering::Acquire), 0);
 
-            cache.sync();
-            assert_eq_with_mode!(cache.entry_count(), 1, delivery_mode);
+        // "a" is stale. The old value is returned and only one reload is started.
+        mock.increment(Duration::from_secs(6)); // 11 secs.
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert!(cache.get(&"a").is_some());
+        assert!(cache.get_with("a", || unreachable!()) > 0);
 
-            mock.increment(Duration::from_secs(10)); // 25 secs
-            expected.push((Arc::new("b"), "bob", RemovalCause::Expired));
+        let mut retries = 0;
+        while cache.get(&"a") != Some(2) {
+            assert!(retries < 200, "The reload did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+            retries += 1;
+        }
+        assert_eq!(reload_count.load(Ordering::Acquire), 1);
+        assert!(cache.contains_key(&"a"));
 
-            assert_eq_with_mode!(cache.get(&"a"), None, delivery_mode);
-            assert_eq_with_mode!(cache.get(&"b"), None, delivery_mode);
-            assert_with_mode!(!cache.contains_key(&"a"), delivery_mode);
-            assert_with_mode!(!cache.contains_key(&"b"), delivery_mode);
+        // The reloader returns `None` for "b", so the value is kept.
+        assert_eq!(cache.get(&"b"), Some(10));
+        let mut retries = 0;
+        while reload_count.load(Ordering::Acquire) < 2 {
+            assert!(retries < 200, "The reload did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+            retries += 1;
+        }
+        assert_eq!(cache.get(&"b"), Some(10));
 
-            assert_eq_with_mode!(cache.iter().count(), 0, delivery_mode);
+        // "b" is still stale, so the last read started another reload.
+        let mut retries = 0;
+        while reload_count.load(Ordering::Acquire) < 3 {
+            assert!(retries < 200, "The reload did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+            retries += 1;
+        }
 
-            cache.sync();
-            assert_with_mode!(cache.is_table_empty(), delivery_mode);
+        // "a" is updated while it is being reloaded. The reloaded value must not
+        // overwrite the new value.
+        mock.increment(Duration::from_secs(11)); // 22 secs.
+        let guard = gate.lock();
+        assert_eq!(cache.get(&"a"), Some(2));
+        cache.insert("a", 100);
+        drop(guard);
 
-            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        let key = Arc::new("a");
+        let mut retries = 0;
+        while !cache.value_initializer.try_start_refresh(&key) {
+            assert!(retries < 200, "The reload did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+            retries += 1;
         }
+        cache.value_initializer.finish_refresh(&key);
+        assert_eq!(reload_count.load(Ordering::Acquire), 4);
+        assert_eq!(cache.get(&"a"), Some(100));
     }
-
     #[test]
     fn test_iter() {
         const NUM_KEYS: usize = 50;
@@ -2651,31 +4414,330 @@ mod tests {
             })
         };
 
-        // Thread4 will call `get` for the same key. It will call when thread1's init
-        // closure is still running, so it will get none for the key.
-        let thread4 = {
-            let cache4 = cache.clone();
+        // Thread4 will call `get` for the same key. It will call when thread1's init
+        // closure is still running, so it will get none for the key.
+        let thread4 = {
+            let cache4 = cache.clone();
+            spawn(move || {
+                // Wait for 200 ms before calling `get`.
+                sleep(Duration::from_millis(200));
+                let maybe_v = cache4.get(&KEY);
+                assert!(maybe_v.is_none());
+            })
+        };
+
+        // Thread5 will call `get` for the same key. It will call after thread1's init
+        // closure finished, so it will get the value insert by thread1's init closure.
+        let thread5 = {
+            let cache5 = cache.clone();
+            spawn(move || {
+                // Wait for 400 ms before calling `get`.
+                sleep(Duration::from_millis(400));
+                let maybe_v = cache5.get(&KEY);
+                assert_eq!(maybe_v, Some("thread1"));
+            })
+        };
+
+        for t in vec![thread1, thread2, thread3, thread4, thread5] {
+            t.join().expect("Failed to join");
+        }
+    }
+
+    #[test]
+    fn conditional_writes() {
+        run_test(DeliveryMode::Immediate);
+        run_test(DeliveryMode::Queued);
+
+        fn run_test(delivery_mode: DeliveryMode) {
+            // The following `Vec`s will hold actual and expected notifications.
+            let actual = Arc::new(Mutex::new(Vec::new()));
+            let mut expected = Vec::new();
+
+            // Create an eviction listener.
+            let a1 = Arc::clone(&actual);
+            let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+            let listener_conf = notification::Configuration::builder()
+                .delivery_mode(delivery_mode)
+                .build();
+
+            // Create a cache with the eviction listener.
+            let mut cache = Cache::builder()
+                .max_capacity(100)
+                .time_to_live(Duration::from_secs(10))
+                .eviction_listener_with_conf(listener, listener_conf)
+                .build();
+            cache.reconfigure_for_testing();
+
+            let (clock, mock) = Clock::mock();
+            cache.set_expiration_clock(Some(clock));
+
+            // Make the cache exterior immutable.
+            let cache = cache;
+
+            assert_eq_with_mode!(cache.insert_if_absent("a", "alice"), None, delivery_mode);
+            assert_eq_with_mode!(
+                cache.insert_if_absent("a", "anna"),
+                Some("alice"),
+                delivery_mode
+            );
+
+            assert_with_mode!(!cache.replace(&"a", &"anna", "amy"), delivery_mode);
+            assert_with_mode!(cache.replace(&"a", &"alice", "amy"), delivery_mode);
+            expected.push((Arc::new("a"), "alice", RemovalCause::Replaced));
+            assert_eq_with_mode!(cache.get(&"a"), Some("amy"), delivery_mode);
+
+            // `replace` does not insert a missing key.
+            assert_with_mode!(!cache.replace(&"b", &"bob", "bill"), delivery_mode);
+            assert_with_mode!(!cache.contains_key(&"b"), delivery_mode);
+
+            assert_eq_with_mode!(
+                cache.remove_if(&"a", |v| *v == "alice"),
+                None,
+                delivery_mode
+            );
+            assert_with_mode!(cache.contains_key(&"a"), delivery_mode);
+            assert_eq_with_mode!(
+                cache.remove_if(&"a", |v| *v == "amy"),
+                Some("amy"),
+                delivery_mode
+            );
+            expected.push((Arc::new("a"), "amy", RemovalCause::Explicit));
+            assert_with_mode!(!cache.contains_key(&"a"), delivery_mode);
+
+            cache.insert("c", "cindy");
+            cache.sync();
+
+            mock.increment(Duration::from_secs(10)); // 10 secs from the start.
+
+            // An expired entry is treated as absent.
+            assert_with_mode!(!cache.replace(&"c", &"cindy", "carl"), delivery_mode);
+            assert_eq_with_mode!(cache.remove_if(&"c", |_| true), None, delivery_mode);
+            assert_eq_with_mode!(cache.insert_if_absent("c", "carl"), None, delivery_mode);
+            expected.push((Arc::new("c"), "cindy", RemovalCause::Expired));
+            assert_eq_with_mode!(cache.get(&"c"), Some("carl"), delivery_mode);
+
+            cache.sync();
+            assert_eq_with_mode!(cache.entry_count(), 1, delivery_mode);
+
+            verify_notification_vec(&cache, actual, &expected, delivery_mode);
+        }
+    }
+
+    #[test]
+    fn remove_and_remove_entry() {
+        use std::thread::spawn;
+
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        // Create a cache with the eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .eviction_listener(listener)
+            .build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a", "alice");
+        cache.insert("b", "bob");
+        assert_eq!(cache.remove(&"a"), Some("alice"));
+        expected.push((Arc::new("a"), "alice", RemovalCause::Explicit));
+        assert_eq!(cache.remove(&"a"), None);
+        assert_eq!(cache.remove_entry(&"b"), Some((Arc::new("b"), "bob")));
+        expected.push((Arc::new("b"), "bob", RemovalCause::Explicit));
+        assert!(!cache.contains_key(&"b"));
+
+        // An expired value is not returned, but the entry is removed.
+        cache.insert("c", "cindy");
+        cache.sync();
+        mock.increment(Duration::from_secs(10)); // 10 secs from the start.
+        assert_eq!(cache.remove(&"c"), None);
+        expected.push((Arc::new("c"), "cindy", RemovalCause::Expired));
+
+        cache.sync();
+        assert!(cache.is_table_empty());
+        verify_notification_vec(&cache, actual, &expected, DeliveryMode::Immediate);
+
+        // Only one thread can take the value.
+        let cache = Cache::new(100);
+        cache.insert(0, 0);
+        let threads = (0..16)
+            .map(|_| {
+                let cache = cache.clone();
+                spawn(move || cache.remove(&0).is_some())
+            })
+            .collect::<Vec<_>>();
+        let taken = threads
+            .into_iter()
+            .map(|t| t.join().expect("Failed to join"))
+            .filter(|taken| *taken)
+            .count();
+        assert_eq!(taken, 1);
+    }
+
+    #[test]
+    fn concurrent_conditional_writes() {
+        use std::thread::spawn;
+
+        const NUM_THREADS: usize = 16;
+        let cache = Cache::new(100);
+
+        // Only one thread can insert the value.
+        let threads = (0..NUM_THREADS)
+            .map(|i| {
+                let cache = cache.clone();
+                spawn(move || cache.insert_if_absent(0, i).is_none())
+            })
+            .collect::<Vec<_>>();
+        let inserted = threads
+            .into_iter()
+            .map(|t| t.join().expect("Failed to join"))
+            .filter(|inserted| *inserted)
+            .count();
+        assert_eq!(inserted, 1);
+
+        // Increment the value with compare-and-swap. No increment will be lost.
+        cache.insert(0, 0);
+        let threads = (0..NUM_THREADS)
+            .map(|_| {
+                let cache = cache.clone();
+                spawn(move || {
+                    for _ in 0..100 {
+                        loop {
+                            let v = cache.get(&0).unwrap();
+                            if cache.replace(&0, &v, v + 1) {
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .for_each(|t| t.join().expect("Failed to join"));
+        assert_eq!(cache.get(&0), Some(NUM_THREADS * 100));
+    }
+
+    #[test]
+    fn insert_all_and_invalidate_all_keys() {
+        let mut cache = Cache::new(10_000);
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        // Insert more entries than the write op channel can hold, so that the
+        // pending ops are applied while the entries are being inserted.
+        cache.insert_all((0..5_000).map(|i| (i, i * 10)));
+        cache.sync();
+        assert_eq!(cache.entry_count(), 5_000);
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&4_999), Some(49_990));
+
+        // Invalidate the even keys. The key 10_000 does not exist.
+        let keys = (0..5_000)
+            .step_by(2)
+            .chain(Some(10_000))
+            .collect::<Vec<_>>();
+        cache.invalidate_all_keys(&keys);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2_500);
+        assert_eq!(cache.get(&0), None);
+        assert_eq!(cache.get(&1), Some(10));
+        assert!(!cache.contains_key(&4_998));
+        assert!(cache.contains_key(&4_999));
+
+        // The max capacity is still enforced.
+        let mut cache = Cache::new(100);
+        cache.reconfigure_for_testing();
+        let cache = cache;
+
+        cache.insert_all((0..1_000).map(|i| (i, i)));
+        cache.sync();
+        assert!(cache.entry_count() <= 100);
+    }
+
+    #[test]
+    fn get_all_with() {
+        use std::thread::{sleep, spawn};
+
+        let cache = Cache::new(100);
+        cache.insert(0, "zero".to_string());
+
+        // Duplicate keys and the existing key are not passed to the init closure.
+        let values = cache.get_all_with(vec![0, 1, 2, 2], |keys| {
+            let mut sorted = keys.iter().map(|k| **k).collect::<Vec<u32>>();
+            sorted.sort_unstable();
+            assert_eq!(sorted, vec![1, 2]);
+            keys.iter().map(|k| format!("value {}", k)).collect()
+        });
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[&0], "zero");
+        assert_eq!(values[&1], "value 1");
+        assert_eq!(values[&2], "value 2");
+
+        let values = cache.get_all(vec![1, 2, 3]);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[&2], "value 2");
+
+        // This test will run three threads:
+        //
+        // Thread1 will be the first thread to call `get_all_with` for the keys 10 and
+        // 11, so its init closure will be evaluated for both keys.
+        let thread1 = {
+            let cache1 = cache.clone();
+            spawn(move || {
+                let values = cache1.get_all_with(vec![10, 11], |keys| {
+                    assert_eq!(keys.len(), 2);
+                    // Wait for 300 ms and return the values.
+                    sleep(Duration::from_millis(300));
+                    keys.iter().map(|k| format!("thread1 {}", k)).collect()
+                });
+                assert_eq!(values[&10], "thread1 10");
+                assert_eq!(values[&11], "thread1 11");
+            })
+        };
+
+        // Thread2 will call `get_with` for the key 11 while thread1's init closure
+        // is being evaluated. It will get the value loaded by thread1.
+        let thread2 = {
+            let cache2 = cache.clone();
             spawn(move || {
-                // Wait for 200 ms before calling `get`.
-                sleep(Duration::from_millis(200));
-                let maybe_v = cache4.get(&KEY);
-                assert!(maybe_v.is_none());
+                sleep(Duration::from_millis(100));
+                let v = cache2.get_with(11, || unreachable!());
+                assert_eq!(v, "thread1 11");
             })
         };
 
-        // Thread5 will call `get` for the same key. It will call after thread1's init
-        // closure finished, so it will get the value insert by thread1's init closure.
-        let thread5 = {
-            let cache5 = cache.clone();
+        // Thread3 will call `get_all_with` for the keys 11 and 12 while thread1's
+        // init closure is being evaluated. Its init closure will be evaluated only
+        // for the key 12.
+        let thread3 = {
+            let cache3 = cache.clone();
             spawn(move || {
-                // Wait for 400 ms before calling `get`.
-                sleep(Duration::from_millis(400));
-                let maybe_v = cache5.get(&KEY);
-                assert_eq!(maybe_v, Some("thread1"));
+                sleep(Duration::from_millis(100));
+                let values = cache3.get_all_with(vec![11, 12], |keys| {
+                    assert_eq!(keys, &[&12]);
+                    vec!["thread3 12".to_string()]
+                });
+                assert_eq!(values[&11], "thread1 11");
+                assert_eq!(values[&12], "thread3 12");
             })
         };
 
-        for t in vec![thread1, thread2, thread3, thread4, thread5] {
+        for t in vec![thread1, thread2, thread3] {
             t.join().expect("Failed to join");
         }
     }
@@ -2763,6 +4825,101 @@ mod tests {
         }
     }
 
+    #[test]
+    fn entry_and_compute_with() {
+        use crate::{ops::compute::Op, Entry};
+        use std::thread::spawn;
+
+        let cache: Cache<u32, u32> = Cache::new(100);
+        const KEY: u32 = 0;
+
+        // `Op::Nop` on a missing entry does not insert one.
+        let entry = cache.entry(KEY).and_compute_with(|maybe_entry| {
+            assert!(maybe_entry.is_none());
+            Op::Nop
+        });
+        assert!(entry.is_none());
+        assert!(!cache.contains_key(&KEY));
+
+        // `and_compute_if_present` does not evaluate the closure for a missing entry.
+        let entry = cache
+            .entry_by_ref(&KEY)
+            .and_compute_if_present(|_entry| unreachable!());
+        assert!(entry.is_none());
+
+        // `Op::Put` inserts the value.
+        let entry = cache.entry(KEY).and_compute_with(|_| Op::Put(1)).unwrap();
+        assert!(entry.is_fresh());
+        assert_eq!(entry.into_value(), 1);
+        assert_eq!(cache.get(&KEY), Some(1));
+
+        // `Op::Nop` on an existing entry returns the entry as is.
+        let entry = cache
+            .entry(KEY)
+            .and_compute_with(|maybe_entry| {
+                assert_eq!(maybe_entry.map(Entry::into_value), Some(1));
+                Op::Nop
+            })
+            .unwrap();
+        assert!(!entry.is_fresh());
+        assert_eq!(entry.into_value(), 1);
+
+        // Increment the value from eight threads. As a compute closure is evaluated
+        // again when the entry has been modified by another thread, no increment
+        // will be lost.
+        let threads = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                spawn(move || {
+                    for _ in 0..100 {
+                        cache
+                            .entry(KEY)
+                            .and_upsert_with(|maybe_entry| maybe_entry.unwrap().into_value() + 1);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .for_each(|t| t.join().expect("Failed to join"));
+        assert_eq!(cache.get(&KEY), Some(801));
+
+        // Neither is a value inserted while the closure is being evaluated.
+        let mut calls = 0;
+        let entry = cache.entry(KEY).and_compute_with(|maybe_entry| {
+            calls += 1;
+            if calls == 1 {
+                cache.insert(KEY, 1000);
+            }
+            Op::Put(maybe_entry.unwrap().into_value() + 1)
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(entry.map(Entry::into_value), Some(1001));
+        assert_eq!(cache.get(&KEY), Some(1001));
+
+        // `Op::Remove` does not remove a value inserted while the closure is being
+        // evaluated without evaluating the closure again.
+        let mut calls = 0;
+        let entry = cache.entry(KEY).and_compute_with(|maybe_entry| {
+            calls += 1;
+            if calls == 1 {
+                cache.insert(KEY, 2000);
+                return Op::Remove;
+            }
+            assert_eq!(maybe_entry.map(Entry::into_value), Some(2000));
+            Op::Nop
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(entry.map(Entry::into_value), Some(2000));
+
+        // `Op::Remove` removes the entry.
+        let entry = cache
+            .entry_by_ref(&KEY)
+            .and_compute_if_present(|_entry| Op::Remove);
+        assert!(entry.is_none());
+        assert!(!cache.contains_key(&KEY));
+    }
+
     #[test]
     fn entry_or_insert_with_if() {
         use std::thread::{sleep, spawn};
@@ -3626,6 +5783,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn removal_notifications_of_invalidated_entries() {
+        // The following `Vec`s will hold actual and expected notifications.
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        // Create an eviction listener.
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        // Create a cache with the eviction listener.
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .support_invalidation_closures()
+            .eviction_listener(listener)
+            .build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("alice", "a0");
+        cache.insert("bob", "b0");
+        cache.insert("cindy", "c0");
+        cache.insert("david", "d0");
+        cache.sync();
+
+        // Remove and replace the entries matched by the predicate before the
+        // invalidation task removes them. They should be notified with the handle
+        // of the predicate.
+        let id = cache
+            .invalidate_entries_if(|k, _v| *k == "alice" || *k == "bob")
+            .unwrap();
+        cache.invalidate(&"alice");
+        expected.push((Arc::new("alice"), "a0", invalidated_by(&id)));
+        cache.insert("bob", "b1");
+        expected.push((Arc::new("bob"), "b0", invalidated_by(&id)));
+        cache.invalidate(&"cindy");
+        expected.push((Arc::new("cindy"), "c0", RemovalCause::Explicit));
+        assert_eq!(*actual.lock(), expected);
+
+        // Remove an entry invalidated by `invalidate_all` before the cache removes
+        // it.
+        cache.invalidate_all();
+        cache.invalidate(&"david");
+        let cause = RemovalCause::Invalidated { predicate_id: None };
+        expected.push((Arc::new("david"), "d0", cause));
+        assert_eq!(*actual.lock(), expected);
+
+        // The handle is equal to the `PredicateId`.
+        let cause = actual.lock()[0].2;
+        match cause {
+            RemovalCause::Invalidated {
+                predicate_id: Some(handle),
+            } => {
+                assert_eq!(handle, id);
+                assert_eq!(handle.to_string(), id);
+            }
+            cause => panic!("Unexpected cause: {:?}", cause),
+        }
+    }
+
     #[test]
     fn test_removal_notifications() {
         run_test(DeliveryMode::Immediate);
@@ -4151,6 +6370,305 @@ mod tests {
             let enabled_pools = ThreadPoolRegistry::enabled_pools();
             assert!(enabled_pools.is_empty());
         }
+
+        // Use a custom executor: Enable no pools.
+        {
+            let listener = |_k, _v, _cause| {};
+            let listener_conf = notification::Configuration::builder()
+                .delivery_mode(DeliveryMode::Queued)
+                .build();
+            let cache = Cache::builder()
+                .thread_pool_enabled(true)
+                .support_invalidation_closures()
+                .eviction_listener_with_conf(listener, listener_conf)
+                .executor(ThreadPerTaskExecutor::default())
+                .build();
+            cache.insert('a', "a");
+            let enabled_pools = ThreadPoolRegistry::enabled_pools();
+            assert!(enabled_pools.is_empty());
+        }
+    }
+
+    #[test]
+    fn custom_executor() -> Result<(), Box<dyn std::error::Error>> {
+        use std::sync::atomic::Ordering;
+
+        let executor = Arc::new(ThreadPerTaskExecutor::default());
+
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+        let listener_conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Queued)
+            .build();
+
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .support_invalidation_closures()
+            .eviction_listener_with_conf(listener, listener_conf)
+            .executor(Arc::clone(&executor))
+            .build();
+
+        // The periodical housekeeping task has been scheduled.
+        assert_eq!(executor.delayed_tasks.load(Ordering::Acquire), 1);
+
+        cache.insert(0, "alice");
+        cache.insert(1, "bob");
+        cache.sync();
+
+        let id = cache.invalidate_entries_if(|_k, &v| v == "alice")?;
+
+        // Run the invalidation task and wait for it to finish.
+        cache.sync(); // To submit the invalidation task.
+        std::thread::sleep(Duration::from_millis(200));
+        cache.sync(); // To process the task result.
+        std::thread::sleep(Duration::from_millis(200));
+
+        assert!(cache.get(&0).is_none());
+        assert_eq!(cache.get(&1), Some("bob"));
+
+        // The invalidation task and the notification task ran on the executor.
+        assert!(executor.tasks.load(Ordering::Acquire) >= 2);
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new(0), "alice", invalidated_by(&id))]
+        );
+
+        // After the cache is dropped, the periodical housekeeping task should not be
+        // scheduled anymore.
+        std::mem::drop(cache);
+        std::thread::sleep(Duration::from_millis(800));
+        let delayed_tasks = executor.delayed_tasks.load(Ordering::Acquire);
+        std::thread::sleep(Duration::from_millis(800));
+        assert_eq!(
+            executor.delayed_tasks.load(Ordering::Acquire),
+            delayed_tasks
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn drop_with_executor_holding_tasks() {
+        // An executor that holds the tasks without running them, like a runtime
+        // being shut down.
+        #[derive(Default)]
+        struct HoldingExecutor {
+            tasks: Mutex<Vec<Task>>,
+        }
+
+        impl Executor for HoldingExecutor {
+            fn execute(&self, task: Task) {
+                self.tasks.lock().push(task);
+            }
+
+            fn execute_after(&self, _delay: Duration, task: Task) {
+                self.tasks.lock().push(task);
+            }
+        }
+
+        let executor = Arc::new(HoldingExecutor::default());
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .executor(Arc::clone(&executor))
+            .build();
+
+        // Schedule an on-demand housekeeping task by filling the write log.
+        for i in 0..1024 {
+            cache.insert(i, i);
+        }
+        // The periodical task and at least one on-demand task.
+        assert!(executor.tasks.lock().len() >= 2);
+
+        // Dropping the cache should not wait for the tasks that never run.
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            std::mem::drop(cache);
+            tx.send(()).unwrap();
+        });
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // Running the tasks after the cache is dropped should do nothing.
+        let tasks = std::mem::take(&mut *executor.tasks.lock());
+        for task in tasks {
+            task();
+        }
+    }
+
+    #[test]
+    fn expiration_scheduling() {
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+
+        let start = std::time::Instant::now();
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_millis(1200))
+            .eviction_listener(listener)
+            .expiration_scheduling_enabled(true)
+            .build();
+
+        cache.insert('a', "alice");
+
+        // The periodical housekeeping runs at about 500, 800, 1100 and 1400
+        // milliseconds after the cache is created. The entry expires at 1200
+        // milliseconds, and it should be removed by the scheduled housekeeping
+        // rather than the periodical one at 1400 milliseconds.
+        if let Some(d) = Duration::from_millis(1300).checked_sub(start.elapsed()) {
+            std::thread::sleep(d);
+        }
+
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new('a'), "alice", RemovalCause::Expired)]
+        );
+        assert_eq!(cache.entry_count(), 0);
+    }
+
+    #[test]
+    fn ticker_and_deterministic_mode() {
+        use crate::ticker::MockTicker;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let ticker = Arc::new(MockTicker::new());
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        let listener = move |k, v, cause| a1.lock().push((k, v, cause));
+        let listener_conf = notification::Configuration::builder()
+            .delivery_mode(DeliveryMode::Queued)
+            .build();
+        let reload_count = Arc::new(AtomicU32::new(0));
+        let count = Arc::clone(&reload_count);
+
+        let cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(30))
+            .eviction_listener_with_conf(listener, listener_conf)
+            .support_invalidation_closures()
+            .refresh_after_write(Duration::from_secs(10))
+            .reloader(move |_k: &&str, v: &u32| {
+                count.fetch_add(1, Ordering::AcqRel);
+                Some(*v + 1)
+            })
+            .ticker(Arc::clone(&ticker))
+            .deterministic_mode(true)
+            .build();
+
+        cache.insert("alice", 1);
+        cache.insert("bob", 10);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+
+        // The invalidation task and the notification task are run by sync.
+        let id = cache.invalidate_entries_if(|k, _v| *k == "bob").unwrap();
+        cache.sync();
+        assert_eq!(cache.entry_count(), 1);
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new("bob"), 10, invalidated_by(&id))]
+        );
+        actual.lock().clear();
+
+        // "alice" is stale. The reload is run by sync.
+        ticker.advance(Duration::from_secs(11));
+        assert_eq!(cache.get(&"alice"), Some(1));
+        assert_eq!(reload_count.load(Ordering::Acquire), 0);
+        cache.sync();
+        assert_eq!(reload_count.load(Ordering::Acquire), 1);
+        assert_eq!(cache.get(&"alice"), Some(2));
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new("alice"), 1, RemovalCause::Replaced)]
+        );
+        actual.lock().clear();
+
+        // "alice" expires 30 seconds after the reload.
+        ticker.advance(Duration::from_secs(29));
+        assert!(cache.contains_key(&"alice"));
+        ticker.advance(Duration::from_secs(1));
+        assert!(!cache.contains_key(&"alice"));
+        assert!(actual.lock().is_empty());
+
+        cache.sync();
+        assert_eq!(cache.entry_count(), 0);
+        assert_eq!(
+            *actual.lock(),
+            vec![(Arc::new("alice"), 2, RemovalCause::Expired)]
+        );
+    }
+
+    #[test]
+    fn memory_based_bounding() {
+        use crate::common::concurrent::entry_overhead;
+        use std::mem::size_of;
+
+        // Use the deterministic mode rather than `reconfigure_for_testing`, which
+        // sizes the frequency sketch by the max capacity in bytes.
+        //
+        // Weights larger than u32::MAX.
+        let gib: u64 = 1024 * 1024 * 1024;
+        let cache = Cache::builder()
+            .max_capacity(12 * gib)
+            .weigher_u64(move |_k: &char, v: &u64| *v * gib)
+            .deterministic_mode(true)
+            .build();
+
+        cache.insert('a', 5);
+        cache.insert('b', 5);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2);
+        assert_eq!(cache.weighted_size(), 10 * gib);
+
+        // Bounded by the memory usage of the keys and values.
+        let weight = (size_of::<u32>() + size_of::<String>() + 100) as u64;
+        let cache = Cache::builder()
+            .max_memory_bytes(3 * weight)
+            .deterministic_mode(true)
+            .build();
+
+        for key in 0..3u32 {
+            cache.insert(key, String::with_capacity(100));
+        }
+        cache.sync();
+        assert_eq!(cache.entry_count(), 3);
+        assert_eq!(cache.weighted_size(), 3 * weight);
+
+        for key in 3..6u32 {
+            cache.insert(key, String::with_capacity(100));
+        }
+        cache.sync();
+        assert_eq!(cache.entry_count(), 3);
+        assert_eq!(cache.weighted_size(), 3 * weight);
+
+        // With the per-entry overhead of the cache.
+        let overhead = entry_overhead::<u32, String>(true, false);
+        assert!(overhead > 0);
+        let cache = Cache::builder()
+            .max_memory_bytes(1024 * 1024)
+            .include_entry_overhead(true)
+            .time_to_live(Duration::from_secs(60))
+            .deterministic_mode(true)
+            .build();
+
+        cache.insert(0, String::with_capacity(100));
+        cache.sync();
+        assert_eq!(cache.weighted_size(), weight + overhead);
+
+        // Without a weigher.
+        let cache = Cache::builder()
+            .max_capacity(1024 * 1024)
+            .include_entry_overhead(true)
+            .deterministic_mode(true)
+            .build();
+
+        cache.insert(0u32, String::with_capacity(100));
+        cache.sync();
+        let overhead = entry_overhead::<u32, String>(false, false);
+        assert_eq!(
+            cache.weighted_size(),
+            (size_of::<u32>() + size_of::<String>()) as u64 + overhead
+        );
     }
 
     #[test]
@@ -4168,6 +6686,151 @@ mod tests {
         assert!(debug_str.ends_with('}'));
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_and_restore() {
+        let mut cache = Cache::new(3);
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        cache.insert("c".to_string(), 3);
+        cache.sync();
+        assert_eq!(cache.get("c"), Some(3));
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("b"), Some(2));
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("b"), Some(2));
+        cache.sync();
+        // counts: a -> 2, b -> 2, c -> 1
+        // access order: c, a, b
+
+        let mut snapshot = Vec::new();
+        cache.snapshot_to(&mut snapshot).unwrap();
+
+        let mut restored: Cache<String, u32> = Cache::builder()
+            .max_capacity(3)
+            .restore_from(&snapshot[..])
+            .unwrap();
+        restored.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let restored = restored;
+
+        assert_eq!(restored.entry_count(), 3);
+        assert!(restored.contains_key("a"));
+        assert!(restored.contains_key("b"));
+        assert!(restored.contains_key("c"));
+
+        // "d" should not be admitted because its frequency is not higher than c's.
+        restored.insert("d".to_string(), 4); //   count: d -> 0
+        restored.sync();
+        assert_eq!(restored.get("d"), None); //   d -> 1
+        restored.insert("d".to_string(), 4);
+        restored.sync();
+        assert!(!restored.contains_key("d"));
+        assert_eq!(restored.get("d"), None); //   d -> 2
+
+        // "d" should be admitted and "c" should be evicted because "c" is at the
+        // LRU position.
+        restored.insert("d".to_string(), 4);
+        restored.sync();
+        assert_eq!(restored.get("a"), Some(1));
+        assert_eq!(restored.get("b"), Some(2));
+        assert_eq!(restored.get("c"), None);
+        assert_eq!(restored.get("d"), Some(4));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_and_restore_with_expiration() {
+        let ttl = Duration::from_millis(1500);
+        let mut cache = Cache::builder().time_to_live(ttl).build();
+        cache.reconfigure_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert("a".to_string(), 1);
+        cache.sync();
+
+        mock.increment(Duration::from_millis(1000));
+        cache.insert("b".to_string(), 2);
+        cache.sync();
+
+        let mut snapshot = Vec::new();
+        cache.snapshot_to(&mut snapshot).unwrap();
+
+        // The restored cache uses the real clock. "a" has 500 ms to live and "b"
+        // has 1500 ms.
+        let restored: Cache<String, u32> = Cache::builder()
+            .time_to_live(ttl)
+            .restore_from(&snapshot[..])
+            .unwrap();
+        assert_eq!(restored.get("a"), Some(1));
+        assert_eq!(restored.get("b"), Some(2));
+
+        std::thread::sleep(Duration::from_millis(800));
+        assert_eq!(restored.get("a"), None);
+        assert_eq!(restored.get("b"), Some(2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn restore_from_invalid_snapshot() {
+        use crate::SnapshotError;
+
+        let result = Cache::<String, u32>::builder().restore_from(&b"not a snapshot"[..]);
+        assert!(matches!(result, Err(SnapshotError::Serialization(_))));
+
+        let result = Cache::<String, u32>::builder().restore_from(&b""[..]);
+        assert!(matches!(result, Err(SnapshotError::Io(_))));
+
+        // A snapshot of a cache with different key type.
+        let cache = Cache::new(10);
+        cache.insert(1u8, 1u32);
+        let mut snapshot = Vec::new();
+        cache.snapshot_to(&mut snapshot).unwrap();
+        let result = Cache::<String, u32>::builder().restore_from(&snapshot[..]);
+        assert!(matches!(result, Err(SnapshotError::Serialization(_))));
+    }
+
+    /// An executor running each task on a new thread, which counts the tasks.
+    #[derive(Default)]
+    struct ThreadPerTaskExecutor {
+        tasks: std::sync::atomic::AtomicUsize,
+        delayed_tasks: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Executor for ThreadPerTaskExecutor {
+        fn execute(&self, task: Task) {
+            self.tasks.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+            std::thread::spawn(task);
+        }
+
+        fn execute_after(&self, delay: Duration, task: Task) {
+            self.delayed_tasks
+                .fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                task();
+            });
+        }
+    }
+
+    /// Returns the removal cause of an entry invalidated by the predicate.
+    fn invalidated_by(predicate_id: &str) -> RemovalCause {
+        RemovalCause::Invalidated {
+            predicate_id: Some(PredicateHandle::parse(predicate_id)),
+        }
+    }
+
     type NotificationTuple<K, V> = (Arc<K>, V, RemovalCause);
 
     fn verify_notification_vec<K, V, S>(
@@ -1,4 +1,4 @@
-use crate::Entry;
+use crate::{ops::compute::Op, Entry};
 
 use super::Cache;
 
@@ -6,6 +6,7 @@ use std::{
     borrow::Borrow,
     hash::{BuildHasher, Hash},
     sync::Arc,
+    time::Duration,
 };
 
 /// Provides advanced methods to select or insert an entry of the cache.
@@ -68,7 +69,7 @@ where
     {
         let key = Arc::new(self.owned_key);
         self.cache
-            .get_or_insert_with_hash(key, self.hash, Default::default)
+            .get_or_insert_with_hash(key, self.hash, Default::default, None)
     }
 
     /// Returns the corresponding [`Entry`] for the key given when this entry
@@ -98,7 +99,38 @@ where
     pub fn or_insert(self, default: V) -> Entry<K, V> {
         let key = Arc::new(self.owned_key);
         let init = || default;
-        self.cache.get_or_insert_with_hash(key, self.hash, init)
+        self.cache
+            .get_or_insert_with_hash(key, self.hash, init, None)
+    }
+
+    /// Works like [`or_insert`](#method.or_insert), but the inserted entry will
+    /// expire after the given `ttl`, regardless of the `time_to_live` and
+    /// `expire_after` settings of the cache. The TTL is not applied if the entry
+    /// already exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    /// use std::time::Duration;
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// let ttl = Duration::from_secs(30);
+    /// let entry = cache.entry(key.clone()).or_insert_with_ttl(3, ttl);
+    /// assert!(entry.is_fresh());
+    /// assert_eq!(entry.into_value(), 3);
+    ///
+    /// let entry = cache.entry(key).or_insert_with_ttl(6, ttl);
+    /// assert!(!entry.is_fresh());
+    /// assert_eq!(entry.into_value(), 3);
+    /// ```
+    pub fn or_insert_with_ttl(self, default: V, ttl: Duration) -> Entry<K, V> {
+        let key = Arc::new(self.owned_key);
+        let init = || default;
+        self.cache
+            .get_or_insert_with_hash(key, self.hash, init, Some(ttl))
     }
 
     /// Returns the corresponding [`Entry`] for the key given when this entry
@@ -279,6 +311,125 @@ where
         self.cache
             .get_or_try_insert_with_hash_and_fun(key, self.hash, init, true)
     }
+
+    /// Performs a compute operation on the cached entry by evaluating the closure
+    /// `f`. The closure takes the current entry (`None` if the entry does not
+    /// exist), and returns an [`Op`] telling what to do with the entry:
+    ///
+    /// - `Op::Put(V)`: Inserts the value, or replaces the existing value with it.
+    /// - `Op::Remove`: Removes the entry.
+    /// - `Op::Nop`: Leaves the entry as it is.
+    ///
+    /// Returns the entry after the operation, or `None` if the entry does not exist
+    /// (e.g. it has been removed). The returned entry is fresh only if the value was
+    /// put by `f`.
+    ///
+    /// [`Op`]: ../ops/compute/enum.Op.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::{ops::compute::Op, sync::Cache};
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// // Increment the counter, or remove it when it reaches 2.
+    /// let incr = |maybe_entry: Option<moka::Entry<String, u32>>| match maybe_entry {
+    ///     Some(entry) if *entry.value() >= 2 => Op::Remove,
+    ///     Some(entry) => Op::Put(entry.into_value() + 1),
+    ///     None => Op::Put(1),
+    /// };
+    ///
+    /// let entry = cache.entry(key.clone()).and_compute_with(incr);
+    /// assert_eq!(entry.map(|e| e.into_value()), Some(1));
+    /// let entry = cache.entry(key.clone()).and_compute_with(incr);
+    /// assert_eq!(entry.map(|e| e.into_value()), Some(2));
+    /// let entry = cache.entry(key.clone()).and_compute_with(incr);
+    /// assert!(entry.is_none());
+    /// assert!(!cache.contains_key(&key));
+    /// ```
+    ///
+    /// # Concurrent calls on the same key
+    ///
+    /// The compute operation is atomic: the returned `Op` is applied only if the
+    /// entry has not been modified by another write, such as `insert`, `invalidate`
+    /// or another compute operation, since `f` read it. Otherwise, `f` is evaluated
+    /// again with the new entry. Therefore, `f` can be evaluated more than once
+    /// and should not have side effects.
+    pub fn and_compute_with<F>(self, f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Op<V>,
+    {
+        let key = Arc::new(self.owned_key);
+        self.cache.compute_with_hash_and_fun(key, self.hash, f)
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the closure
+    /// `f` is evaluated only when the entry exists. If the entry does not exist,
+    /// returns `None` without doing anything.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::{ops::compute::Op, sync::Cache};
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// let entry = cache
+    ///     .entry(key.clone())
+    ///     .and_compute_if_present(|entry| Op::Put(entry.into_value() + 1));
+    /// assert!(entry.is_none());
+    ///
+    /// cache.insert(key.clone(), 1);
+    /// let entry = cache
+    ///     .entry(key.clone())
+    ///     .and_compute_if_present(|entry| Op::Put(entry.into_value() + 1));
+    /// assert_eq!(entry.map(|e| e.into_value()), Some(2));
+    /// ```
+    pub fn and_compute_if_present<F>(self, mut f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Entry<K, V>) -> Op<V>,
+    {
+        let key = Arc::new(self.owned_key);
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, |maybe_entry| match maybe_entry {
+                Some(entry) => f(entry),
+                None => Op::Nop,
+            })
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the closure
+    /// `f` always returns a value to insert or to replace the existing value with.
+    /// Returns the entry holding the new value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// let incr = |maybe_entry: Option<moka::Entry<String, u32>>| {
+    ///     maybe_entry.map(|e| e.into_value() + 1).unwrap_or(1)
+    /// };
+    ///
+    /// let entry = cache.entry(key.clone()).and_upsert_with(incr);
+    /// assert_eq!(entry.into_value(), 1);
+    /// let entry = cache.entry(key.clone()).and_upsert_with(incr);
+    /// assert_eq!(entry.into_value(), 2);
+    /// ```
+    pub fn and_upsert_with<F>(self, mut f: F) -> Entry<K, V>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> V,
+    {
+        let key = Arc::new(self.owned_key);
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, |maybe_entry| Op::Put(f(maybe_entry)))
+            .expect("Bug: The upserted entry is None")
+    }
 }
 
 /// Provides advanced methods to select or insert an entry of the cache.
@@ -345,7 +496,7 @@ where
         V: Default,
     {
         self.cache
-            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, Default::default)
+            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, Default::default, None)
     }
 
     /// Returns the corresponding [`Entry`] for the reference of the key given when
@@ -375,7 +526,36 @@ where
     pub fn or_insert(self, default: V) -> Entry<K, V> {
         let init = || default;
         self.cache
-            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, init)
+            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, init, None)
+    }
+
+    /// Works like [`or_insert`](#method.or_insert), but the inserted entry will
+    /// expire after the given `ttl`, regardless of the `time_to_live` and
+    /// `expire_after` settings of the cache. The TTL is not applied if the entry
+    /// already exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    /// use std::time::Duration;
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// let ttl = Duration::from_secs(30);
+    /// let entry = cache.entry_by_ref(&key).or_insert_with_ttl(3, ttl);
+    /// assert!(entry.is_fresh());
+    /// assert_eq!(entry.into_value(), 3);
+    ///
+    /// let entry = cache.entry_by_ref(&key).or_insert_with_ttl(6, ttl);
+    /// assert!(!entry.is_fresh());
+    /// assert_eq!(entry.into_value(), 3);
+    /// ```
+    pub fn or_insert_with_ttl(self, default: V, ttl: Duration) -> Entry<K, V> {
+        let init = || default;
+        self.cache
+            .get_or_insert_with_hash_by_ref(self.ref_key, self.hash, init, Some(ttl))
     }
 
     /// Returns the corresponding [`Entry`] for the reference of the key given when
@@ -564,4 +744,123 @@ where
         self.cache
             .get_or_try_insert_with_hash_by_ref_and_fun(self.ref_key, self.hash, init, true)
     }
+
+    /// Performs a compute operation on the cached entry by evaluating the closure
+    /// `f`. The closure takes the current entry (`None` if the entry does not
+    /// exist), and returns an [`Op`] telling what to do with the entry:
+    ///
+    /// - `Op::Put(V)`: Inserts the value, or replaces the existing value with it.
+    /// - `Op::Remove`: Removes the entry.
+    /// - `Op::Nop`: Leaves the entry as it is.
+    ///
+    /// Returns the entry after the operation, or `None` if the entry does not exist
+    /// (e.g. it has been removed). The returned entry is fresh only if the value was
+    /// put by `f`.
+    ///
+    /// [`Op`]: ../ops/compute/enum.Op.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::{ops::compute::Op, sync::Cache};
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// // Increment the counter, or remove it when it reaches 2.
+    /// let incr = |maybe_entry: Option<moka::Entry<String, u32>>| match maybe_entry {
+    ///     Some(entry) if *entry.value() >= 2 => Op::Remove,
+    ///     Some(entry) => Op::Put(entry.into_value() + 1),
+    ///     None => Op::Put(1),
+    /// };
+    ///
+    /// let entry = cache.entry_by_ref(&key).and_compute_with(incr);
+    /// assert_eq!(entry.map(|e| e.into_value()), Some(1));
+    /// let entry = cache.entry_by_ref(&key).and_compute_with(incr);
+    /// assert_eq!(entry.map(|e| e.into_value()), Some(2));
+    /// let entry = cache.entry_by_ref(&key).and_compute_with(incr);
+    /// assert!(entry.is_none());
+    /// assert!(!cache.contains_key(&key));
+    /// ```
+    ///
+    /// # Concurrent calls on the same key
+    ///
+    /// The compute operation is atomic: the returned `Op` is applied only if the
+    /// entry has not been modified by another write, such as `insert`, `invalidate`
+    /// or another compute operation, since `f` read it. Otherwise, `f` is evaluated
+    /// again with the new entry. Therefore, `f` can be evaluated more than once
+    /// and should not have side effects.
+    pub fn and_compute_with<F>(self, f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> Op<V>,
+    {
+        let key = Arc::new(self.ref_key.to_owned());
+        self.cache.compute_with_hash_and_fun(key, self.hash, f)
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the closure
+    /// `f` is evaluated only when the entry exists. If the entry does not exist,
+    /// returns `None` without doing anything.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::{ops::compute::Op, sync::Cache};
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// let entry = cache
+    ///     .entry_by_ref(&key)
+    ///     .and_compute_if_present(|entry| Op::Put(entry.into_value() + 1));
+    /// assert!(entry.is_none());
+    ///
+    /// cache.insert(key.clone(), 1);
+    /// let entry = cache
+    ///     .entry_by_ref(&key)
+    ///     .and_compute_if_present(|entry| Op::Put(entry.into_value() + 1));
+    /// assert_eq!(entry.map(|e| e.into_value()), Some(2));
+    /// ```
+    pub fn and_compute_if_present<F>(self, mut f: F) -> Option<Entry<K, V>>
+    where
+        F: FnMut(Entry<K, V>) -> Op<V>,
+    {
+        let key = Arc::new(self.ref_key.to_owned());
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, |maybe_entry| match maybe_entry {
+                Some(entry) => f(entry),
+                None => Op::Nop,
+            })
+    }
+
+    /// Works like [`and_compute_with`](#method.and_compute_with), but the closure
+    /// `f` always returns a value to insert or to replace the existing value with.
+    /// Returns the entry holding the new value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::sync::Cache;
+    ///
+    /// let cache: Cache<String, u32> = Cache::new(100);
+    /// let key = "key1".to_string();
+    ///
+    /// let incr = |maybe_entry: Option<moka::Entry<String, u32>>| {
+    ///     maybe_entry.map(|e| e.into_value() + 1).unwrap_or(1)
+    /// };
+    ///
+    /// let entry = cache.entry_by_ref(&key).and_upsert_with(incr);
+    /// assert_eq!(entry.into_value(), 1);
+    /// let entry = cache.entry_by_ref(&key).and_upsert_with(incr);
+    /// assert_eq!(entry.into_value(), 2);
+    /// ```
+    pub fn and_upsert_with<F>(self, mut f: F) -> Entry<K, V>
+    where
+        F: FnMut(Option<Entry<K, V>>) -> V,
+    {
+        let key = Arc::new(self.ref_key.to_owned());
+        self.cache
+            .compute_with_hash_and_fun(key, self.hash, |maybe_entry| Op::Put(f(maybe_entry)))
+            .expect("Bug: The upserted entry is None")
+    }
 }
@@ -1,13 +1,26 @@
 use super::{
     cache::Cache, CacheBuilder, ConcurrentCacheExt, OwnedKeyEntrySelector, RefKeyEntrySelector,
+    RefresherArc,
 };
 use crate::{
-    common::concurrent::{housekeeper, Weigher},
+    common::concurrent::{housekeeper, ExpiryArc, Weigher},
     notification::{self, EvictionListener},
+    stats::{CacheStats, StatsCounterArc},
     sync_base::iter::{Iter, ScanningGet},
+    ticker::TickerArc,
     Entry, Policy, PredicateError,
 };
 
+#[cfg(feature = "serde")]
+use crate::{
+    sync_base::snapshot::{self, CacheSnapshot},
+    SnapshotError,
+};
+#[cfg(feature = "serde")]
+use serde_crate::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "serde")]
+use std::io::{Read, Write};
+
 use std::{
     borrow::Borrow,
     collections::hash_map::RandomState,
@@ -108,8 +121,12 @@ where
             None,
             None,
             None,
+            None,
             false,
-            housekeeper::Configuration::new_thread_pool(true),
+            None,
+            None,
+            None,
+            housekeeper::Configuration::new_thread_pool(true, None),
         )
     }
 
@@ -136,6 +153,17 @@ impl<K, V, S> SegmentedCache<K, V, S> {
         let mut policy = self.inner.segments[0].policy();
         policy.set_max_capacity(self.inner.desired_capacity);
         policy.set_num_segments(self.inner.segments.len());
+        if policy.window_capacity().is_some() {
+            // Each segment adapts its own split, so sum them up.
+            let (window, protected) = self.inner.segments.iter().fold((0, 0), |acc, seg| {
+                let p = seg.policy();
+                (
+                    acc.0 + p.window_capacity().unwrap_or_default(),
+                    acc.1 + p.protected_capacity().unwrap_or_default(),
+                )
+            });
+            policy.set_window_and_protected_capacities(window, protected);
+        }
         policy
     }
 
@@ -195,6 +223,38 @@ impl<K, V, S> SegmentedCache<K, V, S> {
             .map(|seg| seg.weighted_size())
             .sum()
     }
+
+    /// Returns a snapshot of the statistics of this cache.
+    ///
+    /// The statistics are recorded only when the cache was built with
+    /// [`CacheBuilder::record_stats`][builder-record-stats]. Otherwise, all counters
+    /// in the returned `CacheStats` are zero.
+    ///
+    /// The eviction counts are updated by the pending internal tasks, so they can be
+    /// behind the actual numbers. Performing a `sync()` first will mitigate this.
+    ///
+    /// [builder-record-stats]: ./struct.CacheBuilder.html#method.record_stats
+    pub fn stats(&self) -> CacheStats {
+        // All segments share the same stats counter.
+        self.inner.segments[0].stats()
+    }
+
+    /// Returns the number of the eviction notifications dropped because the queue
+    /// of the [`Queued`][delivery-mode-queued] delivery mode was full.
+    ///
+    /// The notifications are dropped only with the `DropNewest` or `DropOldest`
+    /// [`OverflowPolicy`][overflow-policy]. Unlike [`stats`](#method.stats), this
+    /// count is kept even if the cache was built without `record_stats`.
+    ///
+    /// [delivery-mode-queued]: ../notification/enum.DeliveryMode.html#variant.Queued
+    /// [overflow-policy]: ../notification/enum.OverflowPolicy.html
+    pub fn dropped_notification_count(&self) -> u64 {
+        self.inner
+            .segments
+            .iter()
+            .map(|seg| seg.dropped_notification_count())
+            .sum()
+    }
 }
 
 impl<K, V, S> SegmentedCache<K, V, S>
@@ -218,7 +278,11 @@ where
         eviction_listener_conf: Option<notification::Configuration>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryArc<K, V>>,
         invalidator_enabled: bool,
+        stats_counter: Option<StatsCounterArc>,
+        refresher: Option<RefresherArc<K, V>>,
+        ticker: Option<TickerArc>,
         housekeeper_conf: housekeeper::Configuration,
     ) -> Self {
         Self {
@@ -233,7 +297,11 @@ where
                 eviction_listener_conf,
                 time_to_live,
                 time_to_idle,
+                expiry,
                 invalidator_enabled,
+                stats_counter,
+                refresher,
+                ticker,
                 housekeeper_conf,
             )),
         }
@@ -469,6 +537,79 @@ where
         self.inner.select(hash).insert_with_hash(key, hash, value);
     }
 
+    /// Inserts the key-value pairs into the cache.
+    ///
+    /// This is faster than calling [`insert`](#method.insert) for each pair, as
+    /// the pairs are grouped by the internal segments and the pending writes of
+    /// each segment are applied to the cache policy (admission and eviction) in
+    /// bulk. See [`Cache::insert_all`][cache-insert-all] for more details.
+    ///
+    /// If the cache has a key present, the value is updated.
+    ///
+    /// [cache-insert-all]: ./struct.Cache.html#method.insert_all
+    pub fn insert_all<I>(&self, entries: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut batches = self.inner.new_batches();
+        for (key, value) in entries {
+            let hash = self.inner.hash(&key);
+            let index = self.inner.segment_index_from_hash(hash);
+            batches[index].push((Arc::new(key), hash, value));
+        }
+        for (segment, batch) in self.inner.segments.iter().zip(batches) {
+            if !batch.is_empty() {
+                segment.insert_all_with_hash(batch);
+            }
+        }
+    }
+
+    /// Inserts a key-value pair into the cache with its own time-to-live.
+    ///
+    /// The entry will expire after the given `ttl` from now, regardless of the
+    /// `time_to_live` and `expire_after` settings of the cache. If the cache has
+    /// this key present, the value and its expiration time are updated.
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        let hash = self.inner.hash(&key);
+        let key = Arc::new(key);
+        self.inner
+            .select(hash)
+            .insert_with_hash_and_ttl(key, hash, value, Some(ttl));
+    }
+
+    /// Inserts a key-value pair into the cache only if the key is absent. Returns
+    /// `None` if the value was inserted, or `Some` with a clone of the existing
+    /// value if the key was present.
+    ///
+    /// See [`Cache::insert_if_absent`][cache-insert-if-absent] for more details.
+    ///
+    /// [cache-insert-if-absent]: ./struct.Cache.html#method.insert_if_absent
+    pub fn insert_if_absent(&self, key: K, value: V) -> Option<V> {
+        let hash = self.inner.hash(&key);
+        let key = Arc::new(key);
+        self.inner
+            .select(hash)
+            .insert_if_absent_with_hash(key, hash, value)
+    }
+
+    /// Replaces the value of the key with `new` only if the current value is equal
+    /// to `old`. Returns `true` if the value was replaced.
+    ///
+    /// See [`Cache::replace`][cache-replace] for more details.
+    ///
+    /// [cache-replace]: ./struct.Cache.html#method.replace
+    pub fn replace<Q>(&self, key: &Q, old: &V, new: V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: PartialEq,
+    {
+        let hash = self.inner.hash(key);
+        self.inner
+            .select(hash)
+            .replace_with_hash(key, hash, old, new)
+    }
+
     /// Discards any cached value for the key.
     ///
     /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
@@ -482,6 +623,75 @@ where
         self.inner.select(hash).invalidate_with_hash(key, hash);
     }
 
+    /// Discards any cached value for the key and returns a _clone_ of the value.
+    ///
+    /// See [`Cache::remove`][cache-remove] for more details.
+    ///
+    /// [cache-remove]: ./struct.Cache.html#method.remove
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_k, v)| v)
+    }
+
+    /// Works like [`remove`](#method.remove), but returns the key along with the
+    /// value.
+    pub fn remove_entry<Q>(&self, key: &Q) -> Option<(Arc<K>, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.inner.hash(key);
+        self.inner.select(hash).remove_entry_with_hash(key, hash)
+    }
+
+    /// Discards the cached value for the key only if the `predicate` returns `true`
+    /// for the value. Returns the removed value.
+    ///
+    /// See [`Cache::remove_if`][cache-remove-if] for more details.
+    ///
+    /// [cache-remove-if]: ./struct.Cache.html#method.remove_if
+    pub fn remove_if<Q, F>(&self, key: &Q, predicate: F) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnMut(&V) -> bool,
+    {
+        let hash = self.inner.hash(key);
+        self.inner
+            .select(hash)
+            .remove_if_with_hash(key, hash, predicate)
+    }
+
+    /// Discards any cached values for the keys.
+    ///
+    /// Like [`insert_all`](#method.insert_all), this is faster than calling
+    /// [`invalidate`](#method.invalidate) for each key, as the removals are
+    /// applied to the cache policy in bulk.
+    ///
+    /// The key may be any borrowed form of the cache's key type, but `Hash` and `Eq`
+    /// on the borrowed form _must_ match those for the key type.
+    pub fn invalidate_all_keys<'a, I, Q>(&self, keys: I)
+    where
+        I: IntoIterator<Item = &'a Q>,
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+    {
+        let mut batches = self.inner.new_batches();
+        for key in keys {
+            let hash = self.inner.hash(key);
+            let index = self.inner.segment_index_from_hash(hash);
+            batches[index].push((key, hash));
+        }
+        for (segment, batch) in self.inner.segments.iter().zip(batches) {
+            if !batch.is_empty() {
+                segment.invalidate_all_keys_with_hash(batch);
+            }
+        }
+    }
+
     /// Discards all cached values.
     ///
     /// This method returns immediately and a background thread will evict all the
@@ -498,6 +708,17 @@ where
         }
     }
 
+    /// Discards all cached values, and blocks until all eviction notifications,
+    /// including the ones queued before this call, have been delivered to the
+    /// eviction listener.
+    ///
+    /// See [`Cache::shutdown`](./struct.Cache.html#method.shutdown) for more details.
+    pub fn shutdown(&self) {
+        for segment in self.inner.segments.iter() {
+            segment.shutdown();
+        }
+    }
+
     /// Discards cached values that satisfy a predicate.
     ///
     /// `invalidate_entries_if` takes a closure that returns `true` or `false`. This
@@ -603,6 +824,94 @@ where
     // }
 }
 
+#[cfg(feature = "serde")]
+impl<K, V, S> SegmentedCache<K, V, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    /// Writes a snapshot of this cache to the `writer`, so that the cache can be
+    /// restored later by [`CacheBuilder::restore_from`][restore-from].
+    ///
+    /// See [`Cache::snapshot_to`][cache-snapshot-to] for what will be included in
+    /// the snapshot. The snapshots of the internal segments are taken one by one,
+    /// so they may not be consistent with each other when the cache is updated
+    /// concurrently.
+    ///
+    /// This method is available only when the `serde` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`][snapshot-error] if it fails to serialize a key or
+    /// a value, or to write to the `writer`.
+    ///
+    /// [restore-from]: ./struct.CacheBuilder.html#method.restore_from
+    /// [cache-snapshot-to]: ./struct.Cache.html#method.snapshot_to
+    /// [snapshot-error]: ../enum.SnapshotError.html
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn snapshot_to<W>(&self, writer: W) -> Result<(), SnapshotError>
+    where
+        K: Serialize,
+        V: Serialize,
+        W: Write,
+    {
+        let snapshots = self
+            .inner
+            .segments
+            .iter()
+            .map(|segment| segment.snapshot())
+            .collect::<Vec<_>>();
+        let snapshots = snapshots.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+        snapshot::write_snapshot(writer, &snapshots)
+    }
+
+    pub(crate) fn restore_snapshot<R>(&self, reader: R) -> Result<(), SnapshotError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        R: Read,
+    {
+        let mut snapshots = snapshot::read_snapshot::<K, V>(reader)?;
+        let segments = &self.inner.segments;
+
+        // The hashes of the keys are different from the ones when the snapshot was
+        // taken, so distribute the entries to the segments again.
+        let mut batches = self.inner.new_batches();
+        for snapshot in &mut snapshots {
+            for entry in snapshot.entries.drain(..) {
+                let index = self
+                    .inner
+                    .segment_index_from_hash(self.inner.hash(&entry.key));
+                batches[index].push(entry);
+            }
+        }
+
+        // The policy states other than the entries, such as the window sizes, are
+        // restored only when the number of segments has not been changed.
+        let restore_segment_states = snapshots.len() == segments.len();
+        let sketch_enabled = snapshots.iter().any(|s| s.frequency_sketch_enabled);
+        let mut snapshots = snapshots.into_iter();
+        for (segment, entries) in segments.iter().zip(batches) {
+            let snapshot = match snapshots.next() {
+                Some(snapshot) if restore_segment_states => CacheSnapshot {
+                    entries,
+                    ..snapshot
+                },
+                _ => CacheSnapshot {
+                    max_capacity: None,
+                    window_max_weight: 0,
+                    protected_max_weight: 0,
+                    frequency_sketch_enabled: sketch_enabled,
+                    entries,
+                },
+            };
+            segment.restore(snapshot);
+        }
+        Ok(())
+    }
+}
+
 impl<'a, K, V, S> IntoIterator for &'a SegmentedCache<K, V, S>
 where
     K: Hash + Eq + Send + Sync + 'static,
@@ -713,7 +1022,11 @@ where
         eviction_listener_conf: Option<notification::Configuration>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryArc<K, V>>,
         invalidator_enabled: bool,
+        stats_counter: Option<StatsCounterArc>,
+        refresher: Option<RefresherArc<K, V>>,
+        ticker: Option<TickerArc>,
         housekeeper_conf: housekeeper::Configuration,
     ) -> Self {
         assert!(num_segments > 0);
@@ -737,7 +1050,11 @@ where
                     eviction_listener_conf.clone(),
                     time_to_live,
                     time_to_idle,
+                    expiry.as_ref().map(Arc::clone),
                     invalidator_enabled,
+                    stats_counter.as_ref().map(Arc::clone),
+                    refresher.as_ref().map(Arc::clone),
+                    ticker.clone(),
                     housekeeper_conf.clone(),
                 )
             })
@@ -768,6 +1085,11 @@ where
         &self.segments[index]
     }
 
+    /// Creates an empty batch for each segment.
+    fn new_batches<T>(&self) -> Vec<Vec<T>> {
+        (0..self.segments.len()).map(|_| Vec::new()).collect()
+    }
+
     #[inline]
     fn segment_index_from_hash(&self, hash: u64) -> usize {
         if self.segment_shift == 64 {
@@ -784,7 +1106,7 @@ mod tests {
     use crate::notification::{
         self,
         macros::{assert_eq_with_mode, assert_with_mode},
-        DeliveryMode, RemovalCause,
+        DeliveryMode, PredicateHandle, RemovalCause,
     };
     use parking_lot::Mutex;
     use std::{sync::Arc, time::Duration};
@@ -894,6 +1216,37 @@ mod tests {
         assert_eq!(cache.iter().count(), 3);
     }
 
+    #[test]
+    fn stats() {
+        let mut cache = SegmentedCache::builder(4)
+            .max_capacity(100)
+            .record_stats()
+            .build();
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        for i in 0..10 {
+            cache.insert(i, i);
+        }
+        cache.sync();
+
+        // The segments share the same stats counter.
+        for i in 0..20 {
+            cache.get(&i);
+        }
+        for i in 20..25 {
+            cache.get_with(i, || i);
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.hit_count(), 10);
+        assert_eq!(stats.miss_count(), 15);
+        assert_eq!(stats.load_success_count(), 5);
+        assert_eq!(stats.load_failure_count(), 0);
+    }
+
     #[test]
     fn size_aware_eviction() {
         run_test(DeliveryMode::Immediate);
@@ -1106,9 +1459,18 @@ mod tests {
             // https://github.com/moka-rs/moka/issues/155
 
             cache.invalidate_all();
-            expected.insert(Arc::new("a"), ("alice", RemovalCause::Explicit));
-            expected.insert(Arc::new("b"), ("bob", RemovalCause::Explicit));
-            expected.insert(Arc::new("c"), ("cindy", RemovalCause::Explicit));
+            expected.insert(
+                Arc::new("a"),
+                ("alice", RemovalCause::Invalidated { predicate_id: None }),
+            );
+            expected.insert(
+                Arc::new("b"),
+                ("bob", RemovalCause::Invalidated { predicate_id: None }),
+            );
+            expected.insert(
+                Arc::new("c"),
+                ("cindy", RemovalCause::Invalidated { predicate_id: None }),
+            );
             cache.sync();
 
             cache.insert("d", "david");
@@ -1127,6 +1489,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn conditional_writes() {
+        let cache = SegmentedCache::new(100, 4);
+
+        assert_eq!(cache.insert_if_absent("a", "alice"), None);
+        assert_eq!(cache.insert_if_absent("a", "anna"), Some("alice"));
+
+        assert!(!cache.replace(&"a", &"anna", "amy"));
+        assert!(cache.replace(&"a", &"alice", "amy"));
+        assert_eq!(cache.get(&"a"), Some("amy"));
+        assert!(!cache.replace(&"b", &"bob", "bill"));
+        assert!(!cache.contains_key(&"b"));
+
+        assert_eq!(cache.remove_if(&"a", |v| *v == "alice"), None);
+        assert_eq!(cache.remove_if(&"a", |v| *v == "amy"), Some("amy"));
+        assert!(!cache.contains_key(&"a"));
+    }
+
+    #[test]
+    fn shutdown() {
+        let actual = Arc::new(Mutex::new(Vec::new()));
+        let a1 = Arc::clone(&actual);
+        let cache = SegmentedCache::builder(4)
+            .max_capacity(100)
+            .eviction_listener(move |k, v, cause| a1.lock().push((k, v, cause)))
+            .build();
+
+        for i in 0..20 {
+            cache.insert(i, i * 10);
+        }
+        cache.shutdown();
+        assert_eq!(cache.entry_count(), 0);
+
+        let mut actual = actual.lock().clone();
+        actual.sort_by_key(|(k, _, _)| **k);
+        let expected = (0..20)
+            .map(|i| (Arc::new(i), i * 10, RemovalCause::Shutdown))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn remove_and_remove_entry() {
+        let cache = SegmentedCache::new(100, 4);
+        cache.insert("a", "alice");
+        cache.insert("b", "bob");
+
+        assert_eq!(cache.remove(&"a"), Some("alice"));
+        assert_eq!(cache.remove(&"a"), None);
+        assert_eq!(cache.remove_entry(&"b"), Some((Arc::new("b"), "bob")));
+        assert!(!cache.contains_key(&"b"));
+    }
+
+    #[test]
+    fn insert_all_and_invalidate_all_keys() {
+        let mut cache = SegmentedCache::new(10_000, 4);
+        cache.reconfigure_for_testing();
+
+        // Make the cache exterior immutable.
+        let cache = cache;
+
+        cache.insert_all((0..5_000).map(|i| (i, i * 10)));
+        cache.sync();
+        assert_eq!(cache.entry_count(), 5_000);
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&4_999), Some(49_990));
+
+        // Invalidate the even keys. The key 10_000 does not exist.
+        let keys = (0..5_000)
+            .step_by(2)
+            .chain(Some(10_000))
+            .collect::<Vec<_>>();
+        cache.invalidate_all_keys(&keys);
+        cache.sync();
+        assert_eq!(cache.entry_count(), 2_500);
+        assert_eq!(cache.get(&0), None);
+        assert_eq!(cache.get(&1), Some(10));
+        assert!(!cache.contains_key(&4_998));
+        assert!(cache.contains_key(&4_999));
+    }
+
     #[test]
     fn invalidate_entries_if() -> Result<(), Box<dyn std::error::Error>> {
         run_test(DeliveryMode::Immediate)?;
@@ -1143,8 +1586,17 @@ mod tests {
             let mut expected = HashMap::new();
 
             // Create an eviction listener.
+            //
+            // Each segment registers the predicate with its own ID, so the listener
+            // drops the IDs before recording the causes.
             let a1 = Arc::clone(&actual);
             let listener = move |k, v, cause| {
+                let cause = match cause {
+                    RemovalCause::Invalidated {
+                        predicate_id: Some(_),
+                    } => invalidated_by_predicate(),
+                    cause => cause,
+                };
                 a1.lock().insert(k, (v, cause));
             };
             let listener_conf = notification::Configuration::builder()
@@ -1185,8 +1637,8 @@ mod tests {
                 SEGMENTS,
                 delivery_mode
             );
-            expected.insert(Arc::new(0), ("alice", RemovalCause::Explicit));
-            expected.insert(Arc::new(2), ("alex", RemovalCause::Explicit));
+            expected.insert(Arc::new(0), ("alice", invalidated_by_predicate()));
+            expected.insert(Arc::new(2), ("alex", invalidated_by_predicate()));
 
             mock.increment(Duration::from_secs(5)); // 10 secs from the start.
 
@@ -1221,8 +1673,8 @@ mod tests {
                 SEGMENTS * 2,
                 delivery_mode
             );
-            expected.insert(Arc::new(1), ("bob", RemovalCause::Explicit));
-            expected.insert(Arc::new(3), ("alice", RemovalCause::Explicit));
+            expected.insert(Arc::new(1), ("bob", invalidated_by_predicate()));
+            expected.insert(Arc::new(3), ("alice", invalidated_by_predicate()));
 
             // Run the invalidation task and wait for it to finish. (TODO: Need a better way than sleeping)
             cache.sync(); // To submit the invalidation task.
@@ -1999,9 +2451,59 @@ mod tests {
         assert!(debug_str.ends_with('}'));
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_and_restore() {
+        use crate::sync::Cache;
+
+        let cache = SegmentedCache::new(100, 4);
+        for i in 0..50u32 {
+            cache.insert(i, i.to_string());
+        }
+        cache.sync();
+
+        let mut snapshot = Vec::new();
+        cache.snapshot_to(&mut snapshot).unwrap();
+
+        // Restore to the same number of segments.
+        let restored: SegmentedCache<u32, String> = SegmentedCache::builder(4)
+            .max_capacity(100)
+            .restore_from(&snapshot[..])
+            .unwrap();
+        assert_eq!(restored.entry_count(), 50);
+
+        // Restore to a different number of segments.
+        let restored: SegmentedCache<u32, String> = SegmentedCache::builder(2)
+            .max_capacity(100)
+            .restore_from(&snapshot[..])
+            .unwrap();
+        assert_eq!(restored.entry_count(), 50);
+        for i in 0..50u32 {
+            assert_eq!(restored.get(&i), Some(i.to_string()));
+        }
+
+        // Restore to a `Cache`.
+        let restored: Cache<u32, String> = Cache::builder()
+            .max_capacity(100)
+            .restore_from(&snapshot[..])
+            .unwrap();
+        assert_eq!(restored.entry_count(), 50);
+        for i in 0..50u32 {
+            assert_eq!(restored.get(&i), Some(i.to_string()));
+        }
+    }
+
     type NotificationPair<V> = (V, RemovalCause);
     type NotificationTriple<K, V> = (Arc<K>, V, RemovalCause);
 
+    /// Returns the removal cause of an entry invalidated by a predicate, with a
+    /// placeholder for the predicate ID.
+    fn invalidated_by_predicate() -> RemovalCause {
+        RemovalCause::Invalidated {
+            predicate_id: Some(PredicateHandle::new(0)),
+        }
+    }
+
     fn verify_notification_vec<K, V, S>(
         cache: &SegmentedCache<K, V, S>,
         actual: Arc<Mutex<Vec<NotificationTriple<K, V>>>>,
@@ -14,6 +14,9 @@ type ErrorObject = Arc<dyn Any + Send + Sync + 'static>;
 type WaiterValue<V> = Option<Result<V, ErrorObject>>;
 type Waiter<V> = TrioArc<RwLock<WaiterValue<V>>>;
 
+// Empty struct to be used as the type ID of the waiters for reloading values.
+struct Refreshing;
+
 pub(crate) enum InitResult<V, E> {
     Initialized(V),
     ReadExisting(V),
@@ -147,6 +150,61 @@ where
         self.do_try_init(&key, type_id, pre_init, init, post_init)
     }
 
+    /// Initializes the values of the keys in batches, and returns the pairs of the
+    /// keys and values. The waiters share the type ID with `init_or_read`, so
+    /// that concurrent `get_with` calls and batch calls on the same key are
+    /// deduplicated.
+    ///
+    /// # Panics
+    /// Panics if the `init` closure has been panicked, or it returned a wrong
+    /// number of values.
+    pub(crate) fn init_or_read_all(
+        &self,
+        keys: Vec<Arc<K>>,
+        get: impl FnMut(&Arc<K>) -> Option<V>,
+        mut init: impl FnMut(&[&K]) -> Vec<V>,
+        insert: impl FnMut(&Arc<K>, V),
+    ) -> Vec<(Arc<K>, V)> {
+        let type_id = TypeId::of::<()>();
+        let init = |keys: &[&K]| Ok::<_, ()>(init(keys));
+        match self.do_try_init_all(keys, type_id, get, init, insert) {
+            Ok(entries) => entries,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// # Panics
+    /// Panics if the `init` closure has been panicked, or it returned a wrong
+    /// number of values.
+    pub(crate) fn try_init_or_read_all<E>(
+        &self,
+        keys: Vec<Arc<K>>,
+        get: impl FnMut(&Arc<K>) -> Option<V>,
+        init: impl FnMut(&[&K]) -> Result<Vec<V>, E>,
+        insert: impl FnMut(&Arc<K>, V),
+    ) -> Result<Vec<(Arc<K>, V)>, Arc<E>>
+    where
+        E: Send + Sync + 'static,
+    {
+        let type_id = TypeId::of::<E>();
+        self.do_try_init_all(keys, type_id, get, init, insert)
+    }
+
+    /// Registers a waiter for reloading the value of the key, so that at most one
+    /// reload runs for a key at a time. Returns `false` if the value of the key is
+    /// already being reloaded. The caller must call `finish_refresh` after the
+    /// reload.
+    pub(crate) fn try_start_refresh(&self, key: &Arc<K>) -> bool {
+        let (cht_key, hash) = self.cht_key_hash(key, TypeId::of::<Refreshing>());
+        let waiter = TrioArc::new(RwLock::new(None));
+        self.try_insert_waiter(cht_key, hash, &waiter).is_none()
+    }
+
+    pub(crate) fn finish_refresh(&self, key: &Arc<K>) {
+        let (cht_key, hash) = self.cht_key_hash(key, TypeId::of::<Refreshing>());
+        self.remove_waiter(cht_key, hash);
+    }
+
     /// # Panics
     /// Panics if the `init` closure has been panicked.
     fn do_try_init<O, E>(
@@ -231,6 +289,142 @@ where
         }
     }
 
+    /// # Panics
+    /// Panics if the `init` closure has been panicked, or it returned a wrong
+    /// number of values.
+    fn do_try_init_all<E>(
+        &self,
+        keys: Vec<Arc<K>>,
+        type_id: TypeId,
+        // Closure to get an existing value from cache.
+        mut get: impl FnMut(&Arc<K>) -> Option<V>,
+        // Closure to evaluate the values of the keys in a batch.
+        mut init: impl FnMut(&[&K]) -> Result<Vec<V>, E>,
+        // Closure to insert a new value into cache.
+        mut insert: impl FnMut(&Arc<K>, V),
+    ) -> Result<Vec<(Arc<K>, V)>, Arc<E>>
+    where
+        E: Send + Sync + 'static,
+    {
+        use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+        const MAX_RETRIES: usize = 200;
+        let mut retries = 0;
+
+        let mut entries = Vec::with_capacity(keys.len());
+        let mut remaining = keys;
+
+        while !remaining.is_empty() {
+            let waiters = remaining
+                .iter()
+                .map(|_| TrioArc::new(RwLock::new(None)))
+                .collect::<Vec<Waiter<V>>>();
+            // The keys whose waiters we inserted, and the keys whose waiters had
+            // been inserted by other threads.
+            let mut ours = Vec::new();
+            let mut others = Vec::new();
+
+            // Insert our waiters. We must not wait for other threads' waiters until
+            // all of ours are unlocked, otherwise two threads could wait for each
+            // other forever.
+            for (key, waiter) in remaining.iter().zip(&waiters) {
+                let (cht_key, hash) = self.cht_key_hash(key, type_id);
+                let mut lock = waiter.write();
+                match self.try_insert_waiter(cht_key.clone(), hash, waiter) {
+                    None => {
+                        // Our waiter was inserted.
+                        // Check if the value has already been inserted by other thread.
+                        if let Some(value) = get(key) {
+                            *lock = Some(Ok(value.clone()));
+                            self.remove_waiter(cht_key, hash);
+                            entries.push((Arc::clone(key), value));
+                        } else {
+                            ours.push((key, cht_key, hash, lock));
+                        }
+                    }
+                    Some(res) => {
+                        std::mem::drop(lock);
+                        others.push((key, res));
+                    }
+                }
+            }
+
+            if !ours.is_empty() {
+                let init_keys = ours.iter().map(|(k, ..)| &***k).collect::<Vec<_>>();
+                // Catching panic is safe here as we do not try to evaluate the
+                // closure again with the same keys.
+                let result = catch_unwind(AssertUnwindSafe(|| init(&init_keys)));
+                match result {
+                    // Evaluated.
+                    Ok(Ok(values)) if values.len() == ours.len() => {
+                        for ((key, cht_key, hash, mut lock), value) in ours.into_iter().zip(values)
+                        {
+                            insert(key, value.clone());
+                            *lock = Some(Ok(value.clone()));
+                            self.remove_waiter(cht_key, hash);
+                            entries.push((Arc::clone(key), value));
+                        }
+                    }
+                    Ok(Ok(values)) => {
+                        let len = ours.len();
+                        for (_key, cht_key, hash, mut lock) in ours {
+                            *lock = None;
+                            // Remove the waiter so that others can retry.
+                            self.remove_waiter(cht_key, hash);
+                        }
+                        panic!(
+                            "The `init` closure returned {} values for {} keys",
+                            values.len(),
+                            len
+                        );
+                    }
+                    Ok(Err(e)) => {
+                        let err: ErrorObject = Arc::new(e);
+                        for (_key, cht_key, hash, mut lock) in ours {
+                            *lock = Some(Err(Arc::clone(&err)));
+                            self.remove_waiter(cht_key, hash);
+                        }
+                        return Err(err.downcast().unwrap());
+                    }
+                    // Panicked.
+                    Err(payload) => {
+                        for (_key, cht_key, hash, mut lock) in ours {
+                            *lock = None;
+                            // Remove the waiter so that others can retry.
+                            self.remove_waiter(cht_key, hash);
+                        }
+                        resume_unwind(payload);
+                    }
+                } // The write locks will be unlocked here.
+            }
+
+            // Wait for the read locks of others' waiters to become available.
+            let mut retry_keys = Vec::new();
+            for (key, waiter) in others {
+                match &*waiter.read() {
+                    Some(Ok(value)) => entries.push((Arc::clone(key), value.clone())),
+                    Some(Err(e)) => return Err(Arc::clone(e).downcast().unwrap()),
+                    // None means somebody else's init closure has been panicked.
+                    None => retry_keys.push(Arc::clone(key)),
+                }
+            }
+
+            if !retry_keys.is_empty() {
+                retries += 1;
+                if retries >= MAX_RETRIES {
+                    panic!(
+                        "Too many retries. Tried to read the return value from the `init` \
+                        closure but failed {} times. Maybe the `init` kept panicking?",
+                        retries
+                    );
+                }
+            }
+            remaining = retry_keys;
+        }
+
+        Ok(entries)
+    }
+
     #[inline]
     fn remove_waiter(&self, cht_key: (Arc<K>, TypeId), hash: u64) {
         self.waiters.remove(hash, |k| k == &cht_key);
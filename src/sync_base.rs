@@ -1,7 +1,12 @@
 pub(crate) mod base_cache;
+mod hill_climber;
 mod invalidator;
 pub(crate) mod iter;
 mod key_lock;
+pub(crate) mod refresher;
+
+#[cfg(feature = "serde")]
+pub(crate) mod snapshot;
 
 /// The type of the unique ID to identify a predicate used by
 /// [`Cache::invalidate_entries_if`][invalidate-if] method.
@@ -1,4 +1,5 @@
 use super::{
+    hill_climber::HillClimber,
     invalidator::{GetOrRemoveEntry, InvalidationResult, Invalidator, KeyDateLite, PredicateFun},
     iter::ScanningGet,
     key_lock::{KeyLock, KeyLockMap},
@@ -11,31 +12,41 @@ use crate::{
         concurrent::{
             atomic_time::AtomicInstant,
             constants::{
-                READ_LOG_FLUSH_POINT, READ_LOG_SIZE, WRITE_LOG_FLUSH_POINT,
+                MAX_SYNC_REPEATS, READ_LOG_FLUSH_POINT, READ_LOG_SIZE, WRITE_LOG_FLUSH_POINT,
                 WRITE_LOG_LOW_WATER_MARK, WRITE_LOG_SIZE,
             },
             deques::Deques,
             entry_info::EntryInfo,
             housekeeper::{self, Housekeeper, InnerSync, SyncPace},
-            AccessTime, KeyDate, KeyHash, KeyHashDate, KvEntry, ReadOp, ValueEntry, Weigher,
-            WriteOp,
+            timer_wheel::TimerWheel,
+            AccessTime, ExpiryArc, KeyDate, KeyHash, KeyHashDate, KvEntry, ReadOp, ValueEntry,
+            Weigher, WriteOp,
         },
         deque::{DeqNode, Deque},
         frequency_sketch::FrequencySketch,
         time::{CheckedTimeOps, Clock, Instant},
         CacheRegion,
     },
+    executor::ExecutorArc,
     notification::{
         self,
         notifier::{RemovalNotifier, RemovedEntry},
-        EvictionListener, RemovalCause,
+        Listener, RemovalCause,
     },
+    stats::{CacheStats, StatsCounter, StatsCounterArc},
+    ticker::TickerArc,
     Entry, Policy, PredicateError,
 };
 
 #[cfg(feature = "unstable-debug-counters")]
 use common::concurrent::debug_counters::CacheDebugStats;
 
+#[cfg(feature = "serde")]
+use super::snapshot::{CacheSnapshot, EntrySnapshot};
+
+#[cfg(feature = "future")]
+use crate::notification::notifier::AsyncRemovalNotifier;
+
 use crossbeam_channel::{Receiver, Sender, TrySendError};
 use crossbeam_utils::atomic::AtomicCell;
 use parking_lot::{Mutex, RwLock};
@@ -56,6 +67,17 @@ use triomphe::Arc as TrioArc;
 
 pub(crate) type HouseKeeperArc<K, V, S> = Arc<Housekeeper<Inner<K, V, S>>>;
 
+/// A value entry replaced by an update. As the old and new entries share the same
+/// `EntryInfo`, the timestamps of the old entry are captured here before they are
+/// overwritten by the update.
+struct ReplacedEntry<K, V> {
+    entry: TrioArc<ValueEntry<K, V>>,
+    last_accessed: Option<Instant>,
+    last_modified: Option<Instant>,
+    expiration_time: Option<Instant>,
+    is_ttl_overridden: bool,
+}
+
 pub(crate) struct BaseCache<K, V, S = RandomState> {
     pub(crate) inner: Arc<Inner<K, V, S>>,
     read_op_ch: Sender<ReadOp<K, V>>,
@@ -102,28 +124,93 @@ impl<K, V, S> BaseCache<K, V, S> {
         self.inner.weighted_size()
     }
 
+    pub(crate) fn stats(&self) -> CacheStats {
+        self.inner.stats()
+    }
+
+    pub(crate) fn dropped_notification_count(&self) -> u64 {
+        self.inner
+            .removal_notifier
+            .as_ref()
+            .map(|rn| rn.dropped_notification_count())
+            .unwrap_or_default()
+    }
+
+    /// Evaluates the `init` closure of `get_with` and friends. If the statistics
+    /// are enabled, records the time to evaluate it, and whether it is succeeded
+    /// or not by calling the `is_success` closure on the returned value.
+    pub(crate) fn load_with_stats<O>(
+        &self,
+        init: impl FnOnce() -> O,
+        is_success: impl FnOnce(&O) -> bool,
+    ) -> O {
+        if let Some(stats) = self.inner.stats_counter() {
+            let start = std::time::Instant::now();
+            let value = init();
+            record_load(stats, is_success(&value), start.elapsed());
+            value
+        } else {
+            init()
+        }
+    }
+
+    /// The async version of [`load_with_stats`](#method.load_with_stats).
+    #[cfg(feature = "future")]
+    pub(crate) async fn load_with_stats_async<O>(
+        &self,
+        init: impl std::future::Future<Output = O>,
+        is_success: impl FnOnce(&O) -> bool,
+    ) -> O {
+        if let Some(stats) = self.inner.stats_counter() {
+            let start = std::time::Instant::now();
+            let value = init.await;
+            record_load(stats, is_success(&value), start.elapsed());
+            value
+        } else {
+            init.await
+        }
+    }
+
     #[inline]
     pub(crate) fn is_removal_notifier_enabled(&self) -> bool {
         self.inner.is_removal_notifier_enabled()
     }
 
     #[inline]
-    #[cfg(feature = "sync")]
     pub(crate) fn is_blocking_removal_notification(&self) -> bool {
         self.inner.is_blocking_removal_notification()
     }
 
+    /// Delivers the queued notifications to the async eviction listener, if the
+    /// cache has one.
+    #[cfg(feature = "future")]
+    pub(crate) async fn run_pending_notifications(&self)
+    where
+        K: Send + Sync + 'static,
+        V: Send + Sync + 'static,
+    {
+        if let Some(notifier) = self.inner.async_removal_notifier() {
+            notifier.run_pending_notifications().await;
+        }
+    }
+
     #[inline]
     pub(crate) fn current_time_from_expiration_clock(&self) -> Instant {
         self.inner.current_time_from_expiration_clock()
     }
 
-    pub(crate) fn notify_invalidate(&self, key: &Arc<K>, entry: &TrioArc<ValueEntry<K, V>>)
-    where
+    /// Sends the removal notification of the entry removed by the user. `cause`
+    /// will be overridden if the entry has expired or been invalidated.
+    pub(crate) fn notify_invalidate(
+        &self,
+        key: &Arc<K>,
+        entry: &TrioArc<ValueEntry<K, V>>,
+        cause: RemovalCause,
+    ) where
         K: Send + Sync + 'static,
         V: Clone + Send + Sync + 'static,
     {
-        self.inner.notify_invalidate(key, entry);
+        self.inner.notify_invalidate(key, entry, cause);
     }
 
     #[cfg(feature = "unstable-debug-counters")]
@@ -156,11 +243,14 @@ where
         initial_capacity: Option<usize>,
         build_hasher: S,
         weigher: Option<Weigher<K, V>>,
-        eviction_listener: Option<EvictionListener<K, V>>,
+        eviction_listener: Option<Listener<K, V>>,
         eviction_listener_conf: Option<notification::Configuration>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryArc<K, V>>,
         invalidator_enabled: bool,
+        stats_counter: Option<StatsCounterArc>,
+        ticker: Option<TickerArc>,
         housekeeper_conf: housekeeper::Configuration,
     ) -> Self {
         let (r_snd, r_rcv) = crossbeam_channel::bounded(READ_LOG_SIZE);
@@ -177,10 +267,15 @@ where
             w_rcv,
             time_to_live,
             time_to_idle,
+            expiry,
             invalidator_enabled,
+            stats_counter,
+            ticker,
+            housekeeper_conf.executor().cloned(),
+            housekeeper_conf.has_task_queue(),
         ));
         if invalidator_enabled {
-            inner.set_invalidator(&inner);
+            inner.set_invalidator(&inner, housekeeper_conf.executor().cloned());
         }
         let housekeeper = Housekeeper::new(Arc::downgrade(&inner), housekeeper_conf);
         Self {
@@ -213,6 +308,7 @@ where
 
                 !is_expired_entry_wo(ttl, va, entry, now)
                     && !is_expired_entry_ao(tti, va, entry, now)
+                    && !is_expired_by_expiry(entry.entry_info(), now)
                     && !i.is_invalidated_entry(k, entry)
             })
             .unwrap_or_default() // `false` is the default for `bool` type.
@@ -295,17 +391,13 @@ where
                     }
                 }
 
-                let i = &self.inner;
-                let (ttl, tti, va) = (&i.time_to_live(), &i.time_to_idle(), &i.valid_after());
-
-                if is_expired_entry_wo(ttl, va, entry, now)
-                    || is_expired_entry_ao(tti, va, entry, now)
-                    || i.is_invalidated_entry(k, entry)
-                {
+                if !self.is_valid_entry(k, entry, now) {
                     // Expired or invalidated entry.
                     None
                 } else {
                     // Valid entry.
+                    let i = &self.inner;
+                    i.expire_after_read(k, &entry.value, entry.entry_info(), now);
                     let maybe_key = if need_key { Some(Arc::clone(k)) } else { None };
                     Some((maybe_key, TrioArc::clone(entry), now))
                 }
@@ -321,7 +413,29 @@ where
         }
     }
 
-    #[cfg(feature = "sync")]
+    /// Returns the key and the entry if the entry was written `refresh_after_write`
+    /// or more ago. The reload passes the entry to `do_compute_put_with_hash` to
+    /// replace it only if it has not been modified during the reload.
+    pub(crate) fn stale_entry_with_hash<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        refresh_after_write: Duration,
+    ) -> Option<KvEntry<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.current_time_from_expiration_clock();
+        self.inner.get_key_value_and_then(key, hash, |k, entry| {
+            entry
+                .last_modified()
+                .and_then(|ts| ts.checked_add(refresh_after_write))
+                .filter(|refresh_time| *refresh_time <= now)
+                .map(|_| KvEntry::new(Arc::clone(k), TrioArc::clone(entry)))
+        })
+    }
+
     pub(crate) fn get_key_with_hash<Q>(&self, key: &Q, hash: u64) -> Option<Arc<K>>
     where
         K: Borrow<Q>,
@@ -340,6 +454,27 @@ where
         self.inner.remove_entry(key, hash)
     }
 
+    /// Removes the entry if it is neither expired nor invalidated, and the
+    /// `predicate` returns `true` for its value.
+    pub(crate) fn remove_valid_entry_if<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        mut predicate: impl FnMut(&V) -> bool,
+    ) -> Option<KvEntry<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.current_time_from_expiration_clock();
+        self.inner.cache.remove_entry_if_and(
+            hash,
+            |k| (k as &K).borrow() == key,
+            |k, entry| self.is_valid_entry(k, entry, now) && predicate(&entry.value),
+            |k, entry| KvEntry::new(Arc::clone(k), TrioArc::clone(entry)),
+        )
+    }
+
     #[inline]
     pub(crate) fn apply_reads_writes_if_needed(
         inner: &impl InnerSync,
@@ -361,6 +496,55 @@ where
         self.inner.set_valid_after(now);
     }
 
+    /// Returns the keys of all entries in the hash table, including the expired and
+    /// invalidated ones.
+    pub(crate) fn all_keys(&self) -> Vec<Arc<K>> {
+        (0..self.inner.num_cht_segments())
+            .filter_map(|segment| self.inner.keys(segment))
+            .flatten()
+            .collect()
+    }
+
+    /// Applies the pending read and write operations to the cache policy now,
+    /// rather than waiting for the housekeeper.
+    pub(crate) fn apply_pending_ops(&self) {
+        self.inner.sync(MAX_SYNC_REPEATS);
+    }
+
+    /// Applies the pending operations, and runs the background tasks queued in the
+    /// task queue if the cache has no background threads.
+    pub(crate) fn run_pending_tasks(&self) {
+        self.apply_pending_ops();
+        if let Some(queue) = self.housekeeper.as_ref().and_then(|h| h.task_queue()) {
+            // Apply the results of the tasks (e.g. remove the entries invalidated by
+            // the invalidation task), which may submit more tasks (e.g. to deliver
+            // the notifications of the removed entries).
+            while queue.run_pending_tasks() {
+                self.apply_pending_ops();
+            }
+        }
+    }
+
+    /// Returns `true` if the cache has no background threads and its background
+    /// tasks are run by the clients.
+    #[cfg(feature = "future")]
+    pub(crate) fn has_task_queue(&self) -> bool {
+        self.housekeeper
+            .as_ref()
+            .map(|h| h.task_queue().is_some())
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if some eviction notifications have not been delivered to
+    /// the listener yet.
+    pub(crate) fn has_pending_notifications(&self) -> bool {
+        self.inner
+            .removal_notifier
+            .as_ref()
+            .map(|rn| rn.has_pending_notifications())
+            .unwrap_or_default()
+    }
+
     pub(crate) fn invalidate_entries_if(
         &self,
         predicate: PredicateFun<K, V>,
@@ -370,6 +554,89 @@ where
     }
 }
 
+//
+// Snapshot support
+//
+#[cfg(feature = "serde")]
+impl<K, V, S> BaseCache<K, V, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    /// Takes a snapshot of the entries and the cache policy states. The pending
+    /// reads and writes are applied first, and expired or invalidated entries are
+    /// not included.
+    pub(crate) fn snapshot(&self) -> CacheSnapshot<Arc<K>, V> {
+        self.apply_pending_ops();
+
+        let inner = &self.inner;
+        let now = self.current_time_from_expiration_clock();
+        let sketch_enabled = inner.frequency_sketch_enabled.load(Ordering::Acquire);
+
+        // Collect the entries in the access order while holding the locks, and
+        // clone their values after releasing the locks.
+        let mut nodes = Vec::with_capacity(inner.entry_count() as usize);
+        {
+            let deqs = inner.deques.lock();
+            let freq = inner.frequency_sketch.read();
+            for deq in &[&deqs.window, &deqs.probation, &deqs.protected] {
+                let mut next = deq.peek_front();
+                while let Some(node) = next {
+                    next = node.next_node();
+                    if let Some(entry) = inner.entry_of_ao_node(node) {
+                        let elem = &node.element;
+                        let frequency = if sketch_enabled {
+                            freq.frequency(elem.hash())
+                        } else {
+                            0
+                        };
+                        nodes.push((Arc::clone(elem.key()), entry, deq.region(), frequency));
+                    }
+                }
+            }
+        }
+
+        let since = |ts: Option<Instant>| {
+            ts.map_or(Duration::default(), |ts| now.saturating_duration_since(ts))
+        };
+
+        let entries = nodes
+            .into_iter()
+            .filter(|(key, entry, ..)| self.is_valid_entry(key, entry, now))
+            .map(|(key, entry, region, frequency)| EntrySnapshot {
+                value: entry.value.clone(),
+                key,
+                region: region as u8,
+                frequency,
+                since_last_modified: since(entry.last_modified()),
+                since_last_accessed: since(entry.last_accessed()),
+                time_to_expire: remaining_duration(entry.entry_info(), now),
+                is_ttl_overridden: entry.is_ttl_overridden(),
+            })
+            .collect();
+
+        CacheSnapshot {
+            max_capacity: inner.max_capacity,
+            window_max_weight: inner.window_max_weight.load(),
+            protected_max_weight: inner.protected_max_weight.load(),
+            frequency_sketch_enabled: sketch_enabled,
+            entries,
+        }
+    }
+
+    /// Restores the entries and the cache policy states from a snapshot. The
+    /// sizes of the admission window and the protected segment are restored only
+    /// when the max capacity in the snapshot is the same to this cache.
+    ///
+    /// Entries already in this cache are not replaced. If this cache does not have
+    /// enough capacity for all entries, the LRU ones will be evicted.
+    pub(crate) fn restore(&self, snapshot: CacheSnapshot<K, V>) {
+        self.inner.restore(snapshot);
+        self.apply_pending_ops();
+    }
+}
+
 //
 // Iterator support
 //
@@ -392,6 +659,7 @@ where
 
             if is_expired_entry_wo(ttl, va, entry, now)
                 || is_expired_entry_ao(tti, va, entry, now)
+                || is_expired_by_expiry(entry.entry_info(), now)
                 || i.is_invalidated_entry(k, entry)
             {
                 // Expired or invalidated entry.
@@ -423,6 +691,13 @@ where
         op: ReadOp<K, V>,
         now: Instant,
     ) -> Result<(), TrySendError<ReadOp<K, V>>> {
+        if let Some(stats) = self.inner.stats_counter() {
+            match &op {
+                ReadOp::Hit(..) => stats.record_hits(1),
+                ReadOp::Miss(_) => stats.record_misses(1),
+            }
+        }
+
         self.apply_reads_if_needed(&self.inner, now);
         let ch = &self.read_op_ch;
         match ch.try_send(op) {
@@ -433,11 +708,14 @@ where
     }
 
     #[inline]
+    /// Inserts the value. If `ttl` is given, the entry will expire after the
+    /// duration regardless of the `time_to_live` and `Expiry` of the cache.
     pub(crate) fn do_insert_with_hash(
         &self,
         key: Arc<K>,
         hash: u64,
         value: V,
+        ttl: Option<Duration>,
     ) -> (WriteOp<K, V>, Instant) {
         let ts = self.current_time_from_expiration_clock();
         let weight = self.inner.weigh(&key, &value);
@@ -463,7 +741,7 @@ where
             hash,
             // on_insert
             || {
-                let entry = self.new_value_entry(value.clone(), ts, weight);
+                let entry = self.new_value_entry_for_insert(&key, &value, ts, weight, ttl);
                 let cnt = op_cnt1.fetch_add(1, Ordering::Relaxed);
                 op1 = Some((
                     cnt,
@@ -478,19 +756,13 @@ where
             },
             // on_modify
             |_k, old_entry| {
-                // NOTES on `new_value_entry_from` method:
-                // 1. The internal EntryInfo will be shared between the old and new ValueEntries.
-                // 2. This method will set the last_accessed and last_modified to the max value to
-                //    prevent this new ValueEntry from being evicted by an expiration policy.
-                // 3. This method will update the policy_weight with the new weight.
                 let old_weight = old_entry.policy_weight();
-                let old_timestamps = (old_entry.last_accessed(), old_entry.last_modified());
-                let entry = self.new_value_entry_from(value.clone(), ts, weight, old_entry);
+                let (entry, replaced) =
+                    self.new_value_entry_for_update(&key, &value, ts, weight, ttl, old_entry);
                 let cnt = op_cnt2.fetch_add(1, Ordering::Relaxed);
                 op2 = Some((
                     cnt,
-                    TrioArc::clone(old_entry),
-                    old_timestamps,
+                    replaced,
                     WriteOp::Upsert {
                         key_hash: KeyHash::new(Arc::clone(&key), hash),
                         value_entry: TrioArc::clone(&entry),
@@ -504,31 +776,16 @@ where
 
         match (op1, op2) {
             (Some((_cnt, ins_op)), None) => (ins_op, ts),
-            (None, Some((_cnt, old_entry, (old_last_accessed, old_last_modified), upd_op))) => {
-                old_entry.unset_q_nodes();
-                if self.is_removal_notifier_enabled() {
-                    self.inner
-                        .notify_upsert(key, &old_entry, old_last_accessed, old_last_modified);
-                }
+            (None, Some((_cnt, replaced, upd_op))) => {
+                self.notify_replaced(key, &replaced);
                 crossbeam_epoch::pin().flush();
                 (upd_op, ts)
             }
-            (
-                Some((cnt1, ins_op)),
-                Some((cnt2, old_entry, (old_last_accessed, old_last_modified), upd_op)),
-            ) => {
+            (Some((cnt1, ins_op)), Some((cnt2, replaced, upd_op))) => {
                 if cnt1 > cnt2 {
                     (ins_op, ts)
                 } else {
-                    old_entry.unset_q_nodes();
-                    if self.is_removal_notifier_enabled() {
-                        self.inner.notify_upsert(
-                            key,
-                            &old_entry,
-                            old_last_accessed,
-                            old_last_modified,
-                        );
-                    }
+                    self.notify_replaced(key, &replaced);
                     crossbeam_epoch::pin().flush();
                     (upd_op, ts)
                 }
@@ -537,12 +794,351 @@ where
         }
     }
 
+    /// Inserts the value only if the key does not exist, or the existing entry is
+    /// expired or invalidated. Returns `Err` with a clone of the existing value if
+    /// the value was not inserted.
+    pub(crate) fn do_insert_if_absent_with_hash(
+        &self,
+        key: Arc<K>,
+        hash: u64,
+        value: V,
+    ) -> Result<(WriteOp<K, V>, Instant), V> {
+        let ts = self.current_time_from_expiration_clock();
+        let weight = self.inner.weigh(&key, &value);
+        let op_cnt1 = Rc::new(AtomicU8::new(0));
+        let op_cnt2 = Rc::clone(&op_cnt1);
+        let mut op1 = None;
+        let mut op2 = None;
+
+        // Lock the key for update if blocking removal notification is enabled.
+        let kl = self.maybe_key_lock(&key);
+        let _klg = &kl.as_ref().map(|kl| kl.lock());
+
+        // See the comment in `do_insert_with_hash` for why we use the counters.
+        self.inner.cache.insert_with_or_modify(
+            Arc::clone(&key),
+            hash,
+            // on_insert
+            || {
+                let entry = self.new_value_entry_for_insert(&key, &value, ts, weight, None);
+                let cnt = op_cnt1.fetch_add(1, Ordering::Relaxed);
+                op1 = Some((
+                    cnt,
+                    WriteOp::Upsert {
+                        key_hash: KeyHash::new(Arc::clone(&key), hash),
+                        value_entry: TrioArc::clone(&entry),
+                        old_weight: 0,
+                        new_weight: weight,
+                    },
+                ));
+                entry
+            },
+            // on_modify
+            |k, old_entry| {
+                let cnt = op_cnt2.fetch_add(1, Ordering::Relaxed);
+                if self.is_valid_entry(k, old_entry, ts) {
+                    // Keep the existing entry.
+                    op2 = Some((cnt, Err(old_entry.value.clone())));
+                    return TrioArc::clone(old_entry);
+                }
+
+                // The existing entry is expired or invalidated. Replace it.
+                let old_weight = old_entry.policy_weight();
+                let (entry, replaced) =
+                    self.new_value_entry_for_update(&key, &value, ts, weight, None, old_entry);
+                op2 = Some((
+                    cnt,
+                    Ok((
+                        replaced,
+                        WriteOp::Upsert {
+                            key_hash: KeyHash::new(Arc::clone(&key), hash),
+                            value_entry: TrioArc::clone(&entry),
+                            old_weight,
+                            new_weight: weight,
+                        },
+                    )),
+                ));
+                entry
+            },
+        );
+
+        let modified = match (op1, op2) {
+            (Some((_cnt, ins_op)), None) => return Ok((ins_op, ts)),
+            (None, Some((_cnt, modified))) => modified,
+            (Some((cnt1, ins_op)), Some((cnt2, modified))) => {
+                if cnt1 > cnt2 {
+                    return Ok((ins_op, ts));
+                }
+                modified
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let (replaced, upd_op) = modified?;
+        self.notify_replaced(key, &replaced);
+        crossbeam_epoch::pin().flush();
+        Ok((upd_op, ts))
+    }
+
+    /// Replaces the value of the entry with `value` only if the entry is neither
+    /// expired nor invalidated, and its current value is equal to `expected`.
+    /// Returns `None` if the value was not replaced.
+    pub(crate) fn do_replace_with_hash<Q>(
+        &self,
+        key: &Q,
+        hash: u64,
+        expected: &V,
+        value: V,
+    ) -> Option<(WriteOp<K, V>, Instant)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: PartialEq,
+    {
+        let ts = self.current_time_from_expiration_clock();
+        let mut op = None;
+
+        // Lock the key for update if blocking removal notification is enabled.
+        let kl = self
+            .get_key_with_hash(key, hash)
+            .and_then(|k| self.maybe_key_lock(&k));
+        let _klg = &kl.as_ref().map(|kl| kl.lock());
+
+        // `on_modify` can be called more than once when the modification conflicts
+        // with other concurrent hash table operations. `op` holds the WriteOp made by
+        // the last call, which is the one applied to the hash table if the entry was
+        // modified.
+        self.inner.cache.modify_entry_and(
+            hash,
+            |k| (k as &K).borrow() == key,
+            |k, old_entry| {
+                if !self.is_valid_entry(k, old_entry, ts) || old_entry.value != *expected {
+                    return None;
+                }
+
+                let weight = self.inner.weigh(k, &value);
+                let old_weight = old_entry.policy_weight();
+                let (entry, replaced) =
+                    self.new_value_entry_for_update(k, &value, ts, weight, None, old_entry);
+                op = Some((
+                    Arc::clone(k),
+                    replaced,
+                    WriteOp::Upsert {
+                        key_hash: KeyHash::new(Arc::clone(k), hash),
+                        value_entry: TrioArc::clone(&entry),
+                        old_weight,
+                        new_weight: weight,
+                    },
+                ));
+                Some(entry)
+            },
+            |_k, _old_entry| (),
+        )?;
+
+        let (key, replaced, upd_op) = op.expect("Bug: No WriteOp for the modified entry");
+        self.notify_replaced(key, &replaced);
+        crossbeam_epoch::pin().flush();
+        Some((upd_op, ts))
+    }
+
+    /// Returns the entry of the key as it is in the hash table, even if it is
+    /// expired or invalidated. The compute operations pass it back to
+    /// `do_compute_put_with_hash` or `remove_entry_if_same` to detect concurrent
+    /// modifications.
+    pub(crate) fn get_value_entry(&self, key: &K, hash: u64) -> Option<TrioArc<ValueEntry<K, V>>> {
+        self.inner
+            .get_key_value_and(key, hash, |_k, entry| TrioArc::clone(entry))
+    }
+
+    /// Puts the value only if the entry of the key is still the `expected` one
+    /// returned by `get_value_entry` or `stale_entry_with_hash`, or the key is still
+    /// absent if `expected` is `None`. Returns `None` if the entry has been modified
+    /// by another write.
+    pub(crate) fn do_compute_put_with_hash(
+        &self,
+        key: Arc<K>,
+        hash: u64,
+        expected: Option<&TrioArc<ValueEntry<K, V>>>,
+        value: V,
+    ) -> Option<(WriteOp<K, V>, Instant)> {
+        let ts = self.current_time_from_expiration_clock();
+        let weight = self.inner.weigh(&key, &value);
+
+        // Lock the key for update if blocking removal notification is enabled.
+        let kl = self.maybe_key_lock(&key);
+        let _klg = &kl.as_ref().map(|kl| kl.lock());
+
+        let new_op = |entry: &TrioArc<ValueEntry<K, V>>, old_weight| WriteOp::Upsert {
+            key_hash: KeyHash::new(Arc::clone(&key), hash),
+            value_entry: TrioArc::clone(entry),
+            old_weight,
+            new_weight: weight,
+        };
+
+        // The closures can be called more than once when the operation conflicts
+        // with other concurrent hash table operations. The results of the last call
+        // are the ones applied to the hash table.
+        let (upd_op, replaced) = if let Some(expected) = expected {
+            let mut op = None;
+            self.inner.cache.modify_entry_and(
+                hash,
+                |k| k == &key,
+                |k, old_entry| {
+                    if !TrioArc::ptr_eq(old_entry, expected) {
+                        return None;
+                    }
+                    let old_weight = old_entry.policy_weight();
+                    let (entry, replaced) =
+                        self.new_value_entry_for_update(k, &value, ts, weight, None, old_entry);
+                    op = Some((new_op(&entry, old_weight), Some(replaced)));
+                    Some(entry)
+                },
+                |_k, _old_entry| (),
+            )?;
+            op.expect("Bug: No WriteOp for the modified entry")
+        } else {
+            let mut op = None;
+            // If the key has been inserted by another write, keep its entry.
+            let existing = self.inner.cache.insert_with_or_modify_entry_and(
+                Arc::clone(&key),
+                hash,
+                || {
+                    let entry = self.new_value_entry_for_insert(&key, &value, ts, weight, None);
+                    op = Some(new_op(&entry, 0));
+                    entry
+                },
+                |_k, old_entry| TrioArc::clone(old_entry),
+                |_k, _old_entry| (),
+            );
+            if existing.is_some() {
+                return None;
+            }
+            (op.expect("Bug: No WriteOp for the inserted entry"), None)
+        };
+
+        if let Some(replaced) = replaced {
+            self.notify_replaced(key, &replaced);
+            crossbeam_epoch::pin().flush();
+        }
+        Some((upd_op, ts))
+    }
+
+    /// Removes the entry of the key only if it is still the `expected` one
+    /// returned by `get_value_entry`.
+    pub(crate) fn remove_entry_if_same(
+        &self,
+        key: &K,
+        hash: u64,
+        expected: &TrioArc<ValueEntry<K, V>>,
+    ) -> Option<KvEntry<K, V>> {
+        self.inner.cache.remove_entry_if_and(
+            hash,
+            |k| (k as &K) == key,
+            |_k, entry| TrioArc::ptr_eq(entry, expected),
+            |k, entry| KvEntry::new(Arc::clone(k), TrioArc::clone(entry)),
+        )
+    }
+
+    /// Creates a value entry for a key that does not exist in the cache, and sets
+    /// its expiration time. If `ttl` is given, it overrides the expiration policies
+    /// of the cache.
+    fn new_value_entry_for_insert(
+        &self,
+        key: &K,
+        value: &V,
+        ts: Instant,
+        weight: u64,
+        ttl: Option<Duration>,
+    ) -> TrioArc<ValueEntry<K, V>> {
+        let entry = self.new_value_entry(value.clone(), ts, weight);
+        if let Some(ttl) = ttl {
+            Inner::<K, V, S>::override_ttl(entry.entry_info(), ttl, ts);
+        } else {
+            self.inner
+                .expire_after_create(key, value, entry.entry_info(), ts);
+        }
+        entry
+    }
+
+    /// Creates a value entry to replace the `old_entry`, and updates its expiration
+    /// time. Returns the new entry and the replaced entry, which carries the old
+    /// timestamps to tell the removal cause of the old value.
+    fn new_value_entry_for_update(
+        &self,
+        key: &K,
+        value: &V,
+        ts: Instant,
+        weight: u64,
+        ttl: Option<Duration>,
+        old_entry: &TrioArc<ValueEntry<K, V>>,
+    ) -> (TrioArc<ValueEntry<K, V>>, ReplacedEntry<K, V>) {
+        // NOTES on `new_value_entry_from` method:
+        // 1. The internal EntryInfo and deque nodes will be shared between the old
+        //    and new ValueEntries.
+        // 2. This method will set the last_accessed and last_modified to the max value to
+        //    prevent this new ValueEntry from being evicted by an expiration policy.
+        // 3. This method will update the policy_weight with the new weight.
+        let old_info = old_entry.entry_info();
+        let old_expiration_time = old_info.expiration_time();
+        let replaced = ReplacedEntry {
+            entry: TrioArc::clone(old_entry),
+            last_accessed: old_entry.last_accessed(),
+            last_modified: old_entry.last_modified(),
+            expiration_time: old_expiration_time,
+            is_ttl_overridden: old_info.is_ttl_overridden(),
+        };
+        let entry = self.new_value_entry_from(value.clone(), ts, weight, old_entry);
+        let info = entry.entry_info();
+        if let Some(ttl) = ttl {
+            Inner::<K, V, S>::override_ttl(info, ttl, ts);
+        } else {
+            if info.is_ttl_overridden() {
+                // The new value does not have its own TTL. Go back to the
+                // expiration policies of the cache.
+                info.set_ttl_overridden(false);
+                info.set_expiration_time(None);
+            }
+            self.inner
+                .expire_after_update(key, value, info, old_expiration_time, ts);
+        }
+        (entry, replaced)
+    }
+
+    fn notify_replaced(&self, key: Arc<K>, replaced: &ReplacedEntry<K, V>) {
+        if self.is_removal_notifier_enabled() {
+            self.inner.notify_upsert(
+                key,
+                &replaced.entry,
+                replaced.last_accessed,
+                replaced.last_modified,
+                replaced.expiration_time,
+                replaced.is_ttl_overridden,
+            );
+        }
+    }
+
+    /// Returns `true` if the entry is neither expired nor invalidated.
+    pub(crate) fn is_valid_entry(
+        &self,
+        key: &Arc<K>,
+        entry: &TrioArc<ValueEntry<K, V>>,
+        now: Instant,
+    ) -> bool {
+        let i = &self.inner;
+        let (ttl, tti, va) = (&i.time_to_live(), &i.time_to_idle(), &i.valid_after());
+
+        !(is_expired_entry_wo(ttl, va, entry, now)
+            || is_expired_entry_ao(tti, va, entry, now)
+            || is_expired_by_expiry(entry.entry_info(), now)
+            || i.is_invalidated_entry(key, entry))
+    }
+
     #[inline]
     fn new_value_entry(
         &self,
         value: V,
         timestamp: Instant,
-        policy_weight: u32,
+        policy_weight: u64,
     ) -> TrioArc<ValueEntry<K, V>> {
         let info = TrioArc::new(EntryInfo::new(timestamp, policy_weight));
         TrioArc::new(ValueEntry::new(value, info))
@@ -553,7 +1149,7 @@ where
         &self,
         value: V,
         timestamp: Instant,
-        policy_weight: u32,
+        policy_weight: u64,
         other: &ValueEntry<K, V>,
     ) -> TrioArc<ValueEntry<K, V>> {
         let info = TrioArc::clone(other.entry_info());
@@ -620,13 +1216,14 @@ struct EvictionState<'a, K, V> {
     counters: EvictionCounters,
     notifier: Option<&'a RemovalNotifier<K, V>>,
     removed_entries: Option<Vec<RemovedEntry<K, V>>>,
+    stats_counter: Option<&'a dyn StatsCounter>,
 }
 
 impl<'a, K, V> EvictionState<'a, K, V> {
     fn new(
-        entry_count: u64,
-        weighted_size: u64,
+        counters: EvictionCounters,
         notifier: Option<&'a RemovalNotifier<K, V>>,
+        stats_counter: Option<&'a dyn StatsCounter>,
     ) -> Self {
         let removed_entries = notifier.and_then(|n| {
             if n.is_batching_supported() {
@@ -637,16 +1234,23 @@ impl<'a, K, V> EvictionState<'a, K, V> {
         });
 
         Self {
-            counters: EvictionCounters::new(entry_count, weighted_size),
+            counters,
             notifier,
             removed_entries,
+            stats_counter,
         }
     }
 
-    fn is_notifier_enabled(&self) -> bool {
-        self.notifier.is_some()
+    /// Returns `true` if the removed entries need to be passed to
+    /// `add_removed_entry` method, either for the notifications or for the
+    /// statistics.
+    fn is_tracking_removals(&self) -> bool {
+        self.notifier.is_some() || self.stats_counter.is_some()
     }
 
+    /// Records the removal of the entry to the statistics if the cause is an
+    /// eviction, and queues the notification of the removal if the notifier is
+    /// enabled.
     fn add_removed_entry(
         &mut self,
         key: Arc<K>,
@@ -656,7 +1260,11 @@ impl<'a, K, V> EvictionState<'a, K, V> {
         K: Send + Sync + 'static,
         V: Clone + Send + Sync + 'static,
     {
-        debug_assert!(self.is_notifier_enabled());
+        if let Some(stats) = self.stats_counter {
+            if cause.was_evicted() {
+                stats.record_eviction(entry.policy_weight(), cause);
+            }
+        }
 
         if let Some(removed) = &mut self.removed_entries {
             removed.push(RemovedEntry::new(key, entry.value.clone(), cause));
@@ -680,29 +1288,73 @@ impl<'a, K, V> EvictionState<'a, K, V> {
 struct EvictionCounters {
     entry_count: u64,
     weighted_size: u64,
+    // The weighted sizes of the admission window and the protected segment of the
+    // main space. The weighted size of the probation segment is not tracked as it
+    // can be calculated from the others.
+    window_weighted_size: u64,
+    protected_weighted_size: u64,
 }
 
 impl EvictionCounters {
     #[inline]
-    fn new(entry_count: u64, weighted_size: u64) -> Self {
+    fn new(
+        entry_count: u64,
+        weighted_size: u64,
+        window_weighted_size: u64,
+        protected_weighted_size: u64,
+    ) -> Self {
         Self {
             entry_count,
             weighted_size,
+            window_weighted_size,
+            protected_weighted_size,
         }
     }
 
     #[inline]
-    fn saturating_add(&mut self, entry_count: u64, weight: u32) {
+    fn saturating_add(&mut self, entry_count: u64, weight: u64) {
         self.entry_count += entry_count;
         let total = &mut self.weighted_size;
-        *total = total.saturating_add(weight as u64);
+        *total = total.saturating_add(weight);
     }
 
     #[inline]
-    fn saturating_sub(&mut self, entry_count: u64, weight: u32) {
+    fn saturating_sub(&mut self, entry_count: u64, weight: u64) {
         self.entry_count -= entry_count;
         let total = &mut self.weighted_size;
-        *total = total.saturating_sub(weight as u64);
+        *total = total.saturating_sub(weight);
+    }
+
+    #[inline]
+    fn saturating_add_to_region(&mut self, region: CacheRegion, weight: u64) {
+        if let Some(total) = self.region_weighted_size_mut(region) {
+            *total = total.saturating_add(weight);
+        }
+    }
+
+    #[inline]
+    fn saturating_sub_from_region(&mut self, region: CacheRegion, weight: u64) {
+        if let Some(total) = self.region_weighted_size_mut(region) {
+            *total = total.saturating_sub(weight);
+        }
+    }
+
+    #[inline]
+    fn region_weighted_size(&self, region: CacheRegion) -> u64 {
+        match region {
+            CacheRegion::Window => self.window_weighted_size,
+            CacheRegion::MainProtected => self.protected_weighted_size,
+            CacheRegion::MainProbation | CacheRegion::Other => 0,
+        }
+    }
+
+    #[inline]
+    fn region_weighted_size_mut(&mut self, region: CacheRegion) -> Option<&mut u64> {
+        match region {
+            CacheRegion::Window => Some(&mut self.window_weighted_size),
+            CacheRegion::MainProtected => Some(&mut self.protected_weighted_size),
+            CacheRegion::MainProbation | CacheRegion::Other => None,
+        }
     }
 }
 
@@ -713,15 +1365,15 @@ struct EntrySizeAndFrequency {
 }
 
 impl EntrySizeAndFrequency {
-    fn new(policy_weight: u32) -> Self {
+    fn new(policy_weight: u64) -> Self {
         Self {
-            policy_weight: policy_weight as u64,
+            policy_weight,
             ..Default::default()
         }
     }
 
-    fn add_policy_weight(&mut self, weight: u32) {
-        self.policy_weight += weight as u64;
+    fn add_policy_weight(&mut self, weight: u64) {
+        self.policy_weight = self.policy_weight.saturating_add(weight);
     }
 
     fn add_frequency(&mut self, freq: &FrequencySketch, hash: u64) {
@@ -734,11 +1386,11 @@ type AoqNode<K> = NonNull<DeqNode<KeyHashDate<K>>>;
 
 enum AdmissionResult<K> {
     Admitted {
-        victim_nodes: SmallVec<[AoqNode<K>; 8]>,
-        skipped_nodes: SmallVec<[AoqNode<K>; 4]>,
+        victim_nodes: SmallVec<[(CacheRegion, AoqNode<K>); 8]>,
+        skipped_nodes: SmallVec<[(CacheRegion, AoqNode<K>); 4]>,
     },
     Rejected {
-        skipped_nodes: SmallVec<[AoqNode<K>; 4]>,
+        skipped_nodes: SmallVec<[(CacheRegion, AoqNode<K>); 4]>,
     },
 }
 
@@ -749,6 +1401,14 @@ pub(crate) struct Inner<K, V, S> {
     max_capacity: Option<u64>,
     entry_count: AtomicCell<u64>,
     weighted_size: AtomicCell<u64>,
+    window_weighted_size: AtomicCell<u64>,
+    protected_weighted_size: AtomicCell<u64>,
+    // The max weighted sizes of the admission window and the protected segment.
+    // Zero if the cache does not use the window (e.g. it is unbounded). They are
+    // adapted by the hill climber while the housekeeper holds the deques lock.
+    window_max_weight: AtomicCell<u64>,
+    protected_max_weight: AtomicCell<u64>,
+    hill_climber: Mutex<HillClimber>,
     cache: CacheStore<K, V, S>,
     build_hasher: S,
     deques: Mutex<Deques<K>>,
@@ -758,10 +1418,12 @@ pub(crate) struct Inner<K, V, S> {
     write_op_ch: Receiver<WriteOp<K, V>>,
     time_to_live: Option<Duration>,
     time_to_idle: Option<Duration>,
+    expiry: Option<ExpiryArc<K, V>>,
     valid_after: AtomicInstant,
     weigher: Option<Weigher<K, V>>,
     removal_notifier: Option<RemovalNotifier<K, V>>,
     key_locks: Option<KeyLockMap<K, S>>,
+    stats_counter: Option<StatsCounterArc>,
     invalidator_enabled: bool,
     invalidator: RwLock<Option<Invalidator<K, V, S>>>,
     has_expiration_clock: AtomicBool,
@@ -775,7 +1437,12 @@ impl<K, V, S> Inner<K, V, S> {
     }
 
     fn policy(&self) -> Policy {
-        Policy::new(self.max_capacity, 1, self.time_to_live, self.time_to_idle)
+        let mut policy = Policy::new(self.max_capacity, 1, self.time_to_live, self.time_to_idle);
+        policy.set_window_and_protected_capacities(
+            self.window_max_weight.load(),
+            self.protected_max_weight.load(),
+        );
+        policy
     }
 
     #[inline]
@@ -788,13 +1455,23 @@ impl<K, V, S> Inner<K, V, S> {
         self.weighted_size.load()
     }
 
+    fn stats(&self) -> CacheStats {
+        self.stats_counter()
+            .map(StatsCounter::snapshot)
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn stats_counter(&self) -> Option<&dyn StatsCounter> {
+        self.stats_counter.as_deref()
+    }
+
     #[inline]
     fn is_removal_notifier_enabled(&self) -> bool {
         self.removal_notifier.is_some()
     }
 
     #[inline]
-    #[cfg(feature = "sync")]
     fn is_blocking_removal_notification(&self) -> bool {
         self.removal_notifier
             .as_ref()
@@ -802,6 +1479,12 @@ impl<K, V, S> Inner<K, V, S> {
             .unwrap_or_default()
     }
 
+    #[inline]
+    #[cfg(feature = "future")]
+    fn async_removal_notifier(&self) -> Option<&AsyncRemovalNotifier<K, V>> {
+        self.removal_notifier.as_ref().and_then(|rn| rn.as_async())
+    }
+
     #[cfg(feature = "unstable-debug-counters")]
     pub fn debug_stats(&self) -> CacheDebugStats {
         let ec = self.entry_count.load();
@@ -846,7 +1529,74 @@ impl<K, V, S> Inner<K, V, S> {
 
     #[inline]
     fn has_expiry(&self) -> bool {
-        self.time_to_live.is_some() || self.time_to_idle.is_some()
+        self.time_to_live.is_some() || self.time_to_idle.is_some() || self.expiry.is_some()
+    }
+
+    /// Sets the expiration time of a newly created entry by calling the `Expiry`
+    /// of this cache (if any).
+    #[inline]
+    fn expire_after_create(&self, key: &K, value: &V, entry_info: &EntryInfo, now: Instant) {
+        if let Some(expiry) = &self.expiry {
+            let duration = expiry.expire_after_create(key, value);
+            entry_info.set_expiration_time(duration.and_then(|d| now.checked_add(d)));
+        }
+    }
+
+    /// Updates the expiration time of a read entry by calling the `Expiry` of
+    /// this cache (if any). Does nothing if the entry has its own TTL given by the
+    /// `insert_with_ttl` family methods.
+    #[inline]
+    fn expire_after_read(&self, key: &K, value: &V, entry_info: &EntryInfo, now: Instant) {
+        if entry_info.is_ttl_overridden() {
+            return;
+        }
+        if let Some(expiry) = &self.expiry {
+            let current = remaining_duration(entry_info, now);
+            let duration = expiry.expire_after_read(key, value, current);
+            if duration != current {
+                entry_info.set_expiration_time(duration.and_then(|d| now.checked_add(d)));
+            }
+        }
+    }
+
+    /// Updates the expiration time of an entry whose value has been replaced by
+    /// calling the `Expiry` of this cache (if any). `old_expiration_time` is the
+    /// expiration time of the old value.
+    #[inline]
+    fn expire_after_update(
+        &self,
+        key: &K,
+        value: &V,
+        entry_info: &EntryInfo,
+        old_expiration_time: Option<Instant>,
+        now: Instant,
+    ) {
+        if let Some(expiry) = &self.expiry {
+            let duration = match old_expiration_time {
+                // The old value has already expired, so treat this as a creation.
+                Some(ts) if ts <= now => expiry.expire_after_create(key, value),
+                current => {
+                    let current = current.map(|ts| ts.saturating_duration_since(now));
+                    expiry.expire_after_update(key, value, current)
+                }
+            };
+            entry_info.set_expiration_time(duration.and_then(|d| now.checked_add(d)));
+        }
+    }
+
+    /// Sets the expiration time given by the `insert_with_ttl` family methods. The
+    /// time-to-live of the cache will not be applied to the entry.
+    #[inline]
+    fn override_ttl(entry_info: &EntryInfo, ttl: Duration, now: Instant) {
+        entry_info.set_ttl_overridden(true);
+        entry_info.set_expiration_time(now.checked_add(ttl));
+    }
+
+    /// Returns `true` if this cache uses the admission window and the segmented
+    /// (probation and protected) main space.
+    #[inline]
+    fn is_window_enabled(&self) -> bool {
+        self.window_max_weight.load() > 0
     }
 
     #[inline]
@@ -897,13 +1647,18 @@ where
         initial_capacity: Option<usize>,
         build_hasher: S,
         weigher: Option<Weigher<K, V>>,
-        eviction_listener: Option<EvictionListener<K, V>>,
+        eviction_listener: Option<Listener<K, V>>,
         eviction_listener_conf: Option<notification::Configuration>,
         read_op_ch: Receiver<ReadOp<K, V>>,
         write_op_ch: Receiver<WriteOp<K, V>>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryArc<K, V>>,
         invalidator_enabled: bool,
+        stats_counter: Option<StatsCounterArc>,
+        ticker: Option<TickerArc>,
+        executor: Option<ExecutorArc>,
+        has_task_queue: bool,
     ) -> Self {
         let initial_capacity = initial_capacity
             .map(|cap| cap + WRITE_LOG_SIZE)
@@ -914,11 +1669,17 @@ where
             initial_capacity,
             build_hasher.clone(),
         );
+        let (window_max_weight, protected_max_weight) = max_capacity
+            .map(common::window_and_protected_capacities)
+            .unwrap_or_default();
         let (removal_notifier, key_locks) = if let Some(listener) = eviction_listener {
             let rn = RemovalNotifier::new(
                 listener,
                 eviction_listener_conf.unwrap_or_default(),
+                stats_counter.clone(),
                 name.clone(),
+                executor,
+                has_task_queue,
             );
             if rn.is_blocking() {
                 let kl = KeyLockMap::with_hasher(build_hasher.clone());
@@ -935,6 +1696,11 @@ where
             max_capacity,
             entry_count: Default::default(),
             weighted_size: Default::default(),
+            window_weighted_size: Default::default(),
+            protected_weighted_size: Default::default(),
+            window_max_weight: AtomicCell::new(window_max_weight),
+            protected_max_weight: AtomicCell::new(protected_max_weight),
+            hill_climber: Mutex::new(HillClimber::new(max_capacity.unwrap_or_default())),
             cache,
             build_hasher,
             deques: Mutex::new(Default::default()),
@@ -944,20 +1710,25 @@ where
             write_op_ch,
             time_to_live,
             time_to_idle,
+            expiry,
             valid_after: Default::default(),
             weigher,
             removal_notifier,
             key_locks,
+            stats_counter,
             invalidator_enabled,
             // When enabled, this field will be set later via the set_invalidator method.
             invalidator: RwLock::new(None),
-            has_expiration_clock: AtomicBool::new(false),
-            expiration_clock: RwLock::new(None),
+            has_expiration_clock: AtomicBool::new(ticker.is_some()),
+            expiration_clock: RwLock::new(ticker.map(Clock::from_ticker)),
         }
     }
 
-    fn set_invalidator(&self, self_ref: &Arc<Self>) {
-        *self.invalidator.write() = Some(Invalidator::new(Arc::downgrade(&Arc::clone(self_ref))));
+    fn set_invalidator(&self, self_ref: &Arc<Self>, executor: Option<ExecutorArc>) {
+        *self.invalidator.write() = Some(Invalidator::new(
+            Arc::downgrade(&Arc::clone(self_ref)),
+            executor,
+        ));
     }
 
     #[inline]
@@ -1037,7 +1808,7 @@ where
     }
 
     #[inline]
-    fn weigh(&self, key: &K, value: &V) -> u32 {
+    fn weigh(&self, key: &K, value: &V) -> u64 {
         self.weigher.as_ref().map(|w| w(key, value)).unwrap_or(1)
     }
 }
@@ -1056,6 +1827,7 @@ where
         key: &Arc<K>,
         hash: u64,
         condition: impl FnMut(&Arc<K>, &TrioArc<ValueEntry<K, V>>) -> bool,
+        cause: RemovalCause,
     ) -> Option<TrioArc<ValueEntry<K, V>>>
     where
         K: Send + Sync + 'static,
@@ -1068,7 +1840,7 @@ where
         let maybe_entry = self.cache.remove_if(hash, |k| k == key, condition);
         if let Some(entry) = &maybe_entry {
             if self.is_removal_notifier_enabled() {
-                self.notify_single_removal(Arc::clone(key), entry, RemovalCause::Explicit);
+                self.notify_single_removal(Arc::clone(key), entry, cause);
             }
         }
         maybe_entry
@@ -1106,13 +1878,22 @@ where
 
         let current_ec = self.entry_count.load();
         let current_ws = self.weighted_size.load();
-        let mut eviction_state =
-            EvictionState::new(current_ec, current_ws, self.removal_notifier.as_ref());
+        let counters = EvictionCounters::new(
+            current_ec,
+            current_ws,
+            self.window_weighted_size.load(),
+            self.protected_weighted_size.load(),
+        );
+        let mut eviction_state = EvictionState::new(
+            counters,
+            self.removal_notifier.as_ref(),
+            self.stats_counter(),
+        );
 
         while should_sync && calls <= max_repeats {
             let r_len = self.read_op_ch.len();
             if r_len > 0 {
-                self.apply_reads(&mut deqs, r_len);
+                self.apply_reads(&mut deqs, r_len, &mut eviction_state.counters);
             }
 
             let w_len = self.write_op_ch.len();
@@ -1129,7 +1910,7 @@ where
                 || self.write_op_ch.len() >= WRITE_LOG_FLUSH_POINT;
         }
 
-        if self.has_expiry() || self.has_valid_after() {
+        if self.has_expiry() || self.has_valid_after() || deqs.timer_wheel.is_enabled() {
             self.evict_expired(
                 &mut deqs,
                 batch_size::EVICTION_BATCH_SIZE,
@@ -1161,13 +1942,21 @@ where
             );
         }
 
+        if self.is_window_enabled() {
+            self.climb(&mut deqs, &mut eviction_state.counters);
+        }
+
         eviction_state.notify_multiple_removals();
 
         debug_assert_eq!(self.entry_count.load(), current_ec);
         debug_assert_eq!(self.weighted_size.load(), current_ws);
-        self.entry_count.store(eviction_state.counters.entry_count);
-        self.weighted_size
-            .store(eviction_state.counters.weighted_size);
+        let counters = &eviction_state.counters;
+        self.entry_count.store(counters.entry_count);
+        self.weighted_size.store(counters.weighted_size);
+        self.window_weighted_size
+            .store(counters.window_weighted_size);
+        self.protected_weighted_size
+            .store(counters.protected_weighted_size);
 
         crossbeam_epoch::pin().flush();
 
@@ -1185,6 +1974,43 @@ where
     fn now(&self) -> Instant {
         self.current_time_from_expiration_clock()
     }
+
+    fn next_expiration(&self) -> Option<Instant> {
+        let deqs = self.deques.lock();
+        let mut next: Option<Instant> = None;
+        let mut update = |time: Option<Instant>| {
+            if let Some(t) = time {
+                next = Some(next.map_or(t, |n| if t < n { t } else { n }));
+            }
+        };
+
+        if let Some(ttl) = self.time_to_live {
+            // Skip the entries that have their own TTL; the timer wheel handles
+            // them. Give up after a batch so that this will not take long, and let
+            // the periodical sync job sweep them.
+            let mut node = deqs.write_order.peek_front();
+            for _ in 0..batch_size::EVICTION_BATCH_SIZE {
+                match node {
+                    Some(n) if n.is_ttl_overridden() => node = n.next_node(),
+                    Some(n) => {
+                        update(n.last_modified().and_then(|t| t.checked_add(ttl)));
+                        break;
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        if let Some(tti) = self.time_to_idle {
+            for deq in &[&deqs.window, &deqs.probation, &deqs.protected] {
+                let front = deq.peek_front();
+                update(front.and_then(|n| n.last_accessed()?.checked_add(tti)));
+            }
+        }
+
+        update(deqs.timer_wheel.next_advance_time());
+        next
+    }
 }
 
 //
@@ -1196,9 +2022,9 @@ where
     V: Send + Sync + 'static,
     S: BuildHasher + Clone + Send + Sync + 'static,
 {
-    fn has_enough_capacity(&self, candidate_weight: u32, counters: &EvictionCounters) -> bool {
+    fn has_enough_capacity(&self, candidate_weight: u64, counters: &EvictionCounters) -> bool {
         self.max_capacity
-            .map(|limit| counters.weighted_size + candidate_weight as u64 <= limit)
+            .map(|limit| counters.weighted_size.saturating_add(candidate_weight) <= limit)
             .unwrap_or(true)
     }
 
@@ -1246,21 +2072,189 @@ where
         self.frequency_sketch_enabled.store(true, Ordering::Release);
     }
 
-    fn apply_reads(&self, deqs: &mut Deques<K>, count: usize) {
-        use ReadOp::*;
-        let mut freq = self.frequency_sketch.write();
-        let ch = &self.read_op_ch;
-        for _ in 0..count {
-            match ch.try_recv() {
-                Ok(Hit(hash, entry, timestamp)) => {
-                    freq.increment(hash);
-                    entry.set_last_accessed(timestamp);
-                    deqs.move_to_back_ao(&entry)
-                }
-                Ok(Miss(hash)) => freq.increment(hash),
-                Err(_) => break,
-            }
-        }
+    fn apply_reads(&self, deqs: &mut Deques<K>, count: usize, counters: &mut EvictionCounters) {
+        use ReadOp::*;
+        let mut freq = self.frequency_sketch.write();
+        let mut climber = self.hill_climber.lock();
+        let ch = &self.read_op_ch;
+        for _ in 0..count {
+            match ch.try_recv() {
+                Ok(Hit(hash, entry, timestamp)) => {
+                    freq.increment(hash);
+                    climber.record_hit();
+                    entry.set_last_accessed(timestamp);
+                    self.handle_hit(deqs, &entry, counters);
+                    if self.expiry.is_some() {
+                        // The expiration time may have been updated by the read.
+                        deqs.timer_wheel.reschedule(&entry);
+                    }
+                }
+                Ok(Miss(hash)) => {
+                    freq.increment(hash);
+                    climber.record_miss();
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Moves the access-order node of the hit entry to the MRU position. If the
+    /// entry is in the probation segment, it will be promoted to the protected
+    /// segment.
+    fn handle_hit(
+        &self,
+        deqs: &mut Deques<K>,
+        entry: &TrioArc<ValueEntry<K, V>>,
+        counters: &mut EvictionCounters,
+    ) {
+        // Do not promote a dirty entry; its policy weight may not have been applied
+        // to the counters yet.
+        if self.is_window_enabled()
+            && !entry.is_dirty()
+            && entry.access_order_q_region() == Some(CacheRegion::MainProbation)
+            && deqs.move_to_region_ao(CacheRegion::MainProtected, entry)
+        {
+            counters.saturating_add_to_region(CacheRegion::MainProtected, entry.policy_weight());
+            self.demote_from_protected(deqs, counters);
+        } else {
+            deqs.move_to_back_ao(entry);
+        }
+    }
+
+    /// Demotes the LRU entries of the protected segment to the probation segment
+    /// while the protected segment exceeds its max weighted size.
+    fn demote_from_protected(&self, deqs: &mut Deques<K>, counters: &mut EvictionCounters) {
+        let max_weight = self.protected_max_weight.load();
+        self.move_lru_entries(
+            CacheRegion::MainProtected,
+            CacheRegion::MainProbation,
+            max_weight,
+            deqs,
+            counters,
+        );
+    }
+
+    /// Moves the LRU entries of the `from` region to the MRU position of the `to`
+    /// region while the `from` region exceeds the given max weighted size.
+    fn move_lru_entries(
+        &self,
+        from: CacheRegion,
+        to: CacheRegion,
+        max_weight: u64,
+        deqs: &mut Deques<K>,
+        counters: &mut EvictionCounters,
+    ) {
+        const MAX_CONSECUTIVE_RETRIES: usize = 5;
+        let mut retries = 0;
+
+        while counters.region_weighted_size(from) > max_weight {
+            let (node, maybe_entry) = match deqs.ao_deque_mut(from).peek_front() {
+                Some(node) => (NonNull::from(node), self.entry_of_ao_node(node)),
+                None => break,
+            };
+
+            match maybe_entry {
+                Some(entry) if deqs.move_to_region_ao(to, &entry) => {
+                    let weight = entry.policy_weight();
+                    counters.saturating_sub_from_region(from, weight);
+                    counters.saturating_add_to_region(to, weight);
+                    retries = 0;
+                }
+                _ => {
+                    // The entry might have been invalidated. Since the invalidated
+                    // ValueEntry (which should be still in the write op queue) has a
+                    // pointer to this node, move the node to the back of the deque
+                    // instead of popping (dropping) it.
+                    unsafe { deqs.ao_deque_mut(from).move_to_back(node) };
+                    retries += 1;
+                    if retries > MAX_CONSECUTIVE_RETRIES {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adapts the split between the admission window and the main space by the
+    /// hill climbing algorithm.
+    fn climb(&self, deqs: &mut Deques<K>, counters: &mut EvictionCounters) {
+        let adjustment = {
+            let mut climber = self.hill_climber.lock();
+            if !self.frequency_sketch_enabled.load(Ordering::Acquire) {
+                // The hit rate is not meaningful until the cache gets half full.
+                climber.reset_sample();
+                return;
+            }
+            let sample_size = self.frequency_sketch.read().sample_size() as u64;
+            climber.adjustment(sample_size, self.max_capacity.unwrap_or_default())
+        };
+
+        match adjustment.cmp(&0) {
+            std::cmp::Ordering::Greater => self.increase_window(adjustment as u64, deqs, counters),
+            std::cmp::Ordering::Less => {
+                self.decrease_window(adjustment.unsigned_abs(), deqs, counters)
+            }
+            std::cmp::Ordering::Equal => (),
+        }
+    }
+
+    /// Moves the given amount of the max weighted size from the protected segment
+    /// to the admission window.
+    fn increase_window(&self, amount: u64, deqs: &mut Deques<K>, counters: &mut EvictionCounters) {
+        let protected_max = self.protected_max_weight.load();
+        let quota = amount.min(protected_max);
+        if quota == 0 {
+            return;
+        }
+
+        self.window_max_weight
+            .store(self.window_max_weight.load() + quota);
+        self.protected_max_weight.store(protected_max - quota);
+        // The window will be filled by new entries.
+        self.demote_from_protected(deqs, counters);
+    }
+
+    /// Moves the given amount of the max weighted size from the admission window to
+    /// the protected segment.
+    fn decrease_window(&self, amount: u64, deqs: &mut Deques<K>, counters: &mut EvictionCounters) {
+        let window_max = self.window_max_weight.load();
+        // Keep the window at least one weight so that it will not be disabled.
+        let quota = amount.min(window_max.saturating_sub(1));
+        if quota == 0 {
+            return;
+        }
+
+        let window_max = window_max - quota;
+        self.window_max_weight.store(window_max);
+        self.protected_max_weight
+            .store(self.protected_max_weight.load() + quota);
+        // Move the overflowed entries to the main space. They do not need to go
+        // through the admission as the total weighted size does not change.
+        self.move_lru_entries(
+            CacheRegion::Window,
+            CacheRegion::MainProbation,
+            window_max,
+            deqs,
+            counters,
+        );
+    }
+
+    /// Returns the entry in the cache (hash map) that owns the given access-order
+    /// deque node. Returns `None` if the entry has been removed from the cache, or
+    /// if the entry for the key has been replaced by another entry with a
+    /// different node.
+    fn entry_of_ao_node(
+        &self,
+        node: &DeqNode<KeyHashDate<K>>,
+    ) -> Option<TrioArc<ValueEntry<K, V>>> {
+        let elem = &node.element;
+        self.cache
+            .get(elem.hash(), |k| k == elem.key())
+            .filter(|entry| {
+                entry
+                    .access_order_q_node()
+                    .map_or(false, |tagged| std::ptr::eq(tagged.decompose_ptr(), node))
+            })
     }
 
     fn apply_writes(
@@ -1304,8 +2298,8 @@ where
         &self,
         kh: KeyHash<K>,
         entry: TrioArc<ValueEntry<K, V>>,
-        old_weight: u32,
-        new_weight: u32,
+        old_weight: u64,
+        new_weight: u64,
         deqs: &mut Deques<K>,
         freq: &FrequencySketch,
         eviction_state: &mut EvictionState<'_, K, V>,
@@ -1321,21 +2315,33 @@ where
                 // The entry has been already admitted, so treat this as an update.
                 counters.saturating_sub(0, old_weight);
                 counters.saturating_add(0, new_weight);
+                if let Some(region) = entry.access_order_q_region() {
+                    counters.saturating_sub_from_region(region, old_weight);
+                    counters.saturating_add_to_region(region, new_weight);
+                }
                 deqs.move_to_back_ao(&entry);
                 deqs.move_to_back_wo(&entry);
+                self.schedule_timer(deqs, &kh, &entry);
                 return;
             }
 
-            if self.has_enough_capacity(new_weight, counters) {
+            if !self.is_window_enabled() && self.has_enough_capacity(new_weight, counters) {
                 // There are enough room in the cache (or the cache is unbounded).
                 // Add the candidate to the deques.
-                self.handle_admit(kh, &entry, new_weight, deqs, counters);
+                self.handle_admit(
+                    kh,
+                    &entry,
+                    new_weight,
+                    CacheRegion::MainProbation,
+                    deqs,
+                    counters,
+                );
                 return;
             }
         }
 
         if let Some(max) = self.max_capacity {
-            if new_weight as u64 > max {
+            if new_weight > max {
                 // The candidate is too big to fit in the cache. Reject it.
 
                 // Lock the key for removal if blocking removal notification is enabled.
@@ -1344,7 +2350,7 @@ where
 
                 let removed = self.cache.remove(kh.hash, |k| k == &kh.key);
                 if let Some(entry) = removed {
-                    if eviction_state.is_notifier_enabled() {
+                    if eviction_state.is_tracking_removals() {
                         let key = Arc::clone(&kh.key);
                         eviction_state.add_removed_entry(key, &entry, RemovalCause::Size);
                     }
@@ -1353,52 +2359,46 @@ where
             }
         }
 
-        let skipped_nodes;
+        if self.is_window_enabled() {
+            // A new entry is always added to the admission window. Then the LRU
+            // entries of the window will be moved to the main space, or be evicted
+            // if they cannot be admitted to the main space.
+            self.handle_admit(
+                kh,
+                &entry,
+                new_weight,
+                CacheRegion::Window,
+                deqs,
+                &mut eviction_state.counters,
+            );
+            self.evict_from_window(deqs, freq, eviction_state);
+            return;
+        }
+
         let mut candidate = EntrySizeAndFrequency::new(new_weight);
         candidate.add_frequency(freq, kh.hash);
 
         // Try to admit the candidate.
-        match Self::admit(&candidate, &self.cache, deqs, freq) {
+        let skipped_nodes = match Self::admit(&candidate, &self.cache, deqs, freq) {
             AdmissionResult::Admitted {
                 victim_nodes,
-                skipped_nodes: mut skipped,
+                mut skipped_nodes,
             } => {
                 // Try to remove the victims from the cache (hash map).
-                for victim in victim_nodes {
-                    let element = unsafe { &victim.as_ref().element };
-
-                    // Lock the key for removal if blocking removal notification is enabled.
-                    let kl = self.maybe_key_lock(element.key());
-                    let _klg = &kl.as_ref().map(|kl| kl.lock());
-
-                    if let Some((vic_key, vic_entry)) = self
-                        .cache
-                        .remove_entry(element.hash(), |k| k == element.key())
-                    {
-                        if eviction_state.is_notifier_enabled() {
-                            eviction_state.add_removed_entry(
-                                vic_key,
-                                &vic_entry,
-                                RemovalCause::Size,
-                            );
-                        }
-                        // And then remove the victim from the deques.
-                        Self::handle_remove(deqs, vic_entry, &mut eviction_state.counters);
-                    } else {
-                        // Could not remove the victim from the cache. Skip this
-                        // victim node as its ValueEntry might have been
-                        // invalidated. Add it to the skipped nodes.
-                        skipped.push(victim);
-                    }
-                }
-                skipped_nodes = skipped;
+                self.remove_victims(victim_nodes, &mut skipped_nodes, deqs, eviction_state);
 
                 // Add the candidate to the deques.
-                self.handle_admit(kh, &entry, new_weight, deqs, &mut eviction_state.counters);
+                self.handle_admit(
+                    kh,
+                    &entry,
+                    new_weight,
+                    CacheRegion::MainProbation,
+                    deqs,
+                    &mut eviction_state.counters,
+                );
+                skipped_nodes
             }
-            AdmissionResult::Rejected { skipped_nodes: s } => {
-                skipped_nodes = s;
-
+            AdmissionResult::Rejected { skipped_nodes } => {
                 // Lock the key for removal if blocking removal notification is enabled.
                 let kl = self.maybe_key_lock(&kh.key);
                 let _klg = &kl.as_ref().map(|kl| kl.lock());
@@ -1406,16 +2406,153 @@ where
                 // Remove the candidate from the cache (hash map).
                 let key = Arc::clone(&kh.key);
                 self.cache.remove(kh.hash, |k| k == &key);
-                if eviction_state.is_notifier_enabled() {
+                if eviction_state.is_tracking_removals() {
                     eviction_state.add_removed_entry(key, &entry, RemovalCause::Size);
                 }
+                skipped_nodes
             }
         };
 
-        // Move the skipped nodes to the back of the deque. We do not unlink (drop)
-        // them because ValueEntries in the write op queue should be pointing them.
-        for node in skipped_nodes {
-            unsafe { deqs.probation.move_to_back(node) };
+        Self::move_skipped_nodes_to_back(deqs, skipped_nodes);
+    }
+
+    /// Moves the LRU entries of the admission window to the probation segment of
+    /// the main space while the window exceeds its max weighted size.
+    ///
+    /// If the main space is full, a window entry becomes a candidate and competes
+    /// with the LRU entries of the main space by the TinyLFU admission policy. The
+    /// loser will be evicted from the cache.
+    fn evict_from_window(
+        &self,
+        deqs: &mut Deques<K>,
+        freq: &FrequencySketch,
+        eviction_state: &mut EvictionState<'_, K, V>,
+    ) where
+        V: Clone,
+    {
+        const MAX_CONSECUTIVE_RETRIES: usize = 5;
+        let mut retries = 0;
+
+        let window_max = self.window_max_weight.load();
+        while eviction_state.counters.window_weighted_size > window_max {
+            let (node, maybe_entry) = match deqs.window.peek_front() {
+                Some(node) => (NonNull::from(node), self.entry_of_ao_node(node)),
+                None => break,
+            };
+
+            let entry = if let Some(entry) = maybe_entry {
+                retries = 0;
+                entry
+            } else {
+                // The entry might have been invalidated. Move the node to the back of
+                // the deque instead of popping (dropping) it, because the invalidated
+                // ValueEntry (which should be still in the write op queue) has a
+                // pointer to this node.
+                unsafe { deqs.window.move_to_back(node) };
+                retries += 1;
+                if retries > MAX_CONSECUTIVE_RETRIES {
+                    break;
+                }
+                continue;
+            };
+
+            let weight = entry.policy_weight();
+
+            if self.has_enough_capacity(0, &eviction_state.counters) {
+                // There are enough room in the main space. Move the entry to the
+                // probation segment.
+                deqs.move_to_region_ao(CacheRegion::MainProbation, &entry);
+                eviction_state
+                    .counters
+                    .saturating_sub_from_region(CacheRegion::Window, weight);
+                continue;
+            }
+
+            let (key, hash) = {
+                let elem = unsafe { &node.as_ref().element };
+                (Arc::clone(elem.key()), elem.hash())
+            };
+            let mut candidate = EntrySizeAndFrequency::new(weight);
+            candidate.add_frequency(freq, hash);
+
+            // Try to admit the candidate to the main space.
+            let skipped_nodes = match Self::admit(&candidate, &self.cache, deqs, freq) {
+                AdmissionResult::Admitted {
+                    victim_nodes,
+                    mut skipped_nodes,
+                } => {
+                    // Try to remove the victims from the cache (hash map).
+                    self.remove_victims(victim_nodes, &mut skipped_nodes, deqs, eviction_state);
+
+                    // Move the candidate to the probation segment.
+                    deqs.move_to_region_ao(CacheRegion::MainProbation, &entry);
+                    eviction_state
+                        .counters
+                        .saturating_sub_from_region(CacheRegion::Window, weight);
+                    skipped_nodes
+                }
+                AdmissionResult::Rejected { skipped_nodes } => {
+                    // Lock the key for removal if blocking removal notification is enabled.
+                    let kl = self.maybe_key_lock(&key);
+                    let _klg = &kl.as_ref().map(|kl| kl.lock());
+
+                    // Remove the candidate from the cache (hash map) and the deques.
+                    if let Some((key, entry)) = self.cache.remove_entry(hash, |k| k == &key) {
+                        if eviction_state.is_tracking_removals() {
+                            eviction_state.add_removed_entry(key, &entry, RemovalCause::Size);
+                        }
+                        Self::handle_remove(deqs, entry, &mut eviction_state.counters);
+                    }
+                    skipped_nodes
+                }
+            };
+
+            Self::move_skipped_nodes_to_back(deqs, skipped_nodes);
+        }
+    }
+
+    fn remove_victims(
+        &self,
+        victim_nodes: SmallVec<[(CacheRegion, AoqNode<K>); 8]>,
+        skipped_nodes: &mut SmallVec<[(CacheRegion, AoqNode<K>); 4]>,
+        deqs: &mut Deques<K>,
+        eviction_state: &mut EvictionState<'_, K, V>,
+    ) where
+        V: Clone,
+    {
+        for (region, victim) in victim_nodes {
+            let element = unsafe { &victim.as_ref().element };
+
+            // Lock the key for removal if blocking removal notification is enabled.
+            let kl = self.maybe_key_lock(element.key());
+            let _klg = &kl.as_ref().map(|kl| kl.lock());
+
+            if let Some((vic_key, vic_entry)) = self
+                .cache
+                .remove_entry(element.hash(), |k| k == element.key())
+            {
+                if eviction_state.is_tracking_removals() {
+                    eviction_state.add_removed_entry(vic_key, &vic_entry, RemovalCause::Size);
+                }
+                // And then remove the victim from the deques.
+                Self::handle_remove(deqs, vic_entry, &mut eviction_state.counters);
+            } else {
+                // Could not remove the victim from the cache. Skip this victim node
+                // as its ValueEntry might have been invalidated. Add it to the
+                // skipped nodes.
+                skipped_nodes.push((region, victim));
+            }
+        }
+    }
+
+    // Moves the skipped nodes to the back of their deques. We do not unlink (drop)
+    // them because ValueEntries in the write op queue should be pointing them.
+    fn move_skipped_nodes_to_back(
+        deqs: &mut Deques<K>,
+        skipped_nodes: SmallVec<[(CacheRegion, AoqNode<K>); 4]>,
+    ) {
+        for (region, node) in skipped_nodes {
+            unsafe { deqs.ao_deque_mut(region).move_to_back(node) };
         }
     }
 
@@ -1435,6 +2572,8 @@ where
     ///   promoted (to the MRU position?) to force the eviction policy to select a
     ///   different set of victims for the next candidate). We may implement the
     ///   paper's behavior later?
+    /// - The potential victims are selected from the LRU position of the probation
+    ///   segment first, and then from the protected segment.
     ///
     #[inline]
     fn admit(
@@ -1450,7 +2589,8 @@ where
         let mut victim_nodes = SmallVec::default();
         let mut skipped_nodes = SmallVec::default();
 
-        // Get first potential victim at the LRU position.
+        // Get first potential victim at the LRU position of the probation segment.
+        let mut region = CacheRegion::MainProbation;
         let mut next_victim = deqs.probation.peek_front();
 
         // Aggregate potential victims.
@@ -1458,6 +2598,12 @@ where
             if candidate.freq < victims.freq {
                 break;
             }
+            if next_victim.is_none() && region == CacheRegion::MainProbation {
+                // No more potential victims in the probation segment. Continue with
+                // the LRU position of the protected segment.
+                region = CacheRegion::MainProtected;
+                next_victim = deqs.protected.peek_front();
+            }
             if let Some(victim) = next_victim.take() {
                 next_victim = victim.next_node();
                 let vic_elem = &victim.element;
@@ -1465,12 +2611,12 @@ where
                 if let Some(vic_entry) = cache.get(vic_elem.hash(), |k| k == vic_elem.key()) {
                     victims.add_policy_weight(vic_entry.policy_weight());
                     victims.add_frequency(freq, vic_elem.hash());
-                    victim_nodes.push(NonNull::from(victim));
+                    victim_nodes.push((region, NonNull::from(victim)));
                     retries = 0;
                 } else {
                     // Could not get the victim from the cache (hash map). Skip this node
                     // as its ValueEntry might have been invalidated.
-                    skipped_nodes.push(NonNull::from(victim));
+                    skipped_nodes.push((region, NonNull::from(victim)));
 
                     retries += 1;
                     if retries > MAX_CONSECUTIVE_RETRIES {
@@ -1502,23 +2648,36 @@ where
         &self,
         kh: KeyHash<K>,
         entry: &TrioArc<ValueEntry<K, V>>,
-        policy_weight: u32,
+        policy_weight: u64,
+        region: CacheRegion,
         deqs: &mut Deques<K>,
         counters: &mut EvictionCounters,
     ) {
         let key = Arc::clone(&kh.key);
         counters.saturating_add(1, policy_weight);
-        deqs.push_back_ao(
-            CacheRegion::MainProbation,
-            KeyHashDate::new(kh, entry.entry_info()),
-            entry,
-        );
+        counters.saturating_add_to_region(region, policy_weight);
+        self.schedule_timer(deqs, &kh, entry);
+        deqs.push_back_ao(region, KeyHashDate::new(kh, entry.entry_info()), entry);
         if self.is_write_order_queue_enabled() {
             deqs.push_back_wo(KeyDate::new(key, entry.entry_info()), entry);
         }
         entry.set_admitted(true);
     }
 
+    /// Schedules (or reschedules) the timer for the per-entry expiration time of
+    /// the entry. The timer wheel is enabled when it is used for the first time.
+    fn schedule_timer(&self, deqs: &mut Deques<K>, kh: &KeyHash<K>, entry: &ValueEntry<K, V>) {
+        let tw = &mut deqs.timer_wheel;
+        if entry.entry_info().expiration_time().is_some() {
+            if !tw.is_enabled() {
+                tw.enable(self.current_time_from_expiration_clock());
+            }
+            tw.schedule(kh.clone(), entry);
+        } else {
+            tw.deschedule(entry);
+        }
+    }
+
     fn handle_remove(
         deqs: &mut Deques<K>,
         entry: TrioArc<ValueEntry<K, V>>,
@@ -1526,10 +2685,15 @@ where
     ) {
         if entry.is_admitted() {
             entry.set_admitted(false);
-            counters.saturating_sub(1, entry.policy_weight());
+            let weight = entry.policy_weight();
+            counters.saturating_sub(1, weight);
+            if let Some(region) = entry.access_order_q_region() {
+                counters.saturating_sub_from_region(region, weight);
+            }
             // The following two unlink_* functions will unset the deq nodes.
             deqs.unlink_ao(&entry);
             Deques::unlink_wo(&mut deqs.write_order, &entry);
+            deqs.timer_wheel.deschedule(&entry);
         } else {
             entry.unset_q_nodes();
         }
@@ -1539,15 +2703,19 @@ where
         ao_deq_name: &str,
         ao_deq: &mut Deque<KeyHashDate<K>>,
         wo_deq: &mut Deque<KeyDate<K>>,
+        timer_wheel: &mut TimerWheel<K>,
         entry: TrioArc<ValueEntry<K, V>>,
         counters: &mut EvictionCounters,
     ) {
         if entry.is_admitted() {
             entry.set_admitted(false);
-            counters.saturating_sub(1, entry.policy_weight());
+            let weight = entry.policy_weight();
+            counters.saturating_sub(1, weight);
+            counters.saturating_sub_from_region(ao_deq.region(), weight);
             // The following two unlink_* functions will unset the deq nodes.
             Deques::unlink_ao_from_deque(ao_deq_name, ao_deq, &entry);
             Deques::unlink_wo(wo_deq, &entry);
+            timer_wheel.deschedule(&entry);
         } else {
             entry.unset_q_nodes();
         }
@@ -1568,28 +2736,83 @@ where
         }
 
         if self.time_to_idle.is_some() || self.has_valid_after() {
-            let (window, probation, protected, wo) = (
+            let (window, probation, protected, wo, tw) = (
                 &mut deqs.window,
                 &mut deqs.probation,
                 &mut deqs.protected,
                 &mut deqs.write_order,
+                &mut deqs.timer_wheel,
             );
 
-            let mut rm_expired_ao =
-                |name, deq| self.remove_expired_ao(name, deq, wo, batch_size, now, eviction_state);
+            let mut rm_expired_ao = |name, deq| {
+                self.remove_expired_ao(name, deq, wo, tw, batch_size, now, eviction_state)
+            };
 
-            rm_expired_ao("window", window);
             rm_expired_ao("probation", probation);
             rm_expired_ao("protected", protected);
+            rm_expired_ao("window", window);
+        }
+
+        if deqs.timer_wheel.is_enabled() {
+            self.remove_expired_by_timer(deqs, batch_size, now, eviction_state);
+        }
+    }
+
+    /// Advances the timer wheel and removes the entries whose per-entry expiration
+    /// time (calculated by the `Expiry` or given by `insert_with_ttl`) has passed.
+    fn remove_expired_by_timer(
+        &self,
+        deqs: &mut Deques<K>,
+        batch_size: usize,
+        now: Instant,
+        eviction_state: &mut EvictionState<'_, K, V>,
+    ) where
+        V: Clone,
+    {
+        deqs.timer_wheel.advance(now);
+
+        for _ in 0..batch_size {
+            let KeyHash { key, hash } = match deqs.timer_wheel.pop_expired() {
+                Some(kh) => kh,
+                None => break,
+            };
+
+            // Lock the key for removal if blocking removal notification is enabled.
+            let kl = self.maybe_key_lock(&key);
+            let _klg = &kl.as_ref().map(|kl| kl.lock());
+
+            // Check the expiration time again as the entry may have been updated
+            // after its timer was fired.
+            let maybe_entry = self.cache.remove_if(
+                hash,
+                |k| k == &key,
+                |_, v| is_expired_by_expiry(v.entry_info(), now),
+            );
+
+            if let Some(entry) = maybe_entry {
+                if eviction_state.is_tracking_removals() {
+                    eviction_state.add_removed_entry(key, &entry, RemovalCause::Expired);
+                }
+                Self::handle_remove(deqs, entry, &mut eviction_state.counters);
+            } else if let Some(entry) = self.cache.get(hash, |k| k == &key) {
+                // The expiration time has been extended (or cleared). Schedule a
+                // new timer if the entry is still in the deques.
+                if entry.is_admitted() {
+                    deqs.timer_wheel.schedule(KeyHash::new(key, hash), &entry);
+                }
+            }
         }
     }
 
     #[inline]
+    // https://rust-lang.github.io/rust-clippy/master/index.html#too_many_arguments
+    #[allow(clippy::too_many_arguments)]
     fn remove_expired_ao(
         &self,
         deq_name: &str,
         deq: &mut Deque<KeyHashDate<K>>,
         write_order_deq: &mut Deque<KeyDate<K>>,
+        timer_wheel: &mut TimerWheel<K>,
         batch_size: usize,
         now: Instant,
         eviction_state: &mut EvictionState<'_, K, V>,
@@ -1611,7 +2834,7 @@ where
                     (false, true) => Some((
                         Arc::clone(node.element.key()),
                         node.element.hash(),
-                        RemovalCause::Explicit,
+                        RemovalCause::Invalidated { predicate_id: None },
                     )),
                     (false, false) => None,
                 }
@@ -1641,7 +2864,7 @@ where
             );
 
             if let Some(entry) = maybe_entry {
-                if eviction_state.is_notifier_enabled() {
+                if eviction_state.is_tracking_removals() {
                     let key = Arc::clone(key);
                     eviction_state.add_removed_entry(key, &entry, cause);
                 }
@@ -1649,6 +2872,7 @@ where
                     deq_name,
                     deq,
                     write_order_deq,
+                    timer_wheel,
                     entry,
                     &mut eviction_state.counters,
                 );
@@ -1670,7 +2894,13 @@ where
         if let Some(entry) = self.cache.get(hash, |k| (k.borrow() as &K) == key) {
             if entry.is_dirty() {
                 // The key exists and the entry has been updated.
-                Deques::move_to_back_ao_in_deque(deq_name, deq, &entry);
+                if entry.access_order_q_region() == Some(deq.region()) {
+                    Deques::move_to_back_ao_in_deque(deq_name, deq, &entry);
+                } else {
+                    // The entry lives in another deque. The node at the front of
+                    // this deque must belong to an invalidated ValueEntry.
+                    Self::move_front_node_to_back(deq);
+                }
                 Deques::move_to_back_wo_in_deque(write_order_deq, &entry);
                 true
             } else {
@@ -1682,14 +2912,19 @@ where
             // invalidated ValueEntry (which should be still in the write op
             // queue) has a pointer to this node, move the node to the back of
             // the deque instead of popping (dropping) it.
-            if let Some(node) = deq.peek_front() {
-                let node = NonNull::from(node);
-                unsafe { deq.move_to_back(node) };
-            }
+            Self::move_front_node_to_back(deq);
             true
         }
     }
 
+    #[inline]
+    fn move_front_node_to_back(deq: &mut Deque<KeyHashDate<K>>) {
+        if let Some(node) = deq.peek_front() {
+            let node = NonNull::from(node);
+            unsafe { deq.move_to_back(node) };
+        }
+    }
+
     #[inline]
     fn remove_expired_wo(
         &self,
@@ -1703,15 +2938,32 @@ where
         let ttl = &self.time_to_live;
         let va = &self.valid_after();
         for _ in 0..batch_size {
+            let mut ttl_overridden = false;
             let key_cause = deqs.write_order.peek_front().and_then(
                 // TODO: Skip the entry if it is dirty. See `evict_lru_entries` method as an example.
                 |node| match is_entry_expired_wo_or_invalid(ttl, va, node, now) {
                     (true, _) => Some((Arc::clone(node.element.key()), RemovalCause::Expired)),
-                    (false, true) => Some((Arc::clone(node.element.key()), RemovalCause::Explicit)),
-                    (false, false) => None,
+                    (false, true) => Some((
+                        Arc::clone(node.element.key()),
+                        RemovalCause::Invalidated { predicate_id: None },
+                    )),
+                    (false, false) => {
+                        ttl_overridden = node.is_ttl_overridden();
+                        None
+                    }
                 },
             );
 
+            if ttl_overridden {
+                // The entry expires by its own TTL (the timer wheel will take care
+                // of it). Skip it so that it will not block the following entries.
+                if let Some(node) = deqs.write_order.peek_front() {
+                    let node = NonNull::from(node);
+                    unsafe { deqs.write_order.move_to_back(node) };
+                }
+                continue;
+            }
+
             if key_cause.is_none() {
                 break;
             }
@@ -1730,7 +2982,7 @@ where
             );
 
             if let Some(entry) = maybe_entry {
-                if eviction_state.is_notifier_enabled() {
+                if eviction_state.is_tracking_removals() {
                     let key = Arc::clone(key);
                     eviction_state.add_removed_entry(key, &entry, *cause);
                 }
@@ -1842,9 +3094,56 @@ where
     ) where
         V: Clone,
     {
-        const DEQ_NAME: &str = "probation";
         let mut evicted = 0u64;
-        let (deq, write_order_deq) = (&mut deqs.probation, &mut deqs.write_order);
+        let (window, probation, protected, wo, tw) = (
+            &mut deqs.window,
+            &mut deqs.probation,
+            &mut deqs.protected,
+            &mut deqs.write_order,
+            &mut deqs.timer_wheel,
+        );
+
+        // Evict from the probation segment first, then the protected segment, and
+        // finally the admission window, which has the most recently added entries.
+        for (deq_name, deq) in [
+            ("probation", probation),
+            ("protected", protected),
+            ("window", window),
+        ]
+        .iter_mut()
+        {
+            if evicted >= weights_to_evict {
+                break;
+            }
+            evicted = evicted.saturating_add(self.evict_lru_entries_from_deque(
+                deq_name,
+                deq,
+                wo,
+                tw,
+                batch_size,
+                weights_to_evict - evicted,
+                eviction_state,
+            ));
+        }
+    }
+
+    // Returns the total weight of the evicted entries.
+    // https://rust-lang.github.io/rust-clippy/master/index.html#too_many_arguments
+    #[allow(clippy::too_many_arguments)]
+    fn evict_lru_entries_from_deque(
+        &self,
+        deq_name: &str,
+        deq: &mut Deque<KeyHashDate<K>>,
+        write_order_deq: &mut Deque<KeyDate<K>>,
+        timer_wheel: &mut TimerWheel<K>,
+        batch_size: usize,
+        weights_to_evict: u64,
+        eviction_state: &mut EvictionState<'_, K, V>,
+    ) -> u64
+    where
+        V: Clone,
+    {
+        let mut evicted = 0u64;
 
         for _ in 0..batch_size {
             if evicted >= weights_to_evict {
@@ -1867,7 +3166,7 @@ where
                 // `last_modified` and `last_accessed` in `EntryInfo` from `Option<Instant>` to
                 // `Instant`.
                 Some((key, hash, true, _)) | Some((key, hash, false, None)) => {
-                    if self.try_skip_updated_entry(&key, hash, DEQ_NAME, deq, write_order_deq) {
+                    if self.try_skip_updated_entry(&key, hash, deq_name, deq, write_order_deq) {
                         continue;
                     } else {
                         break;
@@ -1893,22 +3192,25 @@ where
             );
 
             if let Some(entry) = maybe_entry {
-                if eviction_state.is_notifier_enabled() {
+                if eviction_state.is_tracking_removals() {
                     eviction_state.add_removed_entry(key, &entry, RemovalCause::Size);
                 }
                 let weight = entry.policy_weight();
                 Self::handle_remove_with_deques(
-                    DEQ_NAME,
+                    deq_name,
                     deq,
                     write_order_deq,
+                    timer_wheel,
                     entry,
                     &mut eviction_state.counters,
                 );
-                evicted = evicted.saturating_add(weight as u64);
-            } else if !self.try_skip_updated_entry(&key, hash, DEQ_NAME, deq, write_order_deq) {
+                evicted = evicted.saturating_add(weight);
+            } else if !self.try_skip_updated_entry(&key, hash, deq_name, deq, write_order_deq) {
                 break;
             }
         }
+
+        evicted
     }
 }
 
@@ -1935,11 +3237,17 @@ where
         entry: &TrioArc<ValueEntry<K, V>>,
         last_accessed: Option<Instant>,
         last_modified: Option<Instant>,
+        expiration_time: Option<Instant>,
+        is_ttl_overridden: bool,
     ) {
         let now = self.current_time_from_expiration_clock();
 
         let mut cause = RemovalCause::Replaced;
 
+        if expiration_time.map(|ts| ts <= now).unwrap_or_default() {
+            cause = RemovalCause::Expired;
+        }
+
         if let Some(last_accessed) = last_accessed {
             if is_expired_by_tti(&self.time_to_idle, last_accessed, now) {
                 cause = RemovalCause::Expired;
@@ -1947,10 +3255,17 @@ where
         }
 
         if let Some(last_modified) = last_modified {
-            if is_expired_by_ttl(&self.time_to_live, last_modified, now) {
+            let ttl = if is_ttl_overridden {
+                &None
+            } else {
+                &self.time_to_live
+            };
+            if is_expired_by_ttl(ttl, last_modified, now) {
                 cause = RemovalCause::Expired;
-            } else if is_invalid_entry(&self.valid_after(), last_modified) {
-                cause = RemovalCause::Explicit;
+            } else if let Some(invalidated) =
+                self.invalidated_cause(&key, &entry.value, last_modified)
+            {
+                cause = invalidated;
             }
         }
 
@@ -1958,10 +3273,19 @@ where
     }
 
     #[inline]
-    fn notify_invalidate(&self, key: &Arc<K>, entry: &TrioArc<ValueEntry<K, V>>) {
+    fn notify_invalidate(
+        &self,
+        key: &Arc<K>,
+        entry: &TrioArc<ValueEntry<K, V>>,
+        cause: RemovalCause,
+    ) {
         let now = self.current_time_from_expiration_clock();
 
-        let mut cause = RemovalCause::Explicit;
+        let mut cause = cause;
+
+        if is_expired_by_expiry(entry.entry_info(), now) {
+            cause = RemovalCause::Expired;
+        }
 
         if let Some(last_accessed) = entry.last_accessed() {
             if is_expired_by_tti(&self.time_to_idle, last_accessed, now) {
@@ -1970,13 +3294,44 @@ where
         }
 
         if let Some(last_modified) = entry.last_modified() {
-            if is_expired_by_ttl(&self.time_to_live, last_modified, now) {
+            if !entry.is_ttl_overridden()
+                && is_expired_by_ttl(&self.time_to_live, last_modified, now)
+            {
                 cause = RemovalCause::Expired;
+            } else if cause != RemovalCause::Expired {
+                if let Some(invalidated) = self.invalidated_cause(key, &entry.value, last_modified)
+                {
+                    cause = invalidated;
+                }
             }
         }
 
         self.notify_single_removal(Arc::clone(key), entry, cause);
     }
+
+    /// Returns `RemovalCause::Invalidated` if the entry written at `last_modified`
+    /// has already been invalidated by `invalidate_all` or by a predicate of
+    /// `invalidate_entries_if`, but not yet removed by the cache.
+    fn invalidated_cause(
+        &self,
+        key: &K,
+        value: &V,
+        last_modified: Instant,
+    ) -> Option<RemovalCause> {
+        if is_invalid_entry(&self.valid_after(), last_modified) {
+            return Some(RemovalCause::Invalidated { predicate_id: None });
+        }
+        if self.invalidator_enabled {
+            if let Some(inv) = &*self.invalidator.read() {
+                return inv
+                    .matching_predicate(key, value, last_modified)
+                    .map(|handle| RemovalCause::Invalidated {
+                        predicate_id: Some(handle),
+                    });
+            }
+        }
+        None
+    }
 }
 
 //
@@ -2008,9 +3363,112 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<K, V, S> Inner<K, V, S>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    S: BuildHasher + Clone + Send + Sync + 'static,
+{
+    fn restore(&self, snapshot: CacheSnapshot<K, V>) {
+        let now = self.current_time_from_expiration_clock();
+        // Instants before the process started may not be representable. In that
+        // case, use the current time.
+        let back_date = |d: Duration| now.checked_sub(d).unwrap_or(now);
+
+        let mut deqs = self.deques.lock();
+        let mut counters = EvictionCounters::new(
+            self.entry_count.load(),
+            self.weighted_size.load(),
+            self.window_weighted_size.load(),
+            self.protected_weighted_size.load(),
+        );
+
+        let window_enabled = self.is_window_enabled();
+        if window_enabled && snapshot.max_capacity == self.max_capacity {
+            self.window_max_weight.store(snapshot.window_max_weight);
+            self.protected_max_weight
+                .store(snapshot.protected_max_weight);
+        }
+
+        let mut restored = Vec::with_capacity(snapshot.entries.len());
+        for es in snapshot.entries {
+            let region = match es.region {
+                0 if window_enabled => CacheRegion::Window,
+                2 if window_enabled => CacheRegion::MainProtected,
+                _ => CacheRegion::MainProbation,
+            };
+            let key = Arc::new(es.key);
+            let hash = self.hash(&*key);
+            let weight = self.weigh(&key, &es.value);
+
+            let info = TrioArc::new(EntryInfo::new(back_date(es.since_last_modified), weight));
+            info.set_last_accessed(back_date(es.since_last_accessed));
+            info.set_ttl_overridden(es.is_ttl_overridden);
+            info.set_expiration_time(es.time_to_expire.and_then(|d| now.checked_add(d)));
+            info.set_dirty(false);
+            let entry = TrioArc::new(ValueEntry::new(es.value, info));
+
+            if self
+                .cache
+                .insert_if_not_present(Arc::clone(&key), hash, TrioArc::clone(&entry))
+                .is_some()
+            {
+                // Keep the existing entry.
+                continue;
+            }
+
+            let kh = KeyHash::new(Arc::clone(&key), hash);
+            counters.saturating_add(1, weight);
+            counters.saturating_add_to_region(region, weight);
+            self.schedule_timer(&mut deqs, &kh, &entry);
+            deqs.push_back_ao(region, KeyHashDate::new(kh, entry.entry_info()), &entry);
+            entry.set_admitted(true);
+            restored.push((key, hash, es.frequency, es.since_last_modified, entry));
+        }
+
+        if self.is_write_order_queue_enabled() {
+            // Push the entries to the write order queue from the least recently
+            // modified one.
+            restored.sort_by_key(|(_, _, _, since_last_modified, _)| {
+                std::cmp::Reverse(*since_last_modified)
+            });
+            for (key, _, _, _, entry) in &restored {
+                deqs.push_back_wo(KeyDate::new(Arc::clone(key), entry.entry_info()), entry);
+            }
+        }
+
+        if snapshot.frequency_sketch_enabled || self.should_enable_frequency_sketch(&counters) {
+            self.enable_frequency_sketch(&counters);
+        }
+        if self.frequency_sketch_enabled.load(Ordering::Acquire) {
+            let mut freq = self.frequency_sketch.write();
+            for (_, hash, frequency, _, _) in &restored {
+                freq.restore_frequency(*hash, *frequency);
+            }
+        }
+
+        self.entry_count.store(counters.entry_count);
+        self.weighted_size.store(counters.weighted_size);
+        self.window_weighted_size
+            .store(counters.window_weighted_size);
+        self.protected_weighted_size
+            .store(counters.protected_weighted_size);
+    }
+}
+
 //
 // private free-standing functions
 //
+#[inline]
+fn record_load(stats: &dyn StatsCounter, is_success: bool, load_time: Duration) {
+    if is_success {
+        stats.record_load_success(load_time);
+    } else {
+        stats.record_load_failure(load_time);
+    }
+}
+
 #[inline]
 fn is_expired_entry_ao(
     time_to_idle: &Option<Duration>,
@@ -2034,7 +3492,10 @@ fn is_expired_entry_wo(
     now: Instant,
 ) -> bool {
     if let Some(ts) = entry.last_modified() {
-        if is_invalid_entry(valid_after, ts) || is_expired_by_ttl(time_to_live, ts, now) {
+        if is_invalid_entry(valid_after, ts) {
+            return true;
+        }
+        if !entry.is_ttl_overridden() && is_expired_by_ttl(time_to_live, ts, now) {
             return true;
         }
     }
@@ -2064,13 +3525,28 @@ fn is_entry_expired_wo_or_invalid(
     now: Instant,
 ) -> (bool, bool) {
     if let Some(ts) = entry.last_modified() {
-        let expired = is_expired_by_ttl(time_to_live, ts, now);
+        let expired = !entry.is_ttl_overridden() && is_expired_by_ttl(time_to_live, ts, now);
         let invalid = is_invalid_entry(valid_after, ts);
         return (expired, invalid);
     }
     (false, false)
 }
 
+#[inline]
+fn is_expired_by_expiry(entry_info: &EntryInfo, now: Instant) -> bool {
+    entry_info
+        .expiration_time()
+        .map(|ts| ts <= now)
+        .unwrap_or_default()
+}
+
+#[inline]
+fn remaining_duration(entry_info: &EntryInfo, now: Instant) -> Option<Duration> {
+    entry_info
+        .expiration_time()
+        .map(|ts| ts.saturating_duration_since(now))
+}
+
 #[inline]
 fn is_invalid_entry(valid_after: &Option<Instant>, entry_ts: Instant) -> bool {
     if let Some(va) = valid_after {
@@ -2138,8 +3614,11 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
                 false,
-                housekeeper::Configuration::new_thread_pool(true),
+                None,
+                None,
+                housekeeper::Configuration::new_thread_pool(true, None),
             );
             cache.inner.enable_frequency_sketch_for_testing();
             assert_eq!(
@@ -0,0 +1,127 @@
+// The hill climbing algorithm to adapt the size of the admission window. This was
+// ported from Caffeine.
+//
+// The climber samples the hit rate of the cache, and moves the split between the
+// admission window and the main space by a step in the direction that improved the
+// hit rate in the previous sample. The step size decays over time to converge, and
+// it will be reset when the hit rate changes significantly (e.g. the workload has
+// changed).
+
+// The percentage change in the hit rate that will restart the climber.
+const RESTART_THRESHOLD: f64 = 0.05;
+// The percentage of the max capacity that will be used as the initial step size.
+const STEP_PERCENT: f64 = 0.0625;
+// The rate to decrease the step size after each sample.
+const STEP_DECAY_RATE: f64 = 0.98;
+
+pub(crate) struct HillClimber {
+    hits_in_sample: u64,
+    misses_in_sample: u64,
+    previous_sample_hit_rate: f64,
+    step_size: f64,
+}
+
+impl HillClimber {
+    pub(crate) fn new(max_capacity: u64) -> Self {
+        Self {
+            hits_in_sample: 0,
+            misses_in_sample: 0,
+            previous_sample_hit_rate: 0.0,
+            // Start with shrinking the window.
+            step_size: -STEP_PERCENT * max_capacity as f64,
+        }
+    }
+
+    pub(crate) fn record_hit(&mut self) {
+        self.hits_in_sample = self.hits_in_sample.saturating_add(1);
+    }
+
+    pub(crate) fn record_miss(&mut self) {
+        self.misses_in_sample = self.misses_in_sample.saturating_add(1);
+    }
+
+    /// Discards the current sample.
+    pub(crate) fn reset_sample(&mut self) {
+        self.hits_in_sample = 0;
+        self.misses_in_sample = 0;
+        self.previous_sample_hit_rate = 0.0;
+    }
+
+    /// Returns the amount of weighted size to move from the main space to the
+    /// admission window. A negative value means to move from the window to the main
+    /// space. Returns `0` if the current sample has not been completed yet.
+    pub(crate) fn adjustment(&mut self, sample_size: u64, max_capacity: u64) -> i64 {
+        let request_count = self.hits_in_sample + self.misses_in_sample;
+        if request_count == 0 || request_count < sample_size {
+            return 0;
+        }
+
+        let hit_rate = self.hits_in_sample as f64 / request_count as f64;
+        let hit_rate_change = hit_rate - self.previous_sample_hit_rate;
+        let amount = if hit_rate_change >= 0.0 {
+            self.step_size
+        } else {
+            -self.step_size
+        };
+        let next_step_size = if hit_rate_change.abs() >= RESTART_THRESHOLD {
+            let step = STEP_PERCENT * max_capacity as f64;
+            if amount >= 0.0 {
+                step
+            } else {
+                -step
+            }
+        } else {
+            STEP_DECAY_RATE * amount
+        };
+
+        self.previous_sample_hit_rate = hit_rate;
+        self.step_size = next_step_size;
+        self.hits_in_sample = 0;
+        self.misses_in_sample = 0;
+
+        amount as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HillClimber;
+
+    #[test]
+    fn incomplete_sample() {
+        let mut climber = HillClimber::new(1_000);
+        for _ in 0..99 {
+            climber.record_hit();
+        }
+        assert_eq!(climber.adjustment(100, 1_000), 0);
+        assert_eq!(climber.hits_in_sample, 99);
+    }
+
+    #[test]
+    fn climb() {
+        let mut climber = HillClimber::new(1_000);
+
+        // The first sample improves the hit rate from zero, so the climber keeps
+        // the initial direction (shrinking the window).
+        record(&mut climber, 50, 50);
+        assert_eq!(climber.adjustment(100, 1_000), -62);
+        assert_eq!(climber.hits_in_sample, 0);
+        assert_eq!(climber.misses_in_sample, 0);
+
+        // The hit rate dropped significantly. Turn the direction and restart with the
+        // initial step size.
+        record(&mut climber, 40, 60);
+        assert_eq!(climber.adjustment(100, 1_000), 62);
+
+        // The hit rate improved slightly. Keep the direction with a decayed step.
+        record(&mut climber, 41, 59);
+        assert_eq!(climber.adjustment(100, 1_000), 62);
+        record(&mut climber, 42, 58);
+        assert_eq!(climber.adjustment(100, 1_000), 61);
+    }
+
+    fn record(climber: &mut HillClimber, hits: usize, misses: usize) {
+        (0..hits).for_each(|_| climber.record_hit());
+        (0..misses).for_each(|_| climber.record_miss());
+    }
+}
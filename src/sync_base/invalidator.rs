@@ -4,12 +4,14 @@ use super::{base_cache::Inner, PredicateId, PredicateIdStr};
 use crate::{
     common::{
         concurrent::{
-            thread_pool::{PoolName, ThreadPool, ThreadPoolRegistry},
+            thread_pool::{PoolExecutor, PoolName},
             unsafe_weak_pointer::UnsafeWeakPointer,
             AccessTime, KvEntry, ValueEntry,
         },
         time::Instant,
     },
+    executor::ExecutorArc,
+    notification::{PredicateHandle, RemovalCause},
     PredicateError,
 };
 
@@ -37,6 +39,7 @@ pub(crate) trait GetOrRemoveEntry<K, V> {
         key: &Arc<K>,
         hash: u64,
         condition: impl FnMut(&Arc<K>, &TrioArc<ValueEntry<K, V>>) -> bool,
+        cause: RemovalCause,
     ) -> Option<TrioArc<ValueEntry<K, V>>>
     where
         K: Send + Sync + 'static,
@@ -87,7 +90,7 @@ pub(crate) struct Invalidator<K, V, S> {
     predicates: RwLock<HashMap<PredicateId, Predicate<K, V>>>,
     is_empty: AtomicBool,
     scan_context: Arc<ScanContext<K, V, S>>,
-    thread_pool: Arc<ThreadPool>,
+    executor: ExecutorArc,
 }
 
 impl<K, V, S> Drop for Invalidator<K, V, S> {
@@ -100,8 +103,6 @@ impl<K, V, S> Drop for Invalidator<K, V, S> {
         while ctx.is_running.load(Ordering::Acquire) {
             std::thread::sleep(Duration::from_millis(1));
         }
-
-        ThreadPoolRegistry::release_pool(&self.thread_pool);
     }
 }
 
@@ -109,13 +110,13 @@ impl<K, V, S> Drop for Invalidator<K, V, S> {
 // Crate public methods.
 //
 impl<K, V, S> Invalidator<K, V, S> {
-    pub(crate) fn new(cache: Weak<Inner<K, V, S>>) -> Self {
-        let thread_pool = ThreadPoolRegistry::acquire_pool(PoolName::Invalidator);
+    pub(crate) fn new(cache: Weak<Inner<K, V, S>>, executor: Option<ExecutorArc>) -> Self {
+        let executor = PoolExecutor::or_acquire(executor, PoolName::Invalidator);
         Self {
             predicates: RwLock::new(HashMap::new()),
             is_empty: AtomicBool::new(true),
             scan_context: Arc::new(ScanContext::new(cache)),
-            thread_pool,
+            executor,
         }
     }
 
@@ -152,13 +153,15 @@ impl<K, V, S> Invalidator<K, V, S> {
         let mut preds = self.predicates.write();
 
         while tries < MAX_RETRY {
-            let id = Uuid::new_v4().as_hyphenated().to_string();
+            let uuid = Uuid::new_v4();
+            let id = uuid.as_hyphenated().to_string();
             if preds.contains_key(&id) {
                 tries += 1;
 
                 continue; // Retry
             }
-            let pred = Predicate::new(&id, predicate, registered_at);
+            let handle = PredicateHandle::new(uuid.as_u128());
+            let pred = Predicate::new(&id, handle, predicate, registered_at);
             preds.insert(id.clone(), pred);
             self.is_empty.store(false, Ordering::Release);
 
@@ -174,15 +177,29 @@ impl<K, V, S> Invalidator<K, V, S> {
     // This method will be called by the get method of Cache.
     #[inline]
     pub(crate) fn apply_predicates(&self, key: &Arc<K>, entry: &TrioArc<ValueEntry<K, V>>) -> bool {
-        if self.is_empty() {
-            false
-        } else if let Some(ts) = entry.last_modified() {
-            Self::do_apply_predicates(self.predicates.read().values(), key, &entry.value, ts)
+        if let Some(ts) = entry.last_modified() {
+            self.matching_predicate(key, &entry.value, ts).is_some()
         } else {
             false
         }
     }
 
+    /// Returns the handle of the first predicate that matches the entry written
+    /// at `last_modified`, or `None` if no predicate matches.
+    #[inline]
+    pub(crate) fn matching_predicate(
+        &self,
+        key: &K,
+        value: &V,
+        last_modified: Instant,
+    ) -> Option<PredicateHandle> {
+        if self.is_empty() {
+            None
+        } else {
+            Self::do_apply_predicates(self.predicates.read().values(), key, value, last_modified)
+        }
+    }
+
     pub(crate) fn is_task_running(&self) -> bool {
         self.scan_context.is_running.load(Ordering::Acquire)
     }
@@ -215,9 +232,9 @@ impl<K, V, S> Invalidator<K, V, S> {
         self.scan_context.is_running.store(true, Ordering::Release);
 
         let task = ScanTask::new(&self.scan_context, candidates, is_truncated);
-        self.thread_pool.pool.execute(move || {
+        self.executor.execute(Box::new(move || {
             task.execute();
-        });
+        }));
     }
 
     pub(crate) fn task_result(&self) -> Option<InvalidationResult<K, V>> {
@@ -236,17 +253,26 @@ impl<K, V, S> Invalidator<K, V, S> {
 // Private methods.
 //
 impl<K, V, S> Invalidator<K, V, S> {
+    /// Returns the handle of the first predicate that matches the entry, or `None`
+    /// if no predicate matches.
     #[inline]
-    fn do_apply_predicates<'a, I>(predicates: I, key: &'a K, value: &'a V, ts: Instant) -> bool
+    fn do_apply_predicates<'a, I>(
+        predicates: I,
+        key: &K,
+        value: &V,
+        ts: Instant,
+    ) -> Option<PredicateHandle>
     where
         I: Iterator<Item = &'a Predicate<K, V>>,
+        K: 'a,
+        V: 'a,
     {
         for predicate in predicates {
             if predicate.is_applicable(ts) && predicate.apply(key, value) {
-                return true;
+                return Some(predicate.handle);
             }
         }
-        false
+        None
     }
 
     fn remove_finished_predicates(&self, ctx: &ScanContext<K, V, S>, result: &ScanResult<K, V>) {
@@ -314,6 +340,7 @@ impl<K, V, S> ScanContext<K, V, S> {
 
 struct Predicate<K, V> {
     id: PredicateId,
+    handle: PredicateHandle,
     f: PredicateFun<K, V>,
     registered_at: Instant,
 }
@@ -322,6 +349,7 @@ impl<K, V> Clone for Predicate<K, V> {
     fn clone(&self) -> Self {
         Self {
             id: self.id.clone(),
+            handle: self.handle,
             f: Arc::clone(&self.f),
             registered_at: self.registered_at,
         }
@@ -329,9 +357,15 @@ impl<K, V> Clone for Predicate<K, V> {
 }
 
 impl<K, V> Predicate<K, V> {
-    fn new(id: PredicateIdStr<'_>, f: PredicateFun<K, V>, registered_at: Instant) -> Self {
+    fn new(
+        id: PredicateIdStr<'_>,
+        handle: PredicateHandle,
+        f: PredicateFun<K, V>,
+        registered_at: Instant,
+    ) -> Self {
         Self {
             id: id.to_string(),
+            handle,
             f,
             registered_at,
         }
@@ -415,8 +449,11 @@ where
             let key = &candidate.key;
             let hash = candidate.hash;
             let ts = candidate.timestamp;
-            if Self::apply(&predicates, cache, key, hash, ts) {
-                if let Some(entry) = Self::invalidate(cache, key, hash, ts) {
+            if let Some(predicate_id) = Self::apply(&predicates, cache, key, hash, ts) {
+                let cause = RemovalCause::Invalidated {
+                    predicate_id: Some(predicate_id),
+                };
+                if let Some(entry) = Self::invalidate(cache, key, hash, ts, cause) {
                     invalidated.push(KvEntry {
                         key: Arc::clone(key),
                         entry,
@@ -439,7 +476,7 @@ where
         key: &Arc<K>,
         hash: u64,
         ts: Instant,
-    ) -> bool
+    ) -> Option<PredicateHandle>
     where
         Arc<C>: GetOrRemoveEntry<K, V>,
     {
@@ -456,7 +493,7 @@ where
             }
         }
 
-        false
+        None
     }
 
     fn invalidate<C>(
@@ -464,19 +501,25 @@ where
         key: &Arc<K>,
         hash: u64,
         ts: Instant,
+        cause: RemovalCause,
     ) -> Option<TrioArc<ValueEntry<K, V>>>
     where
         Arc<C>: GetOrRemoveEntry<K, V>,
         K: Send + Sync + 'static,
         V: Clone + Send + Sync + 'static,
     {
-        cache.remove_key_value_if(key, hash, |_, v| {
-            if let Some(lm) = v.last_modified() {
-                lm == ts
-            } else {
-                false
-            }
-        })
+        cache.remove_key_value_if(
+            key,
+            hash,
+            |_, v| {
+                if let Some(lm) = v.last_modified() {
+                    lm == ts
+                } else {
+                    false
+                }
+            },
+            cause,
+        )
     }
 }
 
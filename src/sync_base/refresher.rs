@@ -0,0 +1,64 @@
+#[cfg(feature = "sync")]
+use crate::{
+    common::concurrent::thread_pool::{PoolExecutor, PoolName},
+    executor::ExecutorArc,
+};
+
+use std::time::Duration;
+
+/// Reloads the values of the entries that have been written more than
+/// `refresh_after_write` ago. The reloads run in the background on the executor, so
+/// the callers of `get` keep getting the old values without blocking.
+///
+/// The refresher of a `future::Cache` has no executor. Its reload futures are
+/// driven by the cache's `run_pending_tasks` on the caller's runtime.
+///
+/// A `Refresher` is shared by the segments of a `SegmentedCache`.
+pub(crate) struct Refresher<R> {
+    refresh_after_write: Duration,
+    reloader: R,
+    #[cfg(feature = "sync")]
+    executor: Option<ExecutorArc>,
+}
+
+impl<R> Refresher<R> {
+    #[cfg(feature = "sync")]
+    pub(crate) fn new(
+        refresh_after_write: Duration,
+        reloader: R,
+        executor: Option<ExecutorArc>,
+    ) -> Self {
+        Self {
+            refresh_after_write,
+            reloader,
+            executor: Some(PoolExecutor::or_acquire(executor, PoolName::Refresher)),
+        }
+    }
+
+    #[cfg(feature = "future")]
+    pub(crate) fn without_executor(refresh_after_write: Duration, reloader: R) -> Self {
+        Self {
+            refresh_after_write,
+            reloader,
+            #[cfg(feature = "sync")]
+            executor: None,
+        }
+    }
+
+    pub(crate) fn refresh_after_write(&self) -> Duration {
+        self.refresh_after_write
+    }
+
+    pub(crate) fn reloader(&self) -> &R {
+        &self.reloader
+    }
+
+    /// Runs the reload job on the executor.
+    #[cfg(feature = "sync")]
+    pub(crate) fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        self.executor
+            .as_ref()
+            .expect("Bug: The refresher has no executor")
+            .execute(Box::new(job));
+    }
+}
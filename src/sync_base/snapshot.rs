@@ -0,0 +1,127 @@
+use crate::SnapshotError;
+
+use serde_crate::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+/// The magic bytes written at the beginning of a snapshot.
+const MAGIC: [u8; 4] = *b"MOKA";
+
+/// The version of the snapshot format. Increment it when the layout of
+/// `CacheSnapshot` or `EntrySnapshot` is changed.
+const FORMAT_VERSION: u32 = 1;
+
+/// A snapshot of a `BaseCache`. A `SegmentedCache` is saved as one
+/// `CacheSnapshot` per segment.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub(crate) struct CacheSnapshot<K, V> {
+    pub(crate) max_capacity: Option<u64>,
+    /// The max weighted sizes of the admission window and the protected segment,
+    /// adapted by the hill climber.
+    pub(crate) window_max_weight: u64,
+    pub(crate) protected_max_weight: u64,
+    pub(crate) frequency_sketch_enabled: bool,
+    /// The entries in the access order: the window, the probation and the
+    /// protected segments, each from the LRU to the MRU position.
+    pub(crate) entries: Vec<EntrySnapshot<K, V>>,
+}
+
+/// A snapshot of a single entry. The timestamps are saved as durations relative to
+/// the time when the snapshot was taken, as `Instant`s are only meaningful within
+/// the current process.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub(crate) struct EntrySnapshot<K, V> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    /// `CacheRegion` as `u8`.
+    pub(crate) region: u8,
+    /// The estimated frequency from the `FrequencySketch` (0 to 15).
+    pub(crate) frequency: u8,
+    pub(crate) since_last_modified: Duration,
+    pub(crate) since_last_accessed: Duration,
+    /// The remaining duration until the per-entry expiration time (if any).
+    pub(crate) time_to_expire: Option<Duration>,
+    pub(crate) is_ttl_overridden: bool,
+}
+
+impl<K, V> CacheSnapshot<Arc<K>, V> {
+    pub(crate) fn as_ref(&self) -> CacheSnapshot<&K, &V> {
+        CacheSnapshot {
+            max_capacity: self.max_capacity,
+            window_max_weight: self.window_max_weight,
+            protected_max_weight: self.protected_max_weight,
+            frequency_sketch_enabled: self.frequency_sketch_enabled,
+            entries: self
+                .entries
+                .iter()
+                .map(|e| EntrySnapshot {
+                    key: &*e.key,
+                    value: &e.value,
+                    region: e.region,
+                    frequency: e.frequency,
+                    since_last_modified: e.since_last_modified,
+                    since_last_accessed: e.since_last_accessed,
+                    time_to_expire: e.time_to_expire,
+                    is_ttl_overridden: e.is_ttl_overridden,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub(crate) fn write_snapshot<K, V>(
+    mut writer: impl Write,
+    segments: &[CacheSnapshot<&K, &V>],
+) -> Result<(), SnapshotError>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    writer.write_all(&MAGIC)?;
+    bincode::serialize_into(&mut writer, &FORMAT_VERSION)?;
+    bincode::serialize_into(&mut writer, segments)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub(crate) fn read_snapshot<K, V>(
+    mut reader: impl Read,
+) -> Result<Vec<CacheSnapshot<K, V>>, SnapshotError>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(SnapshotError::Serialization(
+            "The data is not a snapshot of a cache".into(),
+        ));
+    }
+
+    let version: u32 = bincode::deserialize_from(&mut reader)?;
+    if version != FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    // Read the rest into a buffer rather than deserializing from the reader. When
+    // the data is broken, deserializing from a reader may try to allocate a huge
+    // buffer for a string, while deserializing from a slice will fail with an error.
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    bincode::deserialize(&buf).map_err(|e| SnapshotError::Serialization(e))
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(error: bincode::Error) -> Self {
+        match *error {
+            bincode::ErrorKind::Io(e) => Self::Io(e),
+            e => Self::Serialization(Box::new(e)),
+        }
+    }
+}
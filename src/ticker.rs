@@ -0,0 +1,96 @@
+//! Provides the [`Ticker`](./trait.Ticker.html) trait to give a cache its own time
+//! source, and [`MockTicker`](./struct.MockTicker.html) to control the time in
+//! tests.
+
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
+
+pub(crate) type TickerArc = Arc<dyn Ticker>;
+
+/// A time source of a cache.
+///
+/// By default, a cache reads the current time from the system's monotonic clock.
+/// Implement this trait and give it to a cache via the `ticker` method of the cache
+/// builder to make the cache read the time from it instead. The time is used for the
+/// expirations (`time_to_live`, `time_to_idle`, `expire_after` and
+/// `insert_with_ttl`) and `refresh_after_write`.
+///
+/// The time does not have to be the wall clock time, but it must never go
+/// backward.
+///
+/// To control the time in tests, use [`MockTicker`](./struct.MockTicker.html).
+pub trait Ticker: Send + Sync {
+    /// Returns the time elapsed since a fixed but arbitrary point in time.
+    fn read(&self) -> Duration;
+}
+
+impl<T> Ticker for Arc<T>
+where
+    T: Ticker + ?Sized,
+{
+    fn read(&self) -> Duration {
+        (**self).read()
+    }
+}
+
+/// A [`Ticker`](./trait.Ticker.html) whose time advances only when
+/// [`advance`](#method.advance) is called.
+///
+/// Use it with the `ticker` method of the cache builder to write fast and
+/// reproducible tests of the expirations, without sleeping. To keep the handle to
+/// the ticker after giving it to a cache, wrap it with an `Arc` and pass a clone of
+/// the `Arc` to the builder.
+///
+/// # Example
+///
+/// ```rust
+/// use moka::{
+///     sync::{Cache, ConcurrentCacheExt},
+///     ticker::MockTicker,
+/// };
+/// use std::{sync::Arc, time::Duration};
+///
+/// let ticker = Arc::new(MockTicker::new());
+///
+/// let cache = Cache::builder()
+///     .time_to_live(Duration::from_secs(30))
+///     .ticker(Arc::clone(&ticker))
+///     .deterministic_mode(true)
+///     .build();
+///
+/// cache.insert("session", 1);
+/// cache.sync();
+/// assert_eq!(cache.entry_count(), 1);
+///
+/// ticker.advance(Duration::from_secs(29));
+/// assert_eq!(cache.get(&"session"), Some(1));
+///
+/// ticker.advance(Duration::from_secs(1));
+/// assert_eq!(cache.get(&"session"), None);
+///
+/// // Remove the expired entry.
+/// cache.sync();
+/// assert_eq!(cache.entry_count(), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct MockTicker {
+    elapsed: Mutex<Duration>,
+}
+
+impl MockTicker {
+    /// Creates a `MockTicker` starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the time of this ticker by the given `amount`.
+    pub fn advance(&self, amount: Duration) {
+        *self.elapsed.lock() += amount;
+    }
+}
+
+impl Ticker for MockTicker {
+    fn read(&self) -> Duration {
+        *self.elapsed.lock()
+    }
+}
@@ -19,9 +19,17 @@ pub use cache::Cache;
 #[allow(deprecated)]
 pub use iter::Iter;
 
-use crate::common::{deque::DeqNode, time::Instant};
+use crate::{
+    common::{deque::DeqNode, time::Instant, CacheRegion},
+    notification::RemovalCause,
+    Expiry,
+};
 
-pub(crate) type Weigher<K, V> = Box<dyn FnMut(&K, &V) -> u32>;
+pub(crate) type Weigher<K, V> = Box<dyn FnMut(&K, &V) -> u64>;
+
+pub(crate) type EvictionListener<K, V> = Box<dyn FnMut(Rc<K>, V, RemovalCause)>;
+
+pub(crate) type ExpiryBox<K, V> = Box<dyn Expiry<K, V>>;
 
 pub(crate) trait AccessTime {
     fn last_accessed(&self) -> Option<Instant>;
@@ -66,7 +74,9 @@ type KeyDeqNodeWo<K> = NonNull<DeqNode<KeyDate<K>>>;
 struct EntryInfo<K> {
     access_order_q_node: Option<KeyDeqNodeAo<K>>,
     write_order_q_node: Option<KeyDeqNodeWo<K>>,
-    policy_weight: u32,
+    policy_weight: u64,
+    /// The deadline calculated by the `Expiry` of the cache, if any.
+    expiration_time: Option<Instant>,
 }
 
 pub(crate) struct ValueEntry<K, V> {
@@ -75,19 +85,20 @@ pub(crate) struct ValueEntry<K, V> {
 }
 
 impl<K, V> ValueEntry<K, V> {
-    pub(crate) fn new(value: V, policy_weight: u32) -> Self {
+    pub(crate) fn new(value: V, policy_weight: u64) -> Self {
         Self {
             value,
             info: EntryInfo {
                 access_order_q_node: None,
                 write_order_q_node: None,
                 policy_weight,
+                expiration_time: None,
             },
         }
     }
 
     #[inline]
-    pub(crate) fn replace_deq_nodes_with(&mut self, mut other: Self) {
+    pub(crate) fn replace_deq_nodes_with(&mut self, other: &mut Self) {
         self.info.access_order_q_node = other.info.access_order_q_node.take();
         self.info.write_order_q_node = other.info.write_order_q_node.take();
     }
@@ -97,6 +108,15 @@ impl<K, V> ValueEntry<K, V> {
         self.info.access_order_q_node
     }
 
+    /// Returns the cache region (access-order deque) where this entry currently
+    /// lives, or `None` if the entry has no access-order node.
+    #[inline]
+    pub(crate) fn access_order_q_region(&self) -> Option<CacheRegion> {
+        self.info
+            .access_order_q_node
+            .map(|node| CacheRegion::from(node.decompose_tag()))
+    }
+
     #[inline]
     pub(crate) fn set_access_order_q_node(&mut self, node: Option<KeyDeqNodeAo<K>>) {
         self.info.access_order_q_node = node;
@@ -123,14 +143,24 @@ impl<K, V> ValueEntry<K, V> {
     }
 
     #[inline]
-    pub(crate) fn policy_weight(&self) -> u32 {
+    pub(crate) fn policy_weight(&self) -> u64 {
         self.info.policy_weight
     }
 
     #[inline]
-    pub(crate) fn set_policy_weight(&mut self, policy_weight: u32) {
+    pub(crate) fn set_policy_weight(&mut self, policy_weight: u64) {
         self.info.policy_weight = policy_weight;
     }
+
+    #[inline]
+    pub(crate) fn expiration_time(&self) -> Option<Instant> {
+        self.info.expiration_time
+    }
+
+    #[inline]
+    pub(crate) fn set_expiration_time(&mut self, time: Option<Instant>) {
+        self.info.expiration_time = time;
+    }
 }
 
 impl<K, V> AccessTime for ValueEntry<K, V> {
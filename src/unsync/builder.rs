@@ -1,12 +1,20 @@
 #![allow(deprecated)]
 
-use super::{Cache, Weigher};
-use crate::common::builder_utils;
+use super::{Cache, EvictionListener, ExpiryBox, Weigher};
+use crate::{
+    common::builder_utils,
+    notification::RemovalCause,
+    ticker::{Ticker, TickerArc},
+    Expiry, HeapSize,
+};
 
 use std::{
     collections::hash_map::RandomState,
     hash::{BuildHasher, Hash},
     marker::PhantomData,
+    mem::size_of,
+    rc::Rc,
+    sync::Arc,
     time::Duration,
 };
 
@@ -54,8 +62,11 @@ pub struct CacheBuilder<K, V, C> {
     max_capacity: Option<u64>,
     initial_capacity: Option<usize>,
     weigher: Option<Weigher<K, V>>,
+    eviction_listener: Option<EvictionListener<K, V>>,
     time_to_live: Option<Duration>,
     time_to_idle: Option<Duration>,
+    expiry: Option<ExpiryBox<K, V>>,
+    ticker: Option<TickerArc>,
     cache_type: PhantomData<C>,
 }
 
@@ -68,8 +79,11 @@ where
             max_capacity: None,
             initial_capacity: None,
             weigher: None,
+            eviction_listener: None,
             time_to_live: None,
             time_to_idle: None,
+            expiry: None,
+            ticker: None,
             cache_type: Default::default(),
         }
     }
@@ -103,8 +117,11 @@ where
             self.initial_capacity,
             build_hasher,
             self.weigher,
+            self.eviction_listener,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
+            self.ticker,
         )
     }
 
@@ -125,8 +142,11 @@ where
             self.initial_capacity,
             hasher,
             self.weigher,
+            self.eviction_listener,
             self.time_to_live,
             self.time_to_idle,
+            self.expiry,
+            self.ticker,
         )
     }
 }
@@ -151,14 +171,100 @@ impl<K, V, C> CacheBuilder<K, V, C> {
     /// Sets the weigher closure of the cache.
     ///
     /// The closure should take `&K` and `&V` as the arguments and returns a `u32`
-    /// representing the relative size of the entry.
-    pub fn weigher(self, weigher: impl FnMut(&K, &V) -> u32 + 'static) -> Self {
+    /// representing the relative size of the entry. To use weights larger than
+    /// `u32::MAX`, use [`weigher_u64`](#method.weigher_u64) instead.
+    pub fn weigher(self, mut weigher: impl FnMut(&K, &V) -> u32 + 'static) -> Self {
+        Self {
+            weigher: Some(Box::new(move |k, v| u64::from(weigher(k, v)))),
+            ..self
+        }
+    }
+
+    /// Sets the weigher closure returning a `u64` weight to the cache.
+    ///
+    /// This is the same as [`weigher`](#method.weigher), but the closure can
+    /// return a weight larger than `u32::MAX`, for example the size of the entry in
+    /// bytes.
+    pub fn weigher_u64(self, weigher: impl FnMut(&K, &V) -> u64 + 'static) -> Self {
         Self {
             weigher: Some(Box::new(weigher)),
             ..self
         }
     }
 
+    /// Bounds the cache by the estimated memory usage of the entries in bytes.
+    ///
+    /// This sets `max_capacity` to `bytes`, and the weigher to the one that returns
+    /// the [`size_of`][size-of] the key and value plus their
+    /// [`HeapSize::heap_size`][heap-size]. Then `weighted_size` of the cache
+    /// returns the estimated memory usage in bytes. The memory used by the cache
+    /// itself to store the entries is not counted.
+    ///
+    /// [size-of]: https://doc.rust-lang.org/std/mem/fn.size_of.html
+    /// [heap-size]: ../trait.HeapSize.html#tymethod.heap_size
+    pub fn max_memory_bytes(self, bytes: u64) -> Self
+    where
+        K: HeapSize + 'static,
+        V: HeapSize + 'static,
+    {
+        Self {
+            max_capacity: Some(bytes),
+            ..self
+        }
+        .weigher_u64(|k, v| {
+            (size_of::<K>() + k.heap_size() + size_of::<V>() + v.heap_size()) as u64
+        })
+    }
+
+    /// Sets the eviction listener closure to the cache.
+    ///
+    /// The closure should take `Rc<K>`, `V` and [`RemovalCause`][removal-cause] as
+    /// the arguments. It is called synchronously by the cache operation that
+    /// removed the entry, e.g. `insert` evicting the LRU entries, or `invalidate`.
+    ///
+    /// # Panics
+    ///
+    /// It is very important to make the listener closure not to panic. Otherwise,
+    /// the cache will stop calling the listener after a panic. This is the same
+    /// behavior as the listener of `sync::Cache`.
+    ///
+    /// [removal-cause]: ../notification/enum.RemovalCause.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moka::{notification::RemovalCause, unsync::Cache};
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// let removed = Rc::new(RefCell::new(Vec::new()));
+    /// let removed1 = Rc::clone(&removed);
+    ///
+    /// let mut cache = Cache::builder()
+    ///     .max_capacity(100)
+    ///     .eviction_listener(move |k: Rc<&str>, v, cause| {
+    ///         removed1.borrow_mut().push((*k, v, cause));
+    ///     })
+    ///     .build();
+    ///
+    /// cache.insert("alice", 1);
+    /// cache.insert("alice", 2);
+    /// cache.invalidate(&"alice");
+    ///
+    /// assert_eq!(
+    ///     *removed.borrow(),
+    ///     vec![
+    ///         ("alice", 1, RemovalCause::Replaced),
+    ///         ("alice", 2, RemovalCause::Explicit)
+    ///     ]
+    /// );
+    /// ```
+    pub fn eviction_listener(self, listener: impl FnMut(Rc<K>, V, RemovalCause) + 'static) -> Self {
+        Self {
+            eviction_listener: Some(Box::new(listener)),
+            ..self
+        }
+    }
+
     /// Sets the time to live of the cache.
     ///
     /// A cached entry will be expired after the specified duration past from
@@ -192,6 +298,38 @@ impl<K, V, C> CacheBuilder<K, V, C> {
             ..self
         }
     }
+
+    /// Sets the given `expiry` to the cache to calculate the expiration time of
+    /// each entry.
+    ///
+    /// The per-entry expiration works together with `time_to_live` and
+    /// `time_to_idle`; an entry will be expired when any of them has passed.
+    ///
+    /// See [`Expiry`][expiry-trait] for more details.
+    ///
+    /// [expiry-trait]: ../trait.Expiry.html
+    pub fn expire_after(self, expiry: impl Expiry<K, V> + 'static) -> Self {
+        Self {
+            expiry: Some(Box::new(expiry)),
+            ..self
+        }
+    }
+
+    /// Sets the [`Ticker`][ticker-trait] to read the current time from, instead of
+    /// the system clock.
+    ///
+    /// As this cache removes the expired entries within its methods such as `get`
+    /// and `insert`, a [`MockTicker`][mock-ticker] is all you need to write fast and
+    /// reproducible tests of the expirations.
+    ///
+    /// [ticker-trait]: ../ticker/trait.Ticker.html
+    /// [mock-ticker]: ../ticker/struct.MockTicker.html
+    pub fn ticker(self, ticker: impl Ticker + 'static) -> Self {
+        Self {
+            ticker: Some(Arc::new(ticker)),
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 #![allow(deprecated)]
 
 use super::{
-    deques::Deques, AccessTime, CacheBuilder, Iter, KeyDate, KeyHashDate, ValueEntry, Weigher,
+    deques::Deques, AccessTime, CacheBuilder, EvictionListener, ExpiryBox, Iter, KeyDate,
+    KeyHashDate, ValueEntry, Weigher,
 };
 use crate::{
     common::{
@@ -11,6 +12,8 @@ use crate::{
         time::{CheckedTimeOps, Clock, Instant},
         CacheRegion,
     },
+    notification::RemovalCause,
+    ticker::TickerArc,
     Policy,
 };
 
@@ -180,14 +183,22 @@ pub struct Cache<K, V, S = RandomState> {
     max_capacity: Option<u64>,
     entry_count: u64,
     weighted_size: u64,
+    window_weighted_size: u64,
+    protected_weighted_size: u64,
+    // The max weighted sizes of the admission window and the protected segment.
+    // Zero if the cache does not use the window (e.g. it is unbounded).
+    window_max_weight: u64,
+    protected_max_weight: u64,
     cache: CacheStore<K, V, S>,
     build_hasher: S,
     weigher: Option<Weigher<K, V>>,
+    eviction_listener: Option<EvictionListener<K, V>>,
     deques: Deques<K>,
     frequency_sketch: FrequencySketch,
     frequency_sketch_enabled: bool,
     time_to_live: Option<Duration>,
     time_to_idle: Option<Duration>,
+    expiry: Option<ExpiryBox<K, V>>,
     expiration_clock: Option<Clock>,
 }
 
@@ -221,7 +232,17 @@ where
     /// [builder-struct]: ./struct.CacheBuilder.html
     pub fn new(max_capacity: u64) -> Self {
         let build_hasher = RandomState::default();
-        Self::with_everything(Some(max_capacity), None, build_hasher, None, None, None)
+        Self::with_everything(
+            Some(max_capacity),
+            None,
+            build_hasher,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
     }
 
     /// Returns a [`CacheBuilder`][builder-struct], which can builds a `Cache` with
@@ -242,7 +263,10 @@ impl<K, V, S> Cache<K, V, S> {
     /// At this time, cache policy cannot be modified after cache creation.
     /// A future version may support to modify it.
     pub fn policy(&self) -> Policy {
-        Policy::new(self.max_capacity, 1, self.time_to_live, self.time_to_idle)
+        let mut policy = Policy::new(self.max_capacity, 1, self.time_to_live, self.time_to_idle);
+        policy
+            .set_window_and_protected_capacities(self.window_max_weight, self.protected_max_weight);
+        policy
     }
 
     /// Returns the number of entries in this cache.
@@ -282,32 +306,46 @@ where
     K: Hash + Eq,
     S: BuildHasher + Clone,
 {
+    // https://rust-lang.github.io/rust-clippy/master/index.html#too_many_arguments
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn with_everything(
         max_capacity: Option<u64>,
         initial_capacity: Option<usize>,
         build_hasher: S,
         weigher: Option<Weigher<K, V>>,
+        eviction_listener: Option<EvictionListener<K, V>>,
         time_to_live: Option<Duration>,
         time_to_idle: Option<Duration>,
+        expiry: Option<ExpiryBox<K, V>>,
+        ticker: Option<TickerArc>,
     ) -> Self {
         let cache = HashMap::with_capacity_and_hasher(
             initial_capacity.unwrap_or_default(),
             build_hasher.clone(),
         );
+        let (window_max_weight, protected_max_weight) = max_capacity
+            .map(common::window_and_protected_capacities)
+            .unwrap_or_default();
 
         Self {
             max_capacity,
             entry_count: 0,
             weighted_size: 0,
+            window_weighted_size: 0,
+            protected_weighted_size: 0,
+            window_max_weight,
+            protected_max_weight,
             cache,
             build_hasher,
             weigher,
+            eviction_listener,
             deques: Default::default(),
             frequency_sketch: Default::default(),
             frequency_sketch_enabled: false,
             time_to_live,
             time_to_idle,
-            expiration_clock: None,
+            expiry,
+            expiration_clock: ticker.map(Clock::from_ticker),
         }
     }
 
@@ -336,6 +374,7 @@ where
             (Some(entry), Some(ts)) => {
                 !Self::is_expired_entry_wo(&self.time_to_live, entry, ts)
                     && !Self::is_expired_entry_ao(&self.time_to_idle, entry, ts)
+                    && !Self::is_expired_by_expiry(entry, ts)
             }
         }
     }
@@ -352,33 +391,50 @@ where
         let timestamp = self.evict_expired_if_needed();
         self.evict_lru_entries();
         self.frequency_sketch.increment(self.hash(key));
+        let is_window_enabled = self.is_window_enabled();
 
-        match (self.cache.get_mut(key), timestamp, &mut self.deques) {
+        // (is_hit, weight of the entry promoted to the protected segment)
+        let (is_hit, promoted) = match (self.cache.get_mut(key), timestamp, &mut self.deques) {
             // Value not found.
-            (None, _, _) => None,
+            (None, _, _) => (false, None),
             // Value found, no expiry.
             (Some(entry), None, deqs) => {
-                Self::record_hit(deqs, entry, None);
-                Some(&entry.value)
+                (true, Self::record_hit(deqs, entry, None, is_window_enabled))
             }
             // Value found, check if expired.
             (Some(entry), Some(ts), deqs) => {
                 if Self::is_expired_entry_wo(&self.time_to_live, entry, ts)
                     || Self::is_expired_entry_ao(&self.time_to_idle, entry, ts)
+                    || Self::is_expired_by_expiry(entry, ts)
                 {
-                    None
+                    (false, None)
                 } else {
-                    Self::record_hit(deqs, entry, timestamp);
-                    Some(&entry.value)
+                    (
+                        true,
+                        Self::record_hit(deqs, entry, timestamp, is_window_enabled),
+                    )
                 }
             }
+        };
+
+        if let Some(weight) = promoted {
+            self.saturating_add_to_region_weight(CacheRegion::MainProtected, weight);
+            self.demote_from_protected();
+        }
+
+        if is_hit {
+            if let Some(ts) = timestamp {
+                self.expire_after_read(key, ts);
+            }
+            self.cache.get(key).map(|entry| &entry.value)
+        } else {
+            None
         }
     }
 
     pub(crate) fn is_expired_entry(&self, entry: &ValueEntry<K, V>) -> bool {
         let now = self.current_time_from_expiration_clock();
-        Self::is_expired_entry_wo(&self.time_to_live, entry, now)
-            || Self::is_expired_entry_ao(&self.time_to_idle, entry, now)
+        Self::is_expired_entry_at(&self.time_to_live, &self.time_to_idle, entry, now)
     }
 
     /// Inserts a key-value pair into the cache.
@@ -388,8 +444,10 @@ where
         let timestamp = self.evict_expired_if_needed();
         self.evict_lru_entries();
         let policy_weight = weigh(&mut self.weigher, &key, &value);
+        let expiration_time = self.expire_after_create(&key, &value, timestamp);
         let key = Rc::new(key);
-        let entry = ValueEntry::new(value, policy_weight);
+        let mut entry = ValueEntry::new(value, policy_weight);
+        entry.set_expiration_time(expiration_time);
 
         if let Some(old_entry) = self.cache.insert(Rc::clone(&key), entry) {
             self.handle_update(key, timestamp, policy_weight, old_entry);
@@ -411,11 +469,20 @@ where
         self.evict_expired_if_needed();
         self.evict_lru_entries();
 
-        if let Some(mut entry) = self.cache.remove(key) {
+        if let Some((key, mut entry)) = self.cache.remove_entry(key) {
+            // Get the cause before unlinking the entry, as it drops the timestamps.
+            let cause = self.removal_cause(&entry, RemovalCause::Explicit);
             let weight = entry.policy_weight();
+            let region = entry.access_order_q_region();
             self.deques.unlink_ao(&mut entry);
             Deques::unlink_wo(&mut self.deques.write_order, &mut entry);
-            self.saturating_sub_from_total_weight(weight as u64);
+            self.saturating_sub_from_total_weight(weight);
+            if let Some(region) = region {
+                self.saturating_sub_from_region_weight(region, weight);
+            }
+            if let Some(cause) = cause {
+                notify(&mut self.eviction_listener, key, entry.value, cause);
+            }
         }
     }
 
@@ -425,9 +492,27 @@ where
     /// popularity estimator of keys so that it retains the client activities of
     /// trying to retrieve an item.
     pub fn invalidate_all(&mut self) {
-        self.cache.clear();
+        let mut removed = Vec::new();
+        if self.eviction_listener.is_some() {
+            let entries = self.cache.drain().collect::<Vec<_>>();
+            for (key, entry) in entries {
+                let cause = RemovalCause::Invalidated { predicate_id: None };
+                let cause = self.removal_cause(&entry, cause);
+                removed.push((key, entry.value, cause));
+            }
+        } else {
+            self.cache.clear();
+        }
         self.deques.clear();
         self.weighted_size = 0;
+        self.window_weighted_size = 0;
+        self.protected_weighted_size = 0;
+
+        for (key, value, cause) in removed {
+            if let Some(cause) = cause {
+                notify(&mut self.eviction_listener, key, value, cause);
+            }
+        }
     }
 
     /// Discards cached values that satisfy a predicate.
@@ -445,7 +530,16 @@ where
     // clippy 0.1.52 (9a1dfd2dc5c 2021-04-30) in Rust 1.52.0-beta.7
     #[allow(clippy::needless_collect)]
     pub fn invalidate_entries_if(&mut self, mut predicate: impl FnMut(&K, &V) -> bool) {
-        let Self { cache, deques, .. } = self;
+        let now = self.current_time_from_expiration_clock();
+        let is_notifying = self.eviction_listener.is_some();
+        let mut removed = Vec::new();
+        let Self {
+            cache,
+            deques,
+            time_to_live,
+            time_to_idle,
+            ..
+        } = self;
 
         // Since we can't do cache.iter() and cache.remove() at the same time,
         // invalidation needs to run in two steps:
@@ -459,16 +553,38 @@ where
             .collect::<Vec<_>>();
 
         let mut invalidated = 0u64;
+        let (mut window_invalidated, mut protected_invalidated) = (0u64, 0u64);
 
         keys_to_invalidate.into_iter().for_each(|k| {
             if let Some(mut entry) = cache.remove(&k) {
+                let cause = if !is_notifying {
+                    None
+                } else if Self::is_expired_entry_at(time_to_live, time_to_idle, &entry, now) {
+                    Some(RemovalCause::Expired)
+                } else {
+                    Some(RemovalCause::Invalidated { predicate_id: None })
+                };
                 let weight = entry.policy_weight();
+                match entry.access_order_q_region() {
+                    Some(CacheRegion::Window) => window_invalidated += weight,
+                    Some(CacheRegion::MainProtected) => protected_invalidated += weight,
+                    _ => (),
+                }
                 deques.unlink_ao(&mut entry);
                 Deques::unlink_wo(&mut deques.write_order, &mut entry);
-                invalidated = invalidated.saturating_sub(weight as u64);
+                invalidated = invalidated.saturating_sub(weight);
+                if let Some(cause) = cause {
+                    removed.push((k, entry.value, cause));
+                }
             }
         });
         self.saturating_sub_from_total_weight(invalidated);
+        self.saturating_sub_from_region_weight(CacheRegion::Window, window_invalidated);
+        self.saturating_sub_from_region_weight(CacheRegion::MainProtected, protected_invalidated);
+
+        for (key, value, cause) in removed {
+            notify(&mut self.eviction_listener, key, value, cause);
+        }
     }
 
     /// Creates an iterator visiting all key-value pairs in arbitrary order. The
@@ -519,7 +635,14 @@ where
 
     #[inline]
     fn has_expiry(&self) -> bool {
-        self.time_to_live.is_some() || self.time_to_idle.is_some()
+        self.time_to_live.is_some() || self.time_to_idle.is_some() || self.expiry.is_some()
+    }
+
+    /// Returns `true` if this cache uses the admission window and the segmented
+    /// (probation and protected) main space.
+    #[inline]
+    fn is_window_enabled(&self) -> bool {
+        self.window_max_weight > 0
     }
 
     #[inline]
@@ -574,16 +697,125 @@ where
         false
     }
 
-    fn record_hit(deques: &mut Deques<K>, entry: &mut ValueEntry<K, V>, ts: Option<Instant>) {
+    #[inline]
+    fn is_expired_by_expiry(entry: &ValueEntry<K, V>, now: Instant) -> bool {
+        entry
+            .expiration_time()
+            .map(|ts| ts <= now)
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn is_expired_entry_at(
+        time_to_live: &Option<Duration>,
+        time_to_idle: &Option<Duration>,
+        entry: &ValueEntry<K, V>,
+        now: Instant,
+    ) -> bool {
+        Self::is_expired_entry_wo(time_to_live, entry, now)
+            || Self::is_expired_entry_ao(time_to_idle, entry, now)
+            || Self::is_expired_by_expiry(entry, now)
+    }
+
+    /// Returns the cause to notify the eviction listener of the removal of the
+    /// entry, which is `Expired` if the entry has expired, or `cause` otherwise.
+    /// Returns `None` if there is no listener.
+    ///
+    /// This must be called before unlinking the entry from the deques.
+    fn removal_cause(&self, entry: &ValueEntry<K, V>, cause: RemovalCause) -> Option<RemovalCause> {
+        if self.eviction_listener.is_none() {
+            None
+        } else if self.is_expired_entry(entry) {
+            Some(RemovalCause::Expired)
+        } else {
+            Some(cause)
+        }
+    }
+
+    /// Returns the expiration time of a new entry calculated by the `Expiry` (if
+    /// any).
+    #[inline]
+    fn expire_after_create(&self, key: &K, value: &V, now: Option<Instant>) -> Option<Instant> {
+        match (&self.expiry, now) {
+            (Some(expiry), Some(now)) => expiry
+                .expire_after_create(key, value)
+                .and_then(|d| now.checked_add(d)),
+            _ => None,
+        }
+    }
+
+    /// Updates the expiration time of a read entry by calling the `Expiry` (if
+    /// any).
+    fn expire_after_read<Q>(&mut self, key: &Q, now: Instant)
+    where
+        Rc<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let expiry = match &self.expiry {
+            Some(expiry) => expiry,
+            None => return,
+        };
+
+        let new_time = self.cache.get_key_value(key).and_then(|(k, entry)| {
+            let current = entry
+                .expiration_time()
+                .map(|ts| ts.saturating_duration_since(now));
+            let duration = expiry.expire_after_read(k, &entry.value, current);
+            if duration == current {
+                None
+            } else {
+                Some(duration.and_then(|d| now.checked_add(d)))
+            }
+        });
+
+        if let (Some(new_time), Some(entry)) = (new_time, self.cache.get_mut(key)) {
+            entry.set_expiration_time(new_time);
+        }
+    }
+
+    // Returns the policy weight of the entry if it has been promoted from the
+    // probation segment to the protected segment.
+    fn record_hit(
+        deques: &mut Deques<K>,
+        entry: &mut ValueEntry<K, V>,
+        ts: Option<Instant>,
+        is_window_enabled: bool,
+    ) -> Option<u64> {
         if let Some(ts) = ts {
             entry.set_last_accessed(ts);
         }
-        deques.move_to_back_ao(entry)
+        if is_window_enabled && entry.access_order_q_region() == Some(CacheRegion::MainProbation) {
+            deques.move_to_region_ao(CacheRegion::MainProtected, entry);
+            Some(entry.policy_weight())
+        } else {
+            deques.move_to_back_ao(entry);
+            None
+        }
+    }
+
+    /// Demotes the LRU entries of the protected segment to the probation segment
+    /// while the protected segment exceeds its max weighted size.
+    fn demote_from_protected(&mut self) {
+        while self.protected_weighted_size > self.protected_max_weight {
+            let key = match self.deques.protected.peek_front() {
+                Some(node) => Rc::clone(&node.element.key),
+                None => break,
+            };
+
+            if let Some(entry) = self.cache.get_mut(&key) {
+                let weight = entry.policy_weight();
+                self.deques
+                    .move_to_region_ao(CacheRegion::MainProbation, entry);
+                self.saturating_sub_from_region_weight(CacheRegion::MainProtected, weight);
+            } else {
+                self.deques.protected.pop_front();
+            }
+        }
     }
 
-    fn has_enough_capacity(&self, candidate_weight: u32, ws: u64) -> bool {
+    fn has_enough_capacity(&self, candidate_weight: u64, ws: u64) -> bool {
         self.max_capacity
-            .map(|limit| ws + candidate_weight as u64 <= limit)
+            .map(|limit| ws.saturating_add(candidate_weight) <= limit)
             .unwrap_or(true)
     }
 
@@ -640,48 +872,77 @@ where
         *total = total.saturating_sub(weight);
     }
 
+    fn saturating_add_to_region_weight(&mut self, region: CacheRegion, weight: u64) {
+        if let Some(total) = self.region_weighted_size_mut(region) {
+            *total = total.saturating_add(weight);
+        }
+    }
+
+    fn saturating_sub_from_region_weight(&mut self, region: CacheRegion, weight: u64) {
+        if let Some(total) = self.region_weighted_size_mut(region) {
+            *total = total.saturating_sub(weight);
+        }
+    }
+
+    // The weighted size of the probation segment is not tracked as it can be
+    // calculated from the others.
+    fn region_weighted_size_mut(&mut self, region: CacheRegion) -> Option<&mut u64> {
+        match region {
+            CacheRegion::Window => Some(&mut self.window_weighted_size),
+            CacheRegion::MainProtected => Some(&mut self.protected_weighted_size),
+            CacheRegion::MainProbation | CacheRegion::Other => None,
+        }
+    }
+
     #[inline]
     fn handle_insert(
         &mut self,
         key: Rc<K>,
         hash: u64,
-        policy_weight: u32,
+        policy_weight: u64,
         timestamp: Option<Instant>,
     ) {
+        let is_window_enabled = self.is_window_enabled();
         let has_free_space = self.has_enough_capacity(policy_weight, self.weighted_size);
-        let (cache, deqs, freq) = (&mut self.cache, &mut self.deques, &self.frequency_sketch);
 
-        if has_free_space {
+        if !is_window_enabled && has_free_space {
             // Add the candidate to the deque.
-            let key = Rc::clone(&key);
-            let entry = cache.get_mut(&key).unwrap();
-            deqs.push_back_ao(
+            self.add_to_deques(
                 CacheRegion::MainProbation,
-                KeyHashDate::new(Rc::clone(&key), hash, timestamp),
-                entry,
+                key,
+                hash,
+                policy_weight,
+                timestamp,
             );
-            if self.time_to_live.is_some() {
-                deqs.push_back_wo(KeyDate::new(key, timestamp), entry);
-            }
-            self.entry_count += 1;
-            self.saturating_add_to_total_weight(policy_weight as u64);
-
-            if self.should_enable_frequency_sketch() {
-                self.enable_frequency_sketch();
-            }
-
             return;
         }
 
         if let Some(max) = self.max_capacity {
-            if policy_weight as u64 > max {
+            if policy_weight > max {
                 // The candidate is too big to fit in the cache. Reject it.
-                cache.remove(&Rc::clone(&key));
+                if let Some(entry) = self.cache.remove(&key) {
+                    notify(
+                        &mut self.eviction_listener,
+                        key,
+                        entry.value,
+                        RemovalCause::Size,
+                    );
+                }
                 return;
             }
         }
 
-        let mut candidate = EntrySizeAndFrequency::new(policy_weight as u64);
+        if is_window_enabled {
+            // A new entry is always added to the admission window. Then the LRU
+            // entries of the window will be moved to the main space, or be evicted
+            // if they cannot be admitted to the main space.
+            self.add_to_deques(CacheRegion::Window, key, hash, policy_weight, timestamp);
+            self.evict_from_window();
+            return;
+        }
+
+        let (cache, deqs, freq) = (&self.cache, &self.deques, &self.frequency_sketch);
+        let mut candidate = EntrySizeAndFrequency::new(policy_weight);
         candidate.add_frequency(freq, hash);
 
         match Self::admit(&candidate, cache, deqs, freq, &mut self.weigher) {
@@ -690,41 +951,145 @@ where
                 victims_weight,
             } => {
                 // Remove the victims from the cache (hash map) and deque.
-                for victim in victim_nodes {
-                    // Remove the victim from the hash map.
-                    let mut vic_entry = cache
-                        .remove(unsafe { &victim.as_ref().element.key })
-                        .expect("Cannot remove a victim from the hash map");
-                    // And then remove the victim from the deques.
-                    deqs.unlink_ao(&mut vic_entry);
-                    Deques::unlink_wo(&mut deqs.write_order, &mut vic_entry);
-                    self.entry_count -= 1;
-                }
+                self.remove_victims(victim_nodes);
+                Self::saturating_sub_from_total_weight(self, victims_weight);
 
                 // Add the candidate to the deque.
-                let entry = cache.get_mut(&key).unwrap();
-                let key = Rc::clone(&key);
-                deqs.push_back_ao(
+                self.add_to_deques(
                     CacheRegion::MainProbation,
-                    KeyHashDate::new(Rc::clone(&key), hash, timestamp),
-                    entry,
+                    key,
+                    hash,
+                    policy_weight,
+                    timestamp,
                 );
-                if self.time_to_live.is_some() {
-                    deqs.push_back_wo(KeyDate::new(key, timestamp), entry);
+            }
+            AdmissionResult::Rejected => {
+                // Remove the candidate from the cache.
+                if let Some(entry) = self.cache.remove(&key) {
+                    notify(
+                        &mut self.eviction_listener,
+                        key,
+                        entry.value,
+                        RemovalCause::Size,
+                    );
                 }
+            }
+        }
+    }
 
-                self.entry_count += 1;
-                Self::saturating_sub_from_total_weight(self, victims_weight);
-                Self::saturating_add_to_total_weight(self, policy_weight as u64);
+    // Adds the entry for the key to the access-order deque of the given region, and
+    // to the write-order deque if needed.
+    fn add_to_deques(
+        &mut self,
+        region: CacheRegion,
+        key: Rc<K>,
+        hash: u64,
+        policy_weight: u64,
+        timestamp: Option<Instant>,
+    ) {
+        let deqs = &mut self.deques;
+        let entry = self.cache.get_mut(&key).unwrap();
+        deqs.push_back_ao(
+            region,
+            KeyHashDate::new(Rc::clone(&key), hash, timestamp),
+            entry,
+        );
+        if self.time_to_live.is_some() {
+            deqs.push_back_wo(KeyDate::new(key, timestamp), entry);
+        }
 
-                if self.should_enable_frequency_sketch() {
-                    self.enable_frequency_sketch();
-                }
+        self.entry_count += 1;
+        self.saturating_add_to_total_weight(policy_weight);
+        self.saturating_add_to_region_weight(region, policy_weight);
+
+        if self.should_enable_frequency_sketch() {
+            self.enable_frequency_sketch();
+        }
+    }
+
+    fn remove_victims(&mut self, victim_nodes: SmallVec<[AoqNode<K>; 8]>) {
+        for victim in victim_nodes {
+            // Remove the victim from the hash map.
+            let (vic_key, mut vic_entry) = self
+                .cache
+                .remove_entry(unsafe { &victim.as_ref().element.key })
+                .expect("Cannot remove a victim from the hash map");
+            let region = vic_entry.access_order_q_region();
+            // And then remove the victim from the deques.
+            self.deques.unlink_ao(&mut vic_entry);
+            Deques::unlink_wo(&mut self.deques.write_order, &mut vic_entry);
+            self.entry_count -= 1;
+            if let Some(region) = region {
+                let weight = vic_entry.policy_weight();
+                self.saturating_sub_from_region_weight(region, weight);
             }
-            AdmissionResult::Rejected => {
-                // Remove the candidate from the cache.
-                cache.remove(&key);
+            let value = vic_entry.value;
+            notify(
+                &mut self.eviction_listener,
+                vic_key,
+                value,
+                RemovalCause::Size,
+            );
+        }
+    }
+
+    /// Moves the LRU entries of the admission window to the probation segment of
+    /// the main space while the window exceeds its max weighted size.
+    ///
+    /// If the main space is full, a window entry becomes a candidate and competes
+    /// with the LRU entries of the main space by the TinyLFU admission policy. The
+    /// loser will be evicted from the cache.
+    fn evict_from_window(&mut self) {
+        while self.window_weighted_size > self.window_max_weight {
+            let (key, hash) = match self.deques.window.peek_front() {
+                Some(node) => (Rc::clone(&node.element.key), node.element.hash),
+                None => break,
+            };
+            let weight = match self.cache.get(&key) {
+                Some(entry) => entry.policy_weight(),
+                None => {
+                    self.deques.window.pop_front();
+                    continue;
+                }
+            };
+
+            let is_admitted = if self.has_enough_capacity(0, self.weighted_size) {
+                // There are enough room in the main space.
+                true
+            } else {
+                let (cache, deqs, freq) = (&self.cache, &self.deques, &self.frequency_sketch);
+                let mut candidate = EntrySizeAndFrequency::new(weight);
+                candidate.add_frequency(freq, hash);
+
+                match Self::admit(&candidate, cache, deqs, freq, &mut self.weigher) {
+                    AdmissionResult::Admitted {
+                        victim_nodes,
+                        victims_weight,
+                    } => {
+                        self.remove_victims(victim_nodes);
+                        self.saturating_sub_from_total_weight(victims_weight);
+                        true
+                    }
+                    AdmissionResult::Rejected => false,
+                }
+            };
+
+            if is_admitted {
+                // Move the entry to the probation segment.
+                let entry = self.cache.get_mut(&key).unwrap();
+                self.deques
+                    .move_to_region_ao(CacheRegion::MainProbation, entry);
+            } else {
+                // Remove the entry from the cache (hash map) and the deques.
+                let mut entry = self.cache.remove(&key).unwrap();
+                self.deques.unlink_ao(&mut entry);
+                Deques::unlink_wo(&mut self.deques.write_order, &mut entry);
+                self.entry_count -= 1;
+                self.saturating_sub_from_total_weight(weight);
+                let value = entry.value;
+                notify(&mut self.eviction_listener, key, value, RemovalCause::Size);
             }
+            self.saturating_sub_from_region_weight(CacheRegion::Window, weight);
         }
     }
 
@@ -744,6 +1109,8 @@ where
     ///   promoted (to the MRU position?) to force the eviction policy to select a
     ///   different set of victims for the next candidate). We may implement the
     ///   paper's behavior later?
+    /// - The potential victims are selected from the LRU position of the probation
+    ///   segment first, and then from the protected segment.
     ///
     #[inline]
     fn admit(
@@ -756,7 +1123,8 @@ where
         let mut victims = EntrySizeAndFrequency::default();
         let mut victim_nodes = SmallVec::default();
 
-        // Get first potential victim at the LRU position.
+        // Get first potential victim at the LRU position of the probation segment.
+        let mut is_probation = true;
         let mut next_victim = deqs.probation.peek_front();
 
         // Aggregate potential victims.
@@ -764,6 +1132,12 @@ where
             if candidate.freq < victims.freq {
                 break;
             }
+            if next_victim.is_none() && is_probation {
+                // No more potential victims in the probation segment. Continue with
+                // the LRU position of the protected segment.
+                is_probation = false;
+                next_victim = deqs.protected.peek_front();
+            }
             if let Some(victim) = next_victim.take() {
                 next_victim = victim.next_node();
 
@@ -798,18 +1172,33 @@ where
         &mut self,
         key: Rc<K>,
         timestamp: Option<Instant>,
-        policy_weight: u32,
-        old_entry: ValueEntry<K, V>,
+        policy_weight: u64,
+        mut old_entry: ValueEntry<K, V>,
     ) {
         let old_policy_weight = old_entry.policy_weight();
 
+        let old_expiration_time = old_entry.expiration_time();
+        let cause = self.removal_cause(&old_entry, RemovalCause::Replaced);
+
         let entry = self.cache.get_mut(&key).unwrap();
-        entry.replace_deq_nodes_with(old_entry);
+        entry.replace_deq_nodes_with(&mut old_entry);
+        if let (Some(expiry), Some(now)) = (&self.expiry, timestamp) {
+            let duration = match old_expiration_time {
+                // The old value has already expired, so treat this as a creation.
+                Some(ts) if ts <= now => expiry.expire_after_create(&key, &entry.value),
+                current => {
+                    let current = current.map(|ts| ts.saturating_duration_since(now));
+                    expiry.expire_after_update(&key, &entry.value, current)
+                }
+            };
+            entry.set_expiration_time(duration.and_then(|d| now.checked_add(d)));
+        }
         if let Some(ts) = timestamp {
             entry.set_last_accessed(ts);
             entry.set_last_modified(ts);
         }
         entry.set_policy_weight(policy_weight);
+        let region = entry.access_order_q_region();
 
         let deqs = &mut self.deques;
         deqs.move_to_back_ao(entry);
@@ -817,8 +1206,16 @@ where
             deqs.move_to_back_wo(entry);
         }
 
-        self.saturating_sub_from_total_weight(old_policy_weight as u64);
-        self.saturating_add_to_total_weight(policy_weight as u64);
+        self.saturating_sub_from_total_weight(old_policy_weight);
+        self.saturating_add_to_total_weight(policy_weight);
+        if let Some(region) = region {
+            self.saturating_sub_from_region_weight(region, old_policy_weight);
+            self.saturating_add_to_region_weight(region, policy_weight);
+        }
+
+        if let Some(cause) = cause {
+            notify(&mut self.eviction_listener, key, old_entry.value, cause);
+        }
     }
 
     fn evict_expired(&mut self, now: Instant) {
@@ -830,12 +1227,13 @@ where
 
         if self.time_to_idle.is_some() {
             let deqs = &mut self.deques;
-            let (window, probation, protected, wo, cache, time_to_idle) = (
+            let (window, probation, protected, wo, cache, listener, time_to_idle) = (
                 &mut deqs.window,
                 &mut deqs.probation,
                 &mut deqs.protected,
                 &mut deqs.write_order,
                 &mut self.cache,
+                &mut self.eviction_listener,
                 &self.time_to_idle,
             );
 
@@ -845,30 +1243,93 @@ where
                     deq,
                     wo,
                     cache,
+                    listener,
                     time_to_idle,
                     EVICTION_BATCH_SIZE,
                     now,
                 )
             };
 
-            let (count1, weight1) = rm_expired_ao("window", window);
-            let (count2, weight2) = rm_expired_ao("probation", probation);
-            let (count3, weight3) = rm_expired_ao("protected", protected);
+            let (count1, weight1) = rm_expired_ao("probation", probation);
+            let (count2, weight2) = rm_expired_ao("protected", protected);
+            let (count3, weight3) = rm_expired_ao("window", window);
 
             self.entry_count -= count1 + count2 + count3;
             self.saturating_sub_from_total_weight(weight1);
             self.saturating_sub_from_total_weight(weight2);
             self.saturating_sub_from_total_weight(weight3);
+            self.saturating_sub_from_region_weight(CacheRegion::MainProtected, weight2);
+            self.saturating_sub_from_region_weight(CacheRegion::Window, weight3);
+        }
+
+        if self.expiry.is_some() {
+            self.remove_expired_by_expiry(EVICTION_BATCH_SIZE, now);
+        }
+    }
+
+    /// Removes the entries whose per-entry expiration time (calculated by the
+    /// `Expiry`) has passed.
+    ///
+    /// The expiration times are not ordered in any deque, so this method scans the
+    /// access-order deques from their cursors, visiting at most `batch_size`
+    /// nodes per call.
+    fn remove_expired_by_expiry(&mut self, batch_size: usize, now: Instant) {
+        let mut budget = batch_size;
+        let mut expired = Vec::new();
+        {
+            let Self { cache, deques, .. } = self;
+            let mut scan = |deq: &mut Deque<KeyHashDate<K>>| {
+                while budget > 0 {
+                    if let Some(element) = (&mut *deq).next() {
+                        budget -= 1;
+                        let is_expired = cache
+                            .get(&element.key)
+                            .map(|entry| Self::is_expired_by_expiry(entry, now))
+                            .unwrap_or_default();
+                        if is_expired {
+                            expired.push(Rc::clone(&element.key));
+                        }
+                    } else {
+                        deq.reset_cursor();
+                        break;
+                    }
+                }
+            };
+            scan(&mut deques.probation);
+            scan(&mut deques.protected);
+            scan(&mut deques.window);
+        }
+
+        for key in expired {
+            if let Some(mut entry) = self.cache.remove(&key) {
+                let weight = entry.policy_weight();
+                let region = entry.access_order_q_region();
+                self.deques.unlink_ao(&mut entry);
+                Deques::unlink_wo(&mut self.deques.write_order, &mut entry);
+                self.entry_count -= 1;
+                self.saturating_sub_from_total_weight(weight);
+                if let Some(region) = region {
+                    self.saturating_sub_from_region_weight(region, weight);
+                }
+                notify(
+                    &mut self.eviction_listener,
+                    key,
+                    entry.value,
+                    RemovalCause::Expired,
+                );
+            }
         }
     }
 
     // Returns (u64, u64) where (evicted_entry_count, evicted_policy_weight).
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn remove_expired_ao(
         deq_name: &str,
         deq: &mut Deque<KeyHashDate<K>>,
         write_order_deq: &mut Deque<KeyDate<K>>,
         cache: &mut CacheStore<K, V, S>,
+        listener: &mut Option<EvictionListener<K, V>>,
         time_to_idle: &Option<Duration>,
         batch_size: usize,
         now: Instant,
@@ -899,7 +1360,8 @@ where
                 Deques::unlink_ao_from_deque(deq_name, deq, &mut entry);
                 Deques::unlink_wo(write_order_deq, &mut entry);
                 evicted_entry_count += 1;
-                evicted_policy_weight = evicted_policy_weight.saturating_add(weight as u64);
+                evicted_policy_weight = evicted_policy_weight.saturating_add(weight);
+                notify(listener, key, entry.value, RemovalCause::Expired);
             } else {
                 deq.pop_front();
             }
@@ -913,6 +1375,7 @@ where
     fn remove_expired_wo(&mut self, batch_size: usize, now: Instant) -> (u64, u64) {
         let mut evicted_entry_count = 0u64;
         let mut evicted_policy_weight = 0u64;
+        let (mut window_evicted, mut protected_evicted) = (0u64, 0u64);
         let time_to_live = &self.time_to_live;
 
         for _ in 0..batch_size {
@@ -937,59 +1400,95 @@ where
 
             if let Some(mut entry) = self.cache.remove(&key) {
                 let weight = entry.policy_weight();
+                match entry.access_order_q_region() {
+                    Some(CacheRegion::Window) => window_evicted += weight,
+                    Some(CacheRegion::MainProtected) => protected_evicted += weight,
+                    _ => (),
+                }
                 self.deques.unlink_ao(&mut entry);
                 Deques::unlink_wo(&mut self.deques.write_order, &mut entry);
                 evicted_entry_count += 1;
-                evicted_policy_weight = evicted_policy_weight.saturating_sub(weight as u64);
+                evicted_policy_weight = evicted_policy_weight.saturating_sub(weight);
+                notify(
+                    &mut self.eviction_listener,
+                    key,
+                    entry.value,
+                    RemovalCause::Expired,
+                );
             } else {
                 self.deques.write_order.pop_front();
             }
         }
 
+        self.saturating_sub_from_region_weight(CacheRegion::Window, window_evicted);
+        self.saturating_sub_from_region_weight(CacheRegion::MainProtected, protected_evicted);
+
         (evicted_entry_count, evicted_policy_weight)
     }
 
     #[inline]
     fn evict_lru_entries(&mut self) {
-        const DEQ_NAME: &str = "probation";
-
         let weights_to_evict = self.weights_to_evict();
         let mut evicted_count = 0u64;
         let mut evicted_policy_weight = 0u64;
+        let (mut window_evicted, mut protected_evicted) = (0u64, 0u64);
 
         {
             let deqs = &mut self.deques;
-            let (probation, wo, cache) =
-                (&mut deqs.probation, &mut deqs.write_order, &mut self.cache);
+            let (window, probation, protected, wo, cache, listener) = (
+                &mut deqs.window,
+                &mut deqs.probation,
+                &mut deqs.protected,
+                &mut deqs.write_order,
+                &mut self.cache,
+                &mut self.eviction_listener,
+            );
 
-            for _ in 0..EVICTION_BATCH_SIZE {
-                if evicted_policy_weight >= weights_to_evict {
-                    break;
-                }
+            // Evict from the probation segment first, then the protected segment,
+            // and finally the admission window, which has the most recently added
+            // entries.
+            for (deq_name, deq) in [
+                ("probation", probation),
+                ("protected", protected),
+                ("window", window),
+            ]
+            .iter_mut()
+            {
+                for _ in 0..EVICTION_BATCH_SIZE {
+                    if evicted_policy_weight >= weights_to_evict {
+                        break;
+                    }
 
-                let key = probation
-                    .peek_front()
-                    .map(|node| Rc::clone(&node.element.key));
+                    let key = deq.peek_front().map(|node| Rc::clone(&node.element.key));
 
-                if key.is_none() {
-                    break;
-                }
-                let key = key.unwrap();
-
-                if let Some(mut entry) = cache.remove(&key) {
-                    let weight = entry.policy_weight();
-                    Deques::unlink_ao_from_deque(DEQ_NAME, probation, &mut entry);
-                    Deques::unlink_wo(wo, &mut entry);
-                    evicted_count += 1;
-                    evicted_policy_weight = evicted_policy_weight.saturating_add(weight as u64);
-                } else {
-                    probation.pop_front();
+                    if key.is_none() {
+                        break;
+                    }
+                    let key = key.unwrap();
+
+                    if let Some(mut entry) = cache.remove(&key) {
+                        let weight = entry.policy_weight();
+                        Deques::unlink_ao_from_deque(deq_name, deq, &mut entry);
+                        Deques::unlink_wo(wo, &mut entry);
+                        evicted_count += 1;
+                        evicted_policy_weight = evicted_policy_weight.saturating_add(weight);
+                        match deq.region() {
+                            CacheRegion::Window => window_evicted += weight,
+                            CacheRegion::MainProtected => protected_evicted += weight,
+                            _ => (),
+                        }
+                        notify(listener, key, entry.value, RemovalCause::Size);
+                    } else {
+                        deq.pop_front();
+                    }
                 }
             }
         }
 
         self.entry_count -= evicted_count;
         self.saturating_sub_from_total_weight(evicted_policy_weight);
+        self.saturating_sub_from_region_weight(CacheRegion::Window, window_evicted);
+        self.saturating_sub_from_region_weight(CacheRegion::MainProtected, protected_evicted);
     }
 }
 
@@ -1022,7 +1521,7 @@ impl EntrySizeAndFrequency {
     }
 
     fn add_policy_weight<K, V>(&mut self, key: &K, value: &V, weigher: &mut Option<Weigher<K, V>>) {
-        self.weight += weigh(weigher, key, value) as u64;
+        self.weight += weigh(weigher, key, value);
     }
 
     fn add_frequency(&mut self, freq: &FrequencySketch, hash: u64) {
@@ -1044,8 +1543,29 @@ enum AdmissionResult<K> {
 //
 // private free-standing functions
 //
+/// Calls the eviction listener, if any. If the listener panics, disables it by
+/// dropping it, as the concurrent caches do.
 #[inline]
-fn weigh<K, V>(weigher: &mut Option<Weigher<K, V>>, key: &K, value: &V) -> u32 {
+fn notify<K, V>(
+    listener: &mut Option<EvictionListener<K, V>>,
+    key: Rc<K>,
+    value: V,
+    cause: RemovalCause,
+) {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    if let Some(listener_clo) = listener {
+        // Safety: It is safe to assert unwind safety here because we will not
+        // call the listener again if it has been panicked.
+        let result = catch_unwind(AssertUnwindSafe(|| listener_clo(key, value, cause)));
+        if result.is_err() {
+            *listener = None;
+        }
+    }
+}
+
+#[inline]
+fn weigh<K, V>(weigher: &mut Option<Weigher<K, V>>, key: &K, value: &V) -> u64 {
     weigher.as_mut().map(|w| w(key, value)).unwrap_or(1)
 }
 
@@ -1053,9 +1573,9 @@ fn weigh<K, V>(weigher: &mut Option<Weigher<K, V>>, key: &K, value: &V) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::Cache;
-    use crate::common::time::Clock;
+    use crate::{common::time::Clock, notification::RemovalCause, Expiry};
 
-    use std::time::Duration;
+    use std::{cell::RefCell, rc::Rc, time::Duration};
 
     #[test]
     fn basic_single_thread() {
@@ -1107,6 +1627,131 @@ mod tests {
         assert!(!cache.contains_key(&"b"));
     }
 
+    #[test]
+    fn eviction_listener() {
+        let actual = Rc::new(RefCell::new(Vec::new()));
+        let mut expected = Vec::new();
+
+        let a1 = Rc::clone(&actual);
+        let mut cache = Cache::builder()
+            .max_capacity(3)
+            .time_to_live(Duration::from_secs(10))
+            .eviction_listener(move |k: Rc<&str>, v, cause| a1.borrow_mut().push((*k, v, cause)))
+            .build();
+        cache.enable_frequency_sketch_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        cache.insert("a", "alice");
+        cache.insert("b", "bob");
+        cache.insert("c", "cindy");
+        assert_eq!(cache.get(&"a"), Some(&"alice"));
+        assert_eq!(cache.get(&"b"), Some(&"bob"));
+        assert_eq!(cache.get(&"c"), Some(&"cindy"));
+
+        // "d" should not be admitted because its frequency is too low.
+        cache.insert("d", "david");
+        expected.push(("d", "david", RemovalCause::Size));
+        assert_eq!(*actual.borrow(), expected);
+
+        cache.insert("a", "anna");
+        expected.push(("a", "alice", RemovalCause::Replaced));
+        assert_eq!(*actual.borrow(), expected);
+
+        cache.invalidate(&"a");
+        expected.push(("a", "anna", RemovalCause::Explicit));
+        assert_eq!(*actual.borrow(), expected);
+
+        cache.invalidate_entries_if(|_k, &v| v == "bob");
+        expected.push(("b", "bob", invalidated()));
+        assert_eq!(*actual.borrow(), expected);
+
+        mock.increment(Duration::from_secs(5)); // 5 secs from the start.
+        cache.insert("e", "emily");
+
+        mock.increment(Duration::from_secs(5)); // 10 secs.
+        assert!(!cache.contains_key(&"c"));
+        expected.push(("c", "cindy", RemovalCause::Expired));
+        assert_eq!(*actual.borrow(), expected);
+
+        cache.invalidate_all();
+        expected.push(("e", "emily", invalidated()));
+        assert_eq!(*actual.borrow(), expected);
+
+        fn invalidated() -> RemovalCause {
+            RemovalCause::Invalidated { predicate_id: None }
+        }
+    }
+
+    #[test]
+    fn recover_from_panicking_eviction_listener() {
+        let actual = Rc::new(RefCell::new(Vec::new()));
+
+        let a1 = Rc::clone(&actual);
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .eviction_listener(move |k: Rc<&str>, v, cause| {
+                if v == "panic now!" {
+                    panic!("Panic now!");
+                }
+                a1.borrow_mut().push((*k, v, cause));
+            })
+            .build();
+
+        cache.insert("alice", "a0");
+        cache.insert("alice", "panic now!");
+        // The listener has panicked and been disabled, but the cache should still
+        // work.
+        cache.insert("alice", "a2");
+        assert_eq!(cache.get(&"alice"), Some(&"a2"));
+        cache.invalidate(&"alice");
+        assert_eq!(cache.get(&"alice"), None);
+
+        assert_eq!(
+            *actual.borrow(),
+            vec![("alice", "a0", RemovalCause::Replaced)]
+        );
+    }
+
+    #[test]
+    fn admission_window() {
+        // A cache with max capacity 100 has an admission window of size 1.
+        let mut cache = Cache::new(100);
+        cache.enable_frequency_sketch_for_testing();
+
+        for i in 0..100 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.entry_count(), 100);
+
+        for i in 0..100 {
+            assert_eq!(cache.get(&i), Some(&i));
+            assert_eq!(cache.get(&i), Some(&i));
+        }
+        // counts: 0..100 -> 2
+
+        // 100 should be added to the window even though the cache is full and its
+        // frequency is lower than others. 99 was at the window, so it should be
+        // evicted because its frequency is not higher than the main space's.
+        cache.insert(100, 100);
+        assert_eq!(cache.entry_count(), 100);
+        assert!(cache.contains_key(&100));
+        assert!(!cache.contains_key(&99));
+
+        assert_eq!(cache.get(&100), Some(&100));
+        assert_eq!(cache.get(&100), Some(&100));
+        assert_eq!(cache.get(&100), Some(&100));
+        // counts: 100 -> 3
+
+        // 100 should be moved from the window to the main space because its
+        // frequency is higher than the main space's.
+        cache.insert(101, 101);
+        assert_eq!(cache.entry_count(), 100);
+        assert!(cache.contains_key(&100));
+        assert!(cache.contains_key(&101));
+    }
+
     #[test]
     fn size_aware_eviction() {
         let weigher = |_k: &&str, v: &(&str, u32)| v.1;
@@ -1381,7 +2026,112 @@ mod tests {
         assert!(cache.cache.is_empty());
     }
 
+    #[test]
+    fn expire_after() {
+        struct MyExpiry;
+
+        impl Expiry<&'static str, &'static str> for MyExpiry {
+            fn expire_after_create(&self, key: &&str, _value: &&str) -> Option<Duration> {
+                if *key == "c" {
+                    None
+                } else {
+                    Some(Duration::from_secs(10))
+                }
+            }
+
+            fn expire_after_update(
+                &self,
+                _key: &&str,
+                _value: &&str,
+                _current_duration: Option<Duration>,
+            ) -> Option<Duration> {
+                Some(Duration::from_secs(3))
+            }
+        }
+
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .expire_after(MyExpiry)
+            .build();
+        cache.enable_frequency_sketch_for_testing();
+
+        let (clock, mock) = Clock::mock();
+        cache.set_expiration_clock(Some(clock));
+
+        cache.insert("a", "alice");
+        cache.insert("b", "bob");
+        cache.insert("c", "cindy");
+
+        mock.increment(Duration::from_secs(5)); // 5 secs from the start.
+
+        assert_eq!(cache.get(&"a"), Some(&"alice"));
+
+        // The update shortens the remaining time of "b" to 3 secs.
+        cache.insert("b", "bill");
+
+        mock.increment(Duration::from_secs(4)); // 9 secs.
+
+        assert_eq!(cache.get(&"a"), Some(&"alice"));
+        assert_eq!(cache.get(&"b"), None);
+        assert!(!cache.contains_key(&"b"));
+        assert_eq!(cache.iter().count(), 2);
+        assert_eq!(cache.cache.len(), 2);
+
+        mock.increment(Duration::from_secs(1)); // 10 secs.
+
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"c"), Some(&"cindy"));
+        assert_eq!(cache.cache.len(), 1);
+
+        // "c" has no expiration time.
+        mock.increment(Duration::from_secs(1000)); // 1010 secs.
+
+        assert_eq!(cache.get(&"c"), Some(&"cindy"));
+    }
+
     #[cfg_attr(target_pointer_width = "16", ignore)]
+    #[test]
+    fn ticker() {
+        use crate::ticker::MockTicker;
+        use std::sync::Arc;
+
+        let ticker = Arc::new(MockTicker::new());
+        let mut cache = Cache::builder()
+            .max_capacity(100)
+            .time_to_live(Duration::from_secs(10))
+            .ticker(Arc::clone(&ticker))
+            .build();
+
+        cache.insert("a", "alice");
+
+        ticker.advance(Duration::from_secs(9));
+        assert_eq!(cache.get(&"a"), Some(&"alice"));
+
+        ticker.advance(Duration::from_secs(1));
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.entry_count(), 0);
+    }
+
+    #[test]
+    fn max_memory_bytes() {
+        use std::mem::size_of;
+
+        let weight = (size_of::<u32>() + size_of::<String>() + 100) as u64;
+        let mut cache = Cache::builder().max_memory_bytes(3 * weight).build();
+
+        for key in 0..3u32 {
+            cache.insert(key, String::with_capacity(100));
+        }
+        assert_eq!(cache.entry_count(), 3);
+        assert_eq!(cache.weighted_size(), 3 * weight);
+
+        for key in 3..6u32 {
+            cache.insert(key, String::with_capacity(100));
+        }
+        assert_eq!(cache.entry_count(), 3);
+        assert_eq!(cache.weighted_size(), 3 * weight);
+    }
+
     #[test]
     fn test_skt_capacity_will_not_overflow() {
         // power of two
@@ -8,9 +8,9 @@ use std::ptr::NonNull;
 use tagptr::TagNonNull;
 
 pub(crate) struct Deques<K> {
-    pub(crate) window: Deque<KeyHashDate<K>>, //    Not used yet.
+    pub(crate) window: Deque<KeyHashDate<K>>,
     pub(crate) probation: Deque<KeyHashDate<K>>,
-    pub(crate) protected: Deque<KeyHashDate<K>>, // Not used yet.
+    pub(crate) protected: Deque<KeyHashDate<K>>,
     pub(crate) write_order: Deque<KeyDate<K>>,
 }
 
@@ -75,6 +75,44 @@ impl<K> Deques<K> {
         }
     }
 
+    /// Moves the access-order node of the entry from its current deque to the back
+    /// of the deque for the given `region`.
+    pub(crate) fn move_to_region_ao<V>(
+        &mut self,
+        region: CacheRegion,
+        entry: &mut ValueEntry<K, V>,
+    ) {
+        if let Some(tagged_node) = entry.access_order_q_node() {
+            let (node, tag) = tagged_node.decompose();
+            let from = self.ao_deque_mut(tag.into());
+            if !from.contains(unsafe { node.as_ref() }) {
+                panic!(
+                    "move_to_region_ao - node is not a member of {:?} deque. {:?}",
+                    from.region(),
+                    unsafe { node.as_ref() }
+                )
+            }
+            // Unlink the node without dropping it, and then push it to the back of
+            // the other deque.
+            let node = unsafe {
+                from.unlink(node);
+                Box::from_raw(node.as_ptr())
+            };
+            let node = self.ao_deque_mut(region).push_back(node);
+            let tagged_node = TagNonNull::compose(node, region as usize);
+            entry.set_access_order_q_node(Some(tagged_node));
+        }
+    }
+
+    fn ao_deque_mut(&mut self, region: CacheRegion) -> &mut Deque<KeyHashDate<K>> {
+        match region {
+            CacheRegion::Window => &mut self.window,
+            CacheRegion::MainProbation => &mut self.probation,
+            CacheRegion::MainProtected => &mut self.protected,
+            CacheRegion::Other => unreachable!(),
+        }
+    }
+
     pub(crate) fn move_to_back_wo<V>(&mut self, entry: &ValueEntry<K, V>) {
         let node = entry.write_order_q_node().unwrap();
         let p = unsafe { node.as_ref() };